@@ -11,7 +11,7 @@ cargo build --release
 echo "Copying binaries to $DEPLOY_DIR..."
 mkdir -p "$DEPLOY_DIR"
 
-for bin in load-rest load-graphql load-vector load-realtime load-blob; do
+for bin in load-rest load-graphql load-vector load-realtime load-blob load-http load-scenario; do
     cp "target/release/$bin" "$DEPLOY_DIR/$bin"
     echo "  $bin -> $DEPLOY_DIR/$bin"
 done
@@ -0,0 +1,235 @@
+use crate::cli::BenchArgs;
+use crate::data::{self, DataSet, Row};
+use base64::Engine;
+use futures_util::StreamExt;
+use reqwest::{Client, RequestBuilder};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+
+/// Refresh a login token this long before it expires.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Retry delay after a failed token refresh.
+const REFRESH_RETRY: Duration = Duration::from_secs(5);
+
+/// Concurrent logins while authenticating --credentials rows.
+const LOGIN_CONCURRENCY: usize = 16;
+
+/// Credentials attached to every benchmark request: basic auth from --auth,
+/// or a bearer token (--bearer-token, or obtained via --login-path).
+pub struct Auth {
+    user: String,
+    pass: String,
+    token: Option<RwLock<String>>,
+}
+
+impl Auth {
+    fn basic(args: &BenchArgs) -> Self {
+        let (user, pass) = args.auth_parts();
+        Self { user: user.to_string(), pass: pass.to_string(), token: None }
+    }
+
+    fn bearer(args: &BenchArgs, token: String) -> Self {
+        Self { token: Some(RwLock::new(token)), ..Self::basic(args) }
+    }
+
+    fn user(user: &str, pass: &str, token: Option<String>) -> Self {
+        Self { user: user.to_string(), pass: pass.to_string(), token: token.map(RwLock::new) }
+    }
+
+    pub fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => req.bearer_auth(token.read().map(|t| t.clone()).unwrap_or_default()),
+            None => req.basic_auth(&self.user, Some(&self.pass)),
+        }
+    }
+}
+
+/// Chainable `.authorize(&auth)` on request builders.
+pub trait Authorize {
+    fn authorize(self, auth: &Auth) -> Self;
+}
+
+impl Authorize for RequestBuilder {
+    fn authorize(self, auth: &Auth) -> Self {
+        auth.apply(self)
+    }
+}
+
+/// Process-wide credentials. The first call logs in when --login-path is set
+/// (exiting on failure) and keeps the token refreshed before it expires.
+pub async fn credentials(args: &BenchArgs, client: &Client) -> Arc<Auth> {
+    static AUTH: OnceCell<Arc<Auth>> = OnceCell::const_new();
+    AUTH.get_or_init(|| async {
+        if let Some(token) = &args.bearer_token {
+            return Arc::new(Auth::bearer(args, token.clone()));
+        }
+        let Some(path) = &args.login_path else {
+            return Arc::new(Auth::basic(args));
+        };
+
+        let (user, pass) = args.auth_parts();
+        match logged_in(args, client, path, user, pass).await {
+            Ok(auth) => auth,
+            Err(e) => {
+                eprintln!("Login failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    })
+    .await
+    .clone()
+}
+
+/// Credentials for each VU: one row of --credentials per VU (wrapping), or
+/// the shared --auth identity for all of them.
+pub struct Identities {
+    shared: Arc<Auth>,
+    per_vu: Vec<Arc<Auth>>,
+}
+
+impl Identities {
+    /// The --auth identity, used for setup, teardown and reporting.
+    pub fn shared(&self) -> Arc<Auth> {
+        self.shared.clone()
+    }
+
+    pub fn for_vu(&self, vu_id: u64) -> Arc<Auth> {
+        if self.per_vu.is_empty() {
+            return self.shared.clone();
+        }
+        self.per_vu[vu_id as usize % self.per_vu.len()].clone()
+    }
+}
+
+/// Load --credentials (when set) and authenticate each row: a `token` column is
+/// sent as a bearer token, otherwise `username`/`password` are used for basic
+/// auth, or to log in when --login-path is set. Exits on any failure.
+pub async fn identities(args: &BenchArgs, client: &Client) -> Identities {
+    let shared = credentials(args, client).await;
+    let Some(path) = &args.credentials else {
+        return Identities { shared, per_vu: Vec::new() };
+    };
+    let rows = match DataSet::load(path) {
+        Ok(rows) if rows.is_empty() => {
+            eprintln!("{} has no credentials", path.display());
+            std::process::exit(1);
+        }
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let per_vu: Result<Vec<_>, String> = futures_util::stream::iter(0..rows.len())
+        .map(|i| identity(args, client, rows.row(i as u64)))
+        .buffered(LOGIN_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect();
+    match per_vu {
+        Ok(per_vu) => Identities { shared, per_vu },
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn identity(args: &BenchArgs, client: &Client, row: &Row) -> Result<Arc<Auth>, String> {
+    if let Some(token) = data::field(Some(row), "token") {
+        return Ok(Arc::new(Auth::user("", "", Some(token.to_string()))));
+    }
+    let user = data::field(Some(row), "username").ok_or("row has neither token nor username")?;
+    let pass = data::field(Some(row), "password").unwrap_or("");
+    match &args.login_path {
+        Some(path) => logged_in(args, client, path, user, pass)
+            .await
+            .map_err(|e| format!("login as {} failed: {}", user, e)),
+        None => Ok(Arc::new(Auth::user(user, pass, None))),
+    }
+}
+
+/// Log in as `user` and keep the token refreshed before it expires.
+async fn logged_in(args: &BenchArgs, client: &Client, path: &str, user: &str, pass: &str) -> Result<Arc<Auth>, String> {
+    let url = format!("{}{}", args.base_url, path);
+    let (token, expires_at) = login(client, args, &url, user, pass).await?;
+    let auth = Arc::new(Auth::user(user, pass, Some(token)));
+    if let Some(expires_at) = expires_at {
+        tokio::spawn(refresh_loop(auth.clone(), client.clone(), args.clone(), url, expires_at));
+    }
+    Ok(auth)
+}
+
+/// POST credentials to the login URL; returns the token and its expiry
+/// (unix seconds) from `expires_in` or the JWT `exp` claim.
+async fn login(
+    client: &Client,
+    args: &BenchArgs,
+    url: &str,
+    user: &str,
+    pass: &str,
+) -> Result<(String, Option<u64>), String> {
+    let resp = client
+        .post(url)
+        .json(&serde_json::json!({ "username": user, "password": pass }))
+        .send()
+        .await
+        .map_err(|e| format!("POST {}: {}", url, e))?;
+    if !resp.status().is_success() {
+        return Err(format!("POST {} returned {}", url, resp.status()));
+    }
+    let body: serde_json::Value = resp.json().await.map_err(|e| format!("invalid login response: {}", e))?;
+    let token = body
+        .get(&args.token_field)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("login response has no '{}' field", args.token_field))?
+        .to_string();
+    let expires_at = body
+        .get("expires_in")
+        .and_then(|v| v.as_u64())
+        .map(|secs| unix_now() + secs)
+        .or_else(|| jwt_expiry(&token));
+    Ok((token, expires_at))
+}
+
+async fn refresh_loop(auth: Arc<Auth>, client: Client, args: BenchArgs, url: String, mut expires_at: u64) {
+    loop {
+        let wait = expires_at.saturating_sub(unix_now() + REFRESH_MARGIN.as_secs());
+        tokio::time::sleep(Duration::from_secs(wait)).await;
+        match login(&client, &args, &url, &auth.user, &auth.pass).await {
+            Ok((token, next)) => {
+                if let Some(lock) = &auth.token
+                    && let Ok(mut current) = lock.write()
+                {
+                    *current = token;
+                }
+                match next {
+                    Some(next) => expires_at = next,
+                    None => return,
+                }
+            }
+            Err(e) => {
+                eprintln!("Token refresh failed: {}", e);
+                tokio::time::sleep(REFRESH_RETRY).await;
+            }
+        }
+    }
+}
+
+/// `exp` claim of a JWT, without verifying the signature.
+fn jwt_expiry(token: &str) -> Option<u64> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    claims.get("exp").and_then(|v| v.as_u64())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
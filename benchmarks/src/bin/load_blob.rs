@@ -1,44 +1,72 @@
 use clap::Parser;
 use std::sync::Arc;
-use std::time::Duration;
-use uuid::Uuid;
-use yeti_benchmarks::{cli::BenchArgs, client, reporter, runner};
+use yeti_benchmarks::runner::SendRetrying;
+use yeti_benchmarks::auth::{self, Authorize};
+use yeti_benchmarks::teardown::Teardown;
+use yeti_benchmarks::metrics::ErrorKind;
+use yeti_benchmarks::{cli::{self, BenchArgs}, client, reporter, rng, runner};
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Yeti blob load test")]
+struct BlobArgs {
+    #[command(flatten)]
+    bench: BenchArgs,
+
+    /// Article content size for blob-retrieval and blob-upload (e.g. 150k, 1m, 10m)
+    #[arg(long, default_value = "150k", value_parser = cli::parse_size)]
+    blob_size: usize,
+}
+
+/// Lorem ipsum text of exactly `size` bytes.
+fn blob_content(size: usize) -> String {
+    const LOREM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ";
+    let mut content = LOREM.repeat(size / LOREM.len() + 1);
+    content.truncate(size);
+    content
+}
+
+/// e.g. "150KB", "10MB"
+fn format_size(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.0}MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.0}KB", bytes as f64 / 1024.0)
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    let args = BenchArgs::parse();
-    let (auth_user, auth_pass) = args.auth_parts();
-    let auth_user = auth_user.to_string();
-    let auth_pass = auth_pass.to_string();
-    let client = client::build_client();
-    let duration = Duration::from_secs(args.duration);
+    let BlobArgs { bench: args, blob_size } = BlobArgs::parse();
+    let client = client::build_client(&args);
+    let auth = auth::credentials(&args, &client).await;
 
     println!(
-        "load-blob: test={}, duration={}s, vus={}, base={}",
-        args.test, args.duration, args.vus, args.base_url
+        "load-blob: test={}, size={}, duration={}s, vus={}, base={}",
+        args.test, format_size(blob_size), args.total_duration().as_secs(), args.peak_vus(), args.base_url
     );
 
-    match args.test.as_str() {
+    let summary = match args.test.as_str() {
         "blob-retrieval" => {
-            // Setup: create one Article with ~150KB content
-            let blob_id = Uuid::new_v4().to_string();
-            let large_content = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(2700); // ~150KB
-            println!("Setup: creating 150KB article (id={})...", &blob_id[..8]);
+            // Setup: create one Article with --blob-size content
+            let blob_id = args.rng(rng::SETUP_STREAM).uuid().to_string();
+            println!("Setup: creating {} article (id={})...", format_size(blob_size), &blob_id[..8]);
 
             let body = serde_json::json!({
                 "id": blob_id,
                 "title": "Blob Benchmark Article",
-                "content": large_content,
+                "content": blob_content(blob_size),
             });
             let url = format!("{}/demo-vector/Article/", args.base_url);
+            let created = Teardown::new(format!("{}/demo-vector/Article", args.base_url));
             match client
                 .post(&url)
-                .basic_auth(&auth_user, Some(&auth_pass))
+                .authorize(&auth)
                 .json(&body)
                 .send()
                 .await
             {
                 Ok(resp) if resp.status().is_success() => {
+                    created.track(blob_id.clone());
                     println!("Setup complete. Starting load test...");
                 }
                 Ok(resp) => {
@@ -52,27 +80,24 @@ async fn main() {
 
             let blob_id = Arc::new(blob_id);
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 move |ctx| {
                     let blob_id = blob_id.clone();
                     async move {
                         let url = format!("{}/demo-vector/Article/{}", ctx.base_url, blob_id);
                         let start = std::time::Instant::now();
                         match ctx.client.get(&url)
-                            .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                            .send().await {
+                            .authorize(&ctx.auth)
+                            .send_retrying(&ctx).await {
                             Ok(resp) => {
-                                let bytes =
-                                    resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                                let status = resp.status().as_u16();
+                                let ttfb = start.elapsed().as_micros() as u64;
+                                let body = resp.bytes().await;
                                 let latency = start.elapsed().as_micros() as u64;
-                                ctx.metrics.record_success(latency, bytes);
+                                ctx.metrics.record_body(status, ttfb, latency, &body);
                             }
-                            Err(_) => ctx.metrics.record_error(),
+                            Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                         }
                     }
                 },
@@ -80,15 +105,71 @@ async fn main() {
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "blob-retrieval", elapsed, &summary,
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            created.run(&args, &client, &auth).await;
+            summary
+        }
+        "blob-upload" => {
+            // The content is escaped once; each request only splices in a new id
+            let content = Arc::new(serde_json::Value::from(blob_content(blob_size)).to_string());
+            let created = Arc::new(Teardown::new(format!("{}/demo-vector/Article", args.base_url)));
+            let (metrics, elapsed) = runner::run_load_test(
+                &args,
+                client.clone(),
+                {
+                    let created = created.clone();
+                    move |ctx| {
+                        let content = content.clone();
+                        let created = created.clone();
+                        async move {
+                            let id = ctx.rng.uuid().to_string();
+                            let body = format!(
+                                r#"{{"id":"{}","title":"Blob Upload {}","content":{}}}"#,
+                                id,
+                                &id[..8],
+                                content
+                            );
+                            let sent = body.len() as u64;
+                            let url = format!("{}/demo-vector/Article/", ctx.base_url);
+                            let start = std::time::Instant::now();
+                            match ctx
+                                .client
+                                .post(&url)
+                                .authorize(&ctx.auth)
+                                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                                .body(body)
+                                .send_retrying(&ctx)
+                                .await
+                            {
+                                Ok(resp) => {
+                                    let status = resp.status().as_u16();
+                                    if resp.status().is_success() {
+                                        created.track(id);
+                                        ctx.metrics.record_sent(sent);
+                                    }
+                                    let ttfb = start.elapsed().as_micros() as u64;
+                                    let body = resp.bytes().await;
+                                    let latency = start.elapsed().as_micros() as u64;
+                                    ctx.metrics.record_body(status, ttfb, latency, &body);
+                                }
+                                Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                            }
+                        }
+                    }
+                },
             )
             .await;
+
+            let summary = metrics.summary(elapsed);
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            created.run(&args, &client, &auth).await;
+            summary
         }
         other => {
             eprintln!("Unknown test for load-blob: {}", other);
             std::process::exit(1);
         }
-    }
+    };
+
+    std::process::exit(reporter::exit_code(&args, &summary));
 }
@@ -1,21 +1,21 @@
 use clap::Parser;
 use std::sync::Arc;
-use std::time::Duration;
-use uuid::Uuid;
+use yeti_benchmarks::runner::SendRetrying;
+use yeti_benchmarks::auth::{self, Auth, Authorize};
+use yeti_benchmarks::metrics::ErrorKind;
 use yeti_benchmarks::{cli::BenchArgs, client, reporter, runner};
 
 /// Fetch real Book IDs from the server via REST API.
 async fn fetch_book_ids(
     client: &reqwest::Client,
     base_url: &str,
-    auth_user: &str,
-    auth_pass: &str,
+    auth: &Auth,
     limit: usize,
 ) -> Vec<String> {
     let url = format!("{}/demo-graphql/Book?limit={}&select=id", base_url, limit);
     match client
         .get(&url)
-        .basic_auth(auth_user, Some(auth_pass))
+        .authorize(auth)
         .send()
         .await
     {
@@ -43,21 +43,18 @@ async fn fetch_book_ids(
 #[tokio::main]
 async fn main() {
     let args = BenchArgs::parse();
-    let (auth_user, auth_pass) = args.auth_parts();
-    let auth_user = auth_user.to_string();
-    let auth_pass = auth_pass.to_string();
-    let client = client::build_client();
-    let duration = Duration::from_secs(args.duration);
+    let client = client::build_client(&args);
+    let auth = auth::credentials(&args, &client).await;
 
     println!(
         "load-graphql: test={}, duration={}s, vus={}, base={}",
-        args.test, args.duration, args.vus, args.base_url
+        args.test, args.total_duration().as_secs(), args.peak_vus(), args.base_url
     );
 
-    match args.test.as_str() {
+    let summary = match args.test.as_str() {
         "graphql-read" => {
             // Pre-fetch real Book IDs (UUID keys, not integers)
-            let ids = fetch_book_ids(&client, &args.base_url, &auth_user, &auth_pass, 100).await;
+            let ids = fetch_book_ids(&client, &args.base_url, &auth, 100).await;
             if ids.is_empty() {
                 eprintln!("ERROR: No Book records found. Run rest-write or graphql-mutation first to seed data.");
                 std::process::exit(1);
@@ -66,12 +63,8 @@ async fn main() {
             let ids = Arc::new(ids);
 
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 move |ctx| {
                     let ids = ids.clone();
                     async move {
@@ -82,14 +75,16 @@ async fn main() {
                         let url = format!("{}/demo-graphql/graphql", ctx.base_url);
                         let start = std::time::Instant::now();
                         match ctx.client.post(&url)
-                            .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                            .json(&query).send().await {
+                            .authorize(&ctx.auth)
+                            .json(&query).send_retrying(&ctx).await {
                             Ok(resp) => {
-                                let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                                let status = resp.status().as_u16();
+                                let ttfb = start.elapsed().as_micros() as u64;
+                                let body = resp.bytes().await;
                                 let latency = start.elapsed().as_micros() as u64;
-                                ctx.metrics.record_success(latency, bytes);
+                                ctx.metrics.record_body(status, ttfb, latency, &body);
                             }
-                            Err(_) => ctx.metrics.record_error(),
+                            Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                         }
                     }
                 },
@@ -97,22 +92,15 @@ async fn main() {
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "graphql-read", elapsed, &summary,
-            )
-            .await;
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            summary
         }
         "graphql-mutation" => {
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 |ctx| async move {
-                    let id = Uuid::new_v4().to_string();
+                    let id = ctx.rng.uuid().to_string();
                     let mutation = format!(
                         r#"mutation {{ createBook(input: {{ id: "{}", title: "GQL Bench {}", isbn: "978-{}", genre: "benchmark", price: 9.99 }}) {{ id }} }}"#,
                         id, &id[..8], &id[..10]
@@ -123,32 +111,31 @@ async fn main() {
                     match ctx
                         .client
                         .post(&url)
-                        .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
+                        .authorize(&ctx.auth)
                         .json(&query)
-                        .send()
+                        .send_retrying(&ctx)
                         .await
                     {
                         Ok(resp) => {
-                            let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                            let status = resp.status().as_u16();
+                            let ttfb = start.elapsed().as_micros() as u64;
+                            let body = resp.bytes().await;
                             let latency = start.elapsed().as_micros() as u64;
-                            ctx.metrics.record_success(latency, bytes);
+                            ctx.metrics.record_body(status, ttfb, latency, &body);
                         }
-                        Err(_) => ctx.metrics.record_error(),
+                        Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                     }
                 },
             )
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "graphql-mutation", elapsed, &summary,
-            )
-            .await;
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            summary
         }
         "graphql-join" => {
             // Pre-fetch real Book IDs (UUID keys, not integers)
-            let ids = fetch_book_ids(&client, &args.base_url, &auth_user, &auth_pass, 100).await;
+            let ids = fetch_book_ids(&client, &args.base_url, &auth, 100).await;
             if ids.is_empty() {
                 eprintln!("ERROR: No Book records found. Run rest-write or graphql-mutation first to seed data.");
                 std::process::exit(1);
@@ -157,12 +144,8 @@ async fn main() {
             let ids = Arc::new(ids);
 
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 move |ctx| {
                     let ids = ids.clone();
                     async move {
@@ -175,14 +158,16 @@ async fn main() {
                         let url = format!("{}/demo-graphql/graphql", ctx.base_url);
                         let start = std::time::Instant::now();
                         match ctx.client.post(&url)
-                            .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                            .json(&query).send().await {
+                            .authorize(&ctx.auth)
+                            .json(&query).send_retrying(&ctx).await {
                             Ok(resp) => {
-                                let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                                let status = resp.status().as_u16();
+                                let ttfb = start.elapsed().as_micros() as u64;
+                                let body = resp.bytes().await;
                                 let latency = start.elapsed().as_micros() as u64;
-                                ctx.metrics.record_success(latency, bytes);
+                                ctx.metrics.record_body(status, ttfb, latency, &body);
                             }
-                            Err(_) => ctx.metrics.record_error(),
+                            Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                         }
                     }
                 },
@@ -190,15 +175,14 @@ async fn main() {
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "graphql-join", elapsed, &summary,
-            )
-            .await;
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            summary
         }
         other => {
             eprintln!("Unknown test for load-graphql: {}", other);
             std::process::exit(1);
         }
-    }
+    };
+
+    std::process::exit(reporter::exit_code(&args, &summary));
 }
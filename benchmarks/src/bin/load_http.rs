@@ -0,0 +1,93 @@
+use clap::Parser;
+use reqwest::Method;
+use std::sync::Arc;
+use yeti_benchmarks::scenario::{render_template, Escape};
+use yeti_benchmarks::runner::SendRetrying;
+use yeti_benchmarks::auth::Authorize;
+use yeti_benchmarks::metrics::ErrorKind;
+use yeti_benchmarks::{cli::BenchArgs, client, reporter, runner};
+
+/// Generic endpoint load test, used for benchmark targets declared in an
+/// app's config.yaml (`benchmarks:` list).
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Yeti generic HTTP endpoint load test")]
+struct HttpArgs {
+    #[command(flatten)]
+    bench: BenchArgs,
+
+    /// HTTP method
+    #[arg(long, default_value = "GET")]
+    method: String,
+
+    /// Request path appended to --base-url (e.g. /my-app/Product?limit=10);
+    /// --data {{column}} placeholders are substituted percent-encoded
+    #[arg(long)]
+    path: String,
+
+    /// JSON body template; {{uuid}}, {{vu}}, {{random}} and --data {{column}}
+    /// are substituted per request, data values JSON-escaped
+    #[arg(long)]
+    body: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = HttpArgs::parse();
+    let bench = &args.bench;
+    let client = client::build_client(bench);
+
+    let method = match Method::from_bytes(args.method.to_uppercase().as_bytes()) {
+        Ok(m) => m,
+        Err(_) => {
+            eprintln!("Invalid HTTP method: {}", args.method);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "load-http: test={}, {} {}, duration={}s, vus={}, base={}",
+        bench.test, method, args.path, bench.total_duration().as_secs(), bench.peak_vus(), bench.base_url
+    );
+
+    let path = Arc::new(args.path.clone());
+    let body = Arc::new(args.body.clone());
+    let (metrics, elapsed) = runner::run_load_test(
+        bench,
+        client.clone(),
+        move |ctx| {
+            let method = method.clone();
+            let path = path.clone();
+            let body = body.clone();
+            async move {
+                let row = ctx.next_row();
+                let url = format!("{}{}", ctx.base_url, render_template(&path, ctx.vu_id, &ctx.rng, row, Escape::Url));
+                let mut req = ctx
+                    .client
+                    .request(method, &url)
+                    .authorize(&ctx.auth);
+                if let Some(template) = body.as_deref() {
+                    req = req
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(render_template(template, ctx.vu_id, &ctx.rng, row, Escape::Json));
+                }
+                let start = std::time::Instant::now();
+                match req.send_retrying(&ctx).await {
+                    Ok(resp) => {
+                        let status = resp.status().as_u16();
+                        let ttfb = start.elapsed().as_micros() as u64;
+                        let body = resp.bytes().await;
+                        let latency = start.elapsed().as_micros() as u64;
+                        ctx.metrics.record_body(status, ttfb, latency, &body);
+                    }
+                    Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                }
+            }
+        },
+    )
+    .await;
+
+    let summary = metrics.summary(elapsed);
+    reporter::report_results(&client, bench, elapsed, &summary).await;
+
+    std::process::exit(reporter::exit_code(bench, &summary));
+}
@@ -1,69 +1,196 @@
 use clap::Parser;
 use futures_util::StreamExt;
-use std::sync::Arc;
-use std::time::Duration;
+use hdrhistogram::Histogram;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::time::Instant;
-use uuid::Uuid;
-use yeti_benchmarks::{cli::BenchArgs, client, metrics::Metrics, reporter};
+use yeti_benchmarks::{
+    auth::{self, Auth, Authorize},
+    cli::{self, BenchArgs},
+    client,
+    metrics::{connection_timings, ErrorKind, Metrics, MetricsSummary},
+    reporter,
+    rng,
+    runner::{self, Stage},
+};
+
+/// Realtime load test options: the common benchmark flags plus sse-reconnect pacing.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Yeti realtime (WebSocket/SSE) load test")]
+struct RealtimeArgs {
+    #[command(flatten)]
+    bench: BenchArgs,
+
+    /// How often sse-reconnect subscribers drop and resume with Last-Event-ID
+    #[arg(long, default_value = "5s", value_parser = cli::parse_duration)]
+    reconnect_every: Duration,
+}
 
 #[tokio::main]
 async fn main() {
-    let args = BenchArgs::parse();
-    let (auth_user, auth_pass) = args.auth_parts();
-    let auth_user = auth_user.to_string();
-    let auth_pass = auth_pass.to_string();
-    let client = client::build_client();
-    let duration = Duration::from_secs(args.duration);
+    let RealtimeArgs { bench: args, reconnect_every } = RealtimeArgs::parse();
+    let client = client::build_client(&args);
+    let auth = auth::credentials(&args, &client).await;
+    let duration = args.total_duration();
 
     println!(
         "load-realtime: test={}, duration={}s, vus={}, base={}",
-        args.test, args.duration, args.vus, args.base_url
+        args.test, args.total_duration().as_secs(), args.peak_vus(), args.base_url
     );
 
-    match args.test.as_str() {
-        "ws" => {
-            run_ws_test(&args, &auth_user, &auth_pass, &client, duration).await;
-        }
-        "sse" => {
-            run_sse_test(&args, &auth_user, &auth_pass, &client, duration).await;
-        }
+    let summary = match args.test.as_str() {
+        "ws" => run_ws_test(&args, &auth, &client, duration).await,
+        "sse" => run_sse_test(&args, &auth, &client, duration, None).await,
+        "sse-reconnect" => run_sse_test(&args, &auth, &client, duration, Some(reconnect_every)).await,
         other => {
             eprintln!("Unknown test for load-realtime: {}", other);
             std::process::exit(1);
         }
+    };
+
+    std::process::exit(reporter::exit_code(&args, &summary));
+}
+
+/// Marker before the publish timestamp (unix microseconds) in message content.
+const SENT_AT: &str = "sentAt=";
+
+fn unix_micros() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_micros() as u64).unwrap_or(0)
+}
+
+/// Marker before the publisher's sequence number in message content.
+const SEQ: &str = "seq=";
+
+/// Number following `marker` in a message payload.
+fn stamp(text: &str, marker: &str) -> Option<u64> {
+    let digits = &text[text.find(marker)? + marker.len()..];
+    let end = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+/// Delivery gaps and reconnect cost across sse-reconnect subscribers.
+struct ReconnectStats {
+    missed: AtomicU64,
+    duplicates: AtomicU64,
+    /// Time to response headers when reconnecting, in microseconds
+    latency: Mutex<Histogram<u64>>,
+}
+
+impl ReconnectStats {
+    fn new() -> Self {
+        Self {
+            missed: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+            latency: Mutex::new(Histogram::new(3).expect("valid histogram precision")),
+        }
+    }
+
+    /// Compare a delivered sequence number with the subscriber's last one.
+    fn observe(&self, last_seq: &mut Option<u64>, seq: u64) {
+        match *last_seq {
+            Some(last) if seq <= last => {
+                self.duplicates.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Some(last) if seq > last + 1 => {
+                self.missed.fetch_add(seq - last - 1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+        *last_seq = Some(seq);
+    }
+
+    fn record_latency(&self, elapsed: Duration) {
+        let _ = self.latency.lock().unwrap().record(elapsed.as_micros() as u64);
+    }
+
+    fn format(&self) -> String {
+        let latency = self.latency.lock().unwrap();
+        format!(
+            "Reconnects: {} (p50={:.2}ms p95={:.2}ms), missed events={}, duplicates={}",
+            latency.len(),
+            latency.value_at_quantile(0.50) as f64 / 1000.0,
+            latency.value_at_quantile(0.95) as f64 / 1000.0,
+            self.missed.load(Ordering::Relaxed),
+            self.duplicates.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// Count a delivered message, with its publish-to-receive latency when the
+/// payload carries a publisher timestamp (wherever the server put the record).
+fn record_delivery(m: &Metrics, payload: &[u8]) {
+    match stamp(&String::from_utf8_lossy(payload), SENT_AT) {
+        Some(sent_at) => m.record_success(unix_micros().saturating_sub(sent_at), payload.len() as u64),
+        None => m.record_untimed(payload.len() as u64),
+    }
+}
+
+/// Wait until the stage ramp reaches subscriber `vu_id`.
+/// Returns false if the run ends before it becomes active.
+async fn wait_for_ramp(stages: &[Stage], vu_id: u64, start: Instant, deadline: Instant) -> bool {
+    while Instant::now() < deadline {
+        if vu_id < runner::target_vus_at(stages, start.elapsed()) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
     }
+    false
+}
+
+/// Whether subscriber `vu_id` should stay connected (ramp-down disconnects it).
+fn still_active(stages: &[Stage], vu_id: u64, start: Instant) -> bool {
+    vu_id < runner::target_vus_at(stages, start.elapsed())
 }
 
 async fn run_ws_test(
     args: &BenchArgs,
-    auth_user: &str,
-    auth_pass: &str,
+    auth: &Arc<Auth>,
     client: &reqwest::Client,
     duration: Duration,
-) {
-    let metrics = Arc::new(Metrics::new());
-    let deadline = Instant::now() + duration;
+) -> MetricsSummary {
+    let stages = Arc::new(args.stages());
+    let metrics = Arc::new(Metrics::with_stages(&stages));
+    connection_timings().attach(&metrics);
+    let start = Instant::now();
+    let deadline = start + duration;
+    let watcher = tokio::spawn(runner::watch_error_rate(
+        metrics.clone(),
+        args.abort_error_rate,
+        args.abort_window,
+    ));
+    let tracker = tokio::spawn(runner::track_stages(metrics.clone(), stages.to_vec(), start.into_std()));
+    tokio::spawn(runner::watch_signals(metrics.clone()));
 
     // Build TLS connector that accepts invalid certs
-    let tls = native_tls::TlsConnector::builder()
-        .danger_accept_invalid_certs(true)
-        .build()
-        .expect("failed to build TLS connector");
+    let tls = client::build_tls_connector(args);
     let connector = tokio_tungstenite::Connector::NativeTls(tls);
+    let headers = Arc::new(args.header_map());
 
     // Spawn subscriber VUs
     let mut handles = Vec::new();
-    for _vu in 0..args.vus {
+    for vu_id in 0..args.peak_vus() {
         let ws_url = format!(
             "{}/demo-realtime/message?stream=ws",
             args.base_url.replace("https://", "wss://").replace("http://", "ws://")
         );
         let m = metrics.clone();
         let conn = connector.clone();
+        let stages = stages.clone();
+        let headers = headers.clone();
 
         handles.push(tokio::spawn(async move {
+            if !wait_for_ramp(&stages, vu_id, start, deadline).await {
+                return;
+            }
+            let Ok(mut request) = ws_url.into_client_request() else {
+                return;
+            };
+            request.headers_mut().extend(headers.iter().map(|(k, v)| (k.clone(), v.clone())));
             let Ok((mut ws, _)) = tokio_tungstenite::connect_async_tls_with_config(
-                &ws_url,
+                request,
                 None,
                 false,
                 Some(conn),
@@ -73,14 +200,14 @@ async fn run_ws_test(
                 return;
             };
 
-            while Instant::now() < deadline {
+            while Instant::now() < deadline && still_active(&stages, vu_id, start) && !m.is_aborted() {
                 match tokio::time::timeout(Duration::from_secs(5), ws.next()).await {
-                    Ok(Some(Ok(msg))) => {
-                        let bytes = msg.into_data().len() as u64;
-                        m.record_success(0, bytes);
+                    Ok(Some(Ok(msg))) if msg.is_text() || msg.is_binary() => {
+                        record_delivery(&m, &msg.into_data());
                     }
+                    Ok(Some(Ok(_))) => continue, // ping/pong/close frames
                     Ok(Some(Err(_))) => {
-                        m.record_error();
+                        m.record_error(ErrorKind::Body);
                         break;
                     }
                     Ok(None) => break,
@@ -98,18 +225,19 @@ async fn run_ws_test(
     // Publisher task: POST messages as fast as possible
     let pub_url = format!("{}/demo-realtime/message", args.base_url);
     let pub_client = client.clone();
-    let pub_user = auth_user.to_string();
-    let pub_pass = auth_pass.to_string();
+    let pub_auth = auth.clone();
+    let pub_metrics = metrics.clone();
+    let pub_rng = args.rng(rng::PUBLISHER_STREAM);
     let pub_handle = tokio::spawn(async move {
-        while Instant::now() < deadline {
+        while Instant::now() < deadline && !pub_metrics.is_aborted() {
             let body = serde_json::json!({
-                "id": Uuid::new_v4().to_string(),
-                "content": "benchmark message",
+                "id": pub_rng.uuid().to_string(),
+                "content": format!("benchmark message {}{}", SENT_AT, unix_micros()),
                 "channel": "bench",
             });
             let _ = pub_client
                 .post(&pub_url)
-                .basic_auth(&pub_user, Some(&pub_pass))
+                .authorize(&pub_auth)
                 .json(&body)
                 .send()
                 .await;
@@ -122,58 +250,116 @@ async fn run_ws_test(
         h.await.ok();
     }
 
-    let elapsed = duration.as_secs_f64();
+    watcher.abort();
+    tracker.abort();
+
+    let elapsed = if metrics.is_aborted() {
+        start.elapsed().as_secs_f64()
+    } else {
+        duration.as_secs_f64()
+    };
     let summary = metrics.summary(elapsed);
-    reporter::report_results(client, &args.base_url, auth_user, auth_pass, "ws", elapsed, &summary)
-        .await;
+    reporter::report_results(client, args, elapsed, &summary).await;
+    summary
 }
 
 async fn run_sse_test(
     args: &BenchArgs,
-    auth_user: &str,
-    auth_pass: &str,
+    auth: &Arc<Auth>,
     client: &reqwest::Client,
     duration: Duration,
-) {
-    let metrics = Arc::new(Metrics::new());
-    let deadline = Instant::now() + duration;
+    reconnect_every: Option<Duration>,
+) -> MetricsSummary {
+    let stages = Arc::new(args.stages());
+    let metrics = Arc::new(Metrics::with_stages(&stages));
+    connection_timings().attach(&metrics);
+    let start = Instant::now();
+    let deadline = start + duration;
+    let watcher = tokio::spawn(runner::watch_error_rate(
+        metrics.clone(),
+        args.abort_error_rate,
+        args.abort_window,
+    ));
+    let tracker = tokio::spawn(runner::track_stages(metrics.clone(), stages.to_vec(), start.into_std()));
+    tokio::spawn(runner::watch_signals(metrics.clone()));
 
     // Build a client for SSE subscribers
-    let sse_client = client::build_client();
+    let sse_client = client::build_client(args);
+    let identities = auth::identities(args, client).await;
 
     // Spawn subscriber VUs
+    let reconnects = Arc::new(ReconnectStats::new());
     let mut handles = Vec::new();
-    for _vu in 0..args.vus {
+    for vu_id in 0..args.peak_vus() {
         let sse_url = format!("{}/demo-realtime/message?stream=sse", args.base_url);
         let m = metrics.clone();
         let c = sse_client.clone();
-        let sse_user = auth_user.to_string();
-        let sse_pass = auth_pass.to_string();
+        let sse_auth = identities.for_vu(vu_id);
+        let stages = stages.clone();
+        let reconnects = reconnects.clone();
 
         handles.push(tokio::spawn(async move {
-            let Ok(resp) = c.get(&sse_url)
-                .basic_auth(&sse_user, Some(&sse_pass))
-                .send().await else {
+            if !wait_for_ramp(&stages, vu_id, start, deadline).await {
                 return;
-            };
-            let mut stream = resp.bytes_stream();
-
-            while Instant::now() < deadline {
-                match tokio::time::timeout(Duration::from_secs(5), stream.next()).await {
-                    Ok(Some(Ok(chunk))) => {
-                        // Count data lines as received messages
-                        let text = String::from_utf8_lossy(&chunk);
-                        let msg_count = text.lines().filter(|l| l.starts_with("data:")).count();
-                        for _ in 0..msg_count.max(1) {
-                            m.record_success(0, chunk.len() as u64);
-                        }
+            }
+            let active = || Instant::now() < deadline && still_active(&stages, vu_id, start) && !m.is_aborted();
+            let mut last_event_id: Option<String> = None;
+            let mut last_seq: Option<u64> = None;
+
+            while active() {
+                // With --reconnect-every, resume from the last event seen
+                let mut req = c.get(&sse_url).authorize(&sse_auth);
+                if let Some(id) = &last_event_id {
+                    req = req.header("Last-Event-ID", id.as_str());
+                }
+                let connect_start = Instant::now();
+                let resp = match req.send().await {
+                    Ok(resp) => resp,
+                    Err(e) if reconnect_every.is_some() => {
+                        m.record_error(ErrorKind::of(&e));
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
                     }
-                    Ok(Some(Err(_))) => {
-                        m.record_error();
-                        break;
+                    Err(_) => return,
+                };
+                if last_event_id.is_some() {
+                    reconnects.record_latency(connect_start.elapsed());
+                }
+                let drop_at = reconnect_every.map(|every| Instant::now() + every);
+                let mut stream = resp.bytes_stream();
+                let mut pending: Vec<u8> = Vec::new();
+
+                while active() && drop_at.is_none_or(|at| Instant::now() < at) {
+                    let wait = drop_at.map_or(Duration::from_secs(5), |at| {
+                        at.saturating_duration_since(Instant::now()).min(Duration::from_secs(5))
+                    });
+                    match tokio::time::timeout(wait, stream.next()).await {
+                        Ok(Some(Ok(chunk))) => {
+                            // Each complete `data:` line is one delivered message
+                            pending.extend_from_slice(&chunk);
+                            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                                let line: Vec<u8> = pending.drain(..=end).collect();
+                                if let Some(id) = line.strip_prefix(b"id:") {
+                                    last_event_id = Some(String::from_utf8_lossy(id).trim().to_string());
+                                } else if let Some(data) = line.strip_prefix(b"data:") {
+                                    record_delivery(&m, data);
+                                    if let Some(seq) = stamp(&String::from_utf8_lossy(data), SEQ) {
+                                        reconnects.observe(&mut last_seq, seq);
+                                    }
+                                }
+                            }
+                        }
+                        Ok(Some(Err(_))) => {
+                            m.record_error(ErrorKind::Body);
+                            break;
+                        }
+                        Ok(None) => break,
+                        Err(_) => continue,
                     }
-                    Ok(None) => break,
-                    Err(_) => continue,
+                }
+                // Steady mode keeps a single connection
+                if reconnect_every.is_none() {
+                    break;
                 }
             }
         }));
@@ -185,18 +371,21 @@ async fn run_sse_test(
     // Publisher task
     let pub_url = format!("{}/demo-realtime/message", args.base_url);
     let pub_client = client.clone();
-    let pub_user = auth_user.to_string();
-    let pub_pass = auth_pass.to_string();
+    let pub_auth = auth.clone();
+    let pub_metrics = metrics.clone();
+    let pub_rng = args.rng(rng::PUBLISHER_STREAM);
     let pub_handle = tokio::spawn(async move {
-        while Instant::now() < deadline {
+        let mut seq = 0u64;
+        while Instant::now() < deadline && !pub_metrics.is_aborted() {
+            seq += 1;
             let body = serde_json::json!({
-                "id": Uuid::new_v4().to_string(),
-                "content": "benchmark sse message",
+                "id": pub_rng.uuid().to_string(),
+                "content": format!("benchmark sse message {}{} {}{}", SEQ, seq, SENT_AT, unix_micros()),
                 "channel": "bench",
             });
             let _ = pub_client
                 .post(&pub_url)
-                .basic_auth(&pub_user, Some(&pub_pass))
+                .authorize(&pub_auth)
                 .json(&body)
                 .send()
                 .await;
@@ -208,8 +397,18 @@ async fn run_sse_test(
         h.await.ok();
     }
 
-    let elapsed = duration.as_secs_f64();
+    watcher.abort();
+    tracker.abort();
+
+    let elapsed = if metrics.is_aborted() {
+        start.elapsed().as_secs_f64()
+    } else {
+        duration.as_secs_f64()
+    };
     let summary = metrics.summary(elapsed);
-    reporter::report_results(client, &args.base_url, auth_user, auth_pass, "sse", elapsed, &summary)
-        .await;
+    reporter::report_results(client, args, elapsed, &summary).await;
+    if reconnect_every.is_some() {
+        println!("{}", reconnects.format());
+    }
+    summary
 }
@@ -1,21 +1,65 @@
 use clap::Parser;
-use std::sync::Arc;
-use std::time::Duration;
-use uuid::Uuid;
-use yeti_benchmarks::{cli::BenchArgs, client, reporter, runner};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use yeti_benchmarks::runner::SendRetrying;
+use yeti_benchmarks::auth::{self, Auth, Authorize};
+use yeti_benchmarks::teardown::Teardown;
+use yeti_benchmarks::metrics::ErrorKind;
+use yeti_benchmarks::{cli::BenchArgs, client, data, reporter, runner};
+
+/// Books each rest-update VU creates during setup and then updates.
+const RECORDS_PER_VU: usize = 5;
+
+/// REST load test options: the common benchmark flags plus bulk-write sizing.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Yeti REST load test")]
+struct RestArgs {
+    #[command(flatten)]
+    bench: BenchArgs,
+
+    /// Records per request for rest-bulk-write
+    #[arg(long, default_value = "100", value_parser = clap::value_parser!(u64).range(1..))]
+    batch_size: u64,
+
+    /// Page size (limit) for rest-scan
+    #[arg(long, default_value = "500", value_parser = clap::value_parser!(u64).range(1..))]
+    page_size: u64,
+}
+
+/// Where a rest-scan VU continues: an offset, or a server-issued cursor.
+enum Page {
+    Offset(u64),
+    Cursor(String),
+}
+
+/// Records in a list response (a bare array or `{"data": [...]}`) and the
+/// continuation cursor, if the server returned one.
+fn parse_page(body: &[u8]) -> (usize, Option<String>) {
+    let Ok(data) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return (0, None);
+    };
+    let count = data
+        .as_array()
+        .or_else(|| data.get("data").and_then(|d| d.as_array()))
+        .map_or(0, |a| a.len());
+    let cursor = ["nextCursor", "cursor", "next"]
+        .iter()
+        .find_map(|k| data.get(k).and_then(|v| v.as_str()))
+        .map(String::from);
+    (count, cursor)
+}
 
 /// Fetch real Book IDs from the server via REST API.
 async fn fetch_book_ids(
     client: &reqwest::Client,
     base_url: &str,
-    auth_user: &str,
-    auth_pass: &str,
+    auth: &Auth,
     limit: usize,
 ) -> Vec<String> {
     let url = format!("{}/demo-graphql/Book?limit={}&select=id", base_url, limit);
     match client
         .get(&url)
-        .basic_auth(auth_user, Some(auth_pass))
+        .authorize(auth)
         .send()
         .await
     {
@@ -42,22 +86,23 @@ async fn fetch_book_ids(
 
 #[tokio::main]
 async fn main() {
-    let args = BenchArgs::parse();
-    let (auth_user, auth_pass) = args.auth_parts();
-    let auth_user = auth_user.to_string();
-    let auth_pass = auth_pass.to_string();
-    let client = client::build_client();
-    let duration = Duration::from_secs(args.duration);
+    let RestArgs { bench: mut args, batch_size, page_size } = RestArgs::parse();
+    // rest-conn-churn is rest-read without connection reuse
+    if args.test == "rest-conn-churn" {
+        args.no_keepalive = true;
+    }
+    let client = client::build_client(&args);
+    let auth = auth::credentials(&args, &client).await;
 
     println!(
         "load-rest: test={}, duration={}s, vus={}, base={}",
-        args.test, args.duration, args.vus, args.base_url
+        args.test, args.total_duration().as_secs(), args.peak_vus(), args.base_url
     );
 
-    match args.test.as_str() {
-        "rest-read" => {
+    let summary = match args.test.as_str() {
+        "rest-read" | "rest-conn-churn" => {
             // Pre-fetch real Book IDs (UUID keys, not integers)
-            let ids = fetch_book_ids(&client, &args.base_url, &auth_user, &auth_pass, 100).await;
+            let ids = fetch_book_ids(&client, &args.base_url, &auth, 100).await;
             if ids.is_empty() {
                 eprintln!("ERROR: No Book records found. Run rest-write first to seed data.");
                 std::process::exit(1);
@@ -66,12 +111,8 @@ async fn main() {
             let ids = Arc::new(ids);
 
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 move |ctx| {
                     let ids = ids.clone();
                     async move {
@@ -79,14 +120,16 @@ async fn main() {
                         let url = format!("{}/demo-graphql/Book/{}", ctx.base_url, id);
                         let start = std::time::Instant::now();
                         match ctx.client.get(&url)
-                            .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                            .send().await {
+                            .authorize(&ctx.auth)
+                            .send_retrying(&ctx).await {
                             Ok(resp) => {
-                                let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                                let status = resp.status().as_u16();
+                                let ttfb = start.elapsed().as_micros() as u64;
+                                let body = resp.bytes().await;
                                 let latency = start.elapsed().as_micros() as u64;
-                                ctx.metrics.record_success(latency, bytes);
+                                ctx.metrics.record_body(status, ttfb, latency, &body);
                             }
-                            Err(_) => ctx.metrics.record_error(),
+                            Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                         }
                     }
                 },
@@ -94,113 +137,260 @@ async fn main() {
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "rest-read", elapsed, &summary,
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            summary
+        }
+        "rest-write" => {
+            let created = Arc::new(Teardown::new(format!("{}/demo-graphql/Book", args.base_url)));
+            let (metrics, elapsed) = runner::run_load_test(
+                &args,
+                client.clone(),
+                {
+                    let created = created.clone();
+                    move |ctx| {
+                        let created = created.clone();
+                        async move {
+                            let id = ctx.rng.uuid().to_string();
+                            let row = ctx.next_row();
+                            let body = serde_json::json!({
+                                "id": id,
+                                "title": data::field(row, "title")
+                                    .map_or_else(|| format!("Bench Book {}", &id[..8]), str::to_string),
+                                "isbn": data::field(row, "isbn")
+                                    .map_or_else(|| format!("978-{}", &id[..10]), str::to_string),
+                                "genre": data::field(row, "genre").unwrap_or("benchmark"),
+                                "price": data::field(row, "price").and_then(|p| p.parse::<f64>().ok()).unwrap_or(9.99),
+                            });
+                            let url = format!("{}/demo-graphql/Book/", ctx.base_url);
+                            let start = std::time::Instant::now();
+                            match ctx
+                                .client
+                                .post(&url)
+                                .authorize(&ctx.auth)
+                                .json(&body)
+                                .send_retrying(&ctx)
+                                .await
+                            {
+                                Ok(resp) => {
+                                    let status = resp.status().as_u16();
+                                    if resp.status().is_success() {
+                                        created.track(id);
+                                    }
+                                    let ttfb = start.elapsed().as_micros() as u64;
+                                    let body = resp.bytes().await;
+                                    let latency = start.elapsed().as_micros() as u64;
+                                    ctx.metrics.record_body(status, ttfb, latency, &body);
+                                }
+                                Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                            }
+                        }
+                    }
+                },
             )
             .await;
+
+            let summary = metrics.summary(elapsed);
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            created.run(&args, &client, &auth).await;
+            summary
         }
-        "rest-write" => {
+        "rest-bulk-write" => {
+            // Each request POSTs an array of `batch_size` records
+            let created = Arc::new(Teardown::new(format!("{}/demo-graphql/Book", args.base_url)));
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
-                |ctx| async move {
-                    let id = Uuid::new_v4().to_string();
-                    let body = serde_json::json!({
-                        "id": id,
-                        "title": format!("Bench Book {}", &id[..8]),
-                        "isbn": format!("978-{}", &id[..10]),
-                        "genre": "benchmark",
-                        "price": 9.99,
-                    });
-                    let url = format!("{}/demo-graphql/Book/", ctx.base_url);
-                    let start = std::time::Instant::now();
-                    match ctx
-                        .client
-                        .post(&url)
-                        .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                        .json(&body)
-                        .send()
-                        .await
-                    {
-                        Ok(resp) => {
-                            let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
-                            let latency = start.elapsed().as_micros() as u64;
-                            ctx.metrics.record_success(latency, bytes);
+                {
+                    let created = created.clone();
+                    move |ctx| {
+                        let created = created.clone();
+                        async move {
+                            let ids: Vec<String> = (0..batch_size).map(|_| ctx.rng.uuid().to_string()).collect();
+                            let books: Vec<serde_json::Value> = ids
+                                .iter()
+                                .map(|id| {
+                                    let row = ctx.next_row();
+                                    serde_json::json!({
+                                        "id": id,
+                                        "title": data::field(row, "title")
+                                            .map_or_else(|| format!("Bulk Book {}", &id[..8]), str::to_string),
+                                        "isbn": data::field(row, "isbn")
+                                            .map_or_else(|| format!("978-{}", &id[..10]), str::to_string),
+                                        "genre": data::field(row, "genre").unwrap_or("benchmark"),
+                                        "price": data::field(row, "price").and_then(|p| p.parse::<f64>().ok()).unwrap_or(9.99),
+                                    })
+                                })
+                                .collect();
+                            let url = format!("{}/demo-graphql/Book/", ctx.base_url);
+                            let start = std::time::Instant::now();
+                            match ctx
+                                .client
+                                .post(&url)
+                                .authorize(&ctx.auth)
+                                .json(&books)
+                                .send_retrying(&ctx)
+                                .await
+                            {
+                                Ok(resp) => {
+                                    let status = resp.status().as_u16();
+                                    if resp.status().is_success() {
+                                        ids.into_iter().for_each(|id| created.track(id));
+                                    }
+                                    let ttfb = start.elapsed().as_micros() as u64;
+                                    let body = resp.bytes().await;
+                                    let latency = start.elapsed().as_micros() as u64;
+                                    ctx.metrics.record_body(status, ttfb, latency, &body);
+                                }
+                                Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                            }
                         }
-                        Err(_) => ctx.metrics.record_error(),
                     }
                 },
             )
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "rest-write", elapsed, &summary,
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            let records = (summary.total - summary.errors) * batch_size;
+            println!(
+                "Ingest: {} records in batches of {} ({:.1} records/s)",
+                records,
+                batch_size,
+                records as f64 / elapsed.max(f64::EPSILON)
+            );
+            created.run(&args, &client, &auth).await;
+            summary
+        }
+        "rest-scan" => {
+            // Each VU pages through the whole table, continuing by cursor when the
+            // server offers one and by offset otherwise; a short page starts over
+            let pages: Arc<Vec<Mutex<Page>>> = Arc::new((0..args.peak_vus()).map(|_| Mutex::new(Page::Offset(0))).collect());
+            let deepest = Arc::new(AtomicU64::new(0));
+            let (metrics, elapsed) = runner::run_load_test(
+                &args,
+                client.clone(),
+                {
+                    let deepest = deepest.clone();
+                    move |ctx| {
+                        let pages = pages.clone();
+                        let deepest = deepest.clone();
+                        async move {
+                            let page = &pages[ctx.vu_id as usize];
+                            let (url, offset) = match &*page.lock().unwrap() {
+                                Page::Offset(offset) => (
+                                    format!("{}/demo-graphql/Book?limit={}&offset={}", ctx.base_url, page_size, offset),
+                                    Some(*offset),
+                                ),
+                                Page::Cursor(cursor) => (
+                                    format!("{}/demo-graphql/Book?limit={}&cursor={}", ctx.base_url, page_size, cursor),
+                                    None,
+                                ),
+                            };
+                            let start = std::time::Instant::now();
+                            match ctx.client.get(&url)
+                                .authorize(&ctx.auth)
+                                .send_retrying(&ctx).await {
+                                Ok(resp) => {
+                                    let status = resp.status().as_u16();
+                                    let ttfb = start.elapsed().as_micros() as u64;
+                                    let body = resp.bytes().await;
+                                    let latency = start.elapsed().as_micros() as u64;
+                                    ctx.metrics.record_body(status, ttfb, latency, &body);
+                                    let body = body.unwrap_or_default();
+
+                                    let (count, cursor) = parse_page(&body);
+                                    if let Some(offset) = offset {
+                                        deepest.fetch_max(offset, Ordering::Relaxed);
+                                    }
+                                    *page.lock().unwrap() = match (cursor, offset) {
+                                        _ if status >= 400 || (count as u64) < page_size => Page::Offset(0),
+                                        (Some(cursor), _) => Page::Cursor(cursor),
+                                        (None, Some(offset)) => Page::Offset(offset + page_size),
+                                        (None, None) => Page::Offset(0),
+                                    };
+                                }
+                                Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                            }
+                        }
+                    }
+                },
             )
             .await;
+
+            let summary = metrics.summary(elapsed);
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            println!("Scan: pages of {}, deepest offset reached {}", page_size, deepest.load(Ordering::Relaxed));
+            summary
         }
         "rest-update" => {
-            // Setup phase: pre-create records
-            let record_count = args.vus * 5;
-            let record_ids: Vec<String> = (0..record_count).map(|_| Uuid::new_v4().to_string()).collect();
-            println!("Setup: creating {} records...", record_count);
+            // Each VU creates its own records during setup, then updates them
+            let created = Arc::new(Teardown::new(format!("{}/demo-graphql/Book", args.base_url)));
+            let records: Arc<Vec<OnceLock<Vec<String>>>> =
+                Arc::new((0..args.peak_vus()).map(|_| OnceLock::new()).collect());
+            println!("Setup: creating {} records per VU...", RECORDS_PER_VU);
 
-            for id in &record_ids {
-                let body = serde_json::json!({
-                    "id": id,
-                    "title": format!("Update Bench {}", &id[..8]),
-                    "isbn": format!("978-{}", &id[..10]),
-                    "genre": "benchmark",
-                    "price": 10.0,
-                });
-                let url = format!("{}/demo-graphql/Book/", args.base_url);
-                let _ = client
-                    .post(&url)
-                    .basic_auth(&auth_user, Some(&auth_pass))
-                    .json(&body)
-                    .send()
-                    .await;
-            }
-            println!("Setup complete. Starting load test...");
-
-            let ids = Arc::new(record_ids);
-            let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+            let setup = {
+                let created = created.clone();
+                let records = records.clone();
+                move |ctx: Arc<runner::ScenarioContext>| {
+                    let created = created.clone();
+                    let records = records.clone();
+                    async move {
+                        let mut ids = Vec::new();
+                        for _ in 0..RECORDS_PER_VU {
+                            let id = ctx.rng.uuid().to_string();
+                            let body = serde_json::json!({
+                                "id": id,
+                                "title": format!("Update Bench {}", &id[..8]),
+                                "isbn": format!("978-{}", &id[..10]),
+                                "genre": "benchmark",
+                                "price": 10.0,
+                            });
+                            let url = format!("{}/demo-graphql/Book/", ctx.base_url);
+                            let resp = ctx.client.post(&url).authorize(&ctx.auth).json(&body).send().await;
+                            if resp.is_ok_and(|r| r.status().is_success()) {
+                                created.track(id.clone());
+                                ids.push(id);
+                            }
+                        }
+                        let _ = records[ctx.vu_id as usize].set(ids);
+                    }
+                }
+            };
+            let hooks = runner::VuHooks { setup, teardown: |_| async {} };
+            let (metrics, elapsed) = runner::run_load_test_with_hooks(
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
+                hooks,
                 move |ctx| {
-                    let ids = ids.clone();
+                    let records = records.clone();
                     async move {
-                        let idx = (ctx.vu_id as usize) % ids.len();
-                        let id = &ids[idx];
-                        let price: f64 = rand::random::<f64>() * 100.0;
+                        let Some(ids) = records[ctx.vu_id as usize].get().filter(|ids| !ids.is_empty()) else {
+                            // Setup could not create any record for this VU
+                            ctx.metrics.record_error(ErrorKind::Other);
+                            return;
+                        };
+                        let id = &ids[ctx.rng.random_range(0..ids.len())];
+                        let price: f64 = ctx.rng.random::<f64>() * 100.0;
                         let body = serde_json::json!({ "price": price });
                         let url = format!("{}/demo-graphql/Book/{}", ctx.base_url, id);
                         let start = std::time::Instant::now();
                         match ctx
                             .client
                             .patch(&url)
-                            .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
+                            .authorize(&ctx.auth)
                             .json(&body)
-                            .send()
+                            .send_retrying(&ctx)
                             .await
                         {
                             Ok(resp) => {
-                                let bytes =
-                                    resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                                let status = resp.status().as_u16();
+                                let ttfb = start.elapsed().as_micros() as u64;
+                                let body = resp.bytes().await;
                                 let latency = start.elapsed().as_micros() as u64;
-                                ctx.metrics.record_success(latency, bytes);
+                                ctx.metrics.record_body(status, ttfb, latency, &body);
                             }
-                            Err(_) => ctx.metrics.record_error(),
+                            Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                         }
                     }
                 },
@@ -208,15 +398,13 @@ async fn main() {
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "rest-update", elapsed, &summary,
-            )
-            .await;
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            created.run(&args, &client, &auth).await;
+            summary
         }
         "rest-join" => {
             // Pre-fetch real Book IDs (UUID keys, not integers)
-            let ids = fetch_book_ids(&client, &args.base_url, &auth_user, &auth_pass, 100).await;
+            let ids = fetch_book_ids(&client, &args.base_url, &auth, 100).await;
             if ids.is_empty() {
                 eprintln!("ERROR: No Book records found. Run rest-write first to seed data.");
                 std::process::exit(1);
@@ -225,12 +413,8 @@ async fn main() {
             let ids = Arc::new(ids);
 
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 move |ctx| {
                     let ids = ids.clone();
                     async move {
@@ -241,14 +425,16 @@ async fn main() {
                         );
                         let start = std::time::Instant::now();
                         match ctx.client.get(&url)
-                            .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                            .send().await {
+                            .authorize(&ctx.auth)
+                            .send_retrying(&ctx).await {
                             Ok(resp) => {
-                                let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                                let status = resp.status().as_u16();
+                                let ttfb = start.elapsed().as_micros() as u64;
+                                let body = resp.bytes().await;
                                 let latency = start.elapsed().as_micros() as u64;
-                                ctx.metrics.record_success(latency, bytes);
+                                ctx.metrics.record_body(status, ttfb, latency, &body);
                             }
-                            Err(_) => ctx.metrics.record_error(),
+                            Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                         }
                     }
                 },
@@ -256,15 +442,14 @@ async fn main() {
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "rest-join", elapsed, &summary,
-            )
-            .await;
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            summary
         }
         other => {
             eprintln!("Unknown test for load-rest: {}", other);
             std::process::exit(1);
         }
-    }
+    };
+
+    std::process::exit(reporter::exit_code(&args, &summary));
 }
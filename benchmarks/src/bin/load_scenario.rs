@@ -0,0 +1,123 @@
+use clap::Parser;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use yeti_benchmarks::scenario::{self, Scenario};
+use yeti_benchmarks::runner::SendRetrying;
+use yeti_benchmarks::auth::Authorize;
+use yeti_benchmarks::metrics::ErrorKind;
+use yeti_benchmarks::{cli::BenchArgs, client, reporter, runner};
+
+/// Scenario load test: runs the weighted steps of a YAML scenario file.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Yeti YAML scenario load test")]
+struct ScenarioArgs {
+    #[command(flatten)]
+    bench: BenchArgs,
+
+    /// Scenario file (YAML)
+    #[arg(long)]
+    scenario: PathBuf,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = ScenarioArgs::parse();
+    let bench = &args.bench;
+    let client = client::build_client(bench);
+
+    let scenario = match Scenario::load(&args.scenario) {
+        Ok(s) => Arc::new(s),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "load-scenario: test={}, scenario={}, steps={}, duration={}s, vus={}, base={}",
+        bench.test,
+        scenario.name.as_deref().unwrap_or("-"),
+        scenario.steps.len(),
+        bench.total_duration().as_secs(),
+        bench.peak_vus(),
+        bench.base_url
+    );
+
+    // Per-step request and failed-check counts
+    let sent: Arc<Vec<AtomicU64>> = Arc::new(scenario.steps.iter().map(|_| AtomicU64::new(0)).collect());
+    let failed: Arc<Vec<AtomicU64>> = Arc::new(scenario.steps.iter().map(|_| AtomicU64::new(0)).collect());
+
+    let (metrics, elapsed) = runner::run_load_test(bench, client.clone(), {
+        let scenario = scenario.clone();
+        let sent = sent.clone();
+        let failed = failed.clone();
+        move |ctx| {
+            let scenario = scenario.clone();
+            let sent = sent.clone();
+            let failed = failed.clone();
+            async move {
+                let i = scenario.pick(&ctx.rng);
+                let step = &scenario.steps[i];
+                let method = step.http_method().expect("validated on load");
+                let row = ctx.next_row();
+                let path = scenario::render_template(&step.path, ctx.vu_id, &ctx.rng, row, scenario::Escape::Url);
+                let url = format!("{}{}", ctx.base_url, path);
+                let mut req = ctx
+                    .client
+                    .request(method, &url)
+                    .authorize(&ctx.auth);
+                if let Some(template) = &step.body {
+                    req = req
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(scenario::render_template(template, ctx.vu_id, &ctx.rng, row, scenario::Escape::Json));
+                }
+
+                sent[i].fetch_add(1, Ordering::Relaxed);
+                let start = std::time::Instant::now();
+                match req.send_retrying(&ctx).await {
+                    Ok(resp) => {
+                        let status = resp.status().as_u16();
+                        let ttfb = start.elapsed().as_micros() as u64;
+                        let body = resp.bytes().await;
+                        let latency = start.elapsed().as_micros() as u64;
+                        ctx.metrics.record_status(status);
+                        ctx.metrics.record_ttfb(ttfb);
+                        let failure = match &body {
+                            Ok(body) if step.checks.passes(status, body, latency as f64 / 1000.0) => None,
+                            Ok(_) => Some(ErrorKind::of_status(status).unwrap_or(ErrorKind::Check)),
+                            Err(_) => Some(ErrorKind::Body),
+                        };
+                        match failure {
+                            None => ctx.metrics.record_success(latency, body.map_or(0, |b| b.len() as u64)),
+                            Some(kind) => {
+                                failed[i].fetch_add(1, Ordering::Relaxed);
+                                ctx.metrics.record_error(kind);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        failed[i].fetch_add(1, Ordering::Relaxed);
+                        ctx.metrics.record_error(ErrorKind::of(&e));
+                    }
+                }
+            }
+        }
+    })
+    .await;
+
+    let summary = metrics.summary(elapsed);
+    reporter::report_results(&client, bench, elapsed, &summary).await;
+
+    println!("\nSteps:");
+    for (i, step) in scenario.steps.iter().enumerate() {
+        println!(
+            "  {:<30} {:>8} requests, {:>6} failed",
+            step.label(),
+            sent[i].load(Ordering::Relaxed),
+            failed[i].load(Ordering::Relaxed)
+        );
+    }
+
+    std::process::exit(reporter::exit_code(bench, &summary));
+}
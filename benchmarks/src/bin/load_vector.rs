@@ -1,7 +1,12 @@
 use clap::Parser;
-use std::time::Duration;
-use uuid::Uuid;
-use yeti_benchmarks::{cli::BenchArgs, client, reporter, runner};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use yeti_benchmarks::runner::SendRetrying;
+use yeti_benchmarks::auth::{self, Auth, Authorize};
+use yeti_benchmarks::runner::ScenarioContext;
+use yeti_benchmarks::teardown::Teardown;
+use yeti_benchmarks::metrics::ErrorKind;
+use yeti_benchmarks::{cli::{self, BenchArgs}, client, data, reporter, runner};
 
 const SAMPLE_TOPICS: &[&str] = &[
     "technology innovation artificial intelligence",
@@ -16,118 +21,252 @@ const SAMPLE_TOPICS: &[&str] = &[
     "cybersecurity threat detection",
 ];
 
+/// Vector load test options: the common benchmark flags plus bulk-load sizing.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Yeti vector load test")]
+struct VectorArgs {
+    #[command(flatten)]
+    bench: BenchArgs,
+
+    /// Articles to insert for vector-bulk-load (unless --iterations is set)
+    #[arg(long, default_value = "50000")]
+    corpus_size: u64,
+
+    /// How long vector-bulk-load waits for the last article to become searchable
+    #[arg(long, default_value = "5m", value_parser = cli::parse_duration)]
+    ready_timeout: Duration,
+}
+
+/// Poll interval while waiting for the vector index to catch up.
+const READY_POLL: Duration = Duration::from_millis(500);
+
+/// Article with unique content, so every insert triggers an embedding. Title and
+/// content come from --data when it has those columns.
+fn article_body(ctx: &ScenarioContext, id: &str) -> serde_json::Value {
+    let topic_idx = ctx.vu_id as usize % SAMPLE_TOPICS.len();
+    let row = ctx.next_row();
+    serde_json::json!({
+        "id": id,
+        "title": data::field(row, "title")
+            .map_or_else(|| format!("Vector Article {}", &id[..8]), str::to_string),
+        "content": data::field(row, "content").map_or_else(
+            || format!(
+                "This article explores {}. Generated for benchmark testing with unique content to trigger embedding computation. ID: {}",
+                SAMPLE_TOPICS[topic_idx], id
+            ),
+            str::to_string,
+        ),
+    })
+}
+
+/// Top-10 vector search for `text`.
+fn search_url(base_url: &str, text: &str) -> String {
+    let query = serde_json::json!({
+        "conditions": [{
+            "field": "embedding",
+            "op": "vector",
+            "value": text
+        }],
+        "limit": 10
+    });
+    format!("{}/demo-vector/Article/?query={}", base_url, urlencoding(query.to_string()))
+}
+
+/// Time until a vector search for `content` returns article `id`, or None if it
+/// does not show up within `timeout`.
+async fn wait_for_index(
+    client: &reqwest::Client,
+    auth: &Auth,
+    base_url: &str,
+    id: &str,
+    content: &str,
+    timeout: Duration,
+) -> Option<Duration> {
+    let start = Instant::now();
+    let url = search_url(base_url, content);
+    while start.elapsed() < timeout {
+        if let Ok(resp) = client.get(&url).authorize(auth).send().await {
+            let found = resp.json::<serde_json::Value>().await.ok().is_some_and(|data| {
+                data.as_array()
+                    .or_else(|| data.get("data").and_then(|d| d.as_array()))
+                    .is_some_and(|hits| hits.iter().any(|h| h.get("id").and_then(|v| v.as_str()) == Some(id)))
+            });
+            if found {
+                return Some(start.elapsed());
+            }
+        }
+        tokio::time::sleep(READY_POLL).await;
+    }
+    None
+}
+
 #[tokio::main]
 async fn main() {
-    let args = BenchArgs::parse();
-    let (auth_user, auth_pass) = args.auth_parts();
-    let auth_user = auth_user.to_string();
-    let auth_pass = auth_pass.to_string();
-    let client = client::build_client();
-    let duration = Duration::from_secs(args.duration);
+    let VectorArgs { bench: mut args, corpus_size, ready_timeout } = VectorArgs::parse();
+    if args.test == "vector-bulk-load" {
+        args.iterations.get_or_insert(corpus_size);
+    }
+    let client = client::build_client(&args);
+    let auth = auth::credentials(&args, &client).await;
 
     println!(
         "load-vector: test={}, duration={}s, vus={}, base={}",
-        args.test, args.duration, args.vus, args.base_url
+        args.test, args.total_duration().as_secs(), args.peak_vus(), args.base_url
     );
 
-    match args.test.as_str() {
+    let summary = match args.test.as_str() {
         "vector-embed" => {
+            let created = Arc::new(Teardown::new(format!("{}/demo-vector/Article", args.base_url)));
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
-                |ctx| async move {
-                    let id = Uuid::new_v4().to_string();
-                    let topic_idx = ctx.vu_id as usize % SAMPLE_TOPICS.len();
-                    let body = serde_json::json!({
-                        "id": id,
-                        "title": format!("Vector Article {}", &id[..8]),
-                        "content": format!(
-                            "This article explores {}. Generated for benchmark testing with unique content to trigger embedding computation. ID: {}",
-                            SAMPLE_TOPICS[topic_idx], id
-                        ),
-                    });
-                    let url = format!("{}/demo-vector/Article/", ctx.base_url);
-                    let start = std::time::Instant::now();
-                    match ctx
-                        .client
-                        .post(&url)
-                        .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                        .json(&body)
-                        .send()
-                        .await
-                    {
-                        Ok(resp) => {
-                            let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
-                            let latency = start.elapsed().as_micros() as u64;
-                            ctx.metrics.record_success(latency, bytes);
+                {
+                    let created = created.clone();
+                    move |ctx| {
+                        let created = created.clone();
+                        async move {
+                            let id = ctx.rng.uuid().to_string();
+                            let body = article_body(&ctx, &id);
+                            let url = format!("{}/demo-vector/Article/", ctx.base_url);
+                            let start = std::time::Instant::now();
+                            match ctx
+                                .client
+                                .post(&url)
+                                .authorize(&ctx.auth)
+                                .json(&body)
+                                .send_retrying(&ctx)
+                                .await
+                            {
+                                Ok(resp) => {
+                                    let status = resp.status().as_u16();
+                                    if resp.status().is_success() {
+                                        created.track(id);
+                                    }
+                                    let ttfb = start.elapsed().as_micros() as u64;
+                                    let body = resp.bytes().await;
+                                    let latency = start.elapsed().as_micros() as u64;
+                                    ctx.metrics.record_body(status, ttfb, latency, &body);
+                                }
+                                Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                            }
                         }
-                        Err(_) => ctx.metrics.record_error(),
                     }
                 },
             )
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "vector-embed", elapsed, &summary,
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            created.run(&args, &client, &auth).await;
+            summary
+        }
+        "vector-bulk-load" => {
+            // Insert a fixed corpus as fast as possible, then time how long the
+            // last article takes to become searchable
+            let created = Arc::new(Teardown::new(format!("{}/demo-vector/Article", args.base_url)));
+            let last: Arc<Mutex<Option<(String, String)>>> = Arc::new(Mutex::new(None));
+            println!("Bulk load: inserting {} articles...", args.iterations.unwrap_or(corpus_size));
+            let (metrics, elapsed) = runner::run_load_test(
+                &args,
+                client.clone(),
+                {
+                    let created = created.clone();
+                    let last = last.clone();
+                    move |ctx| {
+                        let created = created.clone();
+                        let last = last.clone();
+                        async move {
+                            let id = ctx.rng.uuid().to_string();
+                            let body = article_body(&ctx, &id);
+                            let url = format!("{}/demo-vector/Article/", ctx.base_url);
+                            let start = std::time::Instant::now();
+                            match ctx
+                                .client
+                                .post(&url)
+                                .authorize(&ctx.auth)
+                                .json(&body)
+                                .send_retrying(&ctx)
+                                .await
+                            {
+                                Ok(resp) => {
+                                    let status = resp.status().as_u16();
+                                    if resp.status().is_success() {
+                                        let content = body["content"].as_str().unwrap_or_default().to_string();
+                                        *last.lock().unwrap() = Some((id.clone(), content));
+                                        created.track(id);
+                                    }
+                                    let ttfb = start.elapsed().as_micros() as u64;
+                                    let body = resp.bytes().await;
+                                    let latency = start.elapsed().as_micros() as u64;
+                                    ctx.metrics.record_body(status, ttfb, latency, &body);
+                                }
+                                Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
+                            }
+                        }
+                    }
+                },
             )
             .await;
+
+            let last = last.lock().unwrap().take();
+            let ready = match &last {
+                Some((id, content)) => {
+                    println!("Bulk load done. Waiting for the vector index...");
+                    let budget = ready_timeout + reporter::request_budget(&args);
+                    runner::report_phase(&client, &args, &auth, "indexing", budget).await;
+                    wait_for_index(&client, &auth, &args.base_url, id, content, ready_timeout).await
+                }
+                None => None,
+            };
+
+            let summary = metrics.summary(elapsed);
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            let inserted = summary.total - summary.errors;
+            println!("Embed throughput: {:.1} articles/s", inserted as f64 / elapsed.max(f64::EPSILON));
+            match (&last, ready) {
+                (_, Some(ready)) => println!("Index ready {:.1}s after the last insert", ready.as_secs_f64()),
+                (Some(_), None) => println!("Index not ready within {}s", ready_timeout.as_secs()),
+                (None, None) => println!("Index readiness not measured: no article was inserted"),
+            }
+            created.run(&args, &client, &auth).await;
+            summary
         }
         "vector-search" => {
             let (metrics, elapsed) = runner::run_load_test(
-                args.vus,
-                duration,
+                &args,
                 client.clone(),
-                args.base_url.clone(),
-                auth_user.clone(),
-                auth_pass.clone(),
                 |ctx| async move {
                     let topic_idx = ctx.vu_id as usize % SAMPLE_TOPICS.len();
-                    let query = serde_json::json!({
-                        "conditions": [{
-                            "field": "embedding",
-                            "op": "vector",
-                            "value": SAMPLE_TOPICS[topic_idx]
-                        }],
-                        "limit": 10
-                    });
-                    let url = format!(
-                        "{}/demo-vector/Article/?query={}",
-                        ctx.base_url,
-                        urlencoding(query.to_string())
-                    );
+                    let search = data::field(ctx.next_row(), "query").unwrap_or(SAMPLE_TOPICS[topic_idx]);
+                    let url = search_url(&ctx.base_url, search);
                     let start = std::time::Instant::now();
                     match ctx.client.get(&url)
-                        .basic_auth(&ctx.auth_user, Some(&ctx.auth_pass))
-                        .send().await {
+                        .authorize(&ctx.auth)
+                        .send_retrying(&ctx).await {
                         Ok(resp) => {
-                            let bytes = resp.bytes().await.map(|b| b.len() as u64).unwrap_or(0);
+                            let status = resp.status().as_u16();
+                            let ttfb = start.elapsed().as_micros() as u64;
+                            let body = resp.bytes().await;
                             let latency = start.elapsed().as_micros() as u64;
-                            ctx.metrics.record_success(latency, bytes);
+                            ctx.metrics.record_body(status, ttfb, latency, &body);
                         }
-                        Err(_) => ctx.metrics.record_error(),
+                        Err(e) => ctx.metrics.record_error(ErrorKind::of(&e)),
                     }
                 },
             )
             .await;
 
             let summary = metrics.summary(elapsed);
-            reporter::report_results(
-                &client, &args.base_url, &auth_user, &auth_pass,
-                "vector-search", elapsed, &summary,
-            )
-            .await;
+            reporter::report_results(&client, &args, elapsed, &summary).await;
+            summary
         }
         other => {
             eprintln!("Unknown test for load-vector: {}", other);
             std::process::exit(1);
         }
-    }
+    };
+
+    std::process::exit(reporter::exit_code(&args, &summary));
 }
 
 /// Simple percent-encoding for query params.
@@ -1,4 +1,12 @@
+use crate::data::DataSet;
+use crate::output::OutputFormat;
+use crate::rng::SeededRng;
+use crate::runner::{Stage, ThinkTime};
+use crate::threshold::Threshold;
 use clap::Parser;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser, Debug, Clone)]
 #[command(about = "Yeti benchmark load test")]
@@ -15,13 +23,166 @@ pub struct BenchArgs {
     #[arg(long, default_value = "50")]
     pub vus: u64,
 
+    /// Stop once this many iterations have run across all VUs (--duration still caps the run)
+    #[arg(long)]
+    pub iterations: Option<u64>,
+
+    /// Load stages as duration:target pairs (e.g. "10s:50,60s:50,10s:0").
+    /// Overrides --duration and --vus when set.
+    #[arg(long, value_parser = validate_stages)]
+    pub stages: Option<String>,
+
+    /// Warmup before measuring (e.g. "10s"): requests run at the first stage's
+    /// target VUs but are excluded from results. Not applied by load-realtime.
+    #[arg(long, default_value = "0s", value_parser = parse_duration)]
+    pub warmup: Duration,
+
+    /// Pause between iterations per VU: "500ms" (fixed), "200ms-800ms" (uniform)
+    /// or "exp:500ms" (exponential with that mean). Not applied by load-realtime.
+    #[arg(long, default_value = "0s", value_parser = parse_think_time)]
+    pub think_time: ThinkTime,
+
     /// Base URL of the Yeti server
     #[arg(long, default_value = "https://localhost")]
     pub base_url: String,
 
-    /// Basic auth credentials (user:pass)
-    #[arg(long, default_value = "admin:admin123")]
+    /// Per-request timeout, covering the response body (e.g. "30s"; 0 disables)
+    #[arg(long, default_value = "5s", value_parser = parse_duration)]
+    pub timeout: Duration,
+
+    /// Timeout for establishing a connection (DNS, TCP and TLS)
+    #[arg(long, value_parser = parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Idle connections kept open per host for reuse
+    #[arg(long, default_value = "100")]
+    pub pool_max_idle: usize,
+
+    /// Open a new connection for every request (no pooling), to measure
+    /// handshake and accept costs
+    #[arg(long, conflicts_with_all = ["http2_prior_knowledge", "http3"])]
+    pub no_keepalive: bool,
+
+    /// Speak HTTP/2 without negotiation (h2c on http://, no ALPN on https://)
+    #[arg(long, conflicts_with = "http3")]
+    pub http2_prior_knowledge: bool,
+
+    /// HTTP/2 initial stream and connection flow-control window, in bytes
+    #[arg(long)]
+    pub http2_window: Option<u32>,
+
+    /// Let HTTP/2 adapt flow-control windows to measured bandwidth
+    #[arg(long)]
+    pub http2_adaptive_window: bool,
+
+    /// Use HTTP/3 over QUIC (binaries must be built with `--features http3`)
+    #[arg(long)]
+    pub http3: bool,
+
+    /// Client certificate (PEM) for servers requiring mutual TLS
+    #[arg(long, requires = "client_key")]
+    pub client_cert: Option<std::path::PathBuf>,
+
+    /// Private key (PKCS#8 PEM) for --client-cert
+    #[arg(long, requires = "client_cert")]
+    pub client_key: Option<std::path::PathBuf>,
+
+    /// Basic auth credentials (user:pass); the admin runner passes its own
+    /// through YETI_ADMIN_AUTH
+    #[arg(long, env = "YETI_ADMIN_AUTH", hide_env_values = true, default_value = "admin:admin123")]
     pub auth: String,
+
+    /// Send this bearer token instead of basic auth
+    #[arg(long, conflicts_with = "login_path")]
+    pub bearer_token: Option<String>,
+
+    /// Log in first by POSTing --auth credentials as JSON to this path (e.g.
+    /// /yeti-auth/login) and use the returned token; refreshed before it expires
+    #[arg(long)]
+    pub login_path: Option<String>,
+
+    /// Credentials file (.csv with a header row, or .json array of objects) with
+    /// `username`/`password` or `token` columns; VU n authenticates as row n
+    /// (wrapping) instead of every VU sharing --auth
+    #[arg(long, conflicts_with = "bearer_token")]
+    pub credentials: Option<std::path::PathBuf>,
+
+    /// Field of the login response holding the token
+    #[arg(long, default_value = "token")]
+    pub token_field: String,
+
+    /// Extra header sent with every request, "Name: value" (repeatable)
+    #[arg(long = "header", value_name = "HEADER", value_parser = validate_header)]
+    pub headers: Vec<String>,
+
+    /// Data file (.csv with a header row, or .json array of objects) whose
+    /// rows fill {{column}} placeholders; each VU iterates its own slice of rows
+    #[arg(long)]
+    pub data: Option<std::path::PathBuf>,
+
+    /// Retry a request this many times when it fails to send or gets a
+    /// 502/503/504, with exponential backoff; retries are counted separately
+    #[arg(long, default_value = "0")]
+    pub retries: u32,
+
+    /// Delay before the first retry, doubled for each further attempt
+    #[arg(long, default_value = "50ms", value_parser = parse_duration)]
+    pub retry_backoff: Duration,
+
+    /// Keep records created by write tests instead of deleting them after the run
+    #[arg(long)]
+    pub no_teardown: bool,
+
+    /// Seed for generated ids and values, so runs with the same seed send
+    /// identical request streams (random when omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Also write the summary (and per-stage metrics) as json, csv or junit
+    #[arg(long, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// File for --output (stdout when omitted)
+    #[arg(long, requires = "output")]
+    pub output_file: Option<std::path::PathBuf>,
+
+    /// Prometheus Pushgateway URL to push run metrics to (labeled by test,
+    /// host and run id)
+    #[arg(long)]
+    pub pushgateway: Option<String>,
+
+    /// Print a JSON progress snapshot (RPS, errors, p95) every second
+    #[arg(long)]
+    pub progress: bool,
+
+    /// Also POST each progress snapshot to this path (e.g. /admin/runner/progress)
+    #[arg(long)]
+    pub progress_path: Option<String>,
+
+    /// Record ID for the TestRun this run reports (generated by the server when omitted)
+    #[arg(long)]
+    pub run_id: Option<String>,
+
+    /// Latency percentiles to report, comma-separated
+    #[arg(long, default_value = "50,90,95,99,99.9", value_parser = validate_percentiles)]
+    pub percentiles: String,
+
+    /// Environment label recorded on the TestRun (defaults to the hostname)
+    #[arg(long)]
+    pub env: Option<String>,
+
+    /// Pass/fail condition on the results, e.g. "p95<50ms", "error_rate<1%" or
+    /// "rps>5000" (repeatable); the binary exits non-zero when any is violated
+    #[arg(long = "threshold", value_name = "THRESHOLD", value_parser = Threshold::parse)]
+    pub thresholds: Vec<Threshold>,
+
+    /// Abort the run when the error rate stays at or above this fraction (0 disables)
+    #[arg(long, default_value = "0.5")]
+    pub abort_error_rate: f64,
+
+    /// Consecutive seconds the error rate must stay above the threshold before aborting
+    #[arg(long, default_value = "5")]
+    pub abort_window: u64,
 }
 
 impl BenchArgs {
@@ -31,4 +192,195 @@ impl BenchArgs {
             None => (&self.auth, ""),
         }
     }
+
+    /// Headers from --header.
+    pub fn header_map(&self) -> HeaderMap {
+        self.headers
+            .iter()
+            .map(|h| parse_header(h).expect("header validated by clap"))
+            .collect()
+    }
+
+    /// Random source for one stream of generated data (a VU id, or one of the
+    /// reserved streams in `rng`), derived from --seed when set.
+    pub fn rng(&self, stream: u64) -> SeededRng {
+        SeededRng::new(self.seed, stream)
+    }
+
+    /// Rows from --data, if set. Exits when the file cannot be loaded.
+    pub fn data_set(&self) -> Option<Arc<DataSet>> {
+        let path = self.data.as_ref()?;
+        match DataSet::load(path) {
+            Ok(data) => Some(Arc::new(data)),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Protocol label recorded with results.
+    pub fn protocol(&self) -> &'static str {
+        if self.http3 {
+            "h3"
+        } else if self.http2_prior_knowledge {
+            "h2"
+        } else {
+            "http/1.1"
+        }
+    }
+
+    /// Environment key results are grouped under: --env, else the hostname.
+    pub fn environment(&self) -> String {
+        self.env.clone().unwrap_or_else(|| {
+            hostname::get()
+                .map(|h| h.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "unknown".to_string())
+        })
+    }
+
+    /// Percentiles from --percentiles, e.g. [50.0, 90.0, 99.9].
+    pub fn percentiles(&self) -> Vec<f64> {
+        parse_percentiles(&self.percentiles).expect("percentiles validated by clap")
+    }
+
+    /// Load shape for the run. Without --stages, all VUs start immediately
+    /// and hold for --duration.
+    pub fn stages(&self) -> Vec<Stage> {
+        match &self.stages {
+            Some(spec) => parse_stages(spec).expect("stages validated by clap"),
+            None => vec![
+                Stage { duration: Duration::ZERO, target: self.vus },
+                Stage { duration: Duration::from_secs(self.duration), target: self.vus },
+            ],
+        }
+    }
+
+    /// Total run time across all stages.
+    pub fn total_duration(&self) -> Duration {
+        self.stages().iter().map(|s| s.duration).sum()
+    }
+
+    /// Highest VU count reached in any stage.
+    pub fn peak_vus(&self) -> u64 {
+        self.stages().iter().map(|s| s.target).max().unwrap_or(0)
+    }
+}
+
+/// Parse a duration like "500ms", "10s", "2m" or a bare number of seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value: f64 = num
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", s))?;
+    let secs = match unit {
+        "ms" => value / 1000.0,
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("invalid duration unit in '{}' (use ms, s, m or h)", s)),
+    };
+    Ok(Duration::from_secs_f64(secs))
+}
+
+/// Parse a byte size like "150k", "1m", "10m" or a bare number of bytes
+/// (binary units: 1k = 1024 bytes).
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    let s = s.trim();
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let value: f64 = num.parse().map_err(|_| format!("invalid size '{}'", s))?;
+    let scale = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1024.0,
+        "m" | "mb" => 1024.0 * 1024.0,
+        "g" | "gb" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("invalid size unit in '{}' (use k, m or g)", s)),
+    };
+    match (value * scale) as usize {
+        0 => Err(format!("size must be positive: '{}'", s)),
+        bytes => Ok(bytes),
+    }
+}
+
+/// Parse a think-time spec: "500ms", "200ms-800ms" or "exp:500ms".
+pub fn parse_think_time(s: &str) -> Result<ThinkTime, String> {
+    let s = s.trim();
+    let think = if let Some(mean) = s.strip_prefix("exp:") {
+        ThinkTime::Exponential(parse_duration(mean)?)
+    } else if let Some((min, max)) = s.split_once('-') {
+        let (min, max) = (parse_duration(min)?, parse_duration(max)?);
+        if max < min {
+            return Err(format!("invalid think time '{}' (max below min)", s));
+        }
+        ThinkTime::Uniform(min, max)
+    } else {
+        ThinkTime::Fixed(parse_duration(s)?)
+    };
+    Ok(match think {
+        ThinkTime::Fixed(d) if d.is_zero() => ThinkTime::None,
+        other => other,
+    })
+}
+
+/// Parse a header like "X-Org: acme".
+pub fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid header '{}' (expected Name: value)", s))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| format!("invalid header name in '{}'", s))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| format!("invalid header value in '{}'", s))?;
+    Ok((name, value))
+}
+
+fn validate_header(s: &str) -> Result<String, String> {
+    parse_header(s).map(|_| s.to_string())
+}
+
+/// Parse a comma-separated stage list like "10s:10,60s:50,10s:0".
+pub fn parse_stages(spec: &str) -> Result<Vec<Stage>, String> {
+    let mut stages = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (duration, target) = part
+            .split_once(':')
+            .ok_or_else(|| format!("invalid stage '{}' (expected duration:target)", part))?;
+        let duration = parse_duration(duration)?;
+        let target = target
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid VU target in stage '{}'", part))?;
+        stages.push(Stage { duration, target });
+    }
+    if stages.is_empty() {
+        return Err("at least one stage is required".to_string());
+    }
+    Ok(stages)
+}
+
+fn validate_stages(spec: &str) -> Result<String, String> {
+    parse_stages(spec).map(|_| spec.to_string())
+}
+
+/// Parse a comma-separated percentile list like "50,95,99.9".
+pub fn parse_percentiles(spec: &str) -> Result<Vec<f64>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.parse::<f64>() {
+            Ok(v) if v > 0.0 && v <= 100.0 => Ok(v),
+            _ => Err(format!("invalid percentile '{}' (expected 0 < p <= 100)", p)),
+        })
+        .collect()
+}
+
+fn validate_percentiles(spec: &str) -> Result<String, String> {
+    parse_percentiles(spec).map(|_| spec.to_string())
 }
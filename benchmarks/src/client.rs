@@ -1,12 +1,159 @@
+use crate::cli::BenchArgs;
+use crate::metrics::connection_timings;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
 use reqwest::Client;
-use std::time::Duration;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower_layer::Layer;
+use tower_service::Service;
 
-/// Build an HTTP client that accepts self-signed certs.
-pub fn build_client() -> Client {
-    Client::builder()
+/// Build an HTTP client that accepts self-signed certs, using the protocol
+/// options and --header values from `args`. DNS lookups and new connections are timed and
+/// reported to `connection_timings()`, which records them into the attached run.
+pub fn build_client(args: &BenchArgs) -> Client {
+    let mut builder = Client::builder()
         .danger_accept_invalid_certs(true)
-        .pool_max_idle_per_host(100)
-        .timeout(Duration::from_secs(5))
-        .build()
-        .expect("failed to build reqwest client")
+        .pool_max_idle_per_host(args.pool_max_idle)
+        .default_headers(args.header_map())
+        .dns_resolver(Arc::new(TimedResolver))
+        .connector_layer(TimedConnectLayer);
+
+    if !args.timeout.is_zero() {
+        builder = builder.timeout(args.timeout);
+    }
+    if let Some(timeout) = args.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    if args.no_keepalive {
+        builder = builder.pool_max_idle_per_host(0);
+    }
+    if let Some((cert, key)) = client_identity_pem(args) {
+        match reqwest::Identity::from_pkcs8_pem(&cert, &key) {
+            Ok(identity) => builder = builder.identity(identity),
+            Err(e) => {
+                eprintln!("Invalid client certificate/key: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    if let Some(window) = args.http2_window {
+        builder = builder
+            .http2_initial_stream_window_size(window)
+            .http2_initial_connection_window_size(window);
+    }
+    if args.http2_adaptive_window {
+        builder = builder.http2_adaptive_window(true);
+    }
+    if args.http3 {
+        builder = http3(builder);
+    }
+
+    builder.build().expect("failed to build reqwest client")
+}
+
+/// PEM bytes of `--client-cert` and `--client-key`, if set. Exits on read errors.
+pub fn client_identity_pem(args: &BenchArgs) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (cert, key) = (args.client_cert.as_ref()?, args.client_key.as_ref()?);
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).unwrap_or_else(|e| {
+            eprintln!("Cannot read {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    };
+    Some((read(cert), read(key)))
+}
+
+/// TLS connector for WebSocket clients, with the same cert handling as `build_client`.
+pub fn build_tls_connector(args: &BenchArgs) -> native_tls::TlsConnector {
+    let mut builder = native_tls::TlsConnector::builder();
+    builder.danger_accept_invalid_certs(true);
+    if let Some((cert, key)) = client_identity_pem(args) {
+        match native_tls::Identity::from_pkcs8(&cert, &key) {
+            Ok(identity) => {
+                builder.identity(identity);
+            }
+            Err(e) => {
+                eprintln!("Invalid client certificate/key: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    builder.build().expect("failed to build TLS connector")
+}
+
+#[cfg(feature = "http3")]
+fn http3(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    builder.http3_prior_knowledge()
+}
+
+#[cfg(not(feature = "http3"))]
+fn http3(_builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    eprintln!("--http3 requires a build with `--features http3` (and RUSTFLAGS=\"--cfg reqwest_unstable\")");
+    std::process::exit(1);
+}
+
+/// System resolver that records lookup time.
+struct TimedResolver;
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            connection_timings().record_dns(start.elapsed());
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Connector layer recording how long each new connection takes to establish
+/// (DNS + TCP + TLS); pooled connections never reach it.
+#[derive(Clone)]
+struct TimedConnectLayer;
+
+impl<S> Layer<S> for TimedConnectLayer {
+    type Service = TimedConnect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnect { inner }
+    }
+}
+
+#[derive(Clone)]
+struct TimedConnect<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for TimedConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+    S::Response: Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let start = Instant::now();
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let conn = fut.await;
+            if conn.is_ok() {
+                connection_timings().record_connect(start.elapsed());
+            }
+            conn
+        })
+    }
 }
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// One row of a data file: column (or JSON key) to value.
+pub type Row = BTreeMap<String, String>;
+
+/// Rows loaded from `--data`, used to fill request templates with realistic
+/// values. A `.json` file holds an array of objects; anything else is read as
+/// CSV with a header row.
+#[derive(Debug)]
+pub struct DataSet {
+    rows: Vec<Row>,
+}
+
+impl DataSet {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let rows = if is_json { parse_json(&text) } else { parse_csv(&text) }
+            .map_err(|e| format!("invalid data file {}: {}", path.display(), e))?;
+        if rows.is_empty() {
+            return Err(format!("data file {} has no rows", path.display()));
+        }
+        Ok(Self { rows })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Row `index`, wrapping around the end of the file.
+    pub fn row(&self, index: u64) -> &Row {
+        &self.rows[(index % self.rows.len() as u64) as usize]
+    }
+}
+
+fn parse_json(text: &str) -> Result<Vec<Row>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let items = value.as_array().ok_or("expected an array of objects")?;
+    items
+        .iter()
+        .map(|item| {
+            let obj = item.as_object().ok_or("expected an array of objects")?;
+            Ok(obj
+                .iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect())
+        })
+        .collect()
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Row>, String> {
+    let mut records = csv_records(text)?.into_iter();
+    let header = records.next().ok_or("missing header row")?;
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "row {} has {} fields, header has {}",
+                    i + 2,
+                    record.len(),
+                    header.len()
+                ));
+            }
+            Ok(header.iter().cloned().zip(record).collect())
+        })
+        .collect()
+}
+
+/// Split CSV text into records, honouring double-quoted fields (with `""`
+/// escapes and embedded newlines). Blank lines are skipped.
+fn csv_records(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Value of `column` in an optional row.
+pub fn field<'a>(row: Option<&'a Row>, column: &str) -> Option<&'a str> {
+    row?.get(column).map(String::as_str)
+}
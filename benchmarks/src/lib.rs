@@ -1,5 +1,13 @@
+pub mod auth;
 pub mod cli;
 pub mod client;
+pub mod data;
 pub mod metrics;
+pub mod output;
+pub mod pushgateway;
 pub mod reporter;
+pub mod rng;
 pub mod runner;
+pub mod scenario;
+pub mod teardown;
+pub mod threshold;
@@ -1,38 +1,379 @@
+use crate::runner::Stage;
+use base64::Engine;
+use hdrhistogram::serialization::{Serializer, V2DeflateSerializer};
 use hdrhistogram::Histogram;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
 
 pub struct Metrics {
     pub total_requests: AtomicU64,
     pub total_errors: AtomicU64,
     pub total_bytes: AtomicU64,
+    /// Request body bytes of successful uploads
+    pub total_bytes_sent: AtomicU64,
+    /// Extra attempts made by --retries
+    pub total_retries: AtomicU64,
     latency_hist: Mutex<Histogram<u64>>,
+    aborted: AtomicBool,
+    interrupted: AtomicBool,
+    abort_reason: Mutex<Option<String>>,
+    stages: Vec<StageMetrics>,
+    current_stage: AtomicUsize,
+    recording: AtomicBool,
+    status_codes: Mutex<BTreeMap<u16, u64>>,
+    error_kinds: Mutex<BTreeMap<ErrorKind, u64>>,
+    ttfb_hist: Mutex<Histogram<u64>>,
+    /// Latency since the last progress snapshot
+    window_hist: Mutex<Histogram<u64>>,
+    dns_hist: Mutex<Histogram<u64>>,
+    connect_hist: Mutex<Histogram<u64>>,
+}
+
+/// Why a request failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorKind {
+    /// DNS, TCP or TLS failure before a request was sent
+    Connect,
+    Timeout,
+    /// 4xx response
+    Client,
+    /// 5xx response
+    Server,
+    /// Response (or stream) body could not be read
+    Body,
+    /// Response arrived but failed a scenario check
+    Check,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn of(err: &reqwest::Error) -> Self {
+        if err.is_timeout() {
+            ErrorKind::Timeout
+        } else if err.is_connect() {
+            ErrorKind::Connect
+        } else if err.is_body() || err.is_decode() {
+            ErrorKind::Body
+        } else {
+            ErrorKind::Other
+        }
+    }
+
+    /// Kind for an error status; `None` below 400.
+    pub fn of_status(status: u16) -> Option<Self> {
+        match status {
+            400..=499 => Some(ErrorKind::Client),
+            500.. => Some(ErrorKind::Server),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorKind::Connect => "connect",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Client => "4xx",
+            ErrorKind::Server => "5xx",
+            ErrorKind::Body => "body",
+            ErrorKind::Check => "check",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+/// Counters for one load stage; requests are attributed to the stage active
+/// when they complete.
+struct StageMetrics {
+    stage: Stage,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_hist: Mutex<Histogram<u64>>,
+    started: Mutex<Option<Instant>>,
+}
+
+fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 60_000_000, 3).expect("failed to create histogram")
+}
+
+/// Connection setup timings, reported by the client's resolver and connector
+/// (see `client::build_client`) and recorded into the attached run's
+/// `Metrics`. Process-wide because the client outlives runs.
+pub struct ConnectionTimings {
+    current: Mutex<Weak<Metrics>>,
+}
+
+pub fn connection_timings() -> &'static ConnectionTimings {
+    static TIMINGS: OnceLock<ConnectionTimings> = OnceLock::new();
+    TIMINGS.get_or_init(|| ConnectionTimings { current: Mutex::new(Weak::new()) })
+}
+
+impl ConnectionTimings {
+    /// Record subsequent timings into `metrics` (until another run attaches).
+    pub fn attach(&self, metrics: &Arc<Metrics>) {
+        if let Ok(mut current) = self.current.lock() {
+            *current = Arc::downgrade(metrics);
+        }
+    }
+
+    fn metrics(&self) -> Option<Arc<Metrics>> {
+        self.current.lock().ok().and_then(|m| m.upgrade())
+    }
+
+    pub fn record_dns(&self, elapsed: Duration) {
+        if let Some(metrics) = self.metrics() {
+            metrics.record_phase(&metrics.dns_hist, elapsed);
+        }
+    }
+
+    pub fn record_connect(&self, elapsed: Duration) {
+        if let Some(metrics) = self.metrics() {
+            metrics.record_phase(&metrics.connect_hist, elapsed);
+        }
+    }
+}
+
+/// p50/p95 in milliseconds and sample count of a phase histogram.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseSummary {
+    pub count: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+impl PhaseSummary {
+    fn from_histogram(hist: &Histogram<u64>) -> Self {
+        Self {
+            count: hist.len(),
+            p50_ms: hist.value_at_quantile(0.50) as f64 / 1000.0,
+            p95_ms: hist.value_at_quantile(0.95) as f64 / 1000.0,
+        }
+    }
+
+    fn from_lock(hist: &Mutex<Histogram<u64>>) -> Self {
+        hist.lock().map(|h| Self::from_histogram(&h)).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Metrics {
     pub fn new() -> Self {
+        Self::with_stages(&[])
+    }
+
+    /// Metrics that also break results down per load stage (see `enter_stage`).
+    pub fn with_stages(stages: &[Stage]) -> Self {
         Self {
             total_requests: AtomicU64::new(0),
             total_errors: AtomicU64::new(0),
             total_bytes: AtomicU64::new(0),
-            latency_hist: Mutex::new(
-                Histogram::new_with_bounds(1, 60_000_000, 3)
-                    .expect("failed to create histogram"),
-            ),
+            total_bytes_sent: AtomicU64::new(0),
+            total_retries: AtomicU64::new(0),
+            latency_hist: Mutex::new(new_histogram()),
+            aborted: AtomicBool::new(false),
+            interrupted: AtomicBool::new(false),
+            abort_reason: Mutex::new(None),
+            stages: stages
+                .iter()
+                .map(|&stage| StageMetrics {
+                    stage,
+                    requests: AtomicU64::new(0),
+                    errors: AtomicU64::new(0),
+                    latency_hist: Mutex::new(new_histogram()),
+                    started: Mutex::new(None),
+                })
+                .collect(),
+            current_stage: AtomicUsize::new(0),
+            recording: AtomicBool::new(true),
+            status_codes: Mutex::new(BTreeMap::new()),
+            error_kinds: Mutex::new(BTreeMap::new()),
+            ttfb_hist: Mutex::new(new_histogram()),
+            window_hist: Mutex::new(new_histogram()),
+            dns_hist: Mutex::new(new_histogram()),
+            connect_hist: Mutex::new(new_histogram()),
+        }
+    }
+
+    /// Attribute subsequent requests to stage `index`.
+    pub fn enter_stage(&self, index: usize) {
+        if let Some(stage) = self.stages.get(index) {
+            if let Ok(mut started) = stage.started.lock() {
+                started.get_or_insert_with(Instant::now);
+            }
+            self.current_stage.store(index, Ordering::Relaxed);
+        }
+    }
+
+    /// While off (e.g. during warmup), recorded requests are discarded.
+    pub fn set_recording(&self, on: bool) {
+        self.recording.store(on, Ordering::Relaxed);
+    }
+
+    /// Connection setup phase; discarded while not recording, like requests.
+    fn record_phase(&self, hist: &Mutex<Histogram<u64>>, elapsed: Duration) {
+        if !self.recording.load(Ordering::Relaxed) {
+            return;
         }
+        if let Ok(mut hist) = hist.lock() {
+            let _ = hist.record(elapsed.as_micros() as u64);
+        }
+    }
+
+    fn stage(&self) -> Option<&StageMetrics> {
+        self.stages.get(self.current_stage.load(Ordering::Relaxed))
+    }
+
+    /// Stop the run early. VUs check `is_aborted` between iterations.
+    pub fn abort(&self, reason: String) {
+        if let Ok(mut r) = self.abort_reason.lock() {
+            r.get_or_insert(reason);
+        }
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    /// Stop the run early because the user asked to (Ctrl-C or SIGTERM);
+    /// unlike `abort`, the partial results are reported as interrupted rather than failed.
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
     }
 
     pub fn record_success(&self, latency_us: u64, bytes: u64) {
+        self.record_ok(Some(latency_us), bytes);
+    }
+
+    /// Count a success whose latency is unknown, keeping it out of the histograms.
+    pub fn record_untimed(&self, bytes: u64) {
+        self.record_ok(None, bytes);
+    }
+
+    fn record_ok(&self, latency_us: Option<u64>, bytes: u64) {
+        if !self.recording.load(Ordering::Relaxed) {
+            return;
+        }
         self.total_requests.fetch_add(1, Ordering::Relaxed);
         self.total_bytes.fetch_add(bytes, Ordering::Relaxed);
+        let stage = self.stage();
+        if let Some(stage) = stage {
+            stage.requests.fetch_add(1, Ordering::Relaxed);
+        }
+        let Some(latency_us) = latency_us else {
+            return;
+        };
         if let Ok(mut hist) = self.latency_hist.lock() {
             let _ = hist.record(latency_us);
         }
+        if let Ok(mut hist) = self.window_hist.lock() {
+            let _ = hist.record(latency_us);
+        }
+        if let Some(Ok(mut hist)) = stage.map(|s| s.latency_hist.lock()) {
+            let _ = hist.record(latency_us);
+        }
+    }
+
+    /// Record an HTTP response: 4xx/5xx count as errors, anything else as success.
+    /// `ttfb_us` is the time until response headers arrived.
+    pub fn record_response(&self, status: u16, ttfb_us: u64, latency_us: u64, bytes: u64) {
+        self.record_status(status);
+        self.record_ttfb(ttfb_us);
+        match ErrorKind::of_status(status) {
+            Some(kind) => self.record_error(kind),
+            None => self.record_success(latency_us, bytes),
+        }
+    }
+
+    /// `record_response` for a response whose body was read into `body`; a
+    /// failed read counts as a body error (or timeout) whatever the status.
+    pub fn record_body<B: AsRef<[u8]>>(
+        &self,
+        status: u16,
+        ttfb_us: u64,
+        latency_us: u64,
+        body: &reqwest::Result<B>,
+    ) {
+        match body {
+            Ok(body) => self.record_response(status, ttfb_us, latency_us, body.as_ref().len() as u64),
+            Err(e) => {
+                self.record_status(status);
+                self.record_ttfb(ttfb_us);
+                self.record_error(match ErrorKind::of(e) {
+                    ErrorKind::Other => ErrorKind::Body,
+                    kind => kind,
+                });
+            }
+        }
+    }
+
+    /// Count a response status code without classifying the request.
+    pub fn record_status(&self, status: u16) {
+        if !self.recording.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Ok(mut codes) = self.status_codes.lock() {
+            *codes.entry(status).or_insert(0) += 1;
+        }
+    }
+
+    /// Time to first byte (response headers) of one request.
+    pub fn record_ttfb(&self, ttfb_us: u64) {
+        if !self.recording.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Ok(mut hist) = self.ttfb_hist.lock() {
+            let _ = hist.record(ttfb_us);
+        }
+    }
+
+    /// p95 latency (ms) of successful requests since the previous call, then
+    /// start a new window. Used for interim progress snapshots.
+    pub fn take_window_p95_ms(&self) -> f64 {
+        match self.window_hist.lock() {
+            Ok(mut hist) => {
+                let p95 = hist.value_at_quantile(0.95) as f64 / 1000.0;
+                hist.reset();
+                p95
+            }
+            Err(_) => 0.0,
+        }
     }
 
-    pub fn record_error(&self) {
+    /// Count request body bytes delivered by a successful upload.
+    pub fn record_sent(&self, bytes: u64) {
+        if self.recording.load(Ordering::Relaxed) {
+            self.total_bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Count one retried attempt; the request itself is recorded once, by outcome.
+    pub fn record_retry(&self) {
+        if self.recording.load(Ordering::Relaxed) {
+            self.total_retries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_error(&self, kind: ErrorKind) {
+        if !self.recording.load(Ordering::Relaxed) {
+            return;
+        }
         self.total_requests.fetch_add(1, Ordering::Relaxed);
         self.total_errors.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut kinds) = self.error_kinds.lock() {
+            *kinds.entry(kind).or_insert(0) += 1;
+        }
+        if let Some(stage) = self.stage() {
+            stage.requests.fetch_add(1, Ordering::Relaxed);
+            stage.errors.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     pub fn summary(&self, duration_secs: f64) -> MetricsSummary {
@@ -45,48 +386,270 @@ impl Metrics {
             0.0
         };
 
-        let (p50_ms, p99_ms) = if let Ok(hist) = self.latency_hist.lock() {
-            (
-                hist.value_at_quantile(0.50) as f64 / 1000.0,
-                hist.value_at_quantile(0.99) as f64 / 1000.0,
-            )
-        } else {
-            (0.0, 0.0)
-        };
+        let latency = self.latency_hist.lock().map(|h| h.clone()).unwrap_or_else(|_| new_histogram());
+        let p50_ms = latency.value_at_quantile(0.50) as f64 / 1000.0;
+        let p95_ms = latency.value_at_quantile(0.95) as f64 / 1000.0;
+        let p99_ms = latency.value_at_quantile(0.99) as f64 / 1000.0;
+
+        let abort_reason = self.abort_reason.lock().ok().and_then(|r| r.clone());
+
+        // A stage runs until the next one starts, or until now for the last one reached
+        let starts: Vec<Option<Instant>> = self
+            .stages
+            .iter()
+            .map(|s| s.started.lock().ok().and_then(|t| *t))
+            .collect();
+        let stages = self
+            .stages
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !s.stage.duration.is_zero())
+            .filter_map(|(i, s)| {
+                let started = starts[i]?;
+                let ended = starts[i + 1..].iter().flatten().next().copied().unwrap_or_else(Instant::now);
+                let secs = (ended - started).as_secs_f64();
+                let requests = s.requests.load(Ordering::Relaxed);
+                let (p50_ms, p95_ms, p99_ms) = match s.latency_hist.lock() {
+                    Ok(hist) => (
+                        hist.value_at_quantile(0.50) as f64 / 1000.0,
+                        hist.value_at_quantile(0.95) as f64 / 1000.0,
+                        hist.value_at_quantile(0.99) as f64 / 1000.0,
+                    ),
+                    Err(_) => (0.0, 0.0, 0.0),
+                };
+                Some(StageSummary {
+                    index: i,
+                    duration_secs: secs,
+                    target: s.stage.target,
+                    total: requests,
+                    errors: s.errors.load(Ordering::Relaxed),
+                    throughput: if secs > 0.0 { requests as f64 / secs } else { 0.0 },
+                    p50_ms,
+                    p95_ms,
+                    p99_ms,
+                })
+            })
+            .collect();
 
         MetricsSummary {
             throughput,
             p50_ms,
+            p95_ms,
             p99_ms,
             total,
             errors,
             total_bytes: bytes,
+            total_bytes_sent: self.total_bytes_sent.load(Ordering::Relaxed),
+            retries: self.total_retries.load(Ordering::Relaxed),
+            abort_reason,
+            interrupted: self.interrupted.load(Ordering::Relaxed),
+            stages,
+            latency,
+            status_codes: self.status_codes.lock().map(|c| c.clone()).unwrap_or_default(),
+            error_kinds: self.error_kinds.lock().map(|k| k.clone()).unwrap_or_default(),
+            dns: PhaseSummary::from_lock(&self.dns_hist),
+            connect: PhaseSummary::from_lock(&self.connect_hist),
+            ttfb: PhaseSummary::from_lock(&self.ttfb_hist),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct StageSummary {
+    pub index: usize,
+    /// Time actually spent in the stage (shorter than configured if aborted)
+    pub duration_secs: f64,
+    pub target: u64,
+    pub total: u64,
+    pub errors: u64,
+    pub throughput: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct MetricsSummary {
     pub throughput: f64,
     pub p50_ms: f64,
+    pub p95_ms: f64,
     pub p99_ms: f64,
     pub total: u64,
     pub errors: u64,
     pub total_bytes: u64,
+    pub total_bytes_sent: u64,
+    /// Extra attempts made by --retries (not included in `total`)
+    pub retries: u64,
+    /// Set when the error-rate safety valve stopped the run early
+    pub abort_reason: Option<String>,
+    /// Set when the run was stopped by Ctrl-C or SIGTERM; results cover the elapsed time only
+    pub interrupted: bool,
+    /// Per-stage breakdown (empty without stages; zero-length stages omitted)
+    pub stages: Vec<StageSummary>,
+    /// Full latency distribution in microseconds
+    pub latency: Histogram<u64>,
+    /// Response count per HTTP status code
+    pub status_codes: BTreeMap<u16, u64>,
+    /// Failed request count per cause
+    pub error_kinds: BTreeMap<ErrorKind, u64>,
+    /// Name resolution, per lookup
+    pub dns: PhaseSummary,
+    /// New connection setup (DNS + TCP + TLS), per connection
+    pub connect: PhaseSummary,
+    /// Time to response headers, per request
+    pub ttfb: PhaseSummary,
 }
 
 impl MetricsSummary {
+    /// Latency at `percentile` (0-100] in milliseconds.
+    pub fn percentile_ms(&self, percentile: f64) -> f64 {
+        self.latency.value_at_percentile(percentile) as f64 / 1000.0
+    }
+
+    /// TestRun status: "failed" when aborted by the safety valve, "interrupted"
+    /// when stopped by a signal, else "completed".
+    pub fn status(&self) -> &'static str {
+        if self.abort_reason.is_some() {
+            "failed"
+        } else if self.interrupted {
+            "interrupted"
+        } else {
+            "completed"
+        }
+    }
+
+    /// Fraction of requests that failed.
+    pub fn error_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.total as f64
+        }
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        self.latency.max() as f64 / 1000.0
+    }
+
+    /// Latency distribution in log-scale buckets (upper bound in ms, count),
+    /// doubling from 0.1ms up to the slowest request. Compact enough to chart.
+    pub fn latency_buckets(&self) -> Vec<(f64, u64)> {
+        if self.latency.is_empty() {
+            return Vec::new();
+        }
+        self.latency
+            .iter_log(100, 2.0)
+            .map(|v| ((v.value_iterated_to() + 1) as f64 / 1000.0, v.count_since_last_iteration()))
+            .collect()
+    }
+
+    /// Latency histogram as base64 HdrHistogram V2 (deflate), so runs can be
+    /// merged later without losing percentile accuracy.
+    pub fn encoded_histogram(&self) -> Option<String> {
+        let mut buf = Vec::new();
+        V2DeflateSerializer::new().serialize(&self.latency, &mut buf).ok()?;
+        Some(base64::engine::general_purpose::STANDARD.encode(buf))
+    }
+
+    /// e.g. "dns p50=0.10ms, connect p50=3.20ms (50 conns), ttfb p50=1.10ms p95=4.00ms"
+    pub fn format_phases(&self) -> String {
+        format!(
+            "dns p50={:.2}ms, connect p50={:.2}ms p95={:.2}ms ({} conns), ttfb p50={:.2}ms p95={:.2}ms",
+            self.dns.p50_ms,
+            self.connect.p50_ms,
+            self.connect.p95_ms,
+            self.connect.count,
+            self.ttfb.p50_ms,
+            self.ttfb.p95_ms,
+        )
+    }
+
+    /// Response counts per status class ("2xx", "4xx", ...).
+    pub fn status_classes(&self) -> BTreeMap<String, u64> {
+        let mut classes = BTreeMap::new();
+        for (code, count) in &self.status_codes {
+            *classes.entry(format!("{}xx", code / 100)).or_insert(0) += count;
+        }
+        classes
+    }
+
+    /// The `n` most frequent 4xx/5xx codes, most frequent first.
+    pub fn top_error_codes(&self, n: usize) -> Vec<(u16, u64)> {
+        let mut codes: Vec<(u16, u64)> = self
+            .status_codes
+            .iter()
+            .filter(|(code, _)| **code >= 400)
+            .map(|(&code, &count)| (code, count))
+            .collect();
+        codes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        codes.truncate(n);
+        codes
+    }
+
+    /// Errors that never produced a response (connect failures, timeouts, failed checks).
+    pub fn transport_errors(&self) -> u64 {
+        let status_errors: u64 = self.top_error_codes(usize::MAX).iter().map(|(_, c)| c).sum();
+        self.errors.saturating_sub(status_errors)
+    }
+
+    /// Failed requests by cause, most frequent first, e.g. "5xx=120 timeout=4".
+    pub fn format_errors(&self) -> String {
+        let mut kinds: Vec<(&ErrorKind, &u64)> = self.error_kinds.iter().collect();
+        kinds.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        kinds
+            .iter()
+            .map(|(kind, count)| format!("{}={}", kind.label(), count))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// e.g. "p50=1.20ms p90=2.31ms p99.9=8.02ms max=12.40ms"
+    pub fn format_percentiles(&self, percentiles: &[f64]) -> String {
+        let mut parts: Vec<String> = percentiles
+            .iter()
+            .map(|&p| format!("p{}={:.2}ms", p, self.percentile_ms(p)))
+            .collect();
+        parts.push(format!("max={:.2}ms", self.max_ms()));
+        parts.join(" ")
+    }
+
     pub fn format_summary(&self, duration_secs: f64) -> String {
         format!(
-            "{} requests in {:.0}s ({:.1} req/s), p50={:.2}ms p99={:.2}ms, {} errors",
+            "{} requests in {:.0}s ({:.1} req/s), p50={:.2}ms p95={:.2}ms p99={:.2}ms, {} errors",
             format_count(self.total),
             duration_secs,
             self.throughput,
             self.p50_ms,
+            self.p95_ms,
             self.p99_ms,
             self.errors,
         )
     }
+
+    /// One line per stage, or empty for a single-stage run.
+    pub fn format_stages(&self) -> String {
+        if self.stages.len() < 2 {
+            return String::new();
+        }
+        self.stages
+            .iter()
+            .map(|s| {
+                format!(
+                    "  stage {} ({:.0}s -> {} VUs): {} requests ({:.1} req/s), p50={:.2}ms p95={:.2}ms p99={:.2}ms, {} errors",
+                    s.index + 1,
+                    s.duration_secs,
+                    s.target,
+                    format_count(s.total),
+                    s.throughput,
+                    s.p50_ms,
+                    s.p95_ms,
+                    s.p99_ms,
+                    s.errors,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 fn format_count(n: u64) -> String {
@@ -0,0 +1,118 @@
+use crate::cli::BenchArgs;
+use crate::metrics::MetricsSummary;
+use clap::ValueEnum;
+use std::fmt::Write as _;
+
+/// Machine-readable summary format for --output.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Run metadata plus the same results object stored on the TestRun
+    Json,
+    /// One row for the whole run, then one per stage
+    Csv,
+    /// A test suite with one case for the run and one per --threshold
+    Junit,
+}
+
+/// Write the summary in the --output format to --output-file, or stdout.
+pub fn write_output(args: &BenchArgs, duration_secs: f64, summary: &MetricsSummary, results: &serde_json::Value) {
+    let Some(format) = args.output else {
+        return;
+    };
+    let text = match format {
+        OutputFormat::Json => json(args, duration_secs, summary, results),
+        OutputFormat::Csv => csv(summary, duration_secs),
+        OutputFormat::Junit => junit(args, duration_secs, summary),
+    };
+    match &args.output_file {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, text) {
+                eprintln!("Warning: cannot write {}: {}", path.display(), e);
+            }
+        }
+        None => print!("{}", text),
+    }
+}
+
+fn json(args: &BenchArgs, duration_secs: f64, summary: &MetricsSummary, results: &serde_json::Value) -> String {
+    let doc = serde_json::json!({
+        "testName": args.test,
+        "runId": args.run_id,
+        "env": args.environment(),
+        "durationSecs": (duration_secs * 10.0).round() / 10.0,
+        "vus": args.peak_vus(),
+        "status": summary.status(),
+        "abortReason": summary.abort_reason,
+        "interrupted": summary.interrupted,
+        "results": results,
+    });
+    serde_json::to_string_pretty(&doc).unwrap_or_default() + "\n"
+}
+
+fn csv(summary: &MetricsSummary, duration_secs: f64) -> String {
+    let mut out = String::from("scope,target_vus,duration_secs,total,errors,throughput,p50_ms,p95_ms,p99_ms\n");
+    let _ = writeln!(
+        out,
+        "total,,{:.1},{},{},{:.1},{:.2},{:.2},{:.2}",
+        duration_secs, summary.total, summary.errors, summary.throughput, summary.p50_ms, summary.p95_ms, summary.p99_ms
+    );
+    for s in &summary.stages {
+        let _ = writeln!(
+            out,
+            "stage{},{},{:.1},{},{},{:.1},{:.2},{:.2},{:.2}",
+            s.index + 1,
+            s.target,
+            s.duration_secs,
+            s.total,
+            s.errors,
+            s.throughput,
+            s.p50_ms,
+            s.p95_ms,
+            s.p99_ms
+        );
+    }
+    out
+}
+
+fn junit(args: &BenchArgs, duration_secs: f64, summary: &MetricsSummary) -> String {
+    let mut cases = String::new();
+    let mut failures = 0;
+
+    let _ = write!(cases, "    <testcase classname=\"{}\" name=\"run\" time=\"{:.3}\">", xml_escape(&args.test), duration_secs);
+    if let Some(reason) = &summary.abort_reason {
+        failures += 1;
+        let _ = write!(cases, "<failure message=\"aborted early, {}\"/>", xml_escape(reason));
+    }
+    let _ = writeln!(cases, "<system-out>{}</system-out></testcase>", xml_escape(&summary.format_summary(duration_secs)));
+
+    for threshold in &args.thresholds {
+        let name = xml_escape(&threshold.to_string());
+        let _ = write!(cases, "    <testcase classname=\"{}\" name=\"{}\" time=\"0\">", xml_escape(&args.test), name);
+        if !threshold.passes(summary) {
+            failures += 1;
+            let _ = write!(
+                cases,
+                "<failure message=\"{} violated (actual {})\"/>",
+                name,
+                xml_escape(&threshold.format_actual(summary))
+            );
+        }
+        cases.push_str("</testcase>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n{}</testsuite>\n",
+        xml_escape(&args.test),
+        args.thresholds.len() + 1,
+        failures,
+        duration_secs,
+        cases
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
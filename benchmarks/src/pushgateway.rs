@@ -0,0 +1,91 @@
+use crate::cli::BenchArgs;
+use crate::metrics::MetricsSummary;
+use base64::Engine;
+use reqwest::Client;
+use std::fmt::Write as _;
+
+/// Job name runs are grouped under on the Pushgateway.
+const JOB: &str = "yeti_benchmarks";
+
+/// Push run metrics to --pushgateway, grouped by test, host and run id, so
+/// results show up next to production metrics in Prometheus/Grafana.
+pub async fn push(client: &Client, args: &BenchArgs, duration_secs: f64, summary: &MetricsSummary) {
+    let Some(gateway) = &args.pushgateway else {
+        return;
+    };
+    let mut url = format!("{}/metrics/job/{}", gateway.trim_end_matches('/'), JOB);
+    let env = args.environment();
+    let mut labels = vec![("test", args.test.as_str()), ("instance", env.as_str())];
+    if let Some(run_id) = &args.run_id {
+        labels.push(("run_id", run_id.as_str()));
+    }
+    for (name, value) in labels {
+        url.push_str(&grouping_label(name, value));
+    }
+
+    match client.put(&url).body(exposition(args, duration_secs, summary)).send().await {
+        Ok(resp) if !resp.status().is_success() => {
+            eprintln!("Warning: PUT {} returned {}", url, resp.status());
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: Failed to push metrics to {}: {}", url, e),
+    }
+}
+
+/// Prometheus text exposition of the run summary.
+fn exposition(args: &BenchArgs, duration_secs: f64, summary: &MetricsSummary) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+    };
+
+    gauge("yeti_bench_throughput", "Requests per second", &[(String::new(), summary.throughput)]);
+    gauge("yeti_bench_requests", "Requests sent", &[(String::new(), summary.total as f64)]);
+    gauge("yeti_bench_errors", "Failed requests", &[(String::new(), summary.errors as f64)]);
+    gauge("yeti_bench_retries", "Retried attempts", &[(String::new(), summary.retries as f64)]);
+    let kinds: Vec<(String, f64)> = summary
+        .error_kinds
+        .iter()
+        .map(|(kind, &count)| (format!("{{kind=\"{}\"}}", kind.label()), count as f64))
+        .collect();
+    gauge("yeti_bench_errors_by_kind", "Failed requests by cause", &kinds);
+    gauge("yeti_bench_error_rate", "Fraction of requests that failed", &[(String::new(), summary.error_rate())]);
+    gauge("yeti_bench_duration_seconds", "Measured run duration", &[(String::new(), duration_secs)]);
+    gauge("yeti_bench_vus", "Peak virtual users", &[(String::new(), args.peak_vus() as f64)]);
+    let quantiles: Vec<(String, f64)> = args
+        .percentiles()
+        .iter()
+        .map(|&p| (format!("{{quantile=\"{}\"}}", quantile(p)), summary.percentile_ms(p) / 1000.0))
+        .chain(std::iter::once(("{quantile=\"1\"}".to_string(), summary.max_ms() / 1000.0)))
+        .collect();
+    gauge("yeti_bench_latency_seconds", "Request latency by quantile", &quantiles);
+    gauge(
+        "yeti_bench_aborted",
+        "1 when the error-rate safety valve stopped the run",
+        &[(String::new(), if summary.abort_reason.is_some() { 1.0 } else { 0.0 })],
+    );
+    out
+}
+
+/// Percentile as a quantile label value: 99.9 -> "0.999".
+fn quantile(percentile: f64) -> String {
+    let q = format!("{:.6}", percentile / 100.0);
+    q.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// `/name/value` path segment; values that are not URL-safe use the
+/// Pushgateway's base64 form.
+fn grouping_label(name: &str, value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+    if plain {
+        format!("/{}/{}", name, value)
+    } else {
+        let encoded = base64::engine::general_purpose::URL_SAFE.encode(value);
+        format!("/{}@base64/{}", name, if encoded.is_empty() { "=".to_string() } else { encoded })
+    }
+}
@@ -1,56 +1,254 @@
+use crate::auth::{self, Authorize};
+use crate::cli::BenchArgs;
 use crate::metrics::MetricsSummary;
+use crate::{output, pushgateway, runner};
 use reqwest::Client;
+use std::time::Duration;
 
-/// POST test results to /admin/TestRun and print summary to stdout.
+/// Process exit status when the error-rate safety valve aborted the run.
+pub const EXIT_ABORTED: i32 = 2;
+
+/// Process exit status when a --threshold was violated.
+pub const EXIT_THRESHOLD: i32 = 3;
+
+/// Process exit status when the run was stopped by Ctrl-C or SIGTERM.
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Attempts at POSTing the TestRun before falling back to a local file.
+const POST_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry of the TestRun POST, doubled for each further attempt.
+const POST_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Allowance per request in the report budget when --timeout is 0 (disabled).
+const UNBOUNDED_REQUEST_BUDGET: Duration = Duration::from_secs(30);
+
+/// Longest a single request may take: --timeout, or UNBOUNDED_REQUEST_BUDGET
+/// when it is disabled.
+pub fn request_budget(args: &BenchArgs) -> Duration {
+    if args.timeout.is_zero() { UNBOUNDED_REQUEST_BUDGET } else { args.timeout }
+}
+
+/// Worst-case time `report_results` takes: a login, the Pushgateway push and
+/// every TestRun POST attempt running into the request timeout, plus the
+/// backoff between attempts.
+pub fn report_budget(args: &BenchArgs) -> Duration {
+    let request = request_budget(args);
+    let backoff: Duration = (0..POST_ATTEMPTS - 1).map(|i| POST_BACKOFF * 2u32.pow(i)).sum();
+    request * (POST_ATTEMPTS + 2) + backoff
+}
+
+/// Exit status for a finished run. Prints the outcome of each --threshold.
+pub fn exit_code(args: &BenchArgs, summary: &MetricsSummary) -> i32 {
+    let mut violated = false;
+    if !args.thresholds.is_empty() {
+        println!("Thresholds:");
+        for threshold in &args.thresholds {
+            let passed = threshold.passes(summary);
+            violated |= !passed;
+            println!(
+                "  {:<4} {} (actual {})",
+                if passed { "ok" } else { "FAIL" },
+                threshold,
+                threshold.format_actual(summary)
+            );
+        }
+    }
+
+    if summary.abort_reason.is_some() {
+        EXIT_ABORTED
+    } else if summary.interrupted {
+        EXIT_INTERRUPTED
+    } else if violated {
+        EXIT_THRESHOLD
+    } else {
+        0
+    }
+}
+
+/// POST test results to /admin/TestRun and print summary to stdout, plus the
+/// --output format and a Pushgateway push when requested. Announces the
+/// "reporting" phase to the admin runner first.
 pub async fn report_results(
     client: &Client,
-    base_url: &str,
-    auth_user: &str,
-    auth_pass: &str,
-    test_name: &str,
+    args: &BenchArgs,
     duration_secs: f64,
     summary: &MetricsSummary,
 ) {
+    let auth = auth::credentials(args, client).await;
+    runner::report_phase(client, args, &auth, "reporting", report_budget(args)).await;
+
+    let test_name = args.test.as_str();
     let summary_text = summary.format_summary(duration_secs);
     println!("\n=== {} ===", test_name);
     println!("{}", summary_text);
+    println!("Latency: {}", summary.format_percentiles(&args.percentiles()));
+    if !summary.status_codes.is_empty() {
+        let classes: Vec<String> = summary
+            .status_classes()
+            .iter()
+            .map(|(class, count)| format!("{}={}", class, count))
+            .collect();
+        println!("Status: {}, other errors={}", classes.join(" "), summary.transport_errors());
+    }
+    if summary.errors > 0 {
+        println!("Errors: {}", summary.format_errors());
+    }
+    if summary.retries > 0 {
+        println!("Retries: {}", summary.retries);
+    }
+    if summary.ttfb.count > 0 {
+        println!("Phases: {}", summary.format_phases());
+    }
+    let stages_text = summary.format_stages();
+    if !stages_text.is_empty() {
+        println!("{}", stages_text);
+    }
     if summary.total_bytes > 0 {
         let mb = summary.total_bytes as f64 / (1024.0 * 1024.0);
         println!("Total bytes: {:.1} MB ({:.1} MB/s)", mb, mb / duration_secs);
     }
+    if summary.total_bytes_sent > 0 {
+        let mb = summary.total_bytes_sent as f64 / (1024.0 * 1024.0);
+        println!("Total sent: {:.1} MB ({:.1} MB/s)", mb, mb / duration_secs);
+    }
+    if let Some(reason) = &summary.abort_reason {
+        println!("FAILED: aborted early, {}", reason);
+    }
+    if summary.interrupted {
+        println!("INTERRUPTED: partial results for the first {:.0}s", duration_secs);
+    }
 
-    let results_json = serde_json::json!({
+    let mut results_json = serde_json::json!({
         "throughput": (summary.throughput * 10.0).round() / 10.0,
         "p50": (summary.p50_ms * 100.0).round() / 100.0,
+        "p95": (summary.p95_ms * 100.0).round() / 100.0,
         "p99": (summary.p99_ms * 100.0).round() / 100.0,
+        "max": (summary.max_ms() * 100.0).round() / 100.0,
         "total": summary.total,
         "errors": summary.errors,
+        "retries": summary.retries,
+        "mbPerSec": (mb_per_sec(summary.total_bytes, duration_secs) * 100.0).round() / 100.0,
+        "sentMbPerSec": (mb_per_sec(summary.total_bytes_sent, duration_secs) * 100.0).round() / 100.0,
+        "protocol": args.protocol(),
+        "percentiles": args
+            .percentiles()
+            .iter()
+            .map(|&p| (format!("p{}", p), serde_json::json!((summary.percentile_ms(p) * 100.0).round() / 100.0)))
+            .collect::<serde_json::Map<_, _>>(),
+        "histogram": summary.encoded_histogram(),
+        "latencyBuckets": summary
+            .latency_buckets()
+            .iter()
+            .map(|(le, count)| serde_json::json!({"le": le, "count": count}))
+            .collect::<Vec<_>>(),
+        "statusClasses": summary.status_classes(),
+        "topErrorCodes": summary
+            .top_error_codes(5)
+            .iter()
+            .map(|(code, count)| serde_json::json!({"code": code, "count": count}))
+            .collect::<Vec<_>>(),
+        "transportErrors": summary.transport_errors(),
+        "errorKinds": summary
+            .error_kinds
+            .iter()
+            .map(|(kind, count)| (kind.label().to_string(), serde_json::json!(count)))
+            .collect::<serde_json::Map<_, _>>(),
+        "phases": {
+            "dnsP50": (summary.dns.p50_ms * 100.0).round() / 100.0,
+            "connectP50": (summary.connect.p50_ms * 100.0).round() / 100.0,
+            "connectP95": (summary.connect.p95_ms * 100.0).round() / 100.0,
+            "connections": summary.connect.count,
+            "ttfbP50": (summary.ttfb.p50_ms * 100.0).round() / 100.0,
+            "ttfbP95": (summary.ttfb.p95_ms * 100.0).round() / 100.0,
+        },
     });
+    if summary.stages.len() > 1 {
+        results_json["stages"] = summary
+            .stages
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "durationSecs": (s.duration_secs * 10.0).round() / 10.0,
+                    "target": s.target,
+                    "throughput": (s.throughput * 10.0).round() / 10.0,
+                    "p50": (s.p50_ms * 100.0).round() / 100.0,
+                    "p95": (s.p95_ms * 100.0).round() / 100.0,
+                    "p99": (s.p99_ms * 100.0).round() / 100.0,
+                    "total": s.total,
+                    "errors": s.errors,
+                })
+            })
+            .collect();
+    }
 
-    let payload = serde_json::json!({
+    output::write_output(args, duration_secs, summary, &results_json);
+    pushgateway::push(client, args, duration_secs, summary).await;
+
+    let mut payload = serde_json::json!({
         "testName": test_name,
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "durationSecs": (duration_secs * 10.0).round() / 10.0,
+        "vus": args.peak_vus(),
         "results": results_json.to_string(),
         "summary": summary_text,
         "extrapolatedThroughput": format!("{:.1}", summary.throughput),
+        "status": summary.status(),
+        "abortReason": summary.abort_reason,
+        "interrupted": summary.interrupted,
+        "env": args.environment(),
     });
+    // Partial runs are kept for reference but never count as bests
+    if summary.interrupted {
+        payload["excluded"] = serde_json::json!(true);
+    }
+    if let Some(run_id) = &args.run_id {
+        payload["id"] = serde_json::json!(run_id);
+    }
 
-    let url = format!("{}/admin/TestRun", base_url);
-    match client
-        .post(&url)
-        .basic_auth(auth_user, Some(auth_pass))
-        .json(&payload)
-        .send()
-        .await
-    {
-        Ok(resp) => {
-            if !resp.status().is_success() {
+    let url = format!("{}/admin/TestRun", args.base_url);
+    let mut backoff = POST_BACKOFF;
+    for attempt in 1..=POST_ATTEMPTS {
+        let retry = match client.post(&url).authorize(&auth).json(&payload).send().await {
+            Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) => {
                 eprintln!("Warning: POST {} returned {}", url, resp.status());
+                resp.status().is_server_error() || resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
             }
+            Err(e) => {
+                eprintln!("Warning: Failed to POST results to {}: {}", url, e);
+                true
+            }
+        };
+        if !retry || attempt == POST_ATTEMPTS {
+            break;
         }
-        Err(e) => {
-            eprintln!("Warning: Failed to POST results to {}: {}", url, e);
-        }
+        eprintln!("Retrying in {}s...", backoff.as_secs());
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+    save_fallback(args, &payload);
+}
+
+/// Keep a TestRun that could not be POSTed as a JSON file in the working
+/// directory, so it can be inspected or POSTed to /admin/TestRun later.
+fn save_fallback(args: &BenchArgs, payload: &serde_json::Value) {
+    let name = args
+        .run_id
+        .clone()
+        .unwrap_or_else(|| chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string());
+    let path = std::path::PathBuf::from(format!("testrun-{}-{}.json", args.test, name));
+    let json = serde_json::to_string_pretty(payload).unwrap_or_default() + "\n";
+    match std::fs::write(&path, json) {
+        Ok(()) => eprintln!("Results saved to {}", path.display()),
+        Err(e) => eprintln!("Warning: could not save results to {}: {}", path.display(), e),
+    }
+}
+
+fn mb_per_sec(bytes: u64, duration_secs: f64) -> f64 {
+    if duration_secs > 0.0 {
+        bytes as f64 / (1024.0 * 1024.0) / duration_secs
+    } else {
+        0.0
     }
 }
@@ -0,0 +1,48 @@
+use rand::distr::uniform::{SampleRange, SampleUniform};
+use rand::distr::{Distribution, StandardUniform};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Stream used by setup phases (pre-created records, fixtures).
+pub const SETUP_STREAM: u64 = u64::MAX;
+
+/// Stream used by background publishers (load-realtime).
+pub const PUBLISHER_STREAM: u64 = u64::MAX - 1;
+
+/// Random source for generated request data. With --seed, each stream (one
+/// per VU, plus setup and publisher streams) is derived from the seed, so two
+/// runs with the same seed issue identical request streams; otherwise it is
+/// seeded from the OS.
+pub struct SeededRng(Mutex<StdRng>);
+
+impl SeededRng {
+    pub fn new(seed: Option<u64>, stream: u64) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+            None => StdRng::from_os_rng(),
+        };
+        Self(Mutex::new(rng))
+    }
+
+    pub fn random<T>(&self) -> T
+    where
+        StandardUniform: Distribution<T>,
+    {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).random()
+    }
+
+    pub fn random_range<T, R>(&self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).random_range(range)
+    }
+
+    /// Version 4 UUID drawn from this stream.
+    pub fn uuid(&self) -> Uuid {
+        uuid::Builder::from_random_bytes(self.random()).into_uuid()
+    }
+}
@@ -1,63 +1,443 @@
-use crate::metrics::Metrics;
-use reqwest::Client;
+use crate::auth::{self, Auth, Authorize};
+use crate::cli::BenchArgs;
+use crate::data::{DataSet, Row};
+use crate::metrics::{connection_timings, Metrics};
+use crate::reporter;
+use crate::rng::SeededRng;
+use reqwest::{Client, RequestBuilder, Response};
 use std::future::Future;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::task::JoinSet;
 
+/// How often an inactive VU re-checks whether the ramp has reached it.
+const IDLE_POLL: Duration = Duration::from_millis(50);
+
 pub struct ScenarioContext {
     pub client: Client,
     pub base_url: String,
-    pub auth_user: String,
-    pub auth_pass: String,
+    pub auth: Arc<Auth>,
     pub metrics: Arc<Metrics>,
     pub vu_id: u64,
+    /// This VU's random stream (seeded from --seed)
+    pub rng: SeededRng,
+    data: Option<Arc<DataSet>>,
+    /// Next --data row for this VU; advances by the VU count so VUs take
+    /// interleaved rows
+    data_cursor: AtomicU64,
+    data_stride: u64,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl ScenarioContext {
+    /// This VU's next row from --data (wrapping), or None without a data file.
+    pub fn next_row(&self) -> Option<&Row> {
+        let data = self.data.as_ref()?;
+        Some(data.row(self.data_cursor.fetch_add(self.data_stride, Ordering::Relaxed)))
+    }
+
+    /// Send `req`, retrying up to --retries times with exponential backoff
+    /// when it fails to send or the response is a 502/503/504.
+    pub async fn send(&self, req: RequestBuilder) -> reqwest::Result<Response> {
+        let mut backoff = self.retry_backoff;
+        for _ in 0..self.retries {
+            let Some(attempt) = req.try_clone() else {
+                break;
+            };
+            match attempt.send().await {
+                Ok(resp) if !matches!(resp.status().as_u16(), 502..=504) => return Ok(resp),
+                _ => {}
+            }
+            self.metrics.record_retry();
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        req.send().await
+    }
+}
+
+/// Chainable `.send_retrying(&ctx)` on request builders, for VU requests.
+pub trait SendRetrying {
+    fn send_retrying(self, ctx: &ScenarioContext) -> impl Future<Output = reqwest::Result<Response>> + Send;
+}
+
+impl SendRetrying for RequestBuilder {
+    fn send_retrying(self, ctx: &ScenarioContext) -> impl Future<Output = reqwest::Result<Response>> + Send {
+        ctx.send(self)
+    }
+}
+
+/// One segment of the load shape: ramp linearly from the previous stage's
+/// target (0 at the start) to `target` VUs over `duration`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stage {
+    pub duration: Duration,
+    pub target: u64,
+}
+
+/// Pause each VU takes between iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ThinkTime {
+    #[default]
+    None,
+    Fixed(Duration),
+    /// Uniformly distributed between the two bounds
+    Uniform(Duration, Duration),
+    /// Exponentially distributed with the given mean
+    Exponential(Duration),
+}
+
+impl ThinkTime {
+    pub fn sample(&self, rng: &SeededRng) -> Duration {
+        match *self {
+            ThinkTime::None => Duration::ZERO,
+            ThinkTime::Fixed(d) => d,
+            ThinkTime::Uniform(min, max) => {
+                min + (max - min).mul_f64(rng.random::<f64>())
+            }
+            ThinkTime::Exponential(mean) => {
+                mean.mul_f64(-(1.0 - rng.random::<f64>()).ln())
+            }
+        }
+    }
+}
+
+/// Number of VUs that should be active `elapsed` into the run.
+pub fn target_vus_at(stages: &[Stage], elapsed: Duration) -> u64 {
+    let mut from = 0u64;
+    let mut stage_start = Duration::ZERO;
+    for stage in stages {
+        let stage_end = stage_start + stage.duration;
+        if elapsed < stage_end {
+            let progress = (elapsed - stage_start).as_secs_f64() / stage.duration.as_secs_f64();
+            let delta = stage.target as f64 - from as f64;
+            return (from as f64 + delta * progress).round() as u64;
+        }
+        from = stage.target;
+        stage_start = stage_end;
+    }
+    from
+}
+
+/// Move `metrics` into each stage as its start time is reached. Runs until the
+/// last stage has started or the task is cancelled.
+pub async fn track_stages(metrics: Arc<Metrics>, stages: Vec<Stage>, start: Instant) {
+    let mut boundary = Duration::ZERO;
+    for (i, stage) in stages.iter().enumerate() {
+        tokio::time::sleep_until((start + boundary).into()).await;
+        metrics.enter_stage(i);
+        boundary += stage.duration;
+    }
+}
+
+/// Safety valve: abort the run once the per-second error rate stays at or above
+/// `threshold` for `window_secs` consecutive seconds. Runs until aborted or cancelled.
+pub async fn watch_error_rate(metrics: Arc<Metrics>, threshold: f64, window_secs: u64) {
+    if threshold <= 0.0 || window_secs == 0 {
+        return;
+    }
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    ticker.tick().await;
+
+    let (mut last_total, mut last_errors) = (0u64, 0u64);
+    let mut breached_secs = 0u64;
+    loop {
+        ticker.tick().await;
+        let total = metrics.total_requests.load(Ordering::Relaxed);
+        let errors = metrics.total_errors.load(Ordering::Relaxed);
+        let (delta_total, delta_errors) = (total - last_total, errors - last_errors);
+        last_total = total;
+        last_errors = errors;
+
+        if delta_total > 0 && delta_errors as f64 / delta_total as f64 >= threshold {
+            breached_secs += 1;
+        } else {
+            breached_secs = 0;
+        }
+
+        if breached_secs >= window_secs {
+            let reason = format!(
+                "error rate at or above {:.0}% for {}s",
+                threshold * 100.0,
+                window_secs
+            );
+            eprintln!("Aborting run: {}", reason);
+            metrics.abort(reason);
+            return;
+        }
+    }
+}
+
+/// Stop the run on Ctrl-C or SIGTERM: VUs finish their current iteration and
+/// the partial results are still reported. A second signal exits immediately.
+pub async fn watch_signals(metrics: Arc<Metrics>) {
+    shutdown_signal().await;
+    eprintln!("Interrupted, stopping VUs (press Ctrl-C again to exit immediately)");
+    metrics.interrupt();
+    shutdown_signal().await;
+    std::process::exit(reporter::EXIT_INTERRUPTED);
 }
 
-/// Run a load test: spawn `vus` tasks, each looping `scenario_fn` until `duration` elapses.
-/// Returns the shared Metrics and actual elapsed duration.
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut term) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Emit a progress snapshot every second once measuring starts: as a JSON line
+/// on stdout with --progress, POSTed to --progress-path when set, and otherwise
+/// as a readable line on stderr when it is a terminal. Runs until cancelled.
+pub async fn report_progress(
+    metrics: Arc<Metrics>,
+    args: BenchArgs,
+    client: Client,
+    auth: Arc<Auth>,
+    stages: Arc<Vec<Stage>>,
+    measure_start: Instant,
+) {
+    tokio::time::sleep_until(measure_start.into()).await;
+    let console = !args.progress && std::io::stderr().is_terminal();
+    let url = args.progress_path.as_ref().map(|p| format!("{}{}", args.base_url, p));
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    ticker.tick().await;
+
+    let (mut last_total, mut last_errors) = (0u64, 0u64);
+    let mut last_tick = Instant::now();
+    loop {
+        ticker.tick().await;
+        let now = Instant::now();
+        let total = metrics.total_requests.load(Ordering::Relaxed);
+        let errors = metrics.total_errors.load(Ordering::Relaxed);
+        let interval = (now - last_tick).as_secs_f64();
+        let elapsed = now - measure_start;
+        let snapshot = serde_json::json!({
+            "type": "progress",
+            "test": args.test,
+            "runId": args.run_id,
+            "elapsedSecs": (elapsed.as_secs_f64() * 10.0).round() / 10.0,
+            "vus": target_vus_at(&stages, elapsed),
+            "rps": (((total - last_total) as f64 / interval) * 10.0).round() / 10.0,
+            "errors": errors - last_errors,
+            "p95": (metrics.take_window_p95_ms() * 100.0).round() / 100.0,
+            "totalRequests": total,
+            "totalErrors": errors,
+        });
+        (last_total, last_errors, last_tick) = (total, errors, now);
+
+        if args.progress {
+            println!("{}", snapshot);
+        } else if console {
+            eprintln!(
+                "[{:>4}s] {} VUs, {:.1} req/s, {} errors, p95={:.2}ms",
+                elapsed.as_secs(),
+                snapshot["vus"],
+                snapshot["rps"].as_f64().unwrap_or(0.0),
+                snapshot["errors"],
+                snapshot["p95"].as_f64().unwrap_or(0.0),
+            );
+        }
+        if let Some(url) = &url {
+            let _ = client.post(url).authorize(&auth).json(&snapshot).send().await;
+        }
+    }
+}
+
+/// Tell the admin runner (via --progress-path) that measurement is over and the
+/// binary is in `phase` (e.g. "reporting"), expected to take at most `budget`.
+/// The runner keys its stop deadline off these notices instead of the duration.
+pub async fn report_phase(client: &Client, args: &BenchArgs, auth: &Auth, phase: &str, budget: Duration) {
+    let Some(path) = &args.progress_path else { return };
+    let notice = serde_json::json!({
+        "type": "phase",
+        "test": args.test,
+        "runId": args.run_id,
+        "phase": phase,
+        "budgetSecs": budget.as_secs_f64().ceil(),
+    });
+    let url = format!("{}{}", args.base_url, path);
+    let _ = client.post(&url).authorize(auth).json(&notice).send().await;
+}
+
+/// Run a load test: spawn one task per peak VU, each looping `scenario_fn`
+/// while its `vu_id` is below the current stage target, until all stages elapse
+/// or the error-rate safety valve trips. An optional warmup runs first at the
+/// first stage's target without recording metrics. VUs pause for `--think-time`
+/// between iterations; with `--iterations` the run also ends once that many
+/// measured iterations have been claimed. Progress snapshots are emitted with
+/// --progress or --progress-path.
+/// Returns the shared Metrics and actual elapsed duration (excluding warmup).
 pub async fn run_load_test<F, Fut>(
-    vus: u64,
-    duration: Duration,
+    args: &BenchArgs,
+    client: Client,
+    scenario_fn: F,
+) -> (Arc<Metrics>, f64)
+where
+    F: Fn(Arc<ScenarioContext>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let hooks = VuHooks { setup: |_| async {}, teardown: |_| async {} };
+    run_load_test_with_hooks(args, client, hooks, scenario_fn).await
+}
+
+/// Per-VU lifecycle hooks for `run_load_test_with_hooks`, called with the
+/// VU's context. Requests they make are not recorded unless they record them.
+pub struct VuHooks<S, T> {
+    /// Runs once per VU, concurrently across VUs, before the clock starts
+    /// (e.g. to create the records the VU will work on)
+    pub setup: S,
+    /// Runs once per VU after its last iteration
+    pub teardown: T,
+}
+
+/// `run_load_test` with per-VU setup and teardown around the iteration loop.
+pub async fn run_load_test_with_hooks<S, SFut, T, TFut, F, Fut>(
+    args: &BenchArgs,
     client: Client,
-    base_url: String,
-    auth_user: String,
-    auth_pass: String,
+    hooks: VuHooks<S, T>,
     scenario_fn: F,
 ) -> (Arc<Metrics>, f64)
 where
+    S: Fn(Arc<ScenarioContext>) -> SFut,
+    SFut: Future<Output = ()>,
+    T: Fn(Arc<ScenarioContext>) -> TFut + Send + Sync + 'static,
+    TFut: Future<Output = ()> + Send,
     F: Fn(Arc<ScenarioContext>) -> Fut + Send + Sync + 'static,
     Fut: Future<Output = ()> + Send,
 {
-    let metrics = Arc::new(Metrics::new());
+    let stages = Arc::new(args.stages());
+    let metrics = Arc::new(Metrics::with_stages(&stages));
+    connection_timings().attach(&metrics);
     let scenario_fn = Arc::new(scenario_fn);
-    let deadline = Instant::now() + duration;
+    let teardown_fn = Arc::new(hooks.teardown);
+    let peak_vus = args.peak_vus();
+    let identities = auth::identities(args, &client).await;
+    let auth = identities.shared();
+    let data = args.data_set();
+
+    let contexts: Vec<Arc<ScenarioContext>> = (0..peak_vus)
+        .map(|vu_id| {
+            Arc::new(ScenarioContext {
+                client: client.clone(),
+                base_url: args.base_url.clone(),
+                auth: identities.for_vu(vu_id),
+                metrics: metrics.clone(),
+                vu_id,
+                rng: args.rng(vu_id),
+                data: data.clone(),
+                data_cursor: AtomicU64::new(vu_id),
+                data_stride: peak_vus,
+                retries: args.retries,
+                retry_backoff: args.retry_backoff,
+            })
+        })
+        .collect();
+    futures_util::future::join_all(contexts.iter().map(|ctx| (hooks.setup)(ctx.clone()))).await;
+
+    let start = Instant::now();
+    let measure_start = start + args.warmup;
+    let deadline = measure_start + args.total_duration();
+    let warmup_vus = stages.first().map_or(0, |s| s.target);
+    let think_time = args.think_time;
+    let iterations = args.iterations;
+    let claimed = Arc::new(AtomicU64::new(0));
+    if !args.warmup.is_zero() {
+        metrics.set_recording(false);
+    }
+
+    let watcher = tokio::spawn(watch_error_rate(
+        metrics.clone(),
+        args.abort_error_rate,
+        args.abort_window,
+    ));
+    // Kept running after the VUs finish so a second Ctrl-C can still cut reporting short
+    tokio::spawn(watch_signals(metrics.clone()));
+    let progress = (args.progress || args.progress_path.is_some() || std::io::stderr().is_terminal()).then(|| {
+        tokio::spawn(report_progress(
+            metrics.clone(),
+            args.clone(),
+            client.clone(),
+            auth.clone(),
+            stages.clone(),
+            measure_start,
+        ))
+    });
+    let tracker = tokio::spawn({
+        let metrics = metrics.clone();
+        let stages = stages.to_vec();
+        async move {
+            tokio::time::sleep_until(measure_start.into()).await;
+            metrics.set_recording(true);
+            track_stages(metrics, stages, measure_start).await;
+        }
+    });
 
     let mut join_set = JoinSet::new();
 
-    for vu_id in 0..vus {
-        let ctx = Arc::new(ScenarioContext {
-            client: client.clone(),
-            base_url: base_url.clone(),
-            auth_user: auth_user.clone(),
-            auth_pass: auth_pass.clone(),
-            metrics: metrics.clone(),
-            vu_id,
-        });
+    for ctx in contexts {
+        let vu_id = ctx.vu_id;
         let sf = scenario_fn.clone();
+        let teardown = teardown_fn.clone();
+        let stages = stages.clone();
+        let claimed = claimed.clone();
 
         join_set.spawn(async move {
-            while Instant::now() < deadline {
+            loop {
+                let now = Instant::now();
+                if now >= deadline || ctx.metrics.is_aborted() {
+                    break;
+                }
+                let target = if now < measure_start {
+                    warmup_vus
+                } else {
+                    target_vus_at(&stages, now - measure_start)
+                };
+                if vu_id >= target {
+                    tokio::time::sleep(IDLE_POLL).await;
+                    continue;
+                }
+                if now >= measure_start
+                    && iterations.is_some_and(|max| claimed.fetch_add(1, Ordering::Relaxed) >= max)
+                {
+                    break;
+                }
                 sf(ctx.clone()).await;
+
+                let pause = think_time.sample(&ctx.rng);
+                if !pause.is_zero() {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    tokio::time::sleep(pause.min(remaining)).await;
+                }
             }
+            teardown(ctx).await;
         });
     }
 
-    let start = Instant::now();
-
     // Wait for all VUs to finish
     while join_set.join_next().await.is_some() {}
+    watcher.abort();
+    tracker.abort();
+    if let Some(progress) = progress {
+        progress.abort();
+    }
 
-    let elapsed = start.elapsed().as_secs_f64();
+    let elapsed = Instant::now().saturating_duration_since(measure_start).as_secs_f64();
     (metrics, elapsed)
 }
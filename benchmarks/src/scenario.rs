@@ -0,0 +1,201 @@
+use crate::data::Row;
+use crate::rng::SeededRng;
+use reqwest::Method;
+use serde::Deserialize;
+use std::path::Path;
+
+/// A YAML-described workload: each iteration picks one step by weight.
+///
+/// ```yaml
+/// name: catalog
+/// steps:
+///   - name: list
+///     path: /demo/Product?limit=10
+///     weight: 3
+///     checks:
+///       status: 200
+///       maxLatencyMs: 200
+///   - name: create
+///     method: POST
+///     path: /demo/Product
+///     body: '{"id":"{{uuid}}","name":"vu {{vu}}"}'
+///     checks:
+///       status: 201
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    pub name: Option<String>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Step {
+    pub name: Option<String>,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Request path appended to --base-url; --data {{column}} placeholders are substituted
+    /// percent-encoded
+    pub path: String,
+    /// JSON body template; {{uuid}}, {{vu}}, {{random}} and --data {{column}} are substituted
+    /// per request, data values JSON-escaped (place them inside a string)
+    pub body: Option<String>,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub checks: Checks,
+}
+
+/// Assertions on a response; a request failing any check counts as an error.
+/// Without a `status` check, any 4xx/5xx response fails.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checks {
+    pub status: Option<u16>,
+    /// Substring the response body must contain
+    pub contains: Option<String>,
+    pub max_latency_ms: Option<f64>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl Scenario {
+    /// Read and validate a scenario file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let scenario: Scenario = serde_yaml::from_str(&text)
+            .map_err(|e| format!("invalid scenario {}: {}", path.display(), e))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("scenario has no steps".to_string());
+        }
+        if self.total_weight() == 0 {
+            return Err("scenario step weights sum to zero".to_string());
+        }
+        for step in &self.steps {
+            step.http_method()?;
+        }
+        Ok(())
+    }
+
+    fn total_weight(&self) -> u32 {
+        self.steps.iter().map(|s| s.weight).sum()
+    }
+
+    /// Pick a step index, weighted by `weight`.
+    pub fn pick(&self, rng: &SeededRng) -> usize {
+        let mut roll = rng.random_range(0..self.total_weight());
+        for (i, step) in self.steps.iter().enumerate() {
+            if roll < step.weight {
+                return i;
+            }
+            roll -= step.weight;
+        }
+        self.steps.len() - 1
+    }
+}
+
+impl Step {
+    pub fn http_method(&self) -> Result<Method, String> {
+        Method::from_bytes(self.method.to_uppercase().as_bytes())
+            .map_err(|_| format!("invalid HTTP method: {}", self.method))
+    }
+
+    /// Display label: the step name, else "METHOD path".
+    pub fn label(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.method.to_uppercase(), self.path))
+    }
+}
+
+impl Checks {
+    pub fn passes(&self, status: u16, body: &[u8], latency_ms: f64) -> bool {
+        let status_ok = match self.status {
+            Some(expected) => expected == status,
+            None => status < 400,
+        };
+        if !status_ok {
+            return false;
+        }
+        if self.max_latency_ms.is_some_and(|max| latency_ms > max) {
+            return false;
+        }
+        match &self.contains {
+            Some(needle) => String::from_utf8_lossy(body).contains(needle.as_str()),
+            None => true,
+        }
+    }
+}
+
+/// Where a template is used, which decides how --data values are escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// Request path: values are percent-encoded
+    Url,
+    /// JSON body: values are escaped for use inside a JSON string
+    Json,
+}
+
+impl Escape {
+    fn apply(self, value: &str) -> String {
+        match self {
+            Escape::Url => value
+                .bytes()
+                .map(|b| match b {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+                    _ => format!("%{:02X}", b),
+                })
+                .collect(),
+            Escape::Json => {
+                let quoted = serde_json::to_string(value).unwrap_or_default();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+        }
+    }
+}
+
+/// Fill per-request placeholders in a template, drawing {{uuid}} and
+/// {{random}} from `rng` and {{column}} values from a --data `row`, escaped
+/// for where the template goes. One left-to-right pass: substituted text is
+/// never rescanned, and unknown placeholders are left as they are.
+pub fn render_template(template: &str, vu_id: u64, rng: &SeededRng, row: Option<&Row>, escape: Escape) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            rest = &rest[open..];
+            break;
+        };
+        let name = &after[..close];
+        match name {
+            "vu" => out.push_str(&vu_id.to_string()),
+            "uuid" => out.push_str(&rng.uuid().to_string()),
+            "random" => out.push_str(&rng.random::<u32>().to_string()),
+            _ => match row.and_then(|r| r.get(name)) {
+                Some(value) => out.push_str(&escape.apply(value)),
+                None => {
+                    // Not a placeholder: keep the braces and resume just after them
+                    out.push_str("{{");
+                    rest = after;
+                    continue;
+                }
+            },
+        }
+        rest = &after[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
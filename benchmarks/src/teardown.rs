@@ -0,0 +1,66 @@
+use crate::auth::{Auth, Authorize};
+use crate::cli::BenchArgs;
+use crate::{reporter, runner};
+use futures_util::StreamExt;
+use reqwest::Client;
+use std::sync::Mutex;
+
+/// Concurrent DELETE requests during teardown.
+const TEARDOWN_CONCURRENCY: usize = 32;
+
+/// Ids of records a test created, deleted once the run is over so repeat runs
+/// start from the same data. Skipped with --no-teardown.
+pub struct Teardown {
+    /// Collection the ids belong to, e.g. `{base}/demo-graphql/Book`
+    collection_url: String,
+    ids: Mutex<Vec<String>>,
+}
+
+impl Teardown {
+    pub fn new(collection_url: String) -> Self {
+        Self { collection_url, ids: Mutex::new(Vec::new()) }
+    }
+
+    pub fn track(&self, id: String) {
+        self.ids.lock().unwrap_or_else(|e| e.into_inner()).push(id);
+    }
+
+    /// DELETE every tracked record, reporting how many were removed.
+    pub async fn run(&self, args: &BenchArgs, client: &Client, auth: &Auth) {
+        let ids = std::mem::take(&mut *self.ids.lock().unwrap_or_else(|e| e.into_inner()));
+        if ids.is_empty() {
+            return;
+        }
+        if args.no_teardown {
+            println!("Teardown skipped: {} created records left in place", ids.len());
+            return;
+        }
+
+        // Every DELETE wave may run into the request timeout
+        let waves = ids.len().div_ceil(TEARDOWN_CONCURRENCY) as u32;
+        runner::report_phase(client, args, auth, "teardown", reporter::request_budget(args) * waves).await;
+
+        println!("Teardown: deleting {} created records...", ids.len());
+        let deleted = futures_util::stream::iter(&ids)
+            .map(|id| {
+                let url = format!("{}/{}", self.collection_url, id);
+                async move {
+                    client
+                        .delete(&url)
+                        .authorize(auth)
+                        .send()
+                        .await
+                        .is_ok_and(|resp| resp.status().is_success())
+                }
+            })
+            .buffer_unordered(TEARDOWN_CONCURRENCY)
+            .filter(|ok| std::future::ready(*ok))
+            .count()
+            .await;
+        if deleted < ids.len() {
+            eprintln!("Teardown warning: {} of {} records could not be deleted", ids.len() - deleted, ids.len());
+        } else {
+            println!("Teardown complete.");
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::metrics::MetricsSummary;
+use std::fmt;
+
+/// A pass/fail condition on the run summary, e.g. "p95<50ms",
+/// "error_rate<1%" or "rps>5000".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    metric: Metric,
+    op: Op,
+    value: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    /// Latency percentile in milliseconds (p50, p99.9, ...)
+    Percentile(f64),
+    /// Maximum latency in milliseconds
+    Max,
+    /// Fraction of requests that failed
+    ErrorRate,
+    /// Requests per second
+    Rps,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Threshold {
+    /// Parse "<metric><op><value>". Latency values take ms (default), s or us;
+    /// error_rate takes a fraction or a percentage.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid threshold '{}' (e.g. p95<50ms, error_rate<1%, rps>5000)", spec);
+        let compact: String = spec.chars().filter(|c| !c.is_whitespace()).collect();
+        let at = compact.find(['<', '>']).ok_or_else(invalid)?;
+        let (name, rest) = compact.split_at(at);
+        let (op, value) = match rest.strip_prefix("<=").or_else(|| rest.strip_prefix(">=")) {
+            Some(value) if rest.starts_with('<') => (Op::Le, value),
+            Some(value) => (Op::Ge, value),
+            None if rest.starts_with('<') => (Op::Lt, &rest[1..]),
+            None => (Op::Gt, &rest[1..]),
+        };
+
+        let metric = match name.to_ascii_lowercase().as_str() {
+            "max" => Metric::Max,
+            "error_rate" | "errors" => Metric::ErrorRate,
+            "rps" | "throughput" => Metric::Rps,
+            p => match p.strip_prefix('p').and_then(|n| n.parse::<f64>().ok()) {
+                Some(n) if n > 0.0 && n <= 100.0 => Metric::Percentile(n),
+                _ => return Err(invalid()),
+            },
+        };
+
+        let value = match metric {
+            Metric::Percentile(_) | Metric::Max => parse_latency_ms(value),
+            Metric::ErrorRate => match value.strip_suffix('%') {
+                Some(pct) => pct.parse::<f64>().ok().map(|v| v / 100.0),
+                None => value.parse().ok(),
+            },
+            Metric::Rps => value.parse().ok(),
+        }
+        .ok_or_else(invalid)?;
+
+        Ok(Self { metric, op, value })
+    }
+
+    /// Observed value of this threshold's metric.
+    pub fn actual(&self, summary: &MetricsSummary) -> f64 {
+        match self.metric {
+            Metric::Percentile(p) => summary.percentile_ms(p),
+            Metric::Max => summary.max_ms(),
+            Metric::ErrorRate => summary.error_rate(),
+            Metric::Rps => summary.throughput,
+        }
+    }
+
+    pub fn passes(&self, summary: &MetricsSummary) -> bool {
+        let actual = self.actual(summary);
+        match self.op {
+            Op::Lt => actual < self.value,
+            Op::Le => actual <= self.value,
+            Op::Gt => actual > self.value,
+            Op::Ge => actual >= self.value,
+        }
+    }
+
+    /// The observed value formatted in this threshold's units.
+    pub fn format_actual(&self, summary: &MetricsSummary) -> String {
+        self.metric.format(self.actual(summary))
+    }
+}
+
+impl Metric {
+    fn format(&self, value: f64) -> String {
+        match self {
+            Metric::Percentile(_) | Metric::Max => format!("{:.2}ms", value),
+            Metric::ErrorRate => format!("{:.2}%", value * 100.0),
+            Metric::Rps => format!("{:.1}", value),
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.metric {
+            Metric::Percentile(p) => format!("p{}", p),
+            Metric::Max => "max".to_string(),
+            Metric::ErrorRate => "error_rate".to_string(),
+            Metric::Rps => "rps".to_string(),
+        };
+        let op = match self.op {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        };
+        write!(f, "{}{}{}", name, op, self.metric.format(self.value))
+    }
+}
+
+fn parse_latency_ms(value: &str) -> Option<f64> {
+    let (num, scale) = if let Some(n) = value.strip_suffix("ms") {
+        (n, 1.0)
+    } else if let Some(n) = value.strip_suffix("us") {
+        (n, 0.001)
+    } else if let Some(n) = value.strip_suffix('s') {
+        (n, 1000.0)
+    } else {
+        (value, 1.0)
+    };
+    num.parse::<f64>().ok().map(|v| v * scale)
+}
@@ -2,15 +2,90 @@
 //!
 //! REST API for managing Yeti applications.
 //!
-//! | Method | Path                             | Description                    |
-//! |--------|----------------------------------|--------------------------------|
-//! | GET    | /yeti-applications/apps          | List all apps                  |
-//! | GET    | /yeti-applications/apps/{id}     | Get single app detail          |
-//! | POST   | /yeti-applications/apps          | Create new app from template   |
-//! | PUT    | /yeti-applications/apps/{id}     | Update app config.yaml         |
-//! | DELETE | /yeti-applications/apps/{id}     | Remove app directory           |
+//! | Method | Path                                       | Description                  |
+//! |--------|--------------------------------------------|------------------------------|
+//! | GET    | /yeti-applications/apps                    | List all apps                |
+//! | GET    | /yeti-applications/apps/{id}               | Get single app detail        |
+//! | POST   | /yeti-applications/apps                    | Create new app from template |
+//! | PUT    | /yeti-applications/apps/{id}               | Update app config.yaml       |
+//! | DELETE | /yeti-applications/apps/{id}               | Move app to the trash        |
+//! | GET    | /yeti-applications/apps/{id}/export        | Download app as tar.gz       |
+//! | POST   | /yeti-applications/apps/{id}/duplicate     | Copy app under a new id      |
+//! | POST   | /yeti-applications/apps/{id}/rename        | Rename app in place          |
+//! | POST   | /yeti-applications/apps/{id}/validate      | Check config.yaml            |
+//! | POST   | /yeti-applications/apps/{id}/reload        | Reload one app               |
+//! | POST   | /yeti-applications/apps/{id}/check         | Compile-check resources      |
+//! | GET    | /yeti-applications/apps/graph              | Dependency graph             |
+//! | GET    | /yeti-applications/apps/trash              | Deleted apps                 |
+//! | POST   | /yeti-applications/apps/trash/{id}/restore | Restore a deleted app        |
+//! | DELETE | /yeti-applications/apps/trash/{id}         | Purge a deleted app          |
+//!
+//! The list is served from an in-memory metadata index: each app's entry is
+//! rebuilt only when config.yaml, a schema file or the app or `resources/`
+//! directory changed since it was cached (by modification time).
+//!
+//! Create body: `{"id": "orders", "name": "Orders", "description": "...",
+//! "template": "api-starter", "variables": {"port": "8080"}}`. While a template
+//! is copied, `{{app_id}}`, `{{name}}`, `{{description}}` and the variables
+//! declared in the template's `template.yaml` are replaced in its text files.
+//!
+//! PUT deep-merges the body into config.yaml: nested mappings are merged key
+//! by key, `null` deletes a key, and lists and scalars replace. With
+//! `?replace=true` top-level keys are replaced wholesale instead.
+//!
+//! Export downloads the tar.gz of the app directory (`application/gzip`, as
+//! `{app_id}.tar.gz`, its SHA-256 in `X-Content-SHA256`) without `target/`,
+//! `node_modules/` and `.git/`. It is the archive format `/admin/promote`
+//! receives, so an export can be restored on another instance by POSTing it
+//! there base64-encoded.
+//!
+//! Duplicate body: `{"app_id": "my-app-v2", "name": "My App v2", "keepGit":
+//! false}`. The copy skips build artifacts and gets its own `app_id` and
+//! `name` in config.yaml; git history is dropped unless `keepGit`.
+//!
+//! Rename body: `{"app_id": "new-id"}`. The directory (git history included)
+//! is moved and `app_id` rewritten, as is `route_prefix` when it was the
+//! default `/{old-id}`. The plugin cache and its size limit, domain mappings,
+//! app secrets and the default deploy key follow the app to its new id. Apps
+//! with a running build cannot be renamed.
+//!
+//! Validate checks the app's config.yaml, or `{"content": "<yaml>"}` when
+//! given (for unsaved editor content): YAML syntax, required `name` and
+//! `app_id` (matching the directory), `resources` / `schemas` globs and the
+//! files they point at, `static_files` paths, `route_prefix`, `extensions`,
+//! `dependencies` and `depends_on`. Each problem carries a `severity`, the config `path`,
+//! a `message` and the 1-based `line` / `column` it was found at. PUT runs the
+//! same check on the merged config and refuses to write one with errors
+//! (422) unless `?force=true`.
+//!
+//! Reload asks the runtime to re-read the app's config.yaml, recompile its
+//! resources and re-register its routes, leaving other apps running; use it
+//! after editing files through `/admin/files`. The config is validated first
+//! and an invalid one is refused (422) unless `?force=true`.
+//!
+//! Check runs `cargo check` (`?clippy=true` for clippy) on the app's plugin
+//! crate in an isolated target directory, the same as
+//! `/admin/builds/{id}/check`, and returns the compiler diagnostics with
+//! file, line and column, so a broken resource shows up before a reload.
+//!
+//! Apps declare the apps they need with `depends_on: [other-app]`. The graph
+//! lists each app's dependencies, dependents, missing and disabled
+//! dependencies, a load `order` (dependencies first) and the apps caught in a
+//! cycle. Validation rejects self-dependencies and cycles and warns about
+//! missing dependencies, enabling an app whose dependency is disabled and
+//! disabling one that enabled apps depend on. DELETE of an app others depend
+//! on is refused (409) unless `?force=true`.
+//!
+//! DELETE moves the app into `trash/{app_id}-{timestamp}` under the Yeti root
+//! (`?permanent=true` removes it outright); the plugin cache is still cleared.
+//! Trashed apps are purged after `TRASH_RETENTION_DAYS` by the scheduler tick.
+//! Restore puts the app back under its id, or under `{"app_id": "..."}` when
+//! given (config.yaml is rewritten to match). `trash` is reserved as an app id.
 
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
+use std::sync::{Mutex, OnceLock};
 use yeti_core::prelude::*;
 
 pub type Apps = AppsResource;
@@ -18,11 +93,123 @@ pub type Apps = AppsResource;
 #[derive(Default)]
 pub struct AppsResource;
 
-/// Recursively copy a template directory, skipping build artifacts
-fn copy_template(src: &Path, dst: &Path) -> std::io::Result<()> {
+/// Days a deleted app stays in the trash
+const TRASH_RETENTION_DAYS: u64 = 30;
+/// App ids that would collide with sub-paths of this resource
+const RESERVED_IDS: &[&str] = &["trash", "graph"];
+
+fn check_reserved(app_id: &str) -> std::result::Result<(), YetiError> {
+    if RESERVED_IDS.contains(&app_id) {
+        return Err(YetiError::Validation(format!("'{}' is a reserved app id", app_id)));
+    }
+    Ok(())
+}
+
+// ── Trash ──
+
+fn trash_directory() -> std::path::PathBuf {
+    get_root_directory().join("trash")
+}
+
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `(app_id, deleted_at)` from a trash entry named `{app_id}-{timestamp}`
+fn parse_trash_entry(entry: &str) -> Option<(&str, u64)> {
+    let (app_id, ts) = entry.rsplit_once('-')?;
+    Some((app_id, ts.parse().ok()?)).filter(|(id, _)| !id.is_empty())
+}
+
+/// Trash entries, newest first
+fn trash_entries() -> Vec<(String, String, u64)> {
+    let Ok(dir) = std::fs::read_dir(trash_directory()) else { return Vec::new() };
+    let mut entries: Vec<(String, String, u64)> = dir
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            let (app_id, deleted_at) = parse_trash_entry(&name)?;
+            Some((name.clone(), app_id.to_string(), deleted_at))
+        })
+        .collect();
+    entries.sort_by(|a, b| b.2.cmp(&a.2));
+    entries
+}
+
+/// Move an app directory into the trash; returns the trash entry name
+fn move_to_trash(app_id: &str, app_path: &Path) -> std::result::Result<String, String> {
+    let trash = trash_directory();
+    std::fs::create_dir_all(&trash).map_err(|e| format!("Failed to create trash directory: {}", e))?;
+    let mut ts = unix_secs();
+    while trash.join(format!("{}-{}", app_id, ts)).exists() {
+        ts += 1;
+    }
+    let entry = format!("{}-{}", app_id, ts);
+    std::fs::rename(app_path, trash.join(&entry)).map_err(|e| format!("Failed to move app to trash: {}", e))?;
+    Ok(entry)
+}
+
+/// Remove trash entries older than `TRASH_RETENTION_DAYS`
+pub fn purge_trash() {
+    let cutoff = unix_secs().saturating_sub(TRASH_RETENTION_DAYS * 86400);
+    for (entry, _, deleted_at) in trash_entries() {
+        if deleted_at < cutoff {
+            match std::fs::remove_dir_all(trash_directory().join(&entry)) {
+                Ok(()) => yeti_log!(info, "Purged '{}' from the app trash", entry),
+                Err(e) => yeti_log!(warn, "Failed to purge '{}' from the app trash: {}", entry, e),
+            }
+        }
+    }
+}
+
+/// Trash entry named by `/apps/trash/{entry}[/{action}]`
+fn trash_path(uri_path: &str) -> Option<(&str, Option<&str>)> {
+    let rest = uri_path.trim_end_matches('/').split_once("/apps/trash/")?.1;
+    let (entry, action) = match rest.split_once('/') {
+        Some((entry, action)) => (entry, Some(action)),
+        None => (rest, None),
+    };
+    parse_trash_entry(entry).is_some().then_some((entry, action))
+}
+
+/// Text files larger than this are copied without variable substitution
+const MAX_SUBSTITUTE_BYTES: u64 = 1024 * 1024;
+
+/// Recursively copy a template directory, skipping build artifacts and
+/// replacing `{{variable}}` placeholders in text files
+fn copy_template(src: &Path, dst: &Path, vars: &[(String, String)]) -> std::io::Result<()> {
     const SKIP_DIRS: &[&str] = &["source", "node_modules", ".git", "target", "test"];
-    const SKIP_FILES: &[&str] = &["Cargo.toml", "build.rs", ".gitignore"];
+    const SKIP_FILES: &[&str] = &["Cargo.toml", "build.rs", ".gitignore", crate::templates::TEMPLATE_FILE];
+    copy_dir(src, dst, SKIP_DIRS, SKIP_FILES, vars)
+}
+
+/// Recursively copy an app for duplication, skipping build artifacts and
+/// (unless `keep_git`) its git history
+fn copy_app(src: &Path, dst: &Path, keep_git: bool) -> std::io::Result<()> {
+    let skip_dirs: &[&str] = if keep_git { &["node_modules", "target"] } else { &["node_modules", "target", ".git"] };
+    copy_dir(src, dst, skip_dirs, &[], &[])
+}
+
+/// Copy one file, substituting `vars` when it is UTF-8 text without NULs
+fn copy_file(src: &Path, dst: &Path, vars: &[(String, String)]) -> std::io::Result<()> {
+    let small = std::fs::metadata(src).is_ok_and(|m| m.len() <= MAX_SUBSTITUTE_BYTES);
+    if !vars.is_empty() && small {
+        if let Ok(text) = String::from_utf8(std::fs::read(src)?) {
+            if !text.contains('\0') && text.contains("{{") {
+                let replaced = vars.iter().fold(text, |acc, (name, value)| acc.replace(&format!("{{{{{}}}}}", name), value));
+                std::fs::write(dst, replaced)?;
+                return std::fs::set_permissions(dst, std::fs::metadata(src)?.permissions());
+            }
+        }
+    }
+    std::fs::copy(src, dst).map(|_| ())
+}
 
+pub fn copy_dir(src: &Path, dst: &Path, skip_dirs: &[&str], skip_files: &[&str], vars: &[(String, String)]) -> std::io::Result<()> {
     std::fs::create_dir_all(dst)?;
 
     for entry in std::fs::read_dir(src)? {
@@ -31,22 +218,536 @@ fn copy_template(src: &Path, dst: &Path) -> std::io::Result<()> {
         let name = file_name.to_string_lossy();
         let src_path = entry.path();
         let dst_path = dst.join(&file_name);
+        let file_type = entry.file_type()?;
 
-        if src_path.is_dir() {
-            if SKIP_DIRS.contains(&name.as_ref()) {
+        if file_type.is_symlink() {
+            // Recreated as links, never followed: a loop or a link out of
+            // the app would otherwise recurse or copy foreign trees
+            if skip_files.contains(&name.as_ref()) || skip_dirs.contains(&name.as_ref()) {
                 continue;
             }
-            copy_template(&src_path, &dst_path)?;
+            let target = std::fs::read_link(&src_path)?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&target, &dst_path)?;
+            #[cfg(not(unix))]
+            let _ = target;
+        } else if file_type.is_dir() {
+            if skip_dirs.contains(&name.as_ref()) {
+                continue;
+            }
+            copy_dir(&src_path, &dst_path, skip_dirs, skip_files, vars)?;
         } else {
-            if SKIP_FILES.contains(&name.as_ref()) {
+            if skip_files.contains(&name.as_ref()) {
                 continue;
             }
-            std::fs::copy(&src_path, &dst_path)?;
+            copy_file(&src_path, &dst_path, vars)?;
         }
     }
     Ok(())
 }
 
+/// `(app_id, action)` for `/apps/{id}/{action}` paths
+fn app_action(uri_path: &str) -> Option<(&str, &str)> {
+    let rest = uri_path.trim_end_matches('/').split_once("/apps/")?.1;
+    let (app_id, action) = rest.split_once('/')?;
+    (!app_id.is_empty() && !action.is_empty() && !action.contains('/')).then_some((app_id, action))
+}
+
+/// Set top-level string keys in an app's config.yaml and drop the `remove` keys
+fn rewrite_config(app_path: &Path, values: &[(&str, &str)], remove: &[&str]) -> std::result::Result<(), String> {
+    let config_path = app_path.join("config.yaml");
+    let content = std::fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config: {}", e))?;
+    let mut yaml: serde_yaml::Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("Failed to parse config: {}", e))?;
+
+    if let Some(map) = yaml.as_mapping_mut() {
+        for (key, value) in values {
+            map.insert(
+                serde_yaml::Value::String(key.to_string()),
+                serde_yaml::Value::String(value.to_string()),
+            );
+        }
+        for key in remove {
+            map.remove(*key);
+        }
+    }
+
+    let new_content = serde_yaml::to_string(&yaml)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    std::fs::write(&config_path, &new_content)
+        .map_err(|e| format!("Failed to write config: {}", e))
+}
+
+// ── config.yaml validation ──
+
+/// Directories never searched when matching config globs
+const GLOB_SKIP_DIRS: &[&str] = &["target", "node_modules", ".git"];
+/// Files collected at most when matching config globs
+const GLOB_MAX_FILES: usize = 20_000;
+
+/// Problems found in a config, each with the line/column it points at
+struct ConfigIssues<'a> {
+    content: &'a str,
+    errors: Vec<serde_json::Value>,
+    warnings: Vec<serde_json::Value>,
+}
+
+impl ConfigIssues<'_> {
+    fn issue(&self, severity: &str, path: &[&str], message: String) -> serde_json::Value {
+        let (line, column) = locate(self.content, path).unwrap_or((1, 1));
+        json!({
+            "severity": severity,
+            "path": path.join("."),
+            "message": message,
+            "line": line,
+            "column": column,
+        })
+    }
+
+    fn error(&mut self, path: &[&str], message: String) {
+        let issue = self.issue("error", path, message);
+        self.errors.push(issue);
+    }
+
+    fn warning(&mut self, path: &[&str], message: String) {
+        let issue = self.issue("warning", path, message);
+        self.warnings.push(issue);
+    }
+}
+
+/// 1-based line and column of the key (or sequence item) at `path` in YAML
+/// text; falls back to the deepest parent found
+fn locate(content: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let mut lines = content.lines().enumerate();
+    let mut indent: Option<usize> = None;
+    let mut found = None;
+    for segment in path {
+        loop {
+            let Some((n, line)) = lines.next() else { return found };
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let column = line.len() - trimmed.len();
+            match indent {
+                None if column != 0 => continue,
+                Some(parent) if column <= parent && !trimmed.starts_with("- ") => return found,
+                _ => {}
+            }
+            let item = trimmed.strip_prefix("- ").unwrap_or(trimmed);
+            let key = item.split(':').next().unwrap_or("").trim().trim_matches(|c| c == '"' || c == '\'');
+            let value = item.trim().trim_matches(|c| c == '"' || c == '\'');
+            if key == *segment || value == *segment {
+                indent = Some(column);
+                found = Some((n + 1, column + 1 + (trimmed.len() - item.len())));
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// Shell-style glob match: `*` and `?` stay within a path segment, `**`
+/// spans directories, `[abc]` / `[a-z]` classes and `{a,b}` alternatives
+fn glob_match(pattern: &str, path: &str) -> bool {
+    if let Some(start) = pattern.find('{') {
+        if let Some(len) = pattern[start..].find('}') {
+            let (head, tail) = (&pattern[..start], &pattern[start + len + 1..]);
+            return pattern[start + 1..start + len]
+                .split(',')
+                .any(|alt| glob_match(&format!("{}{}{}", head, alt, tail), path));
+        }
+    }
+
+    fn matches(p: &[u8], t: &[u8]) -> bool {
+        match p.first() {
+            None => t.is_empty(),
+            Some(b'*') if p.get(1) == Some(&b'*') => {
+                let rest = p[2..].strip_prefix(b"/").unwrap_or(&p[2..]);
+                rest.is_empty() || (0..=t.len()).any(|i| (i == 0 || t[i - 1] == b'/') && matches(rest, &t[i..]))
+            }
+            Some(b'*') => (0..=t.len())
+                .take_while(|&i| i == 0 || t[i - 1] != b'/')
+                .any(|i| matches(&p[1..], &t[i..])),
+            Some(b'?') => t.first().is_some_and(|&c| c != b'/') && matches(&p[1..], &t[1..]),
+            Some(b'[') => {
+                let Some(end) = p.iter().skip(2).position(|&c| c == b']').map(|i| i + 2) else {
+                    return t.first() == Some(&b'[') && matches(&p[1..], &t[1..]);
+                };
+                let Some(&c) = t.first() else { return false };
+                let (negate, set) = match p[1] {
+                    b'!' | b'^' => (true, &p[2..end]),
+                    _ => (false, &p[1..end]),
+                };
+                let mut hit = false;
+                let mut i = 0;
+                while i < set.len() {
+                    if i + 2 < set.len() && set[i + 1] == b'-' {
+                        hit |= (set[i]..=set[i + 2]).contains(&c);
+                        i += 3;
+                    } else {
+                        hit |= set[i] == c;
+                        i += 1;
+                    }
+                }
+                hit != negate && c != b'/' && matches(&p[end + 1..], &t[1..])
+            }
+            Some(&c) => t.first() == Some(&c) && matches(&p[1..], &t[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+/// Why a config glob is malformed, if it is
+fn glob_error(pattern: &str) -> Option<String> {
+    if pattern.trim().is_empty() {
+        return Some("Pattern is empty".to_string());
+    }
+    if pattern.starts_with('/') || pattern.split('/').any(|c| c == "..") {
+        return Some(format!("'{}' must be relative to the app directory", pattern));
+    }
+    let mut depth = (0i32, 0i32);
+    for c in pattern.chars() {
+        match c {
+            '[' => depth.0 += 1,
+            ']' => depth.0 -= 1,
+            '{' => depth.1 += 1,
+            '}' => depth.1 -= 1,
+            _ => {}
+        }
+        if depth.0 < 0 || depth.1 < 0 {
+            break;
+        }
+    }
+    (depth != (0, 0)).then(|| format!("'{}' has unbalanced brackets or braces", pattern))
+}
+
+/// Every file under an app (relative paths), skipping build artifacts
+fn app_files(app_path: &Path) -> Vec<String> {
+    fn walk(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            if files.len() >= GLOB_MAX_FILES {
+                return;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+            match entry.file_type() {
+                Ok(ft) if ft.is_dir() => {
+                    if !GLOB_SKIP_DIRS.contains(&name.as_str()) {
+                        walk(&entry.path(), &rel, files);
+                    }
+                }
+                Ok(_) => files.push(rel),
+                Err(_) => {}
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(app_path, "", &mut files);
+    files
+}
+
+/// Check a list of globs (`resources`, `schemas`) against the app's files
+fn check_globs(issues: &mut ConfigIssues, key: &str, value: &serde_yaml::Value, files: &[String]) {
+    let Some(items) = value.as_sequence() else {
+        issues.error(&[key], format!("'{}' must be a list of file patterns", key));
+        return;
+    };
+    for item in items {
+        let Some(pattern) = item.as_str() else {
+            issues.error(&[key], format!("Entries of '{}' must be strings", key));
+            continue;
+        };
+        if let Some(e) = glob_error(pattern) {
+            issues.error(&[key, pattern], e);
+        } else if !files.iter().any(|f| glob_match(pattern, f)) {
+            let message = format!("'{}' matches no files", pattern);
+            if pattern.contains(['*', '?', '[', '{']) {
+                issues.warning(&[key, pattern], message);
+            } else {
+                issues.error(&[key, pattern], message);
+            }
+        }
+    }
+}
+
+/// Validate config.yaml text for the app at `app_path`; returns (errors, warnings)
+fn check_config(app_id: &str, app_path: &Path, content: &str) -> (Vec<serde_json::Value>, Vec<serde_json::Value>) {
+    let mut issues = ConfigIssues { content, errors: Vec::new(), warnings: Vec::new() };
+
+    let yaml: serde_yaml::Value = match serde_yaml::from_str(content) {
+        Ok(yaml) => yaml,
+        Err(e) => {
+            let (line, column) = e.location().map(|l| (l.line(), l.column())).unwrap_or((1, 1));
+            issues.errors.push(json!({
+                "severity": "error",
+                "path": "",
+                "message": format!("Invalid YAML: {}", e),
+                "line": line,
+                "column": column,
+            }));
+            return (issues.errors, issues.warnings);
+        }
+    };
+    if !yaml.is_mapping() {
+        issues.error(&[], "Config must be a mapping of keys".to_string());
+        return (issues.errors, issues.warnings);
+    }
+
+    for key in ["name", "app_id"] {
+        match yaml.get(key) {
+            None => issues.error(&[key], format!("'{}' is required", key)),
+            Some(v) if !v.is_string() => issues.error(&[key], format!("'{}' must be a string", key)),
+            _ => {}
+        }
+    }
+    if let Some(id) = yaml.get("app_id").and_then(|v| v.as_str()) {
+        if id != app_id {
+            issues.error(&["app_id"], format!("app_id '{}' does not match the app directory '{}'", id, app_id));
+        }
+    }
+    if let Some(version) = yaml.get("version") {
+        if version.is_number() {
+            issues.warning(&["version"], "'version' is a number; quote it to keep it a string".to_string());
+        } else if !version.is_string() {
+            issues.error(&["version"], "'version' must be a string".to_string());
+        }
+    }
+    if yaml.get("enabled").is_some_and(|v| !v.is_bool()) {
+        issues.error(&["enabled"], "'enabled' must be true or false".to_string());
+    }
+    if let Some(prefix) = yaml.get("route_prefix") {
+        match prefix.as_str() {
+            Some(p) if p.starts_with('/') && !p.contains(char::is_whitespace) => {}
+            _ => issues.error(&["route_prefix"], "'route_prefix' must be a path starting with '/'".to_string()),
+        }
+    }
+
+    let files = app_files(app_path);
+    for key in ["resources", "schemas"] {
+        if let Some(value) = yaml.get(key) {
+            check_globs(&mut issues, key, value, &files);
+        }
+    }
+
+    if let Some(static_files) = yaml.get("static_files") {
+        if !static_files.is_mapping() {
+            issues.error(&["static_files"], "'static_files' must be a mapping".to_string());
+        } else {
+            let built = static_files.get("build").is_some();
+            match static_files.get("path").map(|v| v.as_str()) {
+                None => issues.error(&["static_files"], "'static_files.path' is required".to_string()),
+                Some(None) => issues.error(&["static_files", "path"], "'static_files.path' must be a string".to_string()),
+                Some(Some(path)) => {
+                    let dir = app_path.join(path);
+                    if path.starts_with('/') || path.split('/').any(|c| c == "..") {
+                        issues.error(&["static_files", "path"], format!("'{}' must be inside the app directory", path));
+                    } else if !dir.is_dir() {
+                        // A build step may create the directory
+                        let message = format!("Directory '{}' does not exist", path);
+                        if built {
+                            issues.warning(&["static_files", "path"], message);
+                        } else {
+                            issues.error(&["static_files", "path"], message);
+                        }
+                    } else if let Some(index) = static_files.get("index").and_then(|v| v.as_str()) {
+                        if !dir.join(index).is_file() && !built {
+                            issues.warning(&["static_files", "index"], format!("'{}' not found in '{}'", index, path));
+                        }
+                    }
+                }
+            }
+            match static_files.get("route").map(|v| v.as_str()) {
+                Some(Some(route)) if !route.starts_with('/') => {
+                    issues.error(&["static_files", "route"], "'static_files.route' must start with '/'".to_string());
+                }
+                Some(None) => issues.error(&["static_files", "route"], "'static_files.route' must be a string".to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(extensions) = yaml.get("extensions") {
+        match extensions.as_sequence() {
+            None => issues.error(&["extensions"], "'extensions' must be a list".to_string()),
+            Some(items) => {
+                for item in items {
+                    let valid = item.is_string() || item.as_mapping().is_some_and(|m| m.len() == 1 && m.keys().all(|k| k.is_string()));
+                    if !valid {
+                        issues.error(&["extensions"], "Extensions must be a name or a single `name: options` mapping".to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(dependencies) = yaml.get("dependencies") {
+        match dependencies.as_mapping() {
+            None => issues.error(&["dependencies"], "'dependencies' must be a mapping of crate names".to_string()),
+            Some(deps) => {
+                for (name, spec) in deps {
+                    let name = name.as_str().unwrap_or("");
+                    let valid = spec.is_string()
+                        || spec.as_mapping().is_some_and(|m| ["version", "path", "git"].iter().any(|k| m.contains_key(*k)));
+                    if !valid {
+                        issues.error(&["dependencies", name], format!("Dependency '{}' needs a version, path or git source", name));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(depends_on) = yaml.get("depends_on") {
+        match depends_on.as_sequence() {
+            None => issues.error(&["depends_on"], "'depends_on' must be a list of app ids".to_string()),
+            Some(items) => {
+                let graph = app_graph();
+                let enabled = yaml.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
+                for item in items {
+                    let Some(dep) = item.as_str().filter(|d| validate_identifier(d, "depends_on").is_ok()) else {
+                        issues.error(&["depends_on"], "Dependencies must be app ids".to_string());
+                        continue;
+                    };
+                    if dep == app_id {
+                        issues.error(&["depends_on"], "An app cannot depend on itself".to_string());
+                    } else if let Some(path) = dependency_path(&graph, dep, app_id, &mut BTreeSet::new()) {
+                        issues.error(&["depends_on"], format!("Dependency cycle: {} -> {}", app_id, path.join(" -> ")));
+                    } else {
+                        match graph.iter().find(|(id, _, _)| id == dep) {
+                            None => issues.warning(&["depends_on"], format!("App '{}' is not installed", dep)),
+                            Some((_, false, _)) if enabled => {
+                                issues.warning(&["depends_on"], format!("App '{}' is disabled; enable it first", dep))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Disabling an app that enabled apps depend on
+    if !yaml.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false) {
+        let graph = app_graph();
+        let enabled: Vec<String> = dependents(&graph, app_id)
+            .into_iter()
+            .filter(|d| graph.iter().any(|(id, on, _)| id == d && *on))
+            .collect();
+        if !enabled.is_empty() {
+            issues.warning(&["enabled"], format!("Enabled apps depend on '{}': {}", app_id, enabled.join(", ")));
+        }
+    }
+
+    (issues.errors, issues.warnings)
+}
+
+/// App ids an app declares under `depends_on`
+fn dependencies_of(config: Option<&serde_json::Value>) -> Vec<String> {
+    config
+        .and_then(|c| c.get("depends_on"))
+        .and_then(|v| v.as_array())
+        .map(|deps| deps.iter().filter_map(|d| d.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+/// Every app as (id, enabled, depends_on), sorted by id
+fn app_graph() -> Vec<(String, bool, Vec<String>)> {
+    let Ok(entries) = std::fs::read_dir(get_apps_directory()) else { return Vec::new() };
+    let mut graph: Vec<(String, bool, Vec<String>)> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| {
+            let id = e.file_name().to_str()?.to_string();
+            if id.starts_with('.') {
+                return None;
+            }
+            let config = read_app_config(&e.path());
+            let enabled = config.as_ref().and_then(|c| c.get("enabled")).and_then(|v| v.as_bool()).unwrap_or(false);
+            Some((id, enabled, dependencies_of(config.as_ref())))
+        })
+        .collect();
+    graph.sort_by(|a, b| a.0.cmp(&b.0));
+    graph
+}
+
+/// Apps that declare a dependency on `app_id`
+fn dependents(graph: &[(String, bool, Vec<String>)], app_id: &str) -> Vec<String> {
+    graph.iter().filter(|(_, _, deps)| deps.iter().any(|d| d == app_id)).map(|(id, _, _)| id.clone()).collect()
+}
+
+/// Load order with dependencies first, and the apps left out because they are
+/// in (or depend on) a cycle. Dependencies on missing apps are ignored.
+fn load_order(graph: &[(String, bool, Vec<String>)]) -> (Vec<String>, Vec<String>) {
+    let known = |d: &String| graph.iter().any(|(id, _, _)| id == d);
+    let mut pending: HashMap<&str, usize> = graph
+        .iter()
+        .map(|(id, _, deps)| (id.as_str(), deps.iter().filter(|d| known(d)).collect::<BTreeSet<_>>().len()))
+        .collect();
+    let mut ready: BTreeSet<&str> = pending.iter().filter(|(_, n)| **n == 0).map(|(id, _)| *id).collect();
+    let mut order = Vec::new();
+    while let Some(id) = ready.pop_first() {
+        pending.remove(id);
+        order.push(id.to_string());
+        for (dependent, _, _) in graph.iter().filter(|(_, _, deps)| deps.iter().any(|d| d == id)) {
+            if let Some(n) = pending.get_mut(dependent.as_str()) {
+                *n -= 1;
+                if *n == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+    }
+    let mut blocked: Vec<String> = pending.keys().map(|id| id.to_string()).collect();
+    blocked.sort();
+    (order, blocked)
+}
+
+/// The path from `from` to `target` along `depends_on`, if there is one
+fn dependency_path(
+    graph: &[(String, bool, Vec<String>)],
+    from: &str,
+    target: &str,
+    seen: &mut BTreeSet<String>,
+) -> Option<Vec<String>> {
+    if from == target {
+        return Some(vec![from.to_string()]);
+    }
+    if !seen.insert(from.to_string()) {
+        return None;
+    }
+    let (_, _, deps) = graph.iter().find(|(id, _, _)| id == from)?;
+    deps.iter().find_map(|dep| {
+        dependency_path(graph, dep, target, seen).map(|mut path| {
+            path.insert(0, from.to_string());
+            path
+        })
+    })
+}
+
+/// Merge `updates` into `target`: nested mappings merge key by key, `null`
+/// deletes a key, and anything else (including lists) replaces the old value
+fn deep_merge(target: &mut serde_yaml::Value, updates: &serde_yaml::Value) {
+    let (Some(target_map), Some(updates_map)) = (target.as_mapping_mut(), updates.as_mapping()) else {
+        *target = updates.clone();
+        return;
+    };
+    for (key, value) in updates_map {
+        if value.is_null() {
+            target_map.remove(key);
+        } else if let (Some(existing), true) = (target_map.get_mut(key), value.is_mapping()) {
+            if existing.is_mapping() {
+                deep_merge(existing, value);
+            } else {
+                *existing = value.clone();
+            }
+        } else {
+            target_map.insert(key.clone(), value.clone());
+        }
+    }
+}
+
 /// Read and parse an app's config.yaml
 fn read_app_config(app_path: &Path) -> Option<serde_json::Value> {
     let config_path = app_path.join("config.yaml");
@@ -119,173 +820,549 @@ fn count_tables(app_path: &Path) -> usize {
     let mut count = 0;
     for schema_path in paths_to_check {
         if let Ok(content) = std::fs::read_to_string(&schema_path) {
-            count += content.matches("@table").count();
+            count += crate::schemas::parse_schema(&content)
+                .ok()
+                .and_then(|s| s["tables"].as_array().map(|t| t.len()))
+                .unwrap_or(0);
         }
     }
     count
 }
 
+/// List entry for an app, parsed from its config and schemas
+fn app_summary(id: &str, path: &Path) -> serde_json::Value {
+    let config = read_app_config(path);
+    let field = |key: &str| config.as_ref().and_then(|c| c.get(key));
+    json!({
+        "app_id": id,
+        "name": field("name").and_then(|v| v.as_str()).unwrap_or(id),
+        "enabled": field("enabled").and_then(|v| v.as_bool()).unwrap_or(false),
+        "has_schema": has_schema(path),
+        "resource_count": count_resources(path),
+        "table_count": count_tables(path),
+        "is_extension": field("extension").and_then(|v| v.as_bool()).unwrap_or(false),
+        "depends_on": dependencies_of(config.as_ref()),
+    })
+}
+
+/// A cached list entry and the modification times it was built from
+struct IndexEntry {
+    watched: Vec<(std::path::PathBuf, Option<std::time::SystemTime>)>,
+    summary: serde_json::Value,
+}
+
+fn metadata_index() -> &'static Mutex<HashMap<String, IndexEntry>> {
+    static INDEX: OnceLock<Mutex<HashMap<String, IndexEntry>>> = OnceLock::new();
+    INDEX.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Files whose changes invalidate an app's list entry: config.yaml, the app
+/// and resources directories (files added or removed) and its schema files
+fn watched_paths(path: &Path) -> Vec<(std::path::PathBuf, Option<std::time::SystemTime>)> {
+    let mut paths = vec![path.to_path_buf(), path.join("config.yaml"), path.join("resources"), path.join("schema.graphql")];
+    let schemas = read_app_config(path)
+        .and_then(|c| c.get("schemas").and_then(|v| v.as_array()).cloned())
+        .unwrap_or_default();
+    paths.extend(schemas.iter().filter_map(|v| v.as_str()).map(|p| path.join(p)));
+    paths
+        .into_iter()
+        .map(|p| {
+            let modified = std::fs::metadata(&p).and_then(|m| m.modified()).ok();
+            (p, modified)
+        })
+        .collect()
+}
+
+/// List entry from the metadata index, rebuilt when a watched file changed
+fn cached_summary(id: &str, path: &Path) -> serde_json::Value {
+    if let Some(entry) = metadata_index().lock().unwrap().get(id) {
+        let fresh = entry
+            .watched
+            .iter()
+            .all(|(p, modified)| std::fs::metadata(p).and_then(|m| m.modified()).ok() == *modified);
+        if fresh {
+            return entry.summary.clone();
+        }
+    }
+    // Stamp before parsing, so a write during the parse invalidates again
+    let watched = watched_paths(path);
+    let summary = app_summary(id, path);
+    metadata_index()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), IndexEntry { watched, summary: summary.clone() });
+    summary
+}
+
 impl Resource for AppsResource {
     fn name(&self) -> &str {
         "apps"
     }
 
-    get!(_request, ctx, {
-        let apps_path = get_apps_directory();
+    get!(request, ctx, {
+        crate::metered!(request, "apps", "GET", {
+            let apps_path = get_apps_directory();
 
-        // Single app by path ID
-        if let Some(app_id) = ctx.path_id() {
-            let app_path = apps_path.join(app_id);
-            if !app_path.is_dir() {
-                return not_found(&format!("Application '{}' not found", app_id));
+            // GET /apps/trash
+            if request.uri().path().trim_end_matches('/').ends_with("/apps/trash") {
+                let trash: Vec<serde_json::Value> = trash_entries()
+                    .into_iter()
+                    .map(|(entry, app_id, deleted_at)| json!({
+                        "id": entry,
+                        "app_id": app_id,
+                        "deletedAt": deleted_at,
+                        "purgeAt": deleted_at + TRASH_RETENTION_DAYS * 86400,
+                        "restorable": !apps_path.join(&app_id).exists(),
+                    }))
+                    .collect();
+                return reply().json(json!({
+                    "retentionDays": TRASH_RETENTION_DAYS,
+                    "apps": trash,
+                }));
             }
 
-            let config = read_app_config(&app_path);
-            let files = list_app_files(&app_path);
-            let has_schema = has_schema(&app_path);
-            let resource_count = count_resources(&app_path);
-            let table_count = count_tables(&app_path);
-
-            return reply().json(json!({
-                "app_id": app_id,
-                "config": config,
-                "files": files,
-                "has_schema": has_schema,
-                "resource_count": resource_count,
-                "table_count": table_count,
-            }));
-        }
+            // GET /apps/graph
+            if request.uri().path().trim_end_matches('/').ends_with("/apps/graph") {
+                let graph = tokio::task::spawn_blocking(app_graph)
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Graph task failed: {}", e)))?;
+                let (order, cycles) = load_order(&graph);
+                let apps: Vec<serde_json::Value> = graph
+                    .iter()
+                    .map(|(id, enabled, deps)| {
+                        let missing: Vec<&String> = deps.iter().filter(|d| !graph.iter().any(|(g, _, _)| g == *d)).collect();
+                        let disabled: Vec<&String> = deps.iter().filter(|d| graph.iter().any(|(g, on, _)| g == *d && !on)).collect();
+                        json!({
+                            "app_id": id,
+                            "enabled": enabled,
+                            "depends_on": deps,
+                            "dependents": dependents(&graph, id),
+                            "missing": missing,
+                            "disabled": disabled,
+                        })
+                    })
+                    .collect();
+                return reply().json(json!({
+                    "apps": apps,
+                    "order": order,
+                    "cycles": cycles,
+                }));
+            }
 
-        // List all apps
-        let entries = std::fs::read_dir(&apps_path)
-            .map_err(|e| YetiError::Internal(format!("Cannot read applications dir: {}", e)))?;
+            // GET /apps/{id}/export
+            if let Some((app_id, "export")) = app_action(request.uri().path()) {
+                validate_identifier(app_id, "app_id")?;
+                if !apps_path.join(app_id).is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                let owner = crate::actor!(request);
+                let id = app_id.to_string();
+                let archive = tokio::task::spawn_blocking(move || crate::promote::archive_app(&id, &owner))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Export task failed: {}", e)))?
+                    .map_err(YetiError::Internal)?;
+                let sha256: String = Sha256::digest(&archive).iter().map(|b| format!("{:02x}", b)).collect();
 
-        let mut apps = Vec::new();
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
+                return reply()
+                    .header("Content-Type", "application/gzip")
+                    .header("Content-Disposition", &format!("attachment; filename=\"{}.tar.gz\"", app_id))
+                    .header("X-Content-SHA256", &sha256)
+                    .body(archive);
             }
-            let dir_name = entry.file_name();
-            let Some(id) = dir_name.to_str() else { continue };
 
-            // Skip hidden directories and non-app directories
-            if id.starts_with('.') {
-                continue;
+            // Single app by path ID
+            if let Some(app_id) = ctx.path_id() {
+                let app_path = apps_path.join(app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+
+                let config = read_app_config(&app_path);
+                let files = list_app_files(&app_path);
+                let has_schema = has_schema(&app_path);
+                let resource_count = count_resources(&app_path);
+                let table_count = count_tables(&app_path);
+
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "config": config,
+                    "files": files,
+                    "has_schema": has_schema,
+                    "resource_count": resource_count,
+                    "table_count": table_count,
+                    "domains": crate::domains::for_app(app_id),
+                }));
             }
 
-            let config = read_app_config(&path);
-            let name = config
-                .as_ref()
-                .and_then(|c| c.get("name"))
-                .and_then(|v| v.as_str())
-                .unwrap_or(id)
-                .to_string();
-            let enabled = config
-                .as_ref()
-                .and_then(|c| c.get("enabled"))
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            let has_schema = has_schema(&path);
-            let resource_count = count_resources(&path);
-            let table_count = count_tables(&path);
-            let is_extension = config
-                .as_ref()
-                .and_then(|c| c.get("extension"))
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-
-            apps.push(json!({
-                "app_id": id,
-                "name": name,
-                "enabled": enabled,
-                "has_schema": has_schema,
-                "resource_count": resource_count,
-                "table_count": table_count,
-                "is_extension": is_extension,
-            }));
-        }
+            // List all apps
+            let entries = std::fs::read_dir(&apps_path)
+                .map_err(|e| YetiError::Internal(format!("Cannot read applications dir: {}", e)))?;
+
+            let mut apps = Vec::new();
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !path.is_dir() {
+                    continue;
+                }
+                let dir_name = entry.file_name();
+                let Some(id) = dir_name.to_str() else { continue };
+
+                // Skip hidden directories and non-app directories
+                if id.starts_with('.') {
+                    continue;
+                }
+
+                apps.push(cached_summary(id, &path));
+            }
+            {
+                let mut index = metadata_index().lock().unwrap();
+                index.retain(|id, _| apps.iter().any(|a| a["app_id"] == id.as_str()));
+            }
 
-        apps.sort_by(|a, b| {
-            let a_id = a["app_id"].as_str().unwrap_or("");
-            let b_id = b["app_id"].as_str().unwrap_or("");
-            a_id.cmp(b_id)
-        });
+            apps.sort_by(|a, b| {
+                let a_id = a["app_id"].as_str().unwrap_or("");
+                let b_id = b["app_id"].as_str().unwrap_or("");
+                a_id.cmp(b_id)
+            });
 
-        reply().json(json!(apps))
+            reply().json(json!(apps))
+        })
     });
 
-    post!(request, _ctx, {
-        let body = request.json_value()?;
-        let app_id = body.require_str("id")?;
+    post!(request, ctx, {
+        crate::metered!(request, "apps", "POST", {
+            // POST /apps/trash/{entry}/restore (body optional)
+            if let Some((entry, Some("restore"))) = trash_path(request.uri().path()) {
+                let (original_id, _) = parse_trash_entry(entry).unwrap_or((entry, 0));
+                let trashed = trash_directory().join(entry);
+                if !trashed.is_dir() {
+                    return not_found(&format!("Trash entry '{}' not found", entry));
+                }
+                let app_id = request.json_value().ok()
+                    .and_then(|b| b.get("app_id").and_then(|v| v.as_str()).map(String::from))
+                    .unwrap_or_else(|| original_id.to_string());
+                validate_identifier(&app_id, "app_id")?;
+                check_reserved(&app_id)?;
+                let app_path = get_apps_directory().join(&app_id);
+                if app_path.exists() {
+                    return bad_request(&format!("Application '{}' already exists; restore under another app_id", app_id));
+                }
 
-        validate_identifier(&app_id, "app_id")?;
+                std::fs::rename(&trashed, &app_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to restore app: {}", e)))?;
+                if app_id != original_id {
+                    if let Err(e) = rewrite_config(&app_path, &[("app_id", app_id.as_str())], &[]) {
+                        yeti_log!(warn, "Restored '{}' as '{}' but could not update config: {}", entry, app_id, e);
+                    }
+                }
+                if let Err(e) = crate::env::reload_app(&app_path) {
+                    yeti_log!(warn, "Restored '{}' but reload failed: {}", app_id, e);
+                }
 
-        let name = body.get("name").and_then(|v| v.as_str()).unwrap_or(&app_id);
-        let description = body.get("description").and_then(|v| v.as_str()).unwrap_or("A new Yeti application");
+                crate::audit!(request, ctx, "app.restore", &app_id, json!({"from": entry}));
 
-        let apps_path = get_apps_directory();
-        let app_path = apps_path.join(&app_id);
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "restored": true,
+                    "from": entry,
+                }));
+            }
 
-        if app_path.exists() {
-            return bad_request(&format!("Application '{}' already exists", app_id));
-        }
+            // POST /apps/{id}/validate (body optional)
+            if let Some((app_id, "validate")) = app_action(request.uri().path()) {
+                validate_identifier(app_id, "app_id")?;
+                let app_path = get_apps_directory().join(app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                let content = match request.json_value().ok().and_then(|b| b.get("content").and_then(|v| v.as_str()).map(String::from)) {
+                    Some(content) => content,
+                    None => std::fs::read_to_string(app_path.join("config.yaml"))
+                        .map_err(|e| YetiError::Internal(format!("Failed to read config: {}", e)))?,
+                };
+                let id = app_id.to_string();
+                let (errors, warnings) = tokio::task::spawn_blocking(move || check_config(&id, &app_path, &content))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Validation task failed: {}", e)))?;
 
-        let template = body.get("template").and_then(|v| v.as_str());
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "valid": errors.is_empty(),
+                    "errors": errors,
+                    "warnings": warnings,
+                }));
+            }
 
-        if template == Some("application-template") {
-            // Copy from application-template
-            let template_path = apps_path.join("application-template");
-            if !template_path.is_dir() {
-                return Err(YetiError::Internal("Application template not found".to_string()));
+            // POST /apps/{id}/check[?clippy=true]
+            if let Some((app_id, "check")) = app_action(request.uri().path()) {
+                validate_identifier(app_id, "app_id")?;
+                if !get_apps_directory().join(app_id).is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                let clippy = parse_query_param(request.uri().query().unwrap_or(""), "clippy").is_some_and(|v| v == "true");
+                let (id, owner) = (app_id.to_string(), crate::actor!(request));
+                let result = tokio::task::spawn_blocking(move || crate::builds::check(&id, clippy, &owner))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Check task failed: {}", e)))??;
+                return reply().json(result);
             }
 
-            copy_template(&template_path, &app_path)
-                .map_err(|e| YetiError::Internal(format!("Failed to copy template: {}", e)))?;
+            // POST /apps/{id}/reload
+            if let Some((app_id, "reload")) = app_action(request.uri().path()) {
+                validate_identifier(app_id, "app_id")?;
+                let app_path = get_apps_directory().join(app_id);
+                let config_path = app_path.join("config.yaml");
+                if !config_path.is_file() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if crate::builds::is_building(app_id) {
+                    return bad_request(&format!("A build of '{}' is running", app_id));
+                }
+                let content = std::fs::read_to_string(&config_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to read config: {}", e)))?;
+                let (errors, warnings) = check_config(app_id, &app_path, &content);
+                let force = parse_query_param(request.uri().query().unwrap_or(""), "force").as_deref() == Some("true");
+                if !errors.is_empty() && !force {
+                    return reply().code(422).json(json!({
+                        "error": "Config is invalid; fix the errors or retry with ?force=true",
+                        "errors": errors,
+                        "warnings": warnings,
+                    }));
+                }
+                crate::env::reload_app(&app_path).map_err(YetiError::Internal)?;
 
-            // Update config.yaml with new app_id/name/description
-            let config_path = app_path.join("config.yaml");
-            let content = std::fs::read_to_string(&config_path)
-                .map_err(|e| YetiError::Internal(format!("Failed to read template config: {}", e)))?;
-            let mut yaml: serde_yaml::Value = serde_yaml::from_str(&content)
-                .map_err(|e| YetiError::Internal(format!("Failed to parse template config: {}", e)))?;
-
-            if let Some(map) = yaml.as_mapping_mut() {
-                map.insert(
-                    serde_yaml::Value::String("app_id".to_string()),
-                    serde_yaml::Value::String(app_id.to_string()),
-                );
-                map.insert(
-                    serde_yaml::Value::String("name".to_string()),
-                    serde_yaml::Value::String(name.to_string()),
-                );
-                map.insert(
-                    serde_yaml::Value::String("description".to_string()),
-                    serde_yaml::Value::String(description.to_string()),
-                );
+                crate::audit!(request, ctx, "app.reload", app_id, json!({"forced": force && !errors.is_empty()}));
+
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "reloaded": true,
+                    "warnings": warnings,
+                }));
             }
 
-            let new_content = serde_yaml::to_string(&yaml)
-                .map_err(|e| YetiError::Internal(format!("Failed to serialize config: {}", e)))?;
-            std::fs::write(&config_path, &new_content)
-                .map_err(|e| YetiError::Internal(format!("Failed to write config: {}", e)))?;
+            let body = request.json_value()?;
 
-            reply().code(201).json(json!({
-                "app_id": app_id,
-                "name": name,
-                "description": description,
-                "template": "application-template",
-                "created": true,
-            }))
-        } else {
-            // Create blank app from inline template
-            std::fs::create_dir_all(app_path.join("resources"))
-                .map_err(|e| YetiError::Internal(format!("Failed to create directories: {}", e)))?;
-            std::fs::create_dir_all(app_path.join("web"))
-                .map_err(|e| YetiError::Internal(format!("Failed to create web dir: {}", e)))?;
-
-            let config_content = format!(
-                r#"# Application metadata
+            // POST /apps/{id}/duplicate
+            if let Some((source_id, "duplicate")) = app_action(request.uri().path()) {
+                validate_identifier(source_id, "app_id")?;
+                let app_id = body.require_str("app_id")?;
+                validate_identifier(&app_id, "app_id")?;
+                check_reserved(&app_id)?;
+
+                let apps_path = get_apps_directory();
+                let source_path = apps_path.join(source_id);
+                let app_path = apps_path.join(&app_id);
+                if !source_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", source_id));
+                }
+                if app_path.exists() {
+                    return bad_request(&format!("Application '{}' already exists", app_id));
+                }
+                let keep_git = body.get("keepGit").and_then(|v| v.as_bool()).unwrap_or(false);
+                let name = body.get("name").and_then(|v| v.as_str()).unwrap_or(&app_id).to_string();
+
+                let (src, dst) = (source_path.clone(), app_path.clone());
+                tokio::task::spawn_blocking(move || copy_app(&src, &dst, keep_git))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Copy task failed: {}", e)))?
+                    .map_err(|e| {
+                        let _ = std::fs::remove_dir_all(&app_path);
+                        YetiError::Internal(format!("Failed to copy app: {}", e))
+                    })?;
+                if let Err(e) = rewrite_config(&app_path, &[("app_id", app_id.as_str()), ("name", name.as_str())], &[]) {
+                    let _ = std::fs::remove_dir_all(&app_path);
+                    return Err(YetiError::Internal(e));
+                }
+
+                crate::audit!(request, ctx, "app.duplicate", &app_id, json!({
+                    "source": source_id,
+                    "keepGit": keep_git,
+                }));
+
+                return reply().code(201).json(json!({
+                    "app_id": app_id,
+                    "name": name,
+                    "source": source_id,
+                    "keepGit": keep_git,
+                    "created": true,
+                }));
+            }
+
+            // POST /apps/{id}/rename
+            if let Some((old_id, "rename")) = app_action(request.uri().path()) {
+                validate_identifier(old_id, "app_id")?;
+                let app_id = body.require_str("app_id")?;
+                validate_identifier(&app_id, "app_id")?;
+                check_reserved(&app_id)?;
+                if old_id == "yeti-applications" {
+                    return bad_request("Cannot rename the yeti-applications app");
+                }
+                if old_id == app_id {
+                    return bad_request("New app_id is the same as the current one");
+                }
+
+                let apps_path = get_apps_directory();
+                let old_path = apps_path.join(old_id);
+                let app_path = apps_path.join(&app_id);
+                if !old_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", old_id));
+                }
+                if app_path.exists() {
+                    return bad_request(&format!("Application '{}' already exists", app_id));
+                }
+                if crate::builds::is_building(old_id) {
+                    return bad_request(&format!("A build of '{}' is running", old_id));
+                }
+
+                let old_prefix = format!("/{}", old_id);
+                let new_prefix = format!("/{}", app_id);
+                let self_prefix = read_app_config(&old_path)
+                    .and_then(|c| c.get("route_prefix").and_then(|v| v.as_str()).map(String::from))
+                    .is_some_and(|p| p.trim_end_matches('/') == old_prefix);
+
+                std::fs::rename(&old_path, &app_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to move app directory: {}", e)))?;
+                let mut values = vec![("app_id", app_id.as_str())];
+                if self_prefix {
+                    values.push(("route_prefix", new_prefix.as_str()));
+                }
+                if let Err(e) = rewrite_config(&app_path, &values, &[]) {
+                    let _ = std::fs::rename(&app_path, &old_path);
+                    return Err(YetiError::Internal(e));
+                }
+
+                // Everything keyed by the app id follows; failures here leave
+                // the app renamed and are reported as warnings
+                let mut warnings = Vec::new();
+                let cache_moved = crate::cache::rename_app(old_id, &app_id).unwrap_or_else(|e| {
+                    warnings.push(e);
+                    false
+                });
+                let domains_moved = crate::domains::rename_app(old_id, &app_id).unwrap_or_else(|e| {
+                    warnings.push(e);
+                    0
+                });
+                let secrets = crate::env::read_app_secrets(old_id);
+                let secrets_moved = secrets.len();
+                if !secrets.is_empty() {
+                    match crate::env::write_app_secrets(&app_id, &secrets) {
+                        Ok(()) => {
+                            let _ = crate::env::write_app_secrets(old_id, &serde_json::Map::new());
+                        }
+                        Err(e) => warnings.push(e),
+                    }
+                }
+                if let Err(e) = crate::keys::rename_app(old_id, &app_id) {
+                    warnings.push(e);
+                }
+                if let Err(e) = crate::env::reload_app(&app_path) {
+                    warnings.push(e);
+                }
+                for warning in &warnings {
+                    yeti_log!(warn, "Rename of '{}' to '{}': {}", old_id, app_id, warning);
+                }
+
+                crate::audit!(request, ctx, "app.rename", &app_id, json!({
+                    "from": old_id,
+                }));
+
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "from": old_id,
+                    "routePrefix": if self_prefix { Some(new_prefix) } else { None },
+                    "cacheMoved": cache_moved,
+                    "domainsUpdated": domains_moved,
+                    "secretsMoved": secrets_moved,
+                    "warnings": warnings,
+                }));
+            }
+
+            let app_id = body.require_str("id")?;
+
+            validate_identifier(&app_id, "app_id")?;
+            check_reserved(&app_id)?;
+
+            let name = body.get("name").and_then(|v| v.as_str()).unwrap_or(&app_id);
+            let description = body.get("description").and_then(|v| v.as_str()).unwrap_or("A new Yeti application");
+
+            let apps_path = get_apps_directory();
+            let app_path = apps_path.join(&app_id);
+
+            if app_path.exists() {
+                return bad_request(&format!("Application '{}' already exists", app_id));
+            }
+
+            // `"template": true` means the default template
+            let template = match body.get("template") {
+                Some(serde_json::Value::Bool(true)) => Some(crate::templates::DEFAULT_TEMPLATE.to_string()),
+                Some(v) => v.as_str().map(String::from),
+                None => None,
+            };
+
+            if let Some(template_name) = template {
+                let Some(entry) = crate::templates::find(&template_name) else {
+                    return bad_request(&format!("Unknown template '{}'", template_name));
+                };
+
+                // Fetch the template (git templates are cloned first)
+                let owner = crate::actor!(request);
+                let (src, cleanup) = tokio::task::spawn_blocking(move || crate::templates::fetch(&entry, &owner))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Template task failed: {}", e)))?
+                    .map_err(YetiError::Internal)?;
+                let remove_clone = || {
+                    if let Some(dir) = &cleanup {
+                        let _ = std::fs::remove_dir_all(dir);
+                    }
+                };
+
+                // Built-in and template.yaml variables, substituted while copying
+                let provided = body.get("variables").and_then(|v| v.as_object()).cloned().unwrap_or_default();
+                let builtins = [("app_id", app_id.as_str()), ("name", name), ("description", description)];
+                let vars = match crate::templates::resolve_variables(&src, &provided, &builtins) {
+                    Ok(vars) => vars,
+                    Err(e) => {
+                        remove_clone();
+                        return bad_request(&e);
+                    }
+                };
+
+                let (from, dst) = (src.clone(), app_path.clone());
+                let copied = tokio::task::spawn_blocking(move || copy_template(&from, &dst, &vars)).await;
+                remove_clone();
+                if let Err(e) = copied.map_err(|e| std::io::Error::other(e.to_string())).and_then(|r| r) {
+                    let _ = std::fs::remove_dir_all(&app_path);
+                    return Err(YetiError::Internal(format!("Failed to copy template: {}", e)));
+                }
+
+                // Update config.yaml with new app_id/name/description
+                if let Err(e) = rewrite_config(
+                    &app_path,
+                    &[("app_id", app_id.as_str()), ("name", name), ("description", description)],
+                    &["template"],
+                ) {
+                    let _ = std::fs::remove_dir_all(&app_path);
+                    return Err(YetiError::Internal(e));
+                }
+
+                crate::audit!(request, ctx, "app.create", &app_id, json!({"template": template_name}));
+
+                reply().code(201).json(json!({
+                    "app_id": app_id,
+                    "name": name,
+                    "description": description,
+                    "template": template_name,
+                    "created": true,
+                }))
+            } else {
+                // Create blank app from inline template
+                std::fs::create_dir_all(app_path.join("resources"))
+                    .map_err(|e| YetiError::Internal(format!("Failed to create directories: {}", e)))?;
+                std::fs::create_dir_all(app_path.join("web"))
+                    .map_err(|e| YetiError::Internal(format!("Failed to create web dir: {}", e)))?;
+
+                let config_content = format!(
+                    r#"# Application metadata
 name: "{}"
 app_id: "{}"
 version: "1.0.0"
@@ -303,107 +1380,162 @@ static_files:
   path: web
   route: /
 "#,
-                name, app_id, description
-            );
+                    name, app_id, description
+                );
 
-            std::fs::write(app_path.join("config.yaml"), &config_content)
-                .map_err(|e| YetiError::Internal(format!("Failed to write config: {}", e)))?;
+                std::fs::write(app_path.join("config.yaml"), &config_content)
+                    .map_err(|e| YetiError::Internal(format!("Failed to write config: {}", e)))?;
 
-            let index_html = format!(
-                r#"<!DOCTYPE html>
+                let index_html = format!(
+                    r#"<!DOCTYPE html>
 <html><head><title>{}</title></head>
 <body><h1>{}</h1><p>{}</p></body>
 </html>"#,
-                name, name, description
-            );
-            std::fs::write(app_path.join("web").join("index.html"), &index_html)
-                .map_err(|e| YetiError::Internal(format!("Failed to write index.html: {}", e)))?;
+                    name, name, description
+                );
+                std::fs::write(app_path.join("web").join("index.html"), &index_html)
+                    .map_err(|e| YetiError::Internal(format!("Failed to write index.html: {}", e)))?;
 
-            reply().code(201).json(json!({
-                "app_id": app_id,
-                "name": name,
-                "description": description,
-                "created": true,
-            }))
-        }
+                crate::audit!(request, ctx, "app.create", &app_id, json!({}));
+
+                reply().code(201).json(json!({
+                    "app_id": app_id,
+                    "name": name,
+                    "description": description,
+                    "created": true,
+                }))
+            }
+        })
     });
 
     put!(request, ctx, {
-        let app_id = ctx.require_id()?.to_string();
-        let body = request.json_value()?;
+        crate::metered!(request, "apps", "PUT", {
+            let app_id = ctx.require_id()?.to_string();
+            let body = request.json_value()?;
 
-        let apps_path = get_apps_directory();
-        let app_path = apps_path.join(&app_id);
-        let config_path = app_path.join("config.yaml");
+            let apps_path = get_apps_directory();
+            let app_path = apps_path.join(&app_id);
+            let config_path = app_path.join("config.yaml");
 
-        if !config_path.exists() {
-            return not_found(&format!("Application '{}' not found", app_id));
-        }
+            if !config_path.exists() {
+                return not_found(&format!("Application '{}' not found", app_id));
+            }
 
-        // Read existing config
-        let existing_content = std::fs::read_to_string(&config_path)
-            .map_err(|e| YetiError::Internal(format!("Failed to read config: {}", e)))?;
-        let mut existing: serde_yaml::Value = serde_yaml::from_str(&existing_content)
-            .map_err(|e| YetiError::Internal(format!("Failed to parse config: {}", e)))?;
+            // Read existing config
+            let existing_content = std::fs::read_to_string(&config_path)
+                .map_err(|e| YetiError::Internal(format!("Failed to read config: {}", e)))?;
+            let mut existing: serde_yaml::Value = serde_yaml::from_str(&existing_content)
+                .map_err(|e| YetiError::Internal(format!("Failed to parse config: {}", e)))?;
 
-        // Convert body to serde_yaml::Value for merging
-        let body_str = serde_json::to_string(&body)
-            .map_err(|e| YetiError::Internal(format!("JSON serialize failed: {}", e)))?;
-        let updates: serde_yaml::Value = serde_yaml::from_str(&body_str)
-            .map_err(|e| YetiError::Internal(format!("YAML parse failed: {}", e)))?;
+            // Convert body to serde_yaml::Value for merging
+            let body_str = serde_json::to_string(&body)
+                .map_err(|e| YetiError::Internal(format!("JSON serialize failed: {}", e)))?;
+            let updates: serde_yaml::Value = serde_yaml::from_str(&body_str)
+                .map_err(|e| YetiError::Internal(format!("YAML parse failed: {}", e)))?;
 
-        // Merge updates into existing (top-level keys only)
-        if let (Some(existing_map), Some(updates_map)) = (existing.as_mapping_mut(), updates.as_mapping()) {
-            for (key, value) in updates_map {
-                existing_map.insert(key.clone(), value.clone());
+            // Merge updates into existing: deep by default, top-level keys only with ?replace=true
+            let query = request.uri().query().unwrap_or("");
+            if parse_query_param(query, "replace").as_deref() == Some("true") {
+                if let (Some(existing_map), Some(updates_map)) = (existing.as_mapping_mut(), updates.as_mapping()) {
+                    for (key, value) in updates_map {
+                        existing_map.insert(key.clone(), value.clone());
+                    }
+                }
+            } else {
+                deep_merge(&mut existing, &updates);
             }
-        }
 
-        // Write back
-        let new_content = serde_yaml::to_string(&existing)
-            .map_err(|e| YetiError::Internal(format!("Failed to serialize config: {}", e)))?;
-        std::fs::write(&config_path, &new_content)
-            .map_err(|e| YetiError::Internal(format!("Failed to write config: {}", e)))?;
-
-        // Return updated config as JSON
-        let json_str = serde_json::to_string(&existing)
-            .map_err(|e| YetiError::Internal(format!("JSON serialize failed: {}", e)))?;
-        let json_val: serde_json::Value = serde_json::from_str(&json_str)
-            .map_err(|e| YetiError::Internal(format!("JSON parse failed: {}", e)))?;
-
-        reply().json(json!({
-            "app_id": app_id,
-            "config": json_val,
-            "updated": true,
-        }))
+            // Validate, then write back
+            let new_content = serde_yaml::to_string(&existing)
+                .map_err(|e| YetiError::Internal(format!("Failed to serialize config: {}", e)))?;
+            let (errors, warnings) = check_config(&app_id, &app_path, &new_content);
+            if !errors.is_empty() && parse_query_param(query, "force").as_deref() != Some("true") {
+                return reply().code(422).json(json!({
+                    "error": "Config is invalid; fix the errors or retry with ?force=true",
+                    "errors": errors,
+                    "warnings": warnings,
+                }));
+            }
+            std::fs::write(&config_path, &new_content)
+                .map_err(|e| YetiError::Internal(format!("Failed to write config: {}", e)))?;
+
+            // Return updated config as JSON
+            let json_str = serde_json::to_string(&existing)
+                .map_err(|e| YetiError::Internal(format!("JSON serialize failed: {}", e)))?;
+            let json_val: serde_json::Value = serde_json::from_str(&json_str)
+                .map_err(|e| YetiError::Internal(format!("JSON parse failed: {}", e)))?;
+
+            let keys: Vec<&str> = body.as_object().map(|m| m.keys().map(|k| k.as_str()).collect()).unwrap_or_default();
+            crate::audit!(request, ctx, "app.update", &app_id, json!({"keys": keys}));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "config": json_val,
+                "updated": true,
+                "errors": errors,
+                "warnings": warnings,
+            }))
+        })
     });
 
-    delete!(_request, ctx, {
-        let app_id = ctx.require_id()?.to_string();
+    delete!(request, ctx, {
+        crate::metered!(request, "apps", "DELETE", {
+            // DELETE /apps/trash/{entry}
+            if let Some((entry, None)) = trash_path(request.uri().path()) {
+                let trashed = trash_directory().join(entry);
+                if !trashed.is_dir() {
+                    return not_found(&format!("Trash entry '{}' not found", entry));
+                }
+                std::fs::remove_dir_all(&trashed)
+                    .map_err(|e| YetiError::Internal(format!("Failed to purge app: {}", e)))?;
+                let (app_id, _) = parse_trash_entry(entry).unwrap_or((entry, 0));
+                crate::audit!(request, ctx, "app.purge", app_id, json!({"entry": entry}));
+                return reply().json(json!({"purged": true, "id": entry}));
+            }
 
-        // Cannot delete self
-        if app_id == "yeti-applications" {
-            return bad_request("Cannot delete the yeti-applications app");
-        }
+            let app_id = ctx.require_id()?.to_string();
+
+            // Cannot delete self
+            if app_id == "yeti-applications" {
+                return bad_request("Cannot delete the yeti-applications app");
+            }
 
-        let apps_path = get_apps_directory();
-        let app_path = apps_path.join(&app_id);
+            let apps_path = get_apps_directory();
+            let app_path = apps_path.join(&app_id);
 
-        if !app_path.is_dir() {
-            return not_found(&format!("Application '{}' not found", app_id));
-        }
+            if !app_path.is_dir() {
+                return not_found(&format!("Application '{}' not found", app_id));
+            }
 
-        // Remove app directory
-        std::fs::remove_dir_all(&app_path)
-            .map_err(|e| YetiError::Internal(format!("Failed to remove app directory: {}", e)))?;
+            // Refuse while other apps depend on it
+            let query = request.uri().query().unwrap_or("");
+            let depending = dependents(&app_graph(), &app_id);
+            if !depending.is_empty() && parse_query_param(query, "force").as_deref() != Some("true") {
+                return reply().code(409).json(json!({
+                    "error": format!("Apps depend on '{}'; remove the dependency or retry with ?force=true", app_id),
+                    "dependents": depending,
+                }));
+            }
 
-        // Also remove cache directory if it exists
-        let cache_path = get_cache_directory().join(&app_id);
-        if cache_path.is_dir() {
-            let _ = std::fs::remove_dir_all(&cache_path);
-        }
+            // Move the app directory to the trash (or remove it outright)
+            let trash_entry = if parse_query_param(query, "permanent").as_deref() == Some("true") {
+                std::fs::remove_dir_all(&app_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to remove app directory: {}", e)))?;
+                None
+            } else {
+                Some(move_to_trash(&app_id, &app_path).map_err(YetiError::Internal)?)
+            };
 
-        reply().json(json!({"deleted": true, "app_id": app_id}))
+            // Also remove cache directory if it exists
+            let cache_path = get_cache_directory().join(&app_id);
+            if cache_path.is_dir() {
+                let _ = std::fs::remove_dir_all(&cache_path);
+            }
+
+            crate::audit!(request, ctx, "app.delete", &app_id, json!({"trash": trash_entry}));
+
+            reply().json(json!({"deleted": true, "app_id": app_id, "trash": trash_entry}))
+        })
     });
 }
 
@@ -0,0 +1,211 @@
+//! Audit Log Resource
+//!
+//! Records every mutating admin operation (apps, files, keys, repos, benchmark
+//! runner) in the AuditLog table and serves them back for review.
+//!
+//! | Method | Path         | Description                         |
+//! |--------|--------------|-------------------------------------|
+//! | GET    | /admin/audit | List entries, newest first          |
+//!
+//! Filters: `?actor=`, `?action=` (exact, or a prefix ending in `.` such as
+//! `app.`), `?target=`, `?since=` / `?until=` (RFC 3339 or unix seconds) and
+//! `?limit=` (default 200).
+//!
+//! Handlers record entries with the `audit!` macro once the operation has
+//! succeeded:
+//!
+//! ```ignore
+//! crate::audit!(request, ctx, "key.create", &name, json!({}));
+//! ```
+
+use base64::Engine;
+use std::sync::atomic::{AtomicU64, Ordering};
+use yeti_core::prelude::*;
+
+pub type Audit = AuditResource;
+
+const DEFAULT_LIMIT: usize = 200;
+
+/// The caller of `request`, as recorded in audit entries.
+#[macro_export]
+macro_rules! actor {
+    ($request:expr) => {
+        $crate::audit::actor($request.headers().get("authorization").and_then(|v| v.to_str().ok()))
+    };
+}
+
+/// Append an AuditLog entry for the caller of `request` and publish the
+/// action as an admin event. Failures to write are logged and never fail the
+/// audited operation.
+#[macro_export]
+macro_rules! audit {
+    ($request:expr, $ctx:expr, $action:expr, $target:expr, $details:expr) => {{
+        let actor = $crate::actor!($request);
+        let details: serde_json::Value = $details;
+        $crate::publish!($ctx, $action, $target, details.clone());
+        let entry = $crate::audit::entry(&actor, $action, $target, details);
+        match $ctx.get_table("AuditLog") {
+            Ok(table) => {
+                if let Err(e) = table.put(entry).await {
+                    yeti_log!(warn, "Failed to write audit entry {}: {}", $action, e);
+                }
+            }
+            Err(e) => yeti_log!(warn, "AuditLog table unavailable: {}", e),
+        }
+    }};
+}
+
+/// Who made the request: the basic-auth username, the `sub` (or `email`)
+/// claim of a bearer JWT, or "anonymous".
+pub fn actor(authorization: Option<&str>) -> String {
+    let Some(header) = authorization else {
+        return "anonymous".to_string();
+    };
+    if let Some(encoded) = header.strip_prefix("Basic ") {
+        return base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|creds| creds.split(':').next().map(String::from))
+            .filter(|user| !user.is_empty())
+            .unwrap_or_else(|| "anonymous".to_string());
+    }
+    if let Some(token) = header.strip_prefix("Bearer ") {
+        if let Some(claims) = bearer_claims(token) {
+            for claim in ["sub", "email"] {
+                if let Some(value) = claims.get(claim).and_then(|v| v.as_str()) {
+                    return value.to_string();
+                }
+            }
+        }
+        return "token".to_string();
+    }
+    "anonymous".to_string()
+}
+
+/// The (unverified) payload of a JWT; the auth extension has already
+/// verified the signature by the time a handler runs.
+pub fn bearer_claims(token: &str) -> Option<serde_json::Value> {
+    token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(payload.trim_end_matches('='))
+                .ok()
+        })
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+}
+
+/// Build an AuditLog record. Ids sort by time and stay unique within a
+/// millisecond.
+pub fn entry(actor: &str, action: &str, target: &str, details: serde_json::Value) -> serde_json::Value {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let now = chrono::Utc::now();
+    let seq = SEQ.fetch_add(1, Ordering::Relaxed) % 10_000;
+    json!({
+        "id": format!("{}-{:04}", now.timestamp_millis(), seq),
+        "timestamp": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "actor": actor,
+        "action": action,
+        "target": target,
+        "details": if details.is_null() { None } else { Some(details.to_string()) },
+    })
+}
+
+/// Parse a `since`/`until` bound: RFC 3339 or unix seconds.
+fn parse_time(value: &str) -> std::result::Result<i64, String> {
+    if let Ok(secs) = value.parse::<i64>() {
+        return Ok(secs);
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.timestamp())
+        .map_err(|_| format!("Invalid time '{}': use RFC 3339 or unix seconds", value))
+}
+
+fn entry_time(entry: &serde_json::Value) -> Option<i64> {
+    let ts = entry.get("timestamp")?.as_str()?;
+    chrono::DateTime::parse_from_rfc3339(ts).ok().map(|t| t.timestamp())
+}
+
+fn matches_action(filter: &str, action: &str) -> bool {
+    if filter.ends_with('.') {
+        action.starts_with(filter)
+    } else {
+        action == filter
+    }
+}
+
+#[derive(Default)]
+pub struct AuditResource;
+
+impl Resource for AuditResource {
+    fn name(&self) -> &str {
+        "audit"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "audit", "GET", {
+            let query = request.uri().query().unwrap_or("");
+            let actor = parse_query_param(query, "actor");
+            let action = parse_query_param(query, "action");
+            let target = parse_query_param(query, "target");
+            let since = match parse_query_param(query, "since").map(|s| parse_time(&s)).transpose() {
+                Ok(since) => since,
+                Err(e) => return bad_request(&e),
+            };
+            let until = match parse_query_param(query, "until").map(|s| parse_time(&s)).transpose() {
+                Ok(until) => until,
+                Err(e) => return bad_request(&e),
+            };
+            let limit = parse_query_param(query, "limit")
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_LIMIT);
+
+            let entries = match ctx.get_table("AuditLog") {
+                Ok(table) => table.scan_all().await.unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+
+            let field = |entry: &serde_json::Value, name: &str| -> String {
+                entry.get(name).and_then(|v| v.as_str()).unwrap_or("").to_string()
+            };
+
+            let mut entries: Vec<serde_json::Value> = entries
+                .into_iter()
+                .filter(|e| actor.as_ref().is_none_or(|a| *a == field(e, "actor")))
+                .filter(|e| action.as_ref().is_none_or(|a| matches_action(a, &field(e, "action"))))
+                .filter(|e| target.as_ref().is_none_or(|t| *t == field(e, "target")))
+                .filter(|e| {
+                    let ts = entry_time(e);
+                    since.is_none_or(|s| ts.is_some_and(|ts| ts >= s))
+                        && until.is_none_or(|u| ts.is_some_and(|ts| ts <= u))
+                })
+                .collect();
+
+            // RFC 3339 UTC timestamps with fixed precision sort lexically
+            entries.sort_by(|a, b| field(b, "timestamp").cmp(&field(a, "timestamp")));
+            let total = entries.len();
+            entries.truncate(limit);
+
+            let entries: Vec<serde_json::Value> = entries
+                .into_iter()
+                .map(|mut e| {
+                    if let Some(details) = e.get("details").and_then(|v| v.as_str()) {
+                        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(details) {
+                            e["details"] = parsed;
+                        }
+                    }
+                    e
+                })
+                .collect();
+
+            reply().json(json!({
+                "total": total,
+                "entries": entries,
+            }))
+        })
+    });
+}
+
+register_resource!(AuditResource);
@@ -2,12 +2,53 @@
 //!
 //! Manages benchmark test execution and results aggregation.
 //!
-//! | Method | Path                        | Description                     |
-//! |--------|-----------------------------|---------------------------------|
-//! | GET    | /admin/runner               | Get runner state + configs      |
-//! | POST   | /admin/runner               | Start a benchmark test          |
-//! | GET    | /admin/best-results         | Best result per test from runs  |
+//! | Method | Path                   | Description                      |
+//! |--------|------------------------|----------------------------------|
+//! | GET    | /admin/runner          | Get runner state + configs       |
+//! | POST   | /admin/runner          | Start a test or suite profile    |
+//! | POST   | /admin/runner/progress | Interim snapshot from a binary   |
+//! | POST   | /admin/runner/exclude  | Exclude a test's runs from bests |
+//! | GET    | /admin/best-results    | Best result per test from runs   |
+//!
+//! Runner state transitions (starting → warming → running → finishing → finished →
+//! idle) are written to the RunnerEvent table; subscribe with
+//! `GET /admin/RunnerEvent?stream=ws` for push updates. A launch is "warming" for
+//! TestConfig `warmupSecs` (passed to the binary as `--warmup`) before it is
+//! measured, and "finishing" once the binary posts a phase notice
+//! (`{"type": "phase", "phase": "reporting", "budgetSecs": 27}`) to
+//! /admin/runner/progress; vector index waits and record teardown are announced
+//! the same way, so they never count against the run's duration. A launch still
+//! running past warmup + duration, or past its latest announced budget, gets
+//! SIGTERM and then SIGKILL.
+//! While a test runs, the binary posts a snapshot (RPS, errors, p95) every second
+//! to /admin/runner/progress, republished as "progress" RunnerEvents and, at most
+//! every 10 seconds, as a `benchmark.progress` admin event.
+//!
+//! Besides the built-in tests, apps can declare endpoints under `benchmarks:` in
+//! their config.yaml; these run via the generic `load-http` binary with test id
+//! `{app_id}:{name}`.
+//!
+//! TestConfig may carry SLA criteria (`minThroughput`, `maxP95`, `maxErrorRate`);
+//! after each run the runner stores per-criterion pass/fail on the TestRun (`sla`)
+//! and publishes a `benchmark.regression` event when any criterion fails.
+//!
+//! POST takes `{"test": "..."}`, `{"profile": "smoke"}` (runs the profile's tests
+//! back-to-back), or both (one test with the profile's overrides). Profile
+//! duration/VU overrides are applied on top of TestConfig.
+//!
+//! An optional `"env"` label is recorded on the TestRun (default: the hostname)
+//! so best results can be compared per environment.
+//!
+//! Exclude body: `{"test": "rest-read", "env": "ci"}` (`env` optional) marks every
+//! current run of the test `excluded`, leaving it out of best results.
+//!
+//! POST probes the target first (demo app installed and enabled, one authenticated
+//! read succeeds); pass `"skipHealthCheck": true` to bypass. The probe and the
+//! binaries authenticate with `YETI_ADMIN_AUTH`, honouring `YETI_ADMIN_CACERT` and
+//! `YETI_ADMIN_INSECURE` like scheduled tasks.
 
+use base64::Engine;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, OnceLock};
 use yeti_core::prelude::*;
 
@@ -26,29 +67,274 @@ struct TestDef {
 const TESTS: &[TestDef] = &[
     TestDef { id: "rest-read", name: "REST Reads", binary: "load-rest", duration: 30, vus: 50 },
     TestDef { id: "rest-write", name: "REST Writes", binary: "load-rest", duration: 30, vus: 50 },
+    TestDef { id: "rest-bulk-write", name: "REST Bulk Writes", binary: "load-rest", duration: 30, vus: 10 },
     TestDef { id: "rest-update", name: "REST Update", binary: "load-rest", duration: 30, vus: 50 },
+    TestDef { id: "rest-scan", name: "REST Pagination", binary: "load-rest", duration: 30, vus: 20 },
+    TestDef { id: "rest-conn-churn", name: "REST Connection Churn", binary: "load-rest", duration: 30, vus: 50 },
     TestDef { id: "rest-join", name: "REST Join", binary: "load-rest", duration: 30, vus: 50 },
     TestDef { id: "graphql-read", name: "GraphQL Reads", binary: "load-graphql", duration: 30, vus: 50 },
     TestDef { id: "graphql-mutation", name: "GraphQL Mutations", binary: "load-graphql", duration: 30, vus: 50 },
     TestDef { id: "graphql-join", name: "GraphQL Join", binary: "load-graphql", duration: 30, vus: 50 },
     TestDef { id: "vector-embed", name: "Vector Embed", binary: "load-vector", duration: 30, vus: 50 },
+    TestDef { id: "vector-bulk-load", name: "Vector Bulk Load", binary: "load-vector", duration: 600, vus: 20 },
     TestDef { id: "vector-search", name: "Vector Search", binary: "load-vector", duration: 30, vus: 50 },
     TestDef { id: "ws", name: "WebSocket", binary: "load-realtime", duration: 30, vus: 50 },
     TestDef { id: "sse", name: "SSE Streaming", binary: "load-realtime", duration: 30, vus: 50 },
+    TestDef { id: "sse-reconnect", name: "SSE Reconnect", binary: "load-realtime", duration: 60, vus: 50 },
     TestDef { id: "blob-retrieval", name: "150k Blob Retrieval", binary: "load-blob", duration: 30, vus: 50 },
+    TestDef { id: "blob-upload", name: "150k Blob Upload", binary: "load-blob", duration: 30, vus: 20 },
 ];
 
+/// Position of a built-in test in the canonical TESTS order (used for sorting results)
+pub fn test_position(test_id: &str) -> Option<usize> {
+    TESTS.iter().position(|t| t.id == test_id)
+}
+
+/// Target the benchmark binaries hit by default (their --base-url default)
+const BENCH_BASE_URL: &str = "https://localhost";
+
+/// Demo app and table each binary exercises, used by the pre-run health check
+fn bench_target(binary: &str) -> (&'static str, &'static str) {
+    match binary {
+        "load-vector" | "load-blob" => ("demo-vector", "Article"),
+        "load-realtime" => ("demo-realtime", "message"),
+        _ => ("demo-graphql", "Book"),
+    }
+}
+
+/// Binary that runs endpoints declared under `benchmarks:` in an app's config.yaml
+const GENERIC_BINARY: &str = "load-http";
+const DEFAULT_DURATION: u64 = 30;
+const DEFAULT_VUS: u64 = 50;
+
+/// A benchmarkable endpoint declared by an app, addressed as test id "{app_id}:{name}":
+///
+/// ```yaml
+/// benchmarks:
+///   - name: list-products
+///     method: GET
+///     path: /Product?limit=10
+///   - name: create-product
+///     method: POST
+///     path: /Product/
+///     body: '{"id": "{{uuid}}", "name": "Bench {{vu}}"}'
+/// ```
+struct AppTarget {
+    app_id: String,
+    name: String,
+    method: String,
+    /// Full request path including the app's route prefix
+    path: String,
+    body: Option<String>,
+}
+
+/// Read the `benchmarks:` endpoints declared in an app's config.yaml
+fn app_targets(app_id: &str) -> Vec<AppTarget> {
+    let config = std::fs::read_to_string(get_apps_directory().join(app_id).join("config.yaml"))
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok());
+    let Some(config) = config else { return Vec::new() };
+
+    let prefix = config.get("route_prefix")
+        .and_then(|v| v.as_str())
+        .map(|p| p.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("/{}", app_id));
+
+    let Some(entries) = config.get("benchmarks").and_then(|v| v.as_sequence()) else {
+        return Vec::new();
+    };
+
+    entries.iter()
+        .filter_map(|e| {
+            let name = e.get("name")?.as_str()?.to_string();
+            let path = e.get("path")?.as_str()?;
+            let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+            Some(AppTarget {
+                app_id: app_id.to_string(),
+                name,
+                method: e.get("method").and_then(|v| v.as_str()).unwrap_or("GET").to_uppercase(),
+                path: format!("{}{}", prefix, path),
+                body: e.get("body").and_then(|v| v.as_str()).map(String::from),
+            })
+        })
+        .collect()
+}
+
+/// Resolve a "{app_id}:{name}" test id to its declared endpoint
+fn find_app_target(test_id: &str) -> Option<AppTarget> {
+    let (app_id, name) = test_id.split_once(':')?;
+    if validate_identifier(app_id, "app_id").is_err() {
+        return None;
+    }
+    app_targets(app_id).into_iter().find(|t| t.name == name)
+}
+
+/// All endpoints declared across installed apps
+fn list_app_targets() -> Vec<serde_json::Value> {
+    let mut targets = Vec::new();
+    let Ok(entries) = std::fs::read_dir(get_apps_directory()) else { return targets };
+    let mut app_ids: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|id| !id.starts_with('.'))
+        .collect();
+    app_ids.sort();
+
+    for app_id in app_ids {
+        for t in app_targets(&app_id) {
+            targets.push(json!({
+                "id": format!("{}:{}", t.app_id, t.name),
+                "appId": t.app_id,
+                "name": t.name,
+                "method": t.method,
+                "path": t.path,
+                "hasBody": t.body.is_some(),
+            }));
+        }
+    }
+    targets
+}
+
+/// Tests that read pre-existing records and fail without seed data
+const NEEDS_SEED_DATA: &[&str] = &["rest-read", "rest-conn-churn", "rest-join", "graphql-read", "graphql-join"];
+
+/// The app a benchmark targets must be installed and enabled
+fn check_app_enabled(app: &str) -> std::result::Result<(), String> {
+    let app_path = get_apps_directory().join(app);
+    if !app_path.is_dir() {
+        return Err(format!("Required app '{}' is not installed", app));
+    }
+    let enabled = std::fs::read_to_string(app_path.join("config.yaml"))
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+        .and_then(|y| y.get("enabled").and_then(|v| v.as_bool()))
+        .unwrap_or(false);
+    if !enabled {
+        return Err(format!("Required app '{}' is not enabled", app));
+    }
+    Ok(())
+}
+
+/// Probe the target before spawning a benchmark: the demo app must be installed
+/// and enabled, and one authenticated read against it must succeed.
+fn health_check(test_def: &TestDef) -> std::result::Result<(), String> {
+    let (app, table) = bench_target(test_def.binary);
+    check_app_enabled(app)?;
+
+    // The same credentials the binaries inherit for --auth
+    let auth = std::env::var("YETI_ADMIN_AUTH")
+        .ok()
+        .filter(|a| a.contains(':'))
+        .ok_or("YETI_ADMIN_AUTH (user:password) is not set; the health check needs admin credentials")?;
+    let url = format!("{}/{}/{}?limit=1", BENCH_BASE_URL, app, table);
+
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["-s", "--max-time", "5", "-w", "\n%{http_code}"]);
+    if let Ok(ca) = std::env::var("YETI_ADMIN_CACERT") {
+        cmd.arg("--cacert").arg(ca);
+    }
+    if std::env::var("YETI_ADMIN_INSECURE").is_ok_and(|v| v == "true") && crate::cron::is_local(&url) {
+        cmd.arg("-k");
+    }
+    let authorization = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(&auth));
+    let header_path = crate::processes::header_file("benchmarks", &authorization)?;
+    cmd.arg("-H").arg(format!("@{}", header_path.display()));
+    cmd.arg(&url);
+
+    let output = crate::processes::output("benchmark", &format!("health-check:{}", test_def.id), &mut cmd);
+    let _ = std::fs::remove_file(&header_path);
+    let output = output.map_err(|e| format!("Failed to run curl for health check: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Target {} unreachable (curl exit code {})",
+            BENCH_BASE_URL,
+            output.status.code().unwrap_or(-1)
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", stdout.as_ref()));
+    match code.trim() {
+        "401" | "403" => {
+            return Err(format!("Auth check failed against {} (HTTP {})", url, code.trim()));
+        }
+        c if !c.starts_with('2') => {
+            return Err(format!("Health check read {} returned HTTP {}", url, c));
+        }
+        _ => {}
+    }
+
+    if NEEDS_SEED_DATA.contains(&test_def.id) {
+        let has_records = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .map(|v| {
+                let arr = if v.is_array() { v.as_array() } else { v.get("data").and_then(|d| d.as_array()) };
+                arr.is_some_and(|a| !a.is_empty())
+            })
+            .unwrap_or(false);
+        if !has_records {
+            return Err(format!(
+                "No {} records in '{}'. Run a write test first to seed data.",
+                table, app
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Safety valve defaults: abort when 50% of requests fail for 5 consecutive seconds
+const DEFAULT_ABORT_ERROR_RATE: f64 = 0.5;
+const DEFAULT_ABORT_WINDOW_SECS: u64 = 5;
+
+/// Minimum gap between `benchmark.progress` admin events; every snapshot still
+/// goes to RunnerEvent subscribers
+const PROGRESS_EVENT_SECS: f64 = 10.0;
+
+/// How often the monitor checks a running launch for phase changes and its deadline
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Slack past warmup + duration before a launch is stopped, for per-VU setup
+/// before the clock starts and requests still in flight at the end
+const MEASURE_GRACE_SECS: f64 = 10.0;
+
+/// Time between SIGTERM and SIGKILL for the binary to stop its VUs and announce
+/// its "reporting" phase, whose budget then replaces this
+const STOP_GRACE_SECS: f64 = 60.0;
+
+/// Slack on top of the budget a binary announces for a post-measurement phase
+const PHASE_GRACE_SECS: f64 = 10.0;
+
 // ── Runner state (in-memory, shared across requests) ──
 
 #[derive(Clone)]
 struct RunnerState {
-    status: String,       // "idle", "warming", "running"
+    status: String,       // "idle", "starting", "warming", "running", "finishing"
     test_name: Option<String>,
     started_at: Option<f64>,
+    /// Unmeasured warmup before `configured_duration` starts
+    configured_warmup: Option<u64>,
     configured_duration: Option<u64>,
     configured_vus: Option<u64>,
+    abort_error_rate: Option<f64>,
+    abort_window_secs: Option<u64>,
     last_error: Option<String>,
     child_pid: Option<u32>,
+    /// When the current launch is stopped if still running (SIGTERM, then SIGKILL
+    /// STOP_GRACE_SECS later); moved by the binary's phase notices
+    deadline: Option<f64>,
+    /// When SIGTERM was sent to the current launch
+    terminated_at: Option<f64>,
+    /// Suite profile being run, if any
+    profile: Option<String>,
+    /// Tests still to run in the current suite
+    queue: Vec<String>,
+    /// Latest interim snapshot posted by the running binary
+    progress: Option<serde_json::Value>,
+    /// When a snapshot was last published as an admin event
+    progress_published_at: Option<f64>,
 }
 
 impl Default for RunnerState {
@@ -57,10 +343,19 @@ impl Default for RunnerState {
             status: "idle".to_string(),
             test_name: None,
             started_at: None,
+            configured_warmup: None,
             configured_duration: None,
             configured_vus: None,
+            abort_error_rate: None,
+            abort_window_secs: None,
             last_error: None,
             child_pid: None,
+            deadline: None,
+            terminated_at: None,
+            profile: None,
+            queue: Vec::new(),
+            progress: None,
+            progress_published_at: None,
         }
     }
 }
@@ -70,6 +365,146 @@ fn runner_state() -> &'static Arc<Mutex<RunnerState>> {
     STATE.get_or_init(|| Arc::new(Mutex::new(RunnerState::default())))
 }
 
+/// Whether a benchmark (or profile queue) is running
+pub fn runner_busy() -> bool {
+    runner_state().lock().unwrap().status != "idle"
+}
+
+/// Per-test SLA criteria from TestConfig (`minThroughput` req/s, `maxP95` ms,
+/// `maxErrorRate` as a fraction), evaluated against each run's results
+#[derive(Clone, Default)]
+struct Sla {
+    min_throughput: Option<f64>,
+    max_p95: Option<f64>,
+    max_error_rate: Option<f64>,
+}
+
+impl Sla {
+    fn from_config(cfg: &serde_json::Value) -> Self {
+        Self {
+            min_throughput: cfg.get("minThroughput").and_then(|v| v.as_f64()),
+            max_p95: cfg.get("maxP95").and_then(|v| v.as_f64()),
+            max_error_rate: cfg.get("maxErrorRate").and_then(|v| v.as_f64()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min_throughput.is_none() && self.max_p95.is_none() && self.max_error_rate.is_none()
+    }
+
+    /// Pass/fail per criterion plus an overall verdict
+    fn evaluate(&self, results: &serde_json::Value) -> serde_json::Value {
+        let get = |key: &str| results.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let total = get("total");
+        let error_rate = if total > 0.0 { get("errors") / total } else { 0.0 };
+
+        let mut criteria = Vec::new();
+        if let Some(min) = self.min_throughput {
+            let actual = get("throughput");
+            criteria.push(json!({"criterion": "minThroughput", "threshold": min, "actual": actual, "passed": actual >= min}));
+        }
+        if let Some(max) = self.max_p95 {
+            let actual = get("p95");
+            criteria.push(json!({"criterion": "maxP95", "threshold": max, "actual": actual, "passed": actual <= max}));
+        }
+        if let Some(max) = self.max_error_rate {
+            criteria.push(json!({"criterion": "maxErrorRate", "threshold": max, "actual": error_rate, "passed": error_rate <= max}));
+        }
+
+        let passed = criteria.iter().all(|c| c["passed"].as_bool().unwrap_or(false));
+        json!({"passed": passed, "criteria": criteria})
+    }
+}
+
+/// Exit status the benchmark binaries use when the error-rate safety valve trips
+const EXIT_ABORTED: i32 = 2;
+/// Exit status the benchmark binaries use when stopped by SIGINT/SIGTERM (after reporting partial results)
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Describe a non-successful benchmark exit, or None for a clean run
+fn exit_error(state: &RunnerState, status: std::process::ExitStatus) -> Option<String> {
+    let test = state.test_name.clone().unwrap_or_default();
+    match status.code() {
+        Some(0) => None,
+        Some(EXIT_ABORTED) => Some(format!(
+            "Benchmark '{}' aborted: error rate exceeded {:.0}% for {}s",
+            test,
+            state.abort_error_rate.unwrap_or(0.0) * 100.0,
+            state.abort_window_secs.unwrap_or(0),
+        )),
+        Some(EXIT_INTERRUPTED) if state.terminated_at.is_some() => Some(format!(
+            "Benchmark '{}' overran its deadline and was stopped; partial results were recorded",
+            test
+        )),
+        Some(EXIT_INTERRUPTED) => Some(format!(
+            "Benchmark '{}' was interrupted; partial results were recorded",
+            test
+        )),
+        Some(code) => Some(format!("Benchmark '{}' failed with exit code {}", test, code)),
+        None if state.terminated_at.is_some() => Some(format!(
+            "Benchmark '{}' overran its deadline and was killed",
+            test
+        )),
+        None => Some(format!("Benchmark '{}' was terminated by a signal", test)),
+    }
+}
+
+/// RunnerEvent record for a state transition. Admin UIs subscribe with
+/// `GET /admin/RunnerEvent?stream=ws` (or `?stream=sse`) instead of polling GET /admin/runner.
+fn runner_event(state: &RunnerState, status: &str) -> serde_json::Value {
+    let now = now_secs();
+    json!({
+        "id": format!("{}-{}", (now * 1000.0) as u64, status),
+        "status": status,
+        "testName": state.test_name,
+        "timestamp": now,
+        "startedAt": state.started_at,
+        "configuredWarmup": state.configured_warmup,
+        "configuredDuration": state.configured_duration,
+        "configuredVus": state.configured_vus,
+        "profile": state.profile,
+        "queue": state.queue,
+        "progress": state.progress.as_ref().map(|p| p.to_string()),
+        "error": state.last_error,
+    })
+}
+
+/// Parse TestConfig `stages` into (duration_secs, target_vus) pairs.
+/// Accepts a JSON array of `{"duration": 10, "target": 50}` objects, or that
+/// array serialized as a string (the column is stored as String).
+fn parse_stages(value: &serde_json::Value) -> std::result::Result<Vec<(u64, u64)>, String> {
+    let parsed;
+    let value = match value.as_str() {
+        Some(s) if s.trim().is_empty() => return Ok(Vec::new()),
+        Some(s) => {
+            parsed = serde_json::from_str::<serde_json::Value>(s)
+                .map_err(|e| format!("Invalid stages JSON: {}", e))?;
+            &parsed
+        }
+        None => value,
+    };
+    let arr = value.as_array()
+        .ok_or_else(|| "stages must be an array of {duration, target}".to_string())?;
+
+    let mut stages = Vec::new();
+    for stage in arr {
+        let duration = stage.get("duration").and_then(|v| v.as_u64())
+            .ok_or_else(|| "each stage requires an integer 'duration' (seconds)".to_string())?;
+        let target = stage.get("target").and_then(|v| v.as_u64())
+            .ok_or_else(|| "each stage requires an integer 'target' (VUs)".to_string())?;
+        stages.push((duration, target));
+    }
+    Ok(stages)
+}
+
+/// Serialize stages into the benchmark binaries' `--stages` form, e.g. "10s:50,60s:50,10s:0"
+fn stages_arg(stages: &[(u64, u64)]) -> String {
+    stages.iter()
+        .map(|(d, t)| format!("{}s:{}", d, t))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 fn now_secs() -> f64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -77,6 +512,270 @@ fn now_secs() -> f64 {
         .unwrap_or(0.0)
 }
 
+// ── Suite profiles ──
+
+/// Named suite: which tests to run, and overrides applied on top of TestConfig
+struct Profile {
+    id: &'static str,
+    /// Tests in run order; empty means every built-in test
+    tests: &'static [&'static str],
+    duration: Option<u64>,
+    vus: Option<u64>,
+}
+
+const PROFILES: &[Profile] = &[
+    Profile { id: "smoke", tests: &["rest-read", "graphql-read", "vector-search"], duration: Some(5), vus: Some(5) },
+    Profile { id: "standard", tests: &[], duration: None, vus: None },
+    Profile { id: "soak", tests: &["rest-read", "rest-write", "graphql-read"], duration: Some(1800), vus: None },
+];
+
+// ── Launch resolution ──
+
+/// A fully resolved benchmark invocation. Suites resolve every launch up front
+/// so the monitor task can run them back-to-back without touching TestConfig.
+struct Launch {
+    test_id: String,
+    run_id: String,
+    /// Binary path (or bare name when found in PATH)
+    binary: String,
+    args: Vec<String>,
+    /// Unmeasured seconds before `duration` (0 for load-realtime, which has no warmup)
+    warmup: u64,
+    duration: u64,
+    vus: u64,
+    stages: Vec<(u64, u64)>,
+    abort_error_rate: f64,
+    abort_window_secs: u64,
+    sla: Sla,
+}
+
+/// Locate a benchmark binary: cargo build output first, then PATH
+fn find_binary(binary: &str) -> std::result::Result<String, String> {
+    let root = get_root_directory();
+    let bin_path = root.join("applications/admin/benchmarks/target/release").join(binary);
+    if bin_path.exists() {
+        return Ok(bin_path.to_string_lossy().to_string());
+    }
+
+    let in_path = std::process::Command::new("which")
+        .arg(binary)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if in_path {
+        Ok(binary.to_string())
+    } else {
+        Err(format!(
+            "Benchmark binary '{}' not found. Expected at {} or in PATH.",
+            binary,
+            bin_path.display()
+        ))
+    }
+}
+
+/// Resolve a test id against TESTS or app-declared targets, apply TestConfig and
+/// then profile overrides, health-check the target, and build the command line.
+fn resolve_launch(
+    test_id: &str,
+    cfg: Option<&serde_json::Value>,
+    profile: Option<&Profile>,
+    env: Option<&str>,
+    skip_health_check: bool,
+) -> std::result::Result<Launch, String> {
+    // A built-in TestDef, or "{app_id}:{name}" declared in an app's config.yaml
+    let test_def = TESTS.iter().find(|t| t.id == test_id);
+    let app_target = match test_def {
+        Some(_) => None,
+        None => Some(find_app_target(test_id).ok_or_else(|| format!("Unknown test: {}", test_id))?),
+    };
+    let binary = test_def.map(|t| t.binary).unwrap_or(GENERIC_BINARY);
+    let (mut duration, mut vus) = test_def
+        .map(|t| (t.duration, t.vus))
+        .unwrap_or((DEFAULT_DURATION, DEFAULT_VUS));
+
+    // Fail fast instead of producing a run full of connection errors
+    if !skip_health_check {
+        let check = match (test_def, &app_target) {
+            (Some(def), _) => health_check(def),
+            (None, Some(target)) => check_app_enabled(&target.app_id),
+            (None, None) => Ok(()),
+        };
+        check.map_err(|e| format!("Health check failed for '{}': {}", test_id, e))?;
+    }
+
+    // TestConfig overrides
+    let mut abort_error_rate = DEFAULT_ABORT_ERROR_RATE;
+    let mut abort_window_secs = DEFAULT_ABORT_WINDOW_SECS;
+    let mut warmup = 0;
+    let mut sla = Sla::default();
+    let mut stages = Vec::new();
+    if let Some(cfg) = cfg {
+        duration = cfg.get("duration").and_then(|v| v.as_u64()).unwrap_or(duration);
+        vus = cfg.get("vus").and_then(|v| v.as_u64()).unwrap_or(vus);
+        if let Some(rate) = cfg.get("abortErrorRate").and_then(|v| v.as_f64()) {
+            abort_error_rate = rate;
+        }
+        if let Some(window) = cfg.get("abortWindowSecs").and_then(|v| v.as_u64()) {
+            abort_window_secs = window;
+        }
+        if binary != "load-realtime" {
+            warmup = cfg.get("warmupSecs").and_then(|v| v.as_u64()).unwrap_or(0);
+        }
+        sla = Sla::from_config(cfg);
+        if let Some(s) = cfg.get("stages").filter(|s| !s.is_null()) {
+            stages = parse_stages(s).map_err(|e| format!("TestConfig '{}': {}", test_id, e))?;
+        }
+    }
+
+    // Profile overrides define a flat shape, replacing any configured stages
+    if let Some(p) = profile {
+        if p.duration.is_some() || p.vus.is_some() {
+            stages.clear();
+        }
+        duration = p.duration.unwrap_or(duration);
+        vus = p.vus.unwrap_or(vus);
+    }
+
+    // Stages define the full load shape: total duration and peak VUs
+    if !stages.is_empty() {
+        duration = stages.iter().map(|(d, _)| d).sum();
+        vus = stages.iter().map(|(_, t)| *t).max().unwrap_or(vus);
+    }
+
+    let binary = find_binary(binary)?;
+
+    // The binary stores its TestRun under this id so the run can be found afterwards
+    let run_id = format!("{}-{}", test_id, (now_secs() * 1000.0) as u64);
+
+    let mut args: Vec<String> = vec![
+        "--test".into(), test_id.to_string(),
+        "--run-id".into(), run_id.clone(),
+        "--duration".into(), duration.to_string(),
+        "--vus".into(), vus.to_string(),
+    ];
+    if !stages.is_empty() {
+        args.extend(["--stages".into(), stages_arg(&stages)]);
+    }
+    if warmup > 0 {
+        args.extend(["--warmup".into(), format!("{}s", warmup)]);
+    }
+    if let Some(target) = &app_target {
+        args.extend(["--method".into(), target.method.clone(), "--path".into(), target.path.clone()]);
+        if let Some(body) = &target.body {
+            args.extend(["--body".into(), body.clone()]);
+        }
+    }
+    if let Some(env) = env {
+        args.extend(["--env".into(), env.to_string()]);
+    }
+    args.extend([
+        "--abort-error-rate".into(), abort_error_rate.to_string(),
+        "--abort-window".into(), abort_window_secs.to_string(),
+        "--progress-path".into(), "/admin/runner/progress".into(),
+    ]);
+
+    Ok(Launch {
+        test_id: test_id.to_string(),
+        run_id,
+        binary,
+        args,
+        warmup,
+        duration,
+        vus,
+        stages,
+        abort_error_rate,
+        abort_window_secs,
+        sla,
+    })
+}
+
+fn spawn_launch(launch: &Launch, owner: &str) -> std::io::Result<std::process::Child> {
+    let mut cmd = std::process::Command::new(&launch.binary);
+    cmd.args(&launch.args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    crate::processes::spawn("benchmark", owner, &mut cmd)
+}
+
+/// Return the runner to idle after a failed start; returns the "idle" RunnerEvent
+fn mark_idle(error: Option<String>) -> serde_json::Value {
+    let mut state = runner_state().lock().unwrap();
+    state.status = "idle".to_string();
+    state.child_pid = None;
+    state.profile = None;
+    state.queue.clear();
+    if error.is_some() {
+        state.last_error = error;
+    }
+    runner_event(&state, "idle")
+}
+
+/// Record a started launch in the runner state; returns its "warming" (or, without
+/// a warmup, "running") RunnerEvent
+fn mark_running(launch: &Launch, pid: u32, profile: Option<&str>, queue: &VecDeque<Launch>) -> serde_json::Value {
+    let mut state = runner_state().lock().unwrap();
+    state.status = if launch.warmup > 0 { "warming" } else { "running" }.to_string();
+    state.test_name = Some(launch.test_id.clone());
+    state.started_at = Some(now_secs());
+    state.configured_warmup = Some(launch.warmup);
+    state.configured_duration = Some(launch.duration);
+    state.deadline = Some(now_secs() + (launch.warmup + launch.duration) as f64 + MEASURE_GRACE_SECS);
+    state.terminated_at = None;
+    state.configured_vus = Some(launch.vus);
+    state.abort_error_rate = Some(launch.abort_error_rate);
+    state.abort_window_secs = Some(launch.abort_window_secs);
+    state.child_pid = Some(pid);
+    state.profile = profile.map(String::from);
+    state.queue = queue.iter().map(|l| l.test_id.clone()).collect();
+    state.progress = None;
+    state.progress_published_at = None;
+    if profile.is_none() {
+        state.last_error = None;
+    }
+
+    yeti_log!(info, "Benchmark started: test={}, binary={}, warmup={}s, duration={}s, vus={}, stages={}, pid={}",
+        launch.test_id, launch.binary, launch.warmup, launch.duration, launch.vus, stages_arg(&launch.stages), pid);
+
+    let status = state.status.clone();
+    runner_event(&state, &status)
+}
+
+/// Advance the current launch's phase once its warmup has elapsed, and stop it
+/// once past its deadline: SIGTERM first so the binary reports partial results,
+/// SIGKILL if it is still running STOP_GRACE_SECS later. Returns the "running"
+/// RunnerEvent on the warmup transition. Called by the monitor while `pid` runs.
+fn watch(pid: u32) -> Option<serde_json::Value> {
+    let mut state = runner_state().lock().unwrap();
+    if state.child_pid != Some(pid) {
+        return None;
+    }
+    let now = now_secs();
+    let test = state.test_name.clone().unwrap_or_default();
+
+    if state.deadline.is_some_and(|deadline| now > deadline) {
+        if state.terminated_at.is_none() {
+            yeti_log!(warn, "Benchmark '{}' (pid {}) overran its deadline; sending SIGTERM", test, pid);
+            crate::processes::terminate(pid, false);
+            state.terminated_at = Some(now);
+            state.deadline = Some(now + STOP_GRACE_SECS);
+        } else {
+            yeti_log!(warn, "Benchmark '{}' (pid {}) did not exit after SIGTERM; sending SIGKILL", test, pid);
+            crate::processes::terminate(pid, true);
+            state.deadline = None;
+        }
+    }
+
+    if state.status != "warming" {
+        return None;
+    }
+    let started = state.started_at?;
+    if now - started < state.configured_warmup.unwrap_or(0) as f64 {
+        return None;
+    }
+    state.status = "running".to_string();
+    Some(runner_event(&state, "running"))
+}
+
 // ── Resource ──
 
 #[derive(Default)]
@@ -87,210 +786,398 @@ impl Resource for BenchmarksResource {
         "runner"
     }
 
-    get!(_request, ctx, {
-        // Check if this is best-results or runner
-        // The resource name is "runner", but we also handle "best-results"
-        // via path: /admin/runner vs /admin/best-results
-        // Actually, best-results is a separate resource below.
-        // This handles GET /admin/runner
-
-        let state = runner_state().lock().unwrap().clone();
-
-        // Check if a running process has finished
-        let mut current_state = state.clone();
-        if current_state.status != "idle" {
-            let mut should_idle = false;
-
-            if let Some(pid) = current_state.child_pid {
-                // Check if process is still alive via kill -0
-                let alive = std::process::Command::new("kill")
-                    .arg("-0")
-                    .arg(pid.to_string())
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .status()
-                    .map(|s| s.success())
-                    .unwrap_or(false);
-                if !alive {
-                    should_idle = true;
+    get!(request, ctx, {
+        crate::metered!(request, "runner", "GET", {
+            // Check if this is best-results or runner
+            // The resource name is "runner", but we also handle "best-results"
+            // via path: /admin/runner vs /admin/best-results
+            // Actually, best-results is a separate resource below.
+            // This handles GET /admin/runner
+
+            // The monitor reaps the child, stops it past its deadline and returns
+            // the runner to idle once it has exited
+            let current_state = runner_state().lock().unwrap().clone();
+
+            let elapsed = current_state.started_at
+                .map(|s| now_secs() - s)
+                .unwrap_or(0.0);
+
+            let warmup_secs = if current_state.status == "warming" { elapsed } else { 0.0 };
+            // Measured time only; cap at configured_duration so UI doesn't show e.g. 740s / 30s
+            let elapsed_secs = if matches!(current_state.status.as_str(), "running" | "finishing") {
+                let measured = (elapsed - current_state.configured_warmup.unwrap_or(0) as f64).max(0.0);
+                match current_state.configured_duration {
+                    Some(d) => measured.min(d as f64),
+                    None => measured,
                 }
-            }
+            } else {
+                0.0
+            };
 
-            // Duration-based timeout: if elapsed exceeds configured duration + 10s grace,
-            // force transition to idle. Handles PID reuse (kill -0 sees unrelated process)
-            // and zombie processes that never exit.
-            if !should_idle {
-                if let (Some(started), Some(duration)) = (current_state.started_at, current_state.configured_duration) {
-                    let elapsed = now_secs() - started;
-                    if elapsed > (duration as f64) + 10.0 {
-                        should_idle = true;
-                        // Kill the process in case it's actually stuck
-                        if let Some(pid) = current_state.child_pid {
-                            let _ = std::process::Command::new("kill")
-                                .arg("-9")
-                                .arg(pid.to_string())
-                                .stdout(std::process::Stdio::null())
-                                .stderr(std::process::Stdio::null())
-                                .status();
-                        }
+            // Load configs from TestConfig table
+            let configs = match ctx.get_table("TestConfig") {
+                Ok(table) => {
+                    match table.scan_all().await {
+                        Ok(records) => records,
+                        Err(_) => Vec::new(),
                     }
                 }
-            }
+                Err(_) => Vec::new(),
+            };
 
-            if should_idle {
-                let mut guard = runner_state().lock().unwrap();
-                guard.status = "idle".to_string();
-                guard.child_pid = None;
-                current_state = guard.clone();
-            }
-        }
+            reply().json(json!({
+                "status": current_state.status,
+                "testName": current_state.test_name,
+                "startedAt": current_state.started_at,
+                "warmupSecs": warmup_secs,
+                "elapsedSecs": elapsed_secs,
+                "configuredDuration": current_state.configured_duration,
+                "lastError": current_state.last_error,
+                "configs": configs,
+                "appTargets": list_app_targets(),
+                "profile": current_state.profile,
+                "queue": current_state.queue,
+                "progress": current_state.progress,
+                "profiles": PROFILES.iter().map(|p| json!({
+                    "id": p.id,
+                    "tests": p.tests,
+                    "duration": p.duration,
+                    "vus": p.vus,
+                })).collect::<Vec<_>>(),
+            }))
+        })
+    });
 
-        let elapsed = current_state.started_at
-            .map(|s| now_secs() - s)
-            .unwrap_or(0.0);
+    post!(request, ctx, {
+        crate::metered!(request, "runner", "POST", {
+            let body = request.json_value()?;
 
-        let warmup_secs = if current_state.status == "warming" { elapsed } else { 0.0 };
-        // Cap elapsed at configured_duration so UI doesn't show e.g. 740s / 30s
-        let elapsed_secs = if current_state.status == "running" {
-            match current_state.configured_duration {
-                Some(d) => elapsed.min(d as f64),
-                None => elapsed,
+            // Measurement is over: the binary names its next phase (reporting, teardown, ...)
+            // and how long it may take, which becomes the launch's stop deadline
+            if request.uri().path().trim_end_matches('/').ends_with("/progress")
+                && body.get("type").and_then(|v| v.as_str()) == Some("phase")
+            {
+                let budget = body.get("budgetSecs").and_then(|v| v.as_f64()).filter(|b| b.is_finite() && *b >= 0.0);
+                let finishing = {
+                    let mut state = runner_state().lock().unwrap();
+                    let test = body.get("test").and_then(|v| v.as_str());
+                    if !matches!(state.status.as_str(), "warming" | "running" | "finishing")
+                        || state.test_name.as_deref() != test
+                    {
+                        return reply().json(json!({"accepted": false}));
+                    }
+                    if let Some(budget) = budget {
+                        state.deadline = Some(now_secs() + budget + PHASE_GRACE_SECS);
+                    }
+                    (state.status != "finishing").then(|| {
+                        state.status = "finishing".to_string();
+                        runner_event(&state, "finishing")
+                    })
+                };
+                if let (Some(event), Ok(table)) = (finishing, ctx.get_table("RunnerEvent")) {
+                    let _ = table.put(event).await;
+                }
+                return reply().json(json!({"accepted": true}));
             }
-        } else {
-            0.0
-        };
 
-        // Load configs from TestConfig table
-        let configs = match ctx.get_table("TestConfig") {
-            Ok(table) => {
-                match table.scan_all().await {
-                    Ok(records) => records,
-                    Err(_) => Vec::new(),
+            // Interim snapshot from the running binary: keep the latest, fan out as an event
+            if request.uri().path().trim_end_matches('/').ends_with("/progress") {
+                let (event, publish) = {
+                    let mut state = runner_state().lock().unwrap();
+                    let test = body.get("test").and_then(|v| v.as_str());
+                    if state.status != "running" || state.test_name.as_deref() != test {
+                        return reply().json(json!({"accepted": false}));
+                    }
+                    state.progress = Some(body.clone());
+                    let now = now_secs();
+                    let publish = state.progress_published_at.is_none_or(|at| now - at >= PROGRESS_EVENT_SECS);
+                    if publish {
+                        state.progress_published_at = Some(now);
+                    }
+                    (runner_event(&state, "progress"), publish)
+                };
+                if let Ok(table) = ctx.get_table("RunnerEvent") {
+                    let _ = table.put(event).await;
                 }
+                if publish {
+                    let test = body.get("test").and_then(|v| v.as_str()).unwrap_or("");
+                    crate::publish!(ctx, "benchmark.progress", test, body.clone());
+                }
+                return reply().json(json!({"accepted": true}));
             }
-            Err(_) => Vec::new(),
-        };
 
-        reply().json(json!({
-            "status": current_state.status,
-            "testName": current_state.test_name,
-            "startedAt": current_state.started_at,
-            "warmupSecs": warmup_secs,
-            "elapsedSecs": elapsed_secs,
-            "configuredDuration": current_state.configured_duration,
-            "lastError": current_state.last_error,
-            "configs": configs,
-        }))
-    });
+            // Exclude a test's runs from best results (kept out of the public bestresults resource)
+            if request.uri().path().trim_end_matches('/').ends_with("/exclude") {
+                let test_name = body.require_str("test")?;
+                let env_filter = body.get("env").and_then(|v| v.as_str()).map(String::from);
 
-    post!(request, ctx, {
-        let body = request.json_value()?;
-        let test_id = body.require_str("test")?;
+                let table = ctx.get_table("TestRun")?;
+                let runs = table.scan_all().await.unwrap_or_default();
 
-        // Validate test exists
-        let test_def = TESTS.iter().find(|t| t.id == test_id);
-        if test_def.is_none() {
-            return bad_request(&format!("Unknown test: {}", test_id));
-        }
-        let test_def = test_def.unwrap();
+                let mut excluded = 0;
+                for mut run in runs {
+                    if run.get("testName").and_then(|v| v.as_str()) != Some(test_name.as_str())
+                        || crate::bestresults::is_excluded(&run)
+                        || env_filter.as_ref().is_some_and(|f| *f != crate::bestresults::run_env(&run))
+                    {
+                        continue;
+                    }
+                    run["excluded"] = json!(true);
+                    table.put(run).await?;
+                    excluded += 1;
+                }
 
-        // Check not already running
-        {
-            let state = runner_state().lock().unwrap();
-            if state.status != "idle" {
-                return bad_request("A test is already running");
+                crate::bestresults::invalidate_cache();
+                yeti_log!(info, "Excluded {} run(s) of '{}' from best results", excluded, test_name);
+                crate::audit!(request, ctx, "bestresults.exclude", &test_name, json!({
+                    "env": env_filter,
+                    "excluded": excluded,
+                }));
+
+                return reply().json(json!({
+                    "test": test_name,
+                    "env": env_filter,
+                    "excluded": excluded,
+                }));
             }
-        }
 
-        // Load config overrides from TestConfig table
-        let (duration, vus) = match ctx.get_table("TestConfig") {
-            Ok(table) => {
-                match table.get_by_id(&test_id).await {
-                    Ok(Some(cfg)) => {
-                        let d = cfg.get("duration").and_then(|v| v.as_u64()).unwrap_or(test_def.duration);
-                        let v = cfg.get("vus").and_then(|v| v.as_u64()).unwrap_or(test_def.vus);
-                        (d, v)
-                    }
-                    _ => (test_def.duration, test_def.vus),
+            let test_id = body.get("test").and_then(|v| v.as_str()).map(String::from);
+            let profile_id = body.get("profile").and_then(|v| v.as_str());
+
+            let profile = match profile_id {
+                Some(id) => match PROFILES.iter().find(|p| p.id == id) {
+                    Some(p) => Some(p),
+                    None => return bad_request(&format!("Unknown profile: {}", id)),
+                },
+                None => None,
+            };
+
+            // A single test (optionally with profile overrides), or every test in the profile
+            let test_ids: Vec<String> = match (&test_id, profile) {
+                (Some(id), _) => vec![id.clone()],
+                (None, Some(p)) if p.tests.is_empty() => TESTS.iter().map(|t| t.id.to_string()).collect(),
+                (None, Some(p)) => p.tests.iter().map(|t| t.to_string()).collect(),
+                (None, None) => return bad_request("Missing 'test' or 'profile'"),
+            };
+
+            // Reserve the runner under the same lock as the idle check so concurrent
+            // starts cannot both pass it; released below if nothing gets spawned
+            let events = ctx.get_table("RunnerEvent").ok();
+            let starting = {
+                let mut state = runner_state().lock().unwrap();
+                if state.status != "idle" {
+                    return bad_request("A test is already running");
                 }
+                state.status = "starting".to_string();
+                state.test_name = test_ids.first().cloned();
+                state.started_at = None;
+                state.child_pid = None;
+                state.profile = profile.map(|p| p.id.to_string());
+                state.queue = test_ids.iter().skip(1).cloned().collect();
+                state.progress = None;
+                runner_event(&state, "starting")
+            };
+            if let Some(table) = &events {
+                let _ = table.put(starting).await;
             }
-            Err(_) => (test_def.duration, test_def.vus),
-        };
 
-        // Find the benchmark binary
-        // Primary: cargo build output (no manual copy needed)
-        let root = get_root_directory();
-        let bin_dir = root.join("applications/admin/benchmarks/target/release");
-        let bin_path = bin_dir.join(test_def.binary);
-
-        if !bin_path.exists() {
-            // Try in PATH as fallback
-            let which_result = std::process::Command::new("which")
-                .arg(test_def.binary)
-                .output();
-            match which_result {
-                Ok(output) if output.status.success() => {
-                    // Found in PATH, proceed
+            // Resolve every launch before spawning anything so a bad suite fails fast
+            let skip_health_check = body.get("skipHealthCheck").and_then(|v| v.as_bool()).unwrap_or(false);
+            let env = body.get("env").and_then(|v| v.as_str()).map(String::from);
+            let configs = ctx.get_table("TestConfig").ok();
+            let mut resolving = Vec::new();
+            for id in &test_ids {
+                let cfg = match &configs {
+                    Some(table) => table.get_by_id(id).await.ok().flatten(),
+                    None => None,
+                };
+                resolving.push((id.clone(), cfg));
+            }
+            // Health checks shell out to curl; keep them off the async workers
+            let resolved = tokio::task::spawn_blocking(move || {
+                resolving
+                    .iter()
+                    .map(|(id, cfg)| resolve_launch(id, cfg.as_ref(), profile, env.as_deref(), skip_health_check))
+                    .collect::<std::result::Result<VecDeque<Launch>, String>>()
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("Launch resolution task failed: {}", e)));
+            let mut queue = match resolved {
+                Ok(queue) => queue,
+                Err(e) => {
+                    yeti_log!(warn, "{}", e);
+                    let idle = mark_idle(Some(e.clone()));
+                    if let Some(table) = &events {
+                        let _ = table.put(idle).await;
+                    }
+                    return bad_request(&e);
                 }
-                _ => {
-                    return bad_request(&format!(
-                        "Benchmark binary '{}' not found. Expected at {} or in PATH.",
-                        test_def.binary,
-                        bin_path.display()
-                    ));
+            };
+
+            let first = queue.pop_front().expect("at least one test resolved");
+            let owner = crate::actor!(request);
+            let mut child = match spawn_launch(&first, &owner) {
+                Ok(child) => child,
+                Err(e) => {
+                    let msg = format!("Failed to start benchmark '{}': {}", first.binary, e);
+                    yeti_log!(error, "{}", msg);
+                    let idle = mark_idle(Some(msg.clone()));
+                    if let Some(table) = &events {
+                        let _ = table.put(idle).await;
+                    }
+                    return bad_request(&msg);
                 }
+            };
+            let pid = child.id();
+            let started = mark_running(&first, pid, profile.map(|p| p.id), &queue);
+
+            // Push state transitions to subscribers of the RunnerEvent table
+            let admin_events = ctx.get_table("AdminEvent").ok();
+            let runs = ctx.get_table("TestRun").ok();
+            if let Some(table) = &events {
+                let _ = table.put(started).await;
             }
-        }
 
-        // Determine the actual binary path
-        let actual_bin = if bin_path.exists() {
-            bin_path.to_string_lossy().to_string()
-        } else {
-            test_def.binary.to_string()
-        };
+            crate::audit!(request, ctx, "runner.start", &first.test_id, json!({
+                "runId": first.run_id,
+                "profile": profile.map(|p| p.id),
+                "tests": test_ids,
+            }));
+            crate::publish!(ctx, "benchmark.started", &first.test_id, json!({"runId": first.run_id}));
 
-        // Start the benchmark process
-        let child = std::process::Command::new(&actual_bin)
-            .arg("--test")
-            .arg(&test_id)
-            .arg("--duration")
-            .arg(duration.to_string())
-            .arg("--vus")
-            .arg(vus.to_string())
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn();
-
-        match child {
-            Ok(child) => {
-                let pid = child.id();
-                let mut state = runner_state().lock().unwrap();
-                state.status = "running".to_string();
-                state.test_name = Some(test_id.to_string());
-                state.started_at = Some(now_secs());
-                state.configured_duration = Some(duration);
-                state.configured_vus = Some(vus);
-                state.last_error = None;
-                state.child_pid = Some(pid);
-
-                yeti_log!(info, "Benchmark started: test={}, binary={}, duration={}s, vus={}, pid={}",
-                    test_id, test_def.binary, duration, vus, pid);
-
-                reply().json(json!({
-                    "status": "running",
-                    "testName": test_id,
-                    "pid": pid,
-                }))
-            }
-            Err(e) => {
-                let msg = format!("Failed to start benchmark '{}': {}", actual_bin, e);
-                yeti_log!(error, "{}", msg);
-                let mut state = runner_state().lock().unwrap();
-                state.status = "idle".to_string();
-                state.last_error = Some(msg.clone());
-                bad_request(&msg)
-            }
-        }
+            let response = json!({
+                "status": started["status"],
+                "testName": first.test_id,
+                "runId": first.run_id,
+                "pid": pid,
+                "profile": profile.map(|p| p.id),
+                "queue": queue.iter().map(|l| l.test_id.clone()).collect::<Vec<_>>(),
+                "stages": first.stages.iter()
+                    .map(|(d, t)| json!({"duration": d, "target": t}))
+                    .collect::<Vec<_>>(),
+            });
+
+            // Monitor: wait for each process to exit (advancing warming → running on the
+            // way), evaluate SLA criteria against the reported TestRun, publish "finished",
+            // then start the next queued test or go idle
+            tokio::spawn(async move {
+                let mut current = first;
+                let mut pid = pid;
+                loop {
+                    let wait = tokio::task::spawn_blocking(move || child.wait());
+                    while !wait.is_finished() {
+                        tokio::time::sleep(WATCH_INTERVAL).await;
+                        if let (Some(event), Some(table)) = (watch(pid), &events) {
+                            let _ = table.put(event).await;
+                        }
+                    }
+                    let status = wait.await;
+                    crate::processes::reaped(pid);
+
+                    if let Some(table) = runs.as_ref().filter(|_| !current.sla.is_empty()) {
+                        if let Ok(Some(mut run)) = table.get_by_id(&current.run_id).await {
+                            let results: serde_json::Value = run.get("results")
+                                .and_then(|v| v.as_str())
+                                .and_then(|s| serde_json::from_str(s).ok())
+                                .unwrap_or(json!({}));
+                            let verdict = current.sla.evaluate(&results);
+                            run["slaPassed"] = verdict["passed"].clone();
+                            run["sla"] = json!(verdict.to_string());
+                            let _ = table.put(run).await;
+
+                            if verdict["passed"] == false {
+                                let event = crate::events::event("benchmark.regression", &current.test_id, json!({
+                                    "runId": current.run_id,
+                                    "sla": verdict,
+                                }));
+                                crate::webhooks::dispatch(&event);
+                                if let Some(table) = &admin_events {
+                                    let _ = table.put(event).await;
+                                }
+                                crate::notifications::notify("benchmark.regression", "warning", &current.test_id,
+                                    "Benchmark regression",
+                                    &format!("Run {} of {} failed its SLA criteria", current.run_id, current.test_id));
+                            }
+                        }
+                    }
+
+                    // The binary has reported its TestRun by now
+                    crate::bestresults::invalidate_cache();
+
+                    // Abandon the rest of the queue if the runner was reset underneath us
+                    let (finished, idle, still_ours) = {
+                        let mut state = runner_state().lock().unwrap();
+                        if let Ok(Ok(status)) = status {
+                            if let Some(err) = exit_error(&state, status) {
+                                yeti_log!(warn, "{}", err);
+                                state.last_error = Some(err);
+                            }
+                        }
+                        let still_ours = state.child_pid == Some(pid);
+                        let mut idle = None;
+                        if still_ours {
+                            state.child_pid = None;
+                            state.queue = queue.iter().map(|l| l.test_id.clone()).collect();
+                            if queue.is_empty() {
+                                state.status = "idle".to_string();
+                                state.profile = None;
+                                idle = Some(runner_event(&state, "idle"));
+                            }
+                        }
+                        (runner_event(&state, "finished"), idle, still_ours)
+                    };
+                    if let Some(table) = &events {
+                        let _ = table.put(finished).await;
+                        if let Some(idle) = idle {
+                            let _ = table.put(idle).await;
+                        }
+                    }
+                    let event = crate::events::event("benchmark.finished", &current.test_id, json!({
+                        "runId": current.run_id,
+                        "exit": status.as_ref().ok().and_then(|s| s.as_ref().ok()).and_then(|s| s.code()),
+                    }));
+                    crate::webhooks::dispatch(&event);
+                    if let Some(table) = &admin_events {
+                        let _ = table.put(event).await;
+                    }
+
+                    if !still_ours {
+                        break;
+                    }
+                    let Some(next) = queue.pop_front() else { break };
+                    match spawn_launch(&next, &owner) {
+                        Ok(next_child) => {
+                            pid = next_child.id();
+                            child = next_child;
+                            let profile = runner_state().lock().unwrap().profile.clone();
+                            let started = mark_running(&next, pid, profile.as_deref(), &queue);
+                            if let Some(table) = &events {
+                                let _ = table.put(started).await;
+                            }
+                            let event = crate::events::event("benchmark.started", &next.test_id, json!({
+                                "runId": next.run_id,
+                            }));
+                            crate::webhooks::dispatch(&event);
+                            if let Some(table) = &admin_events {
+                                let _ = table.put(event).await;
+                            }
+                            current = next;
+                        }
+                        Err(e) => {
+                            let msg = format!("Failed to start benchmark '{}': {}", next.binary, e);
+                            yeti_log!(error, "{}", msg);
+                            let idle = mark_idle(Some(msg));
+                            if let Some(table) = &events {
+                                let _ = table.put(idle).await;
+                            }
+                            break;
+                        }
+                    }
+                }
+            });
+
+            reply().json(response)
+        })
     });
 }
 
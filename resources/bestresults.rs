@@ -1,15 +1,318 @@
 //! Best Results Resource
 //!
-//! Aggregates best benchmark throughput per test from TestRun records.
+//! Aggregates best benchmark results per test from TestRun records.
 //!
-//! | Method | Path                 | Description                     |
-//! |--------|----------------------|---------------------------------|
-//! | GET    | /admin/bestresults   | Best result per test from runs  |
+//! | Method | Path                      | Description                     |
+//! |--------|---------------------------|---------------------------------|
+//! | GET    | /admin/bestresults        | Best result per test from runs  |
+//! | GET    | /admin/bestresults/trends | Throughput/p95 series per run   |
+//! | GET    | /admin/bestresults/export | Markdown table / shields.io JSON|
+//!
+//! `?metric=` picks the ranking criterion: `throughput` (default, highest wins),
+//! or `p50`, `p95`, `p99`, `errorRate` (lowest wins). Each test also carries the
+//! best value seen for every metric under `bests`.
+//!
+//! `?mode=median&n=5` reports, per test, the median of each metric over the last
+//! N runs instead of the all-time best.
+//!
+//! Entries are returned in the canonical TESTS order (app-declared targets after,
+//! alphabetically) and carry `meta`: run date, duration and VUs.
+//!
+//! Bests are grouped per environment (the run's `env` label, recorded by the load
+//! binaries as `--env` or the hostname); `?env=` restricts to one environment.
+//!
+//! Runs with `excluded: true` are left out of bests and trends but the TestRun
+//! records are kept. This resource is public and read-only: exclude every
+//! current run of a test with the authenticated `POST /admin/runner/exclude`,
+//! and set the flag on a single run, or clear it, with `PUT /admin/TestRun/{id}`.
+//!
+//! `trends?test=rest-read&window=30d` returns throughput and p95 for every run of
+//! one test inside the window (`h`, `d` or `w` suffix; default 30d), oldest first.
+//!
+//! `export?format=markdown` returns `{"markdown": "..."}`, a README-ready table of
+//! best throughput per test; `export?format=shield&test=rest-read` returns a
+//! shields.io endpoint badge for one test. Both accept `?env=`.
+//!
+//! Responses are cached per endpoint and the query parameters it reads (at most
+//! `CACHE_MAX_ENTRIES`). The cache is invalidated whenever the admin writes
+//! TestRun records (run finished, runs excluded, state import), and entries
+//! expire after `CACHE_TTL` to pick up runs reported from outside the runner.
 
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use yeti_core::prelude::*;
 
 pub type BestResults = BestResultsResource;
 
+const DEFAULT_TREND_WINDOW: &str = "30d";
+
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+const CACHE_MAX_ENTRIES: usize = 256;
+
+/// Query parameters that shape a response; anything else is not part of the cache key
+const CACHE_PARAMS: &[&str] = &["metric", "env", "mode", "n", "test", "window", "format"];
+
+// ── Response cache (in-memory, shared across requests) ──
+
+fn cache() -> &'static Mutex<HashMap<String, (Instant, serde_json::Value)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Instant, serde_json::Value)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached(key: &str) -> Option<serde_json::Value> {
+    let cache = cache().lock().unwrap();
+    cache.get(key)
+        .filter(|(at, _)| at.elapsed() < CACHE_TTL)
+        .map(|(_, body)| body.clone())
+}
+
+/// Cache key from the endpoint (`trends`, `export` or the list) and the known params
+fn cache_key(path: &str, query: &str) -> String {
+    let path = path.trim_end_matches('/');
+    let kind = ["trends", "export"].into_iter().find(|k| path.ends_with(&format!("/{}", k))).unwrap_or("best");
+    let params: Vec<String> = CACHE_PARAMS
+        .iter()
+        .filter_map(|p| parse_query_param(query, p).map(|v| format!("{}={}", p, v)))
+        .collect();
+    format!("{}?{}", kind, params.join("&"))
+}
+
+fn remember(key: String, body: &serde_json::Value) {
+    let mut cache = cache().lock().unwrap();
+    cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+    if cache.len() >= CACHE_MAX_ENTRIES {
+        let oldest = cache.iter().min_by_key(|(_, (at, _))| *at).map(|(k, _)| k.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(key, (Instant::now(), body.clone()));
+}
+
+/// Drop all cached aggregations; call after TestRun records change
+pub fn invalidate_cache() {
+    cache().lock().unwrap().clear();
+}
+
+/// Ranking criteria accepted by `?metric=`
+const METRICS: &[&str] = &["throughput", "p50", "p95", "p99", "errorRate"];
+
+/// Value of a ranking metric from a run's parsed results
+fn metric_value(results: &serde_json::Value, metric: &str) -> Option<f64> {
+    match metric {
+        "errorRate" => {
+            let total = results.get("total").and_then(|v| v.as_f64())?;
+            let errors = results.get("errors").and_then(|v| v.as_f64()).unwrap_or(0.0);
+            Some(if total > 0.0 { errors / total } else { 0.0 })
+        }
+        _ => results.get(metric).and_then(|v| v.as_f64()),
+    }
+}
+
+/// Throughput ranks highest-first; latencies and error rate lowest-first
+fn is_better(metric: &str, candidate: f64, existing: f64) -> bool {
+    if metric == "throughput" {
+        candidate > existing
+    } else {
+        candidate < existing
+    }
+}
+
+/// Parse a window like "12h", "30d" or "2w" into seconds
+fn parse_window(window: &str) -> std::result::Result<i64, String> {
+    let window = window.trim();
+    let (num, unit_secs) = if let Some(num) = window.strip_suffix('h') {
+        (num, 3600)
+    } else if let Some(num) = window.strip_suffix('d') {
+        (num, 86400)
+    } else if let Some(num) = window.strip_suffix('w') {
+        (num, 7 * 86400)
+    } else {
+        return Err(format!("Invalid window unit in '{}' (use h, d or w)", window));
+    };
+    num.parse::<i64>()
+        .ok()
+        .filter(|value| *value >= 0)
+        .and_then(|value| value.checked_mul(unit_secs))
+        .ok_or_else(|| format!("Invalid window '{}' (e.g. 12h, 30d, 2w)", window))
+}
+
+/// Parse a TestRun's results JSON string
+fn run_results(run: &serde_json::Value) -> serde_json::Value {
+    let results_str = run.get("results").and_then(|v| v.as_str()).unwrap_or("{}");
+    serde_json::from_str(results_str).unwrap_or(json!({}))
+}
+
+const DEFAULT_MEDIAN_RUNS: usize = 5;
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Run metadata included with every leaderboard entry
+fn run_meta(run: &serde_json::Value) -> serde_json::Value {
+    json!({
+        "date": run.get("timestamp"),
+        "durationSecs": run.get("durationSecs"),
+        "vus": run.get("vus"),
+    })
+}
+
+/// Canonical TESTS order, then app-declared targets alphabetically, then env
+fn sort_entries(tests: &mut [serde_json::Value]) {
+    let key = |entry: &serde_json::Value| {
+        let name = entry.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let env = entry.get("env").and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let position = crate::benchmarks::test_position(&name).unwrap_or(usize::MAX);
+        (position, name, env)
+    };
+    tests.sort_by_key(key);
+}
+
+/// Environment a run was recorded in; runs from before env tracking group as "unknown"
+pub fn run_env(run: &serde_json::Value) -> String {
+    run.get("env")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+pub fn is_excluded(run: &serde_json::Value) -> bool {
+    run.get("excluded").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// TestRun timestamp (RFC 3339) as unix seconds
+fn run_timestamp(run: &serde_json::Value) -> Option<i64> {
+    let ts = run.get("timestamp").and_then(|v| v.as_str())?;
+    chrono::DateTime::parse_from_rfc3339(ts).ok().map(|t| t.timestamp())
+}
+
+/// Human-readable throughput for badges, e.g. "12.3k req/s"
+fn format_throughput(throughput: f64) -> String {
+    if throughput >= 1000.0 {
+        format!("{:.1}k req/s", throughput / 1000.0)
+    } else {
+        format!("{:.0} req/s", throughput)
+    }
+}
+
+fn format_ms(value: Option<&serde_json::Value>) -> String {
+    value.and_then(|v| v.as_f64())
+        .map(|ms| format!("{:.2} ms", ms))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// README table of best entries
+fn markdown_table(tests: &[serde_json::Value]) -> String {
+    let mut out = String::from("| Test | Env | Throughput | p50 | p95 | p99 | Date |\n");
+    out.push_str("|------|-----|-----------:|----:|----:|----:|------|\n");
+    for entry in tests {
+        let results = &entry["results"];
+        let date = entry["meta"]["date"].as_str().unwrap_or("");
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            entry["name"].as_str().unwrap_or(""),
+            entry["env"].as_str().unwrap_or(""),
+            format_throughput(entry["throughput"].as_f64().unwrap_or(0.0)),
+            format_ms(results.get("p50")),
+            format_ms(results.get("p95")),
+            format_ms(results.get("p99")),
+            date.split('T').next().unwrap_or(date),
+        ));
+    }
+    out
+}
+
+/// Best run per (env, testName) by `metric`, sorted; also returns every env seen
+fn aggregate_best(
+    runs: &[serde_json::Value],
+    metric: &str,
+    env_filter: Option<&str>,
+) -> (Vec<serde_json::Value>, std::collections::BTreeSet<String>) {
+    // Group by (env, testName), keep the best run by the selected metric
+    let mut best: HashMap<(String, String), serde_json::Value> = HashMap::new();
+    // Best value of every metric per group, regardless of which run it came from
+    let mut bests: HashMap<(String, String), serde_json::Map<String, serde_json::Value>> =
+        HashMap::new();
+    let mut environments: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+
+    for run in runs {
+        let test_name = match run.get("testName").and_then(|v| v.as_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        if is_excluded(run) {
+            continue;
+        }
+        let env = run_env(run);
+        environments.insert(env.clone());
+        if env_filter.is_some_and(|f| f != env) {
+            continue;
+        }
+        let key = (env.clone(), test_name.clone());
+
+        let results = run_results(run);
+        let throughput = results.get("throughput").and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+        let test_bests = bests.entry(key.clone()).or_default();
+        for m in METRICS {
+            if let Some(value) = metric_value(&results, m) {
+                let improves = match test_bests.get(*m).and_then(|v| v.as_f64()) {
+                    Some(existing) => is_better(m, value, existing),
+                    None => true,
+                };
+                if improves {
+                    test_bests.insert(m.to_string(), json!(value));
+                }
+            }
+        }
+
+        let Some(value) = metric_value(&results, metric) else { continue };
+        let beats_best = match best.get(&key).and_then(|e| e.get("value")).and_then(|v| v.as_f64()) {
+            Some(existing) => is_better(metric, value, existing),
+            None => true,
+        };
+
+        if beats_best {
+            // SLA verdict stored by the runner, if the test has criteria
+            let sla = run.get("sla")
+                .and_then(|v| v.as_str())
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok());
+            best.insert(key, json!({
+                "name": test_name,
+                "env": env,
+                "throughput": throughput,
+                "metric": metric,
+                "value": value,
+                "run": run,
+                "meta": run_meta(run),
+                "results": results,
+                "sla": sla,
+            }));
+        }
+    }
+
+    for (key, entry) in best.iter_mut() {
+        entry["bests"] = json!(bests.remove(key).unwrap_or_default());
+    }
+
+    let mut tests: Vec<serde_json::Value> = best.into_values().collect();
+    sort_entries(&mut tests);
+    (tests, environments)
+}
+
 #[derive(Default)]
 pub struct BestResultsResource;
 
@@ -20,52 +323,177 @@ impl Resource for BestResultsResource {
 
     fn is_public(&self) -> bool { true }
 
-    get!(_request, ctx, {
-        // Query all TestRun records and find the best throughput per test
-        let runs = match ctx.get_table("TestRun") {
-            Ok(table) => table.scan_all().await.unwrap_or_default(),
-            Err(_) => Vec::new(),
-        };
-
-        // Group by testName, keep best throughput
-        let mut best: std::collections::HashMap<String, serde_json::Value> = std::collections::HashMap::new();
+    get!(request, ctx, {
+        crate::metered!(request, "bestresults", "GET", {
+            let cache_key = cache_key(request.uri().path(), request.uri().query().unwrap_or(""));
+            if let Some(body) = cached(&cache_key) {
+                return reply().json(body);
+            }
 
-        for run in &runs {
-            let test_name = match run.get("testName").and_then(|v| v.as_str()) {
-                Some(name) => name.to_string(),
-                None => continue,
+            // Query all TestRun records and find the best result per test
+            let runs = match ctx.get_table("TestRun") {
+                Ok(table) => table.scan_all().await.unwrap_or_default(),
+                Err(_) => Vec::new(),
             };
 
-            // Parse the results JSON string
-            let results_str = run.get("results").and_then(|v| v.as_str()).unwrap_or("{}");
-            let results: serde_json::Value = serde_json::from_str(results_str).unwrap_or(json!({}));
-            let throughput = results.get("throughput").and_then(|v| v.as_f64()).unwrap_or(0.0);
-
-            let is_better = match best.get(&test_name) {
-                Some(existing) => {
-                    let existing_tp = existing.get("throughput")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    throughput > existing_tp
+            // GET /admin/bestresults/trends?test=...&window=...
+            if request.uri().path().trim_end_matches('/').ends_with("/trends") {
+                let query = request.uri().query().unwrap_or("");
+                let test_name = parse_required_query_param(query, "test")?;
+                let window = parse_query_param(query, "window")
+                    .unwrap_or_else(|| DEFAULT_TREND_WINDOW.to_string());
+                let window_secs = match parse_window(&window) {
+                    Ok(secs) => secs,
+                    Err(e) => return bad_request(&e),
+                };
+                let since = chrono::Utc::now().timestamp().saturating_sub(window_secs);
+
+                let mut points: Vec<(i64, serde_json::Value)> = runs.iter()
+                    .filter(|run| !is_excluded(run))
+                    .filter(|run| run.get("testName").and_then(|v| v.as_str()) == Some(test_name.as_str()))
+                    .filter_map(|run| {
+                        let ts = run_timestamp(run).filter(|ts| *ts >= since)?;
+                        let results = run_results(run);
+                        Some((ts, json!({
+                            "id": run.get("id"),
+                            "timestamp": run.get("timestamp"),
+                            "throughput": results.get("throughput").and_then(|v| v.as_f64()),
+                            "p95": results.get("p95").and_then(|v| v.as_f64()),
+                            "status": run.get("status"),
+                        })))
+                    })
+                    .collect();
+                points.sort_by_key(|(ts, _)| *ts);
+
+                let body = json!({
+                    "test": test_name,
+                    "window": window,
+                    "points": points.into_iter().map(|(_, p)| p).collect::<Vec<_>>(),
+                });
+                remember(cache_key, &body);
+                return reply().json(body);
+            }
+
+            // GET /admin/bestresults/export?format=markdown|shield
+            if request.uri().path().trim_end_matches('/').ends_with("/export") {
+                let query = request.uri().query().unwrap_or("");
+                let format = parse_query_param(query, "format").unwrap_or_else(|| "markdown".to_string());
+                let env_filter = parse_query_param(query, "env");
+                let (tests, _) = aggregate_best(&runs, "throughput", env_filter.as_deref());
+
+                let body = match format.as_str() {
+                    "markdown" => json!({ "markdown": markdown_table(&tests) }),
+                    "shield" => {
+                        let test_name = parse_required_query_param(query, "test")?;
+                        // Several environments: badge the fastest
+                        let entry = tests.iter()
+                            .filter(|t| t["name"].as_str() == Some(test_name.as_str()))
+                            .max_by(|a, b| {
+                                let (a, b) = (a["throughput"].as_f64().unwrap_or(0.0), b["throughput"].as_f64().unwrap_or(0.0));
+                                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+                            });
+                        match entry {
+                            Some(entry) => json!({
+                                "schemaVersion": 1,
+                                "label": test_name,
+                                "message": format_throughput(entry["throughput"].as_f64().unwrap_or(0.0)),
+                                "color": "blue",
+                            }),
+                            None => json!({
+                                "schemaVersion": 1,
+                                "label": test_name,
+                                "message": "no runs",
+                                "color": "lightgrey",
+                            }),
+                        }
+                    }
+                    _ => return bad_request(&format!("Unknown format '{}' (use markdown or shield)", format)),
+                };
+                remember(cache_key, &body);
+                return reply().json(body);
+            }
+
+            let query = request.uri().query().unwrap_or("");
+            let metric = parse_query_param(query, "metric").unwrap_or_else(|| "throughput".to_string());
+            let env_filter = parse_query_param(query, "env");
+            if !METRICS.contains(&metric.as_str()) {
+                return bad_request(&format!("Unknown metric '{}' (use one of: {})", metric, METRICS.join(", ")));
+            }
+
+            let mode = parse_query_param(query, "mode").unwrap_or_else(|| "best".to_string());
+            if mode == "median" {
+                let n = match parse_query_param(query, "n") {
+                    Some(n) => match n.parse::<usize>() {
+                        Ok(n) if n > 0 => n,
+                        _ => return bad_request("'n' must be a positive integer"),
+                    },
+                    None => DEFAULT_MEDIAN_RUNS,
+                };
+
+                // Group non-excluded runs by (env, testName)
+                let mut groups: HashMap<(String, String), Vec<&serde_json::Value>> = HashMap::new();
+                let mut environments: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+                for run in runs.iter().filter(|run| !is_excluded(run)) {
+                    let Some(test_name) = run.get("testName").and_then(|v| v.as_str()) else { continue };
+                    let env = run_env(run);
+                    environments.insert(env.clone());
+                    if env_filter.as_ref().is_some_and(|f| *f != env) {
+                        continue;
+                    }
+                    groups.entry((env, test_name.to_string())).or_default().push(run);
                 }
-                None => true,
-            };
 
-            if is_better {
-                best.insert(test_name.clone(), json!({
-                    "name": test_name,
-                    "throughput": throughput,
-                    "run": run,
-                    "results": results,
-                }));
+                let mut tests: Vec<serde_json::Value> = groups.into_iter().map(|((env, test_name), mut group)| {
+                    // Most recent first
+                    group.sort_by_key(|run| std::cmp::Reverse(run_timestamp(run).unwrap_or(0)));
+                    group.truncate(n);
+
+                    let parsed: Vec<serde_json::Value> = group.iter().map(|run| run_results(run)).collect();
+                    let mut medians = serde_json::Map::new();
+                    for m in METRICS {
+                        let mut values: Vec<f64> = parsed.iter().filter_map(|r| metric_value(r, m)).collect();
+                        if let Some(value) = median(&mut values) {
+                            medians.insert(m.to_string(), json!(value));
+                        }
+                    }
+
+                    json!({
+                        "name": test_name,
+                        "env": env,
+                        "throughput": medians.get("throughput").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                        "runs": group.len(),
+                        "run": group.first(),
+                        "meta": group.first().map(|run| run_meta(run)),
+                        "results": medians,
+                    })
+                }).collect();
+                sort_entries(&mut tests);
+
+                let body = json!({
+                    "mode": mode,
+                    "n": n,
+                    "env": env_filter,
+                    "environments": environments,
+                    "tests": tests,
+                });
+                remember(cache_key, &body);
+                return reply().json(body);
+            } else if mode != "best" {
+                return bad_request(&format!("Unknown mode '{}' (use best or median)", mode));
             }
-        }
 
-        let tests: Vec<serde_json::Value> = best.into_values().collect();
+            let (tests, environments) = aggregate_best(&runs, &metric, env_filter.as_deref());
 
-        reply().json(json!({
-            "tests": tests,
-        }))
+            let body = json!({
+                "mode": mode,
+                "metric": metric,
+                "env": env_filter,
+                "environments": environments,
+                "tests": tests,
+            });
+            remember(cache_key, &body);
+            reply().json(body)
+        })
     });
 }
 
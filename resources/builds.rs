@@ -0,0 +1,430 @@
+//! Plugin Build Resource
+//!
+//! Compiles an application's Rust resources as a background job and reports
+//! compiler output and parsed diagnostics.
+//!
+//! | Method | Path                         | Description                           |
+//! |--------|------------------------------|---------------------------------------|
+//! | GET    | /admin/builds                | Latest build of every app             |
+//! | GET    | /admin/builds/{app_id}       | Build status, output, diagnostics     |
+//! | POST   | /admin/builds/{app_id}       | Start a build (202)                   |
+//! | POST   | /admin/builds/{app_id}/check | `cargo check` (or `?clippy=true`)     |
+//!
+//! The build runs `cargo build --release` in the app's plugin crate: the app
+//! directory when it has a Cargo.toml, else the crate generated under the
+//! plugin cache. Output lines are captured as they arrive, so polling the GET
+//! shows progress; `build.start` / `build.finished` admin events are
+//! published as well.
+//!
+//! `/check` runs synchronously for editor diagnostics, one at a time per app.
+//! It runs on a hidden copy of the crate (`.check-<dir>` next to it) with its
+//! own target directory under the plugin cache, so it never touches the
+//! sources, lock file or artifacts of a real build; each diagnostic carries
+//! `file` (`resources/<name>.rs` for the app's resources), `line`, `column`,
+//! `endLine` and `endColumn`.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use yeti_core::prelude::*;
+
+pub type Builds = BuildsResource;
+
+/// Output lines kept per build (oldest dropped first)
+const MAX_OUTPUT_LINES: usize = 2000;
+
+/// Directory depth searched under the plugin cache for a Cargo.toml
+const CRATE_SEARCH_DEPTH: usize = 3;
+
+// ── Build state (in-memory, one entry per app) ──
+
+#[derive(Clone)]
+struct BuildJob {
+    status: String, // "running", "succeeded", "failed"
+    started_at: f64,
+    finished_at: Option<f64>,
+    crate_dir: String,
+    output: Vec<String>,
+    diagnostics: Vec<serde_json::Value>,
+    error: Option<String>,
+}
+
+impl BuildJob {
+    fn to_json(&self, app_id: &str) -> serde_json::Value {
+        let errors = self.diagnostics.iter().filter(|d| d["level"] == "error").count();
+        let warnings = self.diagnostics.iter().filter(|d| d["level"] == "warning").count();
+        json!({
+            "app_id": app_id,
+            "status": self.status,
+            "startedAt": self.started_at,
+            "finishedAt": self.finished_at,
+            "crateDir": self.crate_dir,
+            "errors": errors,
+            "warnings": warnings,
+            "diagnostics": self.diagnostics,
+            "output": self.output,
+            "error": self.error,
+        })
+    }
+}
+
+fn builds() -> &'static Arc<Mutex<HashMap<String, BuildJob>>> {
+    static BUILDS: OnceLock<Arc<Mutex<HashMap<String, BuildJob>>>> = OnceLock::new();
+    BUILDS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// First directory containing a Cargo.toml, breadth-first up to `depth` levels
+fn find_crate(dir: &Path, depth: usize) -> Option<PathBuf> {
+    if dir.join("Cargo.toml").is_file() {
+        return Some(dir.to_path_buf());
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut subdirs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && p.file_name().is_some_and(|n| n != "target" && !n.to_string_lossy().starts_with('.')))
+        .collect();
+    subdirs.sort();
+    subdirs.iter().find_map(|d| find_crate(d, depth - 1))
+}
+
+/// The Cargo crate that compiles an app's resources
+pub fn plugin_crate(app_id: &str) -> std::result::Result<PathBuf, String> {
+    let app_path = get_apps_directory().join(app_id);
+    if !app_path.is_dir() {
+        return Err(format!("Application '{}' not found", app_id));
+    }
+    if app_path.join("Cargo.toml").is_file() {
+        return Ok(app_path);
+    }
+    find_crate(&get_cache_directory().join(app_id), CRATE_SEARCH_DEPTH).ok_or_else(|| {
+        format!("No plugin crate for '{}': it has no Cargo.toml and has not been compiled by yeti yet", app_id)
+    })
+}
+
+/// Map a compiler file name back to the app: `resources/<file>.rs` when the
+/// file is one of the app's resources, else the path as reported.
+fn app_relative(app_id: &str, file_name: &str) -> String {
+    let resources = get_apps_directory().join(app_id).join("resources");
+    let base = Path::new(file_name).file_name().map(|n| n.to_string_lossy().to_string());
+    match base {
+        Some(base) if resources.join(&base).is_file() => format!("resources/{}", base),
+        _ => file_name.to_string(),
+    }
+}
+
+/// Parse one `--message-format=json` line into a diagnostic, if it is a
+/// compiler message; position fields come from the primary span.
+pub fn parse_diagnostic(app_id: &str, line: &str) -> Option<serde_json::Value> {
+    let msg: serde_json::Value = serde_json::from_str(line).ok()?;
+    if msg.get("reason").and_then(|v| v.as_str()) != Some("compiler-message") {
+        return None;
+    }
+    let message = msg.get("message")?;
+    let level = message.get("level").and_then(|v| v.as_str()).unwrap_or("");
+    if level == "failure-note" {
+        return None;
+    }
+    let spans = message.get("spans").and_then(|v| v.as_array());
+    let span = spans.and_then(|s| s.iter().find(|s| s["is_primary"] == true));
+    let file = span
+        .and_then(|s| s.get("file_name"))
+        .and_then(|v| v.as_str())
+        .map(|f| app_relative(app_id, f));
+    Some(json!({
+        "level": level,
+        "message": message.get("message"),
+        "code": message.get("code").and_then(|c| c.get("code")),
+        "file": file,
+        "line": span.and_then(|s| s.get("line_start")),
+        "column": span.and_then(|s| s.get("column_start")),
+        "endLine": span.and_then(|s| s.get("line_end")),
+        "endColumn": span.and_then(|s| s.get("column_end")),
+        "rendered": message.get("rendered"),
+    }))
+}
+
+/// Run `cargo <args> --message-format=json` in `dir`, feeding each
+/// diagnostic and human-readable output line to the callbacks as they arrive.
+/// Returns whether cargo succeeded.
+pub fn run_cargo(
+    dir: &Path,
+    args: &[&str],
+    envs: &[(&str, &Path)],
+    app_id: &str,
+    owner: &str,
+    on_output: impl Fn(String) + Send + Sync + 'static,
+    mut on_diagnostic: impl FnMut(serde_json::Value),
+) -> std::result::Result<bool, String> {
+    let mut cmd = std::process::Command::new("cargo");
+    cmd.args(args)
+        .arg("--message-format=json")
+        .current_dir(dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    for (key, value) in envs {
+        cmd.env(key, value);
+    }
+    let mut child = crate::processes::spawn("build", owner, &mut cmd)
+        .map_err(|e| format!("Failed to run cargo: {}", e))?;
+
+    // cargo's own progress ("Compiling ...") goes to stderr
+    let on_output = Arc::new(on_output);
+    let stderr = child.stderr.take().map(|stderr| {
+        let on_output = on_output.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(std::result::Result::ok) {
+                (*on_output)(line);
+            }
+        })
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(std::result::Result::ok) {
+            if let Some(diagnostic) = parse_diagnostic(app_id, &line) {
+                if let Some(rendered) = diagnostic["rendered"].as_str() {
+                    for l in rendered.trim_end().lines() {
+                        (*on_output)(l.to_string());
+                    }
+                }
+                on_diagnostic(diagnostic);
+            }
+        }
+    }
+    if let Some(handle) = stderr {
+        let _ = handle.join();
+    }
+
+    let status = child.wait();
+    crate::processes::reaped(child.id());
+    let status = status.map_err(|e| format!("Failed to wait for cargo: {}", e))?;
+    Ok(status.success())
+}
+
+/// Isolated target directory for `/check` runs
+fn check_target_dir(app_id: &str) -> PathBuf {
+    get_cache_directory().join(app_id).join("check-target")
+}
+
+/// Apps with a `/check` in progress
+fn checks() -> &'static Mutex<HashSet<String>> {
+    static CHECKS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    CHECKS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Run `cargo check` (or clippy) on a copy of an app's plugin crate in the
+/// isolated check target and collect its diagnostics. Blocks until cargo
+/// exits; a second check of the same app is rejected meanwhile.
+pub fn check(app_id: &str, clippy: bool, owner: &str) -> std::result::Result<serde_json::Value, YetiError> {
+    let crate_dir = plugin_crate(app_id).map_err(YetiError::Validation)?;
+    if !checks().lock().unwrap().insert(app_id.to_string()) {
+        return Err(YetiError::Validation(format!("A check of '{}' is already running", app_id)));
+    }
+    let result = check_workspace(app_id, &crate_dir, clippy, owner);
+    checks().lock().unwrap().remove(app_id);
+    result
+}
+
+/// Copy the crate next to itself (hidden, so relative path dependencies still
+/// resolve), so cargo never writes into the crate a real build is using
+fn check_workspace(app_id: &str, crate_dir: &Path, clippy: bool, owner: &str) -> std::result::Result<serde_json::Value, YetiError> {
+    let name = crate_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let workspace = crate_dir.with_file_name(format!(".check-{}", name));
+    let _ = std::fs::remove_dir_all(&workspace);
+    if let Err(e) = crate::apps::copy_dir(crate_dir, &workspace, &["target", ".git", "node_modules"], &[], &[]) {
+        let _ = std::fs::remove_dir_all(&workspace);
+        return Err(YetiError::Internal(format!("Failed to copy the crate for checking: {}", e)));
+    }
+
+    let target_dir = check_target_dir(app_id);
+    let mut diagnostics = Vec::new();
+    let passed = run_cargo(
+        &workspace,
+        &[if clippy { "clippy" } else { "check" }],
+        &[("CARGO_TARGET_DIR", target_dir.as_path())],
+        app_id,
+        owner,
+        |_| {},
+        |diagnostic| diagnostics.push(diagnostic),
+    );
+    let _ = std::fs::remove_dir_all(&workspace);
+    let passed = passed.map_err(YetiError::Internal)?;
+
+    let errors = diagnostics.iter().filter(|d| d["level"] == "error").count();
+    let warnings = diagnostics.iter().filter(|d| d["level"] == "warning").count();
+    Ok(json!({
+        "app_id": app_id,
+        "tool": if clippy { "clippy" } else { "check" },
+        "passed": passed,
+        "errors": errors,
+        "warnings": warnings,
+        "diagnostics": diagnostics,
+    }))
+}
+
+fn update(app_id: &str, f: impl FnOnce(&mut BuildJob)) {
+    if let Some(job) = builds().lock().unwrap().get_mut(app_id) {
+        f(job);
+    }
+}
+
+/// Number of background builds still running
+pub fn running_builds() -> usize {
+    builds().lock().unwrap().values().filter(|j| j.status == "running").count()
+}
+
+/// Whether a background build of `app_id` is running
+pub fn is_building(app_id: &str) -> bool {
+    builds().lock().unwrap().get(app_id).is_some_and(|j| j.status == "running")
+}
+
+#[derive(Default)]
+pub struct BuildsResource;
+
+impl Resource for BuildsResource {
+    fn name(&self) -> &str {
+        "builds"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "builds", "GET", {
+            let jobs = builds().lock().unwrap();
+
+            if let Some(app_id) = ctx.path_id() {
+                return match jobs.get(app_id) {
+                    Some(job) => reply().json(job.to_json(app_id)),
+                    None => not_found(&format!("No build for '{}'", app_id)),
+                };
+            }
+
+            let mut list: Vec<serde_json::Value> = jobs
+                .iter()
+                .map(|(app_id, job)| {
+                    let mut summary = job.to_json(app_id);
+                    if let Some(obj) = summary.as_object_mut() {
+                        obj.remove("output");
+                        obj.remove("diagnostics");
+                    }
+                    summary
+                })
+                .collect();
+            list.sort_by(|a, b| a["app_id"].as_str().cmp(&b["app_id"].as_str()));
+            reply().json(json!(list))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "builds", "POST", {
+            let uri_path = request.uri().path().trim_end_matches('/');
+
+            // POST /admin/builds/{app_id}/check[?clippy=true]
+            if let Some(prefix) = uri_path.strip_suffix("/check") {
+                let app_id = prefix.rsplit('/').next().unwrap_or("").to_string();
+                validate_identifier(&app_id, "app_id")?;
+                let query = request.uri().query().unwrap_or("");
+                let clippy = parse_query_param(query, "clippy").is_some_and(|v| v == "true");
+                let owner = crate::actor!(request);
+                let result = tokio::task::spawn_blocking(move || check(&app_id, clippy, &owner))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Check task failed: {}", e)))??;
+                return reply().json(result);
+            }
+
+            let app_id = ctx.require_id()?.to_string();
+            validate_identifier(&app_id, "app_id")?;
+
+            let crate_dir = match plugin_crate(&app_id) {
+                Ok(dir) => dir,
+                Err(e) => return bad_request(&e),
+            };
+
+            {
+                let mut jobs = builds().lock().unwrap();
+                if jobs.get(&app_id).is_some_and(|j| j.status == "running") {
+                    return bad_request(&format!("A build of '{}' is already running", app_id));
+                }
+                jobs.insert(app_id.clone(), BuildJob {
+                    status: "running".to_string(),
+                    started_at: now_secs(),
+                    finished_at: None,
+                    crate_dir: crate_dir.to_string_lossy().to_string(),
+                    output: Vec::new(),
+                    diagnostics: Vec::new(),
+                    error: None,
+                });
+            }
+
+            crate::audit!(request, ctx, "build.start", &app_id, json!({}));
+
+            let events = ctx.get_table("AdminEvent").ok();
+            let owner = crate::actor!(request);
+            let job_id = app_id.clone();
+            tokio::spawn(async move {
+                let app_id = job_id.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let output_id = job_id.clone();
+                    run_cargo(
+                        &crate_dir,
+                        &["build", "--release"],
+                        &[],
+                        &job_id,
+                        &owner,
+                        move |line| update(&output_id, |job| {
+                            job.output.push(line);
+                            if job.output.len() > MAX_OUTPUT_LINES {
+                                job.output.remove(0);
+                            }
+                        }),
+                        |diagnostic| update(&job_id, |job| job.diagnostics.push(diagnostic)),
+                    )
+                })
+                .await
+                .unwrap_or_else(|e| Err(format!("Build task failed: {}", e)));
+
+                let mut finished = json!({});
+                update(&app_id, |job| {
+                    job.finished_at = Some(now_secs());
+                    match result {
+                        Ok(true) => job.status = "succeeded".to_string(),
+                        Ok(false) => job.status = "failed".to_string(),
+                        Err(e) => {
+                            job.status = "failed".to_string();
+                            job.error = Some(e);
+                        }
+                    }
+                    finished = job.to_json(&app_id);
+                });
+                yeti_log!(info, "Build of '{}' {}", app_id, finished["status"].as_str().unwrap_or(""));
+
+                let event = crate::events::event("build.finished", &app_id, json!({
+                    "status": finished["status"],
+                    "errors": finished["errors"],
+                    "warnings": finished["warnings"],
+                }));
+                crate::webhooks::dispatch(&event);
+                if let Some(table) = &events {
+                    let _ = table.put(event).await;
+                }
+                let _ = tokio::task::spawn_blocking(move || crate::cache::enforce(&app_id)).await;
+            });
+
+            reply().code(202).json(json!({
+                "app_id": app_id,
+                "status": "running",
+            }))
+        })
+    });
+}
+
+register_resource!(BuildsResource);
@@ -0,0 +1,351 @@
+//! App Cache Resource
+//!
+//! Size, limit and clear each app's cache directory (generated plugin crates,
+//! build and check targets), which otherwise grows until the app is deleted.
+//!
+//! | Method | Path                                | Description                          |
+//! |--------|-------------------------------------|--------------------------------------|
+//! | GET    | /admin/cache                        | Every app's cache size and limit     |
+//! | GET    | /admin/cache/{app_id}               | Entries of one app's cache           |
+//! | PUT    | /admin/cache/{app_id}               | Set (or clear) the app's size limit  |
+//! | PUT    | /admin/cache                        | Set the default limit                |
+//! | DELETE | /admin/cache/{app_id}               | Clear the app's cache                |
+//! | DELETE | /admin/cache/{app_id}?entry={name}  | Remove one entry                     |
+//!
+//! PUT body: `{"limitBytes": 2000000000}` (`null` removes the limit). Limits are
+//! the `cacheLimits` section of the server config (`yeti-config.yaml`), keyed
+//! by app id with `"*"` as the default.
+//!
+//! An entry is a top-level item of `cache/{app_id}/`, aged by the newest
+//! modification time inside it. When an app is over its limit the oldest
+//! entries are evicted until it fits, after each build, when a limit is set,
+//! and every `ENFORCE_INTERVAL_SECS` from the scheduler. Caches of apps with a
+//! running build are left alone.
+
+use std::path::Path;
+use std::sync::Mutex;
+use yeti_core::prelude::*;
+
+pub type Cache = CacheResource;
+
+const LIMITS_KEY: &str = "cacheLimits";
+const DEFAULT_KEY: &str = "*";
+const ENFORCE_INTERVAL_SECS: f64 = 900.0;
+/// Cache subdirectories used by the admin itself, not by an app
+const INTERNAL: &[&str] = &["promote", "instances", "templates"];
+
+struct Entry {
+    name: String,
+    size: u64,
+    modified: f64,
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn mtime(meta: &std::fs::Metadata) -> f64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Total size and newest modification time under `path` (symlinks not followed)
+fn measure(path: &Path) -> (u64, f64) {
+    let Ok(meta) = std::fs::symlink_metadata(path) else { return (0, 0.0) };
+    let mut size = meta.len();
+    let mut modified = mtime(&meta);
+    if meta.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                let (s, m) = measure(&entry.path());
+                size += s;
+                modified = modified.max(m);
+            }
+        }
+    }
+    (size, modified)
+}
+
+fn entries(app_id: &str) -> Vec<Entry> {
+    let Ok(dir) = std::fs::read_dir(get_cache_directory().join(app_id)) else { return Vec::new() };
+    let mut entries: Vec<Entry> = dir
+        .flatten()
+        .map(|e| {
+            let (size, modified) = measure(&e.path());
+            Entry { name: e.file_name().to_string_lossy().to_string(), size, modified }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.modified.partial_cmp(&b.modified).unwrap_or(std::cmp::Ordering::Equal));
+    entries
+}
+
+fn limits() -> serde_json::Map<String, serde_json::Value> {
+    crate::cors::read_server_config().map(|c| limits_of(&c)).unwrap_or_default()
+}
+
+fn limits_of(config: &serde_yaml::Value) -> serde_json::Map<String, serde_json::Value> {
+    config
+        .get(LIMITS_KEY)
+        .and_then(|v| serde_json::to_value(v).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+fn limit_for(limits: &serde_json::Map<String, serde_json::Value>, app_id: &str) -> Option<u64> {
+    limits.get(app_id).or_else(|| limits.get(DEFAULT_KEY)).and_then(|v| v.as_u64())
+}
+
+fn set_limit(key: &str, limit: Option<u64>) -> std::result::Result<(), String> {
+    crate::cors::update_server_config(|config| {
+        let mut limits = limits_of(config);
+        match limit {
+            Some(bytes) => limits.insert(key.to_string(), json!(bytes)),
+            None => limits.remove(key),
+        };
+        let value = serde_yaml::to_value(&limits).map_err(|e| format!("Failed to convert limits: {}", e))?;
+        config
+            .as_mapping_mut()
+            .ok_or("Server config is not a mapping")?
+            .insert(serde_yaml::Value::String(LIMITS_KEY.to_string()), value);
+        Ok(())
+    })
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Evict the oldest entries of an app's cache until it is within its limit;
+/// returns the evicted entry names
+pub fn enforce(app_id: &str) -> Vec<String> {
+    let Some(limit) = limit_for(&limits(), app_id) else { return Vec::new() };
+    if crate::builds::is_building(app_id) {
+        return Vec::new();
+    }
+    let entries = entries(app_id);
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    let mut evicted = Vec::new();
+    for entry in entries {
+        if total <= limit {
+            break;
+        }
+        match remove(&get_cache_directory().join(app_id).join(&entry.name)) {
+            Ok(()) => {
+                total = total.saturating_sub(entry.size);
+                evicted.push(entry.name);
+            }
+            Err(e) => yeti_log!(warn, "Failed to evict cache entry '{}/{}': {}", app_id, entry.name, e),
+        }
+    }
+    if !evicted.is_empty() {
+        yeti_log!(info, "Evicted {} cache entries of '{}': {}", evicted.len(), app_id, evicted.join(", "));
+    }
+    evicted
+}
+
+/// Move an app's cache directory and limit to its new id after a rename;
+/// returns whether a cache directory was moved
+pub fn rename_app(old: &str, new: &str) -> std::result::Result<bool, String> {
+    if let Some(limit) = limits().get(old).and_then(|v| v.as_u64()) {
+        set_limit(new, Some(limit))?;
+        set_limit(old, None)?;
+    }
+    let (from, to) = (get_cache_directory().join(old), get_cache_directory().join(new));
+    if !from.is_dir() {
+        return Ok(false);
+    }
+    if to.exists() {
+        remove(&to).map_err(|e| format!("Failed to clear stale cache of '{}': {}", new, e))?;
+    }
+    std::fs::rename(&from, &to).map_err(|e| format!("Failed to move cache: {}", e))?;
+    Ok(true)
+}
+
+/// App ids with a cache directory
+fn cached_apps() -> Vec<String> {
+    let Ok(dir) = std::fs::read_dir(get_cache_directory()) else { return Vec::new() };
+    let mut apps: Vec<String> = dir
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|name| !name.starts_with('.') && !INTERNAL.contains(&name.as_str()))
+        .collect();
+    apps.sort();
+    apps
+}
+
+/// Enforce every app's limit, at most every `ENFORCE_INTERVAL_SECS`
+pub fn enforce_all() {
+    static LAST_RUN: Mutex<f64> = Mutex::new(0.0);
+    let now = now_secs();
+    {
+        let mut last = LAST_RUN.lock().unwrap();
+        if now - *last < ENFORCE_INTERVAL_SECS {
+            return;
+        }
+        *last = now;
+    }
+    for app_id in cached_apps() {
+        enforce(&app_id);
+    }
+}
+
+fn summary(app_id: &str, limits: &serde_json::Map<String, serde_json::Value>) -> serde_json::Value {
+    let entries = entries(app_id);
+    let size: u64 = entries.iter().map(|e| e.size).sum();
+    let limit = limit_for(limits, app_id);
+    json!({
+        "app_id": app_id,
+        "sizeBytes": size,
+        "limitBytes": limit,
+        "overLimit": limit.is_some_and(|l| size > l),
+        "entries": entries.len(),
+        "orphaned": !get_apps_directory().join(app_id).is_dir(),
+    })
+}
+
+#[derive(Default)]
+pub struct CacheResource;
+
+impl Resource for CacheResource {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "cache", "GET", {
+            let limits = limits();
+            let default_limit = limits.get(DEFAULT_KEY).cloned();
+
+            if let Some(app_id) = ctx.path_id() {
+                validate_identifier(app_id, "app_id")?;
+                let app_id = app_id.to_string();
+                let detail = tokio::task::spawn_blocking(move || {
+                    let mut detail = summary(&app_id, &limits);
+                    detail["entries"] = json!(entries(&app_id).iter().map(|e| json!({
+                        "name": e.name,
+                        "sizeBytes": e.size,
+                        "modifiedAt": e.modified,
+                    })).collect::<Vec<_>>());
+                    detail
+                })
+                .await
+                .map_err(|e| YetiError::Internal(format!("Cache scan failed: {}", e)))?;
+                return reply().json(detail);
+            }
+
+            let apps = tokio::task::spawn_blocking(move || {
+                cached_apps().iter().map(|app_id| summary(app_id, &limits)).collect::<Vec<_>>()
+            })
+            .await
+            .map_err(|e| YetiError::Internal(format!("Cache scan failed: {}", e)))?;
+            let total: u64 = apps.iter().filter_map(|a| a["sizeBytes"].as_u64()).sum();
+            reply().json(json!({
+                "totalBytes": total,
+                "defaultLimitBytes": default_limit,
+                "apps": apps,
+            }))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "cache", "PUT", {
+            let body = request.json_value()?;
+            let limit = match body.get("limitBytes") {
+                Some(serde_json::Value::Null) => None,
+                Some(v) => match v.as_u64() {
+                    Some(bytes) => Some(bytes),
+                    None => return bad_request("'limitBytes' must be a non-negative integer or null"),
+                },
+                None => return bad_request("'limitBytes' is required"),
+            };
+
+            let key = match ctx.path_id() {
+                Some(app_id) => {
+                    validate_identifier(app_id, "app_id")?;
+                    app_id.to_string()
+                }
+                None => DEFAULT_KEY.to_string(),
+            };
+            set_limit(&key, limit).map_err(YetiError::Internal)?;
+
+            let enforce_key = key.clone();
+            let evicted = tokio::task::spawn_blocking(move || {
+                if enforce_key == DEFAULT_KEY {
+                    cached_apps().iter().flat_map(|app_id| {
+                        enforce(app_id).into_iter().map(move |e| format!("{}/{}", app_id, e))
+                    }).collect()
+                } else {
+                    enforce(&enforce_key)
+                }
+            })
+            .await
+            .map_err(|e| YetiError::Internal(format!("Eviction failed: {}", e)))?;
+
+            crate::audit!(request, ctx, "cache.limit", &key, json!({
+                "limitBytes": limit,
+                "evicted": evicted,
+            }));
+
+            reply().json(json!({
+                "app_id": key,
+                "limitBytes": limit,
+                "evicted": evicted,
+            }))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "cache", "DELETE", {
+            let app_id = ctx.require_id()?.to_string();
+            validate_identifier(&app_id, "app_id")?;
+            if crate::builds::is_building(&app_id) {
+                return bad_request(&format!("A build of '{}' is running", app_id));
+            }
+            let cache_path = get_cache_directory().join(&app_id);
+            if !cache_path.is_dir() {
+                return not_found(&format!("No cache for '{}'", app_id));
+            }
+
+            let query = request.uri().query().unwrap_or("");
+            let target = match parse_query_param(query, "entry") {
+                Some(entry) => {
+                    if entry.is_empty() || entry.contains('/') || entry == "." || entry == ".." {
+                        return bad_request(&format!("Invalid entry '{}'", entry));
+                    }
+                    cache_path.join(entry)
+                }
+                None => cache_path.clone(),
+            };
+            if std::fs::symlink_metadata(&target).is_err() {
+                return not_found(&format!("Cache entry not found in '{}'", app_id));
+            }
+            let (freed, _) = measure(&target);
+            remove(&target).map_err(|e| YetiError::Internal(format!("Failed to clear cache: {}", e)))?;
+
+            let entry = target.strip_prefix(&cache_path).ok().map(|p| p.to_string_lossy().to_string()).filter(|p| !p.is_empty());
+            crate::audit!(request, ctx, "cache.clear", &app_id, json!({
+                "entry": entry,
+                "freedBytes": freed,
+            }));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "entry": entry,
+                "freedBytes": freed,
+            }))
+        })
+    });
+}
+
+register_resource!(CacheResource);
@@ -0,0 +1,282 @@
+//! CORS Policy Resource
+//!
+//! View and modify the CORS policy the server applies to admin and app
+//! routes, so external dashboards can call the API.
+//!
+//! | Method | Path        | Description                      |
+//! |--------|-------------|----------------------------------|
+//! | GET    | /admin/cors | Current policy                   |
+//! | PUT    | /admin/cors | Replace (or patch) the policy    |
+//!
+//! The policy is the `cors:` section of the server config (`yeti-config.yaml`
+//! in the root directory). It is written atomically, so the server's config
+//! watcher applies it without a restart. Every resource that edits the server
+//! config goes through `update_server_config`, which serializes the edits and
+//! rewrites only the sections that changed, keeping comments elsewhere.
+//!
+//! ```json
+//! {"allowedOrigins": ["https://dash.example.com"], "allowedMethods": ["GET", "POST"],
+//!  "allowedHeaders": ["Authorization", "Content-Type"], "allowCredentials": true, "maxAgeSecs": 600}
+//! ```
+//!
+//! `allowCredentials` cannot be combined with a `*` origin, as browsers reject it.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use yeti_core::prelude::*;
+
+pub type Cors = CorsResource;
+
+const SERVER_CONFIG: &str = "yeti-config.yaml";
+
+const METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+fn server_config_path() -> PathBuf {
+    get_root_directory().join(SERVER_CONFIG)
+}
+
+pub fn read_server_config() -> std::result::Result<serde_yaml::Value, String> {
+    let path = server_config_path();
+    if !path.exists() {
+        return Ok(serde_yaml::Value::Mapping(Default::default()));
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", SERVER_CONFIG, e))?;
+    serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", SERVER_CONFIG, e))
+}
+
+/// Serializes read-modify-write of the server config across resources
+static SERVER_CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+/// Apply `f` to the server config under a lock and write back only the
+/// top-level sections it changed; other sections and comments stay as they are
+pub fn update_server_config<T>(
+    f: impl FnOnce(&mut serde_yaml::Value) -> std::result::Result<T, String>,
+) -> std::result::Result<T, String> {
+    let _guard = SERVER_CONFIG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut content = match std::fs::read_to_string(server_config_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", SERVER_CONFIG, e)),
+    };
+    let before = match serde_yaml::from_str(&content) {
+        Ok(serde_yaml::Value::Null) => serde_yaml::Value::Mapping(Default::default()),
+        Ok(config) => config,
+        Err(_) if content.trim().is_empty() => serde_yaml::Value::Mapping(Default::default()),
+        Err(e) => return Err(format!("Failed to parse {}: {}", SERVER_CONFIG, e)),
+    };
+    let mut after = before.clone();
+    let result = f(&mut after)?;
+
+    let (Some(old), Some(new)) = (before.as_mapping(), after.as_mapping()) else {
+        return Err(format!("{} is not a mapping", SERVER_CONFIG));
+    };
+    let mut changed = false;
+    for (key, value) in new {
+        if old.get(key) != Some(value) {
+            content = replace_section(&content, key, Some(value))?;
+            changed = true;
+        }
+    }
+    for key in old.keys().filter(|k| !new.contains_key(*k)) {
+        content = replace_section(&content, key, None)?;
+        changed = true;
+    }
+    if changed {
+        write_server_config(&content)?;
+    }
+    Ok(result)
+}
+
+/// Key of a top-level `key: ...` line
+fn section_key(line: &str) -> Option<&str> {
+    if line.starts_with([' ', '\t', '#']) || line == "-" || line.starts_with("- ") || line.starts_with("---") {
+        return None;
+    }
+    let (key, _) = line.split_once(':')?;
+    Some(key.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Replace the text of the top-level section `key` with `value`, appending it
+/// when missing and dropping it when `value` is `None`
+fn replace_section(
+    content: &str,
+    key: &serde_yaml::Value,
+    value: Option<&serde_yaml::Value>,
+) -> std::result::Result<String, String> {
+    let key = key.as_str().ok_or_else(|| format!("{} has a non-string top-level key", SERVER_CONFIG))?;
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let rendered: Vec<String> = match value {
+        Some(value) => {
+            let mut section = serde_yaml::Mapping::new();
+            section.insert(serde_yaml::Value::String(key.to_string()), value.clone());
+            serde_yaml::to_string(&section)
+                .map_err(|e| format!("Failed to serialize '{}': {}", key, e))?
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    match lines.iter().position(|l| section_key(l) == Some(key)) {
+        Some(start) => {
+            let mut end = lines[start + 1..]
+                .iter()
+                .position(|l| !l.trim().is_empty() && !l.starts_with(['#', ' ', '\t', '-']))
+                .map(|i| start + 1 + i)
+                .unwrap_or(lines.len());
+            // Blank lines and comments before the next section belong to it
+            while end > start + 1 && (lines[end - 1].trim().is_empty() || lines[end - 1].starts_with('#')) {
+                end -= 1;
+            }
+            lines.splice(start..end, rendered);
+        }
+        None => lines.extend(rendered),
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+/// Write via a temp file and rename so the watcher never sees a partial file
+fn write_server_config(content: &str) -> std::result::Result<(), String> {
+    let path = server_config_path();
+    let tmp = path.with_extension("yaml.tmp");
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", SERVER_CONFIG, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", SERVER_CONFIG, e))
+}
+
+/// The `cors:` section as JSON, with defaults for missing keys
+fn current_policy(config: &serde_yaml::Value) -> serde_json::Value {
+    let section = config
+        .get("cors")
+        .and_then(|v| serde_json::to_value(v).ok())
+        .unwrap_or_else(|| json!({}));
+    json!({
+        "allowedOrigins": section.get("allowedOrigins").cloned().unwrap_or_else(|| json!([])),
+        "allowedMethods": section.get("allowedMethods").cloned().unwrap_or_else(|| json!(METHODS)),
+        "allowedHeaders": section.get("allowedHeaders").cloned().unwrap_or_else(|| json!(["Authorization", "Content-Type"])),
+        "exposedHeaders": section.get("exposedHeaders").cloned().unwrap_or_else(|| json!([])),
+        "allowCredentials": section.get("allowCredentials").cloned().unwrap_or(json!(false)),
+        "maxAgeSecs": section.get("maxAgeSecs").cloned().unwrap_or(json!(600)),
+    })
+}
+
+fn string_list(policy: &serde_json::Value, key: &str) -> std::result::Result<Vec<String>, String> {
+    policy[key]
+        .as_array()
+        .ok_or_else(|| format!("'{}' must be an array of strings", key))?
+        .iter()
+        .map(|v| v.as_str().map(|s| s.trim().to_string()).ok_or_else(|| format!("'{}' must be an array of strings", key)))
+        .collect()
+}
+
+/// `*`, or scheme://host[:port] with no path
+fn validate_origin(origin: &str) -> std::result::Result<(), String> {
+    if origin == "*" {
+        return Ok(());
+    }
+    let rest = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .ok_or_else(|| format!("Origin '{}' must start with http:// or https://", origin))?;
+    if rest.is_empty() || rest.contains('/') || rest.contains(char::is_whitespace) {
+        return Err(format!("Origin '{}' must be scheme://host[:port] without a path", origin));
+    }
+    Ok(())
+}
+
+fn validate_policy(policy: &serde_json::Value) -> std::result::Result<(), String> {
+    let origins = string_list(policy, "allowedOrigins")?;
+    for origin in &origins {
+        validate_origin(origin)?;
+    }
+    for method in string_list(policy, "allowedMethods")? {
+        if !METHODS.contains(&method.to_uppercase().as_str()) {
+            return Err(format!("Unknown method '{}'", method));
+        }
+    }
+    for key in ["allowedHeaders", "exposedHeaders"] {
+        for header in string_list(policy, key)? {
+            let valid = header == "*" || (!header.is_empty()
+                && header.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            if !valid {
+                return Err(format!("Invalid header name '{}'", header));
+            }
+        }
+    }
+    let credentials = policy["allowCredentials"].as_bool().ok_or("'allowCredentials' must be a boolean")?;
+    if credentials && origins.iter().any(|o| o == "*") {
+        return Err("'allowCredentials' cannot be used with a '*' origin".to_string());
+    }
+    policy["maxAgeSecs"].as_u64().ok_or("'maxAgeSecs' must be a non-negative integer")?;
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct CorsResource;
+
+impl Resource for CorsResource {
+    fn name(&self) -> &str {
+        "cors"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "cors", "GET", {
+            let config = read_server_config().map_err(YetiError::Internal)?;
+            reply().json(current_policy(&config))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "cors", "PUT", {
+            let body = request.json_value()?;
+            let Some(updates) = body.as_object() else {
+                return bad_request("Body must be a JSON object");
+            };
+
+            let known = current_policy(&serde_yaml::Value::Mapping(Default::default()));
+            if let Some(key) = updates.keys().find(|k| known.get(k.as_str()).is_none()) {
+                return bad_request(&format!("Unknown CORS setting '{}'", key));
+            }
+
+            let updated = update_server_config(|config| {
+                let mut policy = current_policy(config);
+                for (key, value) in updates {
+                    policy[key] = value.clone();
+                }
+                if let Some(methods) = policy["allowedMethods"].as_array_mut() {
+                    for m in methods.iter_mut() {
+                        if let Some(s) = m.as_str() {
+                            *m = json!(s.to_uppercase());
+                        }
+                    }
+                }
+                if let Err(e) = validate_policy(&policy) {
+                    return Ok(Err(e));
+                }
+                let section: serde_yaml::Value =
+                    serde_yaml::to_value(&policy).map_err(|e| format!("Failed to convert policy: {}", e))?;
+                config
+                    .as_mapping_mut()
+                    .ok_or_else(|| format!("{} is not a mapping", SERVER_CONFIG))?
+                    .insert(serde_yaml::Value::String("cors".to_string()), section);
+                Ok(Ok(policy))
+            })
+            .map_err(YetiError::Internal)?;
+            let policy = match updated {
+                Ok(policy) => policy,
+                Err(e) => return bad_request(&e),
+            };
+
+            crate::audit!(request, ctx, "cors.update", "server", policy.clone());
+
+            reply().json(policy)
+        })
+    });
+}
+
+register_resource!(CorsResource);
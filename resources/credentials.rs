@@ -0,0 +1,220 @@
+//! Git HTTPS Credential Resource
+//!
+//! Personal access tokens for git servers reached over HTTPS (Azure DevOps,
+//! GitLab and GitHub setups without SSH), stored per host.
+//!
+//! | Method | Path                       | Description                          |
+//! |--------|----------------------------|--------------------------------------|
+//! | GET    | /admin/credentials         | Hosts with a stored token            |
+//! | GET    | /admin/credentials/{host}  | One host (token never returned)      |
+//! | POST   | /admin/credentials         | Store or replace a host's token      |
+//! | DELETE | /admin/credentials/{host}  | Remove a host's token                |
+//!
+//! POST body: `{"host": "dev.azure.com", "token": "...", "username":
+//! "build-bot"}`. `username` defaults to `DEFAULT_USERNAME`; most servers
+//! accept any name with a token (GitLab OAuth tokens need `oauth2`).
+//!
+//! Tokens are kept in the secrets store under the `git-credentials` scope,
+//! encrypted with ChaCha20-Poly1305 under a key generated on first use
+//! (`git-credentials.key` in the keys directory, 0600), so the secrets file
+//! alone does not reveal them. Every git command the admin runs (clone, pull,
+//! push, templates, ...) gets each stored host's token as an
+//! `http.https://{host}/.extraHeader` set through `GIT_CONFIG_*` environment
+//! variables: the token never appears in a URL, on a command line or in the
+//! app's `.git/config`, and is only sent to its own host.
+
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use yeti_core::prelude::*;
+
+pub type Credentials = CredentialsResource;
+
+const SCOPE: &str = "git-credentials";
+const KEY_FILE: &str = "git-credentials.key";
+const DEFAULT_USERNAME: &str = "git";
+/// Prefix of stored ciphertexts, for a future change of scheme
+const CIPHER_PREFIX: &str = "v1:";
+
+fn random_bytes(n: usize) -> std::result::Result<Vec<u8>, String> {
+    let hex = crate::webhooks::random_hex(n)?;
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// The encryption key, created 0600 on first use. Only a missing file makes
+/// a new key; when two first uses race, the loser reads the winner's key.
+fn cipher() -> std::result::Result<ChaCha20Poly1305, String> {
+    let path = get_keys_directory().join(KEY_FILE);
+    let key = match std::fs::read(&path) {
+        Ok(key) => key,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            std::fs::create_dir_all(get_keys_directory()).map_err(|e| format!("Failed to create keys directory: {}", e))?;
+            let key = random_bytes(32)?;
+            match crate::processes::create_private(&path, &key) {
+                Ok(()) => key,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", KEY_FILE, e))?
+                }
+                Err(e) => return Err(format!("Failed to write {}: {}", KEY_FILE, e)),
+            }
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", KEY_FILE, e)),
+    };
+    if key.len() != 32 {
+        return Err(format!("{} is corrupt", KEY_FILE));
+    }
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn encrypt(plaintext: &str) -> std::result::Result<String, String> {
+    let nonce = random_bytes(12)?;
+    let mut sealed = cipher()?
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| "Failed to encrypt token".to_string())?;
+    sealed.splice(0..0, nonce);
+    Ok(format!("{}{}", CIPHER_PREFIX, base64::engine::general_purpose::STANDARD.encode(sealed)))
+}
+
+fn decrypt(stored: &str) -> std::result::Result<String, String> {
+    let sealed = stored
+        .strip_prefix(CIPHER_PREFIX)
+        .and_then(|b| base64::engine::general_purpose::STANDARD.decode(b).ok())
+        .filter(|s| s.len() > 12)
+        .ok_or("Stored token is malformed")?;
+    let (nonce, ciphertext) = sealed.split_at(12);
+    let plaintext = cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Stored token cannot be decrypted (was the key replaced?)".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "Stored token is not UTF-8".to_string())
+}
+
+/// Hostname with an optional port, lowercased
+fn normalize_host(host: &str) -> std::result::Result<String, String> {
+    let host = host.trim().trim_end_matches('/').to_ascii_lowercase();
+    let (name, port) = host.split_once(':').unwrap_or((&host, ""));
+    let valid_name = !name.is_empty()
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid_name || !port.chars().all(|c| c.is_ascii_digit()) || (host.contains(':') && port.is_empty()) {
+        return Err(format!("Invalid host '{}'; give a hostname such as 'dev.azure.com'", host));
+    }
+    Ok(host)
+}
+
+/// Public view of a stored credential
+fn view(host: &str, entry: &serde_json::Value) -> serde_json::Value {
+    json!({
+        "host": host,
+        "username": entry["username"],
+        "created": entry["created"],
+    })
+}
+
+/// `GIT_CONFIG_*` variables adding each stored host's token as an HTTP
+/// header for requests to that host. Hosts whose token cannot be decrypted
+/// are skipped with a warning.
+pub fn git_env() -> Vec<(String, String)> {
+    let stored = crate::env::read_secrets(SCOPE);
+    if stored.is_empty() {
+        return Vec::new();
+    }
+    let mut pairs = Vec::new();
+    for (host, entry) in &stored {
+        let Some(token) = entry["token"].as_str() else { continue };
+        match decrypt(token) {
+            Ok(token) => {
+                let username = entry["username"].as_str().unwrap_or(DEFAULT_USERNAME);
+                let basic = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, token));
+                pairs.push((format!("http.https://{}/.extraHeader", host), format!("Authorization: Basic {}", basic)));
+            }
+            Err(e) => yeti_log!(warn, "Skipping git credential for {}: {}", host, e),
+        }
+    }
+    let mut env = vec![("GIT_CONFIG_COUNT".to_string(), pairs.len().to_string())];
+    for (i, (key, value)) in pairs.into_iter().enumerate() {
+        env.push((format!("GIT_CONFIG_KEY_{}", i), key));
+        env.push((format!("GIT_CONFIG_VALUE_{}", i), value));
+    }
+    env
+}
+
+#[derive(Default)]
+pub struct CredentialsResource;
+
+impl Resource for CredentialsResource {
+    fn name(&self) -> &str {
+        "credentials"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "credentials", "GET", {
+            let stored = crate::env::read_secrets(SCOPE);
+            if let Some(host) = ctx.path_id() {
+                let host = normalize_host(host).map_err(YetiError::Validation)?;
+                return match stored.get(&host) {
+                    Some(entry) => reply().json(view(&host, entry)),
+                    None => not_found(&format!("No credential for '{}'", host)),
+                };
+            }
+            let mut list: Vec<serde_json::Value> = stored.iter().map(|(host, entry)| view(host, entry)).collect();
+            list.sort_by(|a, b| a["host"].as_str().cmp(&b["host"].as_str()));
+            reply().json(json!(list))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "credentials", "POST", {
+            let body = request.json_value()?;
+            let host = normalize_host(&body.require_str("host")?).map_err(YetiError::Validation)?;
+            let token = body.require_str("token")?;
+            if token.trim().is_empty() || token.contains(char::is_whitespace) {
+                return bad_request("'token' must be a non-empty token without whitespace");
+            }
+            let username = body.get("username").and_then(|v| v.as_str()).unwrap_or(DEFAULT_USERNAME).to_string();
+            if username.is_empty() || username.contains(':') || username.contains(char::is_whitespace) {
+                return bad_request("'username' must not be empty or contain ':' or whitespace");
+            }
+
+            let created = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let entry = json!({
+                "username": username,
+                "token": encrypt(&token).map_err(YetiError::Internal)?,
+                "created": created,
+            });
+            let mut stored = crate::env::read_secrets(SCOPE);
+            let replaced = stored.insert(host.clone(), entry.clone()).is_some();
+            crate::env::write_secrets(SCOPE, &stored).map_err(YetiError::Internal)?;
+
+            crate::audit!(request, ctx, "credential.store", &host, json!({"username": username, "replaced": replaced}));
+
+            reply().code(if replaced { 200 } else { 201 }).json(view(&host, &entry))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "credentials", "DELETE", {
+            let host = normalize_host(ctx.require_id()?).map_err(YetiError::Validation)?;
+            let mut stored = crate::env::read_secrets(SCOPE);
+            if stored.remove(&host).is_none() {
+                return not_found(&format!("No credential for '{}'", host));
+            }
+            crate::env::write_secrets(SCOPE, &stored).map_err(YetiError::Internal)?;
+
+            crate::audit!(request, ctx, "credential.delete", &host, json!({}));
+
+            reply().json(json!({"deleted": true, "host": host}))
+        })
+    });
+}
+
+register_resource!(CredentialsResource);
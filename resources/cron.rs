@@ -0,0 +1,600 @@
+//! Scheduled Task Resource
+//!
+//! Recurring admin tasks (auto-pull an app hourly, nightly builds, a weekly
+//! benchmark profile) defined with cron expressions and run by one shared
+//! executor.
+//!
+//! | Method | Path                      | Description                        |
+//! |--------|---------------------------|------------------------------------|
+//! | GET    | /admin/cron               | List tasks with last/next run      |
+//! | GET    | /admin/cron/{id}          | Single task                        |
+//! | POST   | /admin/cron               | Create a task                      |
+//! | POST   | /admin/cron/{id}/run      | Run a task now                     |
+//! | PUT    | /admin/cron/{id}          | Update schedule/task/enabled       |
+//! | DELETE | /admin/cron/{id}          | Remove a task                      |
+//!
+//! Schedules are 5-field cron expressions in UTC (`minute hour day-of-month
+//! month day-of-week`, with `*`, lists, ranges and `/step`) or one of
+//! `@hourly`, `@daily`, `@weekly`, `@monthly`.
+//!
+//! A task is an admin API call, so runs go through the same validation and
+//! audit log as manual operations:
+//!
+//! | kind        | Fields                   | Call                          |
+//! |-------------|--------------------------|-------------------------------|
+//! | `pull`      | `app`                    | POST /admin/repos/pull/{app}  |
+//! | `build`     | `app`                    | POST /admin/builds/{app}      |
+//! | `benchmark` | `profile` or `test`      | POST /admin/runner            |
+//! | `request`   | `method`, `path`, `body` | Any /admin endpoint           |
+//!
+//! ```json
+//! {"name": "Pull my-app", "schedule": "0 * * * *", "task": {"kind": "pull", "app": "my-app"}}
+//! ```
+//!
+//! Tasks and their last run are stored in `admin-schedules.yaml` in the root
+//! directory. The executor calls the API at `YETI_ADMIN_URL` (default
+//! `https://localhost:9996/admin`) with the basic-auth credentials in
+//! `YETI_ADMIN_AUTH` (`user:password`); without them tasks fail instead of
+//! running. The credentials go to curl in a 0600 header file, never on its
+//! command line. TLS is verified (`YETI_ADMIN_CACERT` names a CA file for a
+//! self-signed certificate); `YETI_ADMIN_INSECURE=true` turns verification
+//! off, for a localhost URL only. The executor starts when the resource is
+//! registered, on its own thread, and a task is skipped while its previous
+//! run is still going.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use base64::Engine;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use yeti_core::prelude::*;
+
+pub type Cron = CronResource;
+
+const SCHEDULES_FILE: &str = "admin-schedules.yaml";
+const DEFAULT_ADMIN_URL: &str = "https://localhost:9996/admin";
+const TICK_SECS: u64 = 15;
+const TIMEOUT_SECS: &str = "300";
+const KINDS: &[&str] = &["pull", "build", "benchmark", "request"];
+
+// ── Cron expressions ──
+
+/// A parsed schedule: the allowed values of each field
+struct Schedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// Parse one field (`*`, `5`, `1-5`, `*/15`, `0-30/10`, comma lists)
+fn parse_field(field: &str, min: u32, max: u32) -> std::result::Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().ok().filter(|s| *s > 0)
+                .ok_or_else(|| format!("Invalid step in '{}'", part))?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a = a.parse::<u32>().map_err(|_| format!("Invalid value in '{}'", part))?;
+            let b = b.parse::<u32>().map_err(|_| format!("Invalid value in '{}'", part))?;
+            (a, b)
+        } else {
+            let v = range.parse::<u32>().map_err(|_| format!("Invalid value in '{}'", part))?;
+            (v, if step > 1 { max } else { v })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' is outside {}-{}", part, min, max));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            allowed[v as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+fn parse_schedule(expr: &str) -> std::result::Result<Schedule, String> {
+    let expr = match expr.trim() {
+        "@hourly" => "0 * * * *",
+        "@daily" | "@midnight" => "0 0 * * *",
+        "@weekly" => "0 0 * * 0",
+        "@monthly" => "0 0 1 * *",
+        other => other,
+    };
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let [minute, hour, day, month, weekday] = fields[..] else {
+        return Err(format!("Schedule '{}' must have 5 fields", expr));
+    };
+    let mut weekdays = parse_field(weekday, 0, 7)?;
+    // 7 is Sunday too
+    if weekdays[7] {
+        weekdays[0] = true;
+    }
+    Ok(Schedule {
+        minutes: parse_field(minute, 0, 59)?,
+        hours: parse_field(hour, 0, 23)?,
+        days: parse_field(day, 1, 31)?,
+        months: parse_field(month, 1, 12)?,
+        weekdays,
+        any_day: day == "*",
+        any_weekday: weekday == "*",
+    })
+}
+
+impl Schedule {
+    /// Standard cron: when both day fields are restricted, either may match
+    fn day_matches(&self, t: &DateTime<Utc>) -> bool {
+        let day = self.days[t.day() as usize];
+        let weekday = self.weekdays[t.weekday().num_days_from_sunday() as usize];
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// First matching minute strictly after `after`, within four years
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(4 * 366);
+        while t < limit {
+            if !self.months[t.month() as usize] {
+                let (y, m) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = Utc.with_ymd_and_hms(y, m, 1, 0, 0, 0).single()?;
+            } else if !self.day_matches(&t) {
+                t = (t + Duration::days(1)).with_hour(0)?.with_minute(0)?;
+            } else if !self.hours[t.hour() as usize] {
+                t = (t + Duration::hours(1)).with_minute(0)?;
+            } else if !self.minutes[t.minute() as usize] {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+fn next_run(expr: &str, after: DateTime<Utc>) -> Option<f64> {
+    parse_schedule(expr).ok()?.next_after(after).map(|t| t.timestamp() as f64)
+}
+
+// ── Store ──
+
+fn store() -> &'static Mutex<()> {
+    static STORE: OnceLock<Mutex<()>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(()))
+}
+
+fn running() -> &'static Mutex<HashSet<String>> {
+    static RUNNING: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Number of tasks currently running
+pub fn running_tasks() -> usize {
+    running().lock().unwrap().len()
+}
+
+fn schedules_path() -> PathBuf {
+    get_root_directory().join(SCHEDULES_FILE)
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+pub fn read_tasks() -> Vec<serde_json::Value> {
+    std::fs::read_to_string(schedules_path())
+        .ok()
+        .and_then(|s| serde_yaml::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+}
+
+/// Write via a temp file and rename so a crash never leaves a partial file
+fn write_tasks(tasks: &[serde_json::Value]) -> std::result::Result<(), String> {
+    let path = schedules_path();
+    let tmp = path.with_extension("yaml.tmp");
+    let content = serde_yaml::to_string(tasks).map_err(|e| format!("Failed to serialize schedules: {}", e))?;
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", SCHEDULES_FILE, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", SCHEDULES_FILE, e))
+}
+
+/// Read-modify-write the task list under the store lock
+pub fn modify_tasks<T>(f: impl FnOnce(&mut Vec<serde_json::Value>) -> T) -> std::result::Result<T, String> {
+    let _guard = store().lock().unwrap();
+    let mut tasks = read_tasks();
+    let result = f(&mut tasks);
+    write_tasks(&tasks)?;
+    Ok(result)
+}
+
+/// Task as returned by the API, with its next run time and running flag
+fn view(task: &serde_json::Value) -> serde_json::Value {
+    let mut view = task.clone();
+    let id = task["id"].as_str().unwrap_or("");
+    let enabled = task["enabled"].as_bool().unwrap_or(true);
+    view["nextRunAt"] = match task["schedule"].as_str() {
+        Some(expr) if enabled => json!(next_run(expr, Utc::now())),
+        _ => json!(null),
+    };
+    view["running"] = json!(running().lock().unwrap().contains(id));
+    view
+}
+
+// ── Validation ──
+
+fn validate_task(task: &serde_json::Value) -> std::result::Result<(), String> {
+    let kind = task["kind"].as_str().ok_or("'task.kind' is required")?;
+    if !KINDS.contains(&kind) {
+        return Err(format!("Unknown task kind '{}' (expected one of {})", kind, KINDS.join(", ")));
+    }
+    match kind {
+        "pull" | "build" => {
+            let app = task["app"].as_str().ok_or("'task.app' is required")?;
+            if !get_apps_directory().join(app).is_dir() || app.contains('/') || app.starts_with('.') {
+                return Err(format!("Application '{}' not found", app));
+            }
+        }
+        "benchmark" => {
+            if task["profile"].as_str().is_none() && task["test"].as_str().is_none() {
+                return Err("'task.profile' or 'task.test' is required".to_string());
+            }
+        }
+        _ => {
+            let method = task["method"].as_str().unwrap_or("POST").to_uppercase();
+            if !["GET", "POST", "PUT", "DELETE"].contains(&method.as_str()) {
+                return Err(format!("Unsupported method '{}'", method));
+            }
+            let path = task["path"].as_str().ok_or("'task.path' is required")?;
+            if !path.starts_with('/') || path.contains("..") || path.contains(char::is_whitespace) {
+                return Err(format!("Invalid path '{}' (relative to /admin, e.g. /builds/my-app)", path));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply the writable fields of `body` to `record`
+fn apply_fields(record: &mut serde_json::Value, body: &serde_json::Value) -> std::result::Result<(), String> {
+    if let Some(name) = body.get("name") {
+        record["name"] = json!(name.as_str().ok_or("'name' must be a string")?);
+    }
+    if let Some(schedule) = body.get("schedule") {
+        let schedule = schedule.as_str().ok_or("'schedule' must be a string")?;
+        parse_schedule(schedule)?;
+        record["schedule"] = json!(schedule.trim());
+    }
+    if let Some(task) = body.get("task") {
+        validate_task(task)?;
+        record["task"] = task.clone();
+    }
+    if let Some(enabled) = body.get("enabled") {
+        record["enabled"] = json!(enabled.as_bool().ok_or("'enabled' must be a boolean")?);
+    }
+    Ok(())
+}
+
+// ── Executor ──
+
+/// The admin call a task makes: method, path under /admin, JSON body
+fn task_request(task: &serde_json::Value) -> (String, String, serde_json::Value) {
+    let app = task["app"].as_str().unwrap_or("");
+    match task["kind"].as_str().unwrap_or("") {
+        "pull" => ("POST".to_string(), format!("/repos/pull/{}", app), json!({})),
+        "build" => ("POST".to_string(), format!("/builds/{}", app), json!({})),
+        "benchmark" => {
+            let mut body = json!({});
+            for key in ["profile", "test"] {
+                if let Some(v) = task.get(key).filter(|v| v.is_string()) {
+                    body[key] = v.clone();
+                }
+            }
+            ("POST".to_string(), "/runner".to_string(), body)
+        }
+        _ => (
+            task["method"].as_str().unwrap_or("POST").to_uppercase(),
+            task["path"].as_str().unwrap_or("/").to_string(),
+            task.get("body").cloned().unwrap_or(json!({})),
+        ),
+    }
+}
+
+/// Whether a URL points at this host (the only target TLS checks may be skipped for)
+pub fn is_local(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or("");
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => authority.split(':').next().unwrap_or(""),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Make the task's admin call via curl; returns the HTTP status and body
+fn call_admin(task: &serde_json::Value, id: &str) -> std::result::Result<(u16, String), String> {
+    let (method, path, body) = task_request(&task["task"]);
+    let base = std::env::var("YETI_ADMIN_URL").unwrap_or_else(|_| DEFAULT_ADMIN_URL.to_string());
+    let auth = std::env::var("YETI_ADMIN_AUTH")
+        .ok()
+        .filter(|a| a.contains(':'))
+        .ok_or("YETI_ADMIN_AUTH (user:password) is not set; scheduled tasks need admin credentials")?;
+    let url = format!("{}{}", base.trim_end_matches('/'), path);
+
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["-s", "--max-time", TIMEOUT_SECS, "-X", &method, "-w", "\n%{http_code}"]);
+    if let Ok(ca) = std::env::var("YETI_ADMIN_CACERT") {
+        cmd.arg("--cacert").arg(ca);
+    }
+    if std::env::var("YETI_ADMIN_INSECURE").is_ok_and(|v| v == "true") {
+        if !is_local(&url) {
+            return Err(format!("YETI_ADMIN_INSECURE only applies to localhost, not {}", url));
+        }
+        cmd.arg("-k");
+    }
+    if method != "GET" {
+        cmd.args(["-H", "Content-Type: application/json", "--data-binary", &body.to_string()]);
+    }
+    let authorization = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(&auth));
+    let header_path = crate::processes::header_file("cron", &authorization)?;
+    cmd.arg("-H").arg(format!("@{}", header_path.display()));
+    cmd.arg(&url);
+
+    let output = crate::processes::output("cron", &format!("cron:{}", id), &mut cmd);
+    let _ = std::fs::remove_file(&header_path);
+    let output = output.map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("{} {} unreachable (curl exit code {})", method, url, output.status.code().unwrap_or(-1)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", stdout.as_ref()));
+    let code = code.trim().parse().map_err(|_| "No HTTP status in response".to_string())?;
+    Ok((code, body.to_string()))
+}
+
+/// Run one task in the background and record the outcome on it
+fn run_task(task: serde_json::Value) {
+    let id = task["id"].as_str().unwrap_or("").to_string();
+    if !running().lock().unwrap().insert(id.clone()) {
+        yeti_log!(warn, "Scheduled task '{}' is still running; skipping", id);
+        return;
+    }
+    let started = now_secs();
+    let _ = modify_tasks(|tasks| {
+        if let Some(t) = tasks.iter_mut().find(|t| t["id"] == id.as_str()) {
+            t["lastRunAt"] = json!(started);
+        }
+    });
+
+    let kind = task["task"]["kind"].as_str().unwrap_or("").to_string();
+    tokio::spawn(async move {
+        let task_id = id.clone();
+        let result = tokio::task::spawn_blocking(move || call_admin(&task, &task_id))
+            .await
+            .unwrap_or_else(|e| Err(format!("Task failed: {}", e)));
+        let (status, error) = match result {
+            Ok((code, _)) if (200..300).contains(&code) => ("succeeded", None),
+            Ok((code, body)) => ("failed", Some(format!("HTTP {}: {}", code, body.chars().take(500).collect::<String>()))),
+            Err(e) => ("failed", Some(e)),
+        };
+        match &error {
+            Some(e) => {
+                yeti_log!(warn, "Scheduled task '{}' failed: {}", id, e);
+                crate::notifications::notify("schedule.failed", "warning", &id,
+                    &format!("Scheduled {} failed", kind), e);
+            }
+            None => crate::notifications::resolve("schedule.failed", &id),
+        }
+        let _ = modify_tasks(|tasks| {
+            if let Some(t) = tasks.iter_mut().find(|t| t["id"] == id.as_str()) {
+                t["lastStatus"] = json!(status);
+                t["lastError"] = json!(error);
+                t["lastDurationSecs"] = json!(now_secs() - started);
+            }
+        });
+        running().lock().unwrap().remove(&id);
+    });
+}
+
+/// Start the shared executor once, on a thread with its own runtime so it does
+/// not depend on admin traffic. It wakes every `TICK_SECS` and runs each
+/// enabled task whose schedule has a slot between its previous and current tick.
+fn ensure_executor() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    if std::env::var("YETI_ADMIN_AUTH").is_err() {
+        yeti_log!(warn, "YETI_ADMIN_AUTH is not set; scheduled tasks will fail until it is");
+    }
+    let started = std::thread::Builder::new().name("admin-cron".to_string()).spawn(|| {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                yeti_log!(error, "Failed to start the scheduled task executor: {}", e);
+                return;
+            }
+        };
+        runtime.block_on(run_executor());
+    });
+    if let Err(e) = started {
+        yeti_log!(error, "Failed to start the scheduled task executor: {}", e);
+    }
+}
+
+async fn run_executor() {
+    let mut last_tick = Utc::now();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(TICK_SECS)).await;
+        let now = Utc::now();
+        let due: Vec<serde_json::Value> = {
+            let _guard = store().lock().unwrap();
+            read_tasks()
+        }
+        .into_iter()
+        .filter(|t| t["enabled"].as_bool().unwrap_or(true))
+        .filter(|t| {
+            t["schedule"].as_str()
+                .and_then(|expr| parse_schedule(expr).ok())
+                .and_then(|s| s.next_after(last_tick))
+                .is_some_and(|next| next <= now)
+        })
+        .collect();
+        last_tick = now;
+        for task in due {
+            run_task(task);
+        }
+        let _ = tokio::task::spawn_blocking(crate::notifications::run_checks).await;
+        let _ = tokio::task::spawn_blocking(crate::cache::enforce_all).await;
+        let _ = tokio::task::spawn_blocking(crate::apps::purge_trash).await;
+    }
+}
+
+/// `{id}` from `/cron/{id}/run`
+fn id_before(uri_path: &str, action: &str) -> Option<String> {
+    let prefix = uri_path.trim_end_matches('/').strip_suffix(action)?;
+    Some(prefix.rsplit('/').next().unwrap_or("").to_string())
+}
+
+pub struct CronResource;
+
+impl Default for CronResource {
+    fn default() -> Self {
+        ensure_executor();
+        CronResource
+    }
+}
+
+impl Resource for CronResource {
+    fn name(&self) -> &str {
+        "cron"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "cron", "GET", {
+            let tasks = {
+                let _guard = store().lock().unwrap();
+                read_tasks()
+            };
+            if let Some(id) = ctx.path_id() {
+                return match tasks.iter().find(|t| t["id"] == id) {
+                    Some(task) => reply().json(view(task)),
+                    None => not_found(&format!("Scheduled task '{}' not found", id)),
+                };
+            }
+            let tasks: Vec<serde_json::Value> = tasks.iter().map(view).collect();
+            reply().json(json!(tasks))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "cron", "POST", {
+            // POST /admin/cron/{id}/run
+            if let Some(id) = id_before(request.uri().path(), "/run") {
+                let task = {
+                    let _guard = store().lock().unwrap();
+                    read_tasks().into_iter().find(|t| t["id"] == id.as_str())
+                };
+                let Some(task) = task else {
+                    return not_found(&format!("Scheduled task '{}' not found", id));
+                };
+                if running().lock().unwrap().contains(&id) {
+                    return bad_request(&format!("Scheduled task '{}' is already running", id));
+                }
+                run_task(task);
+                crate::audit!(request, ctx, "cron.run", &id, json!({}));
+                return reply().code(202).json(json!({
+                    "id": id,
+                    "status": "running",
+                }));
+            }
+
+            let body = request.json_value()?;
+            for key in ["name", "schedule", "task"] {
+                if body.get(key).is_none() {
+                    return bad_request(&format!("Missing '{}'", key));
+                }
+            }
+            let mut record = json!({
+                "id": format!("task-{}", (now_secs() * 1000.0) as u64),
+                "enabled": true,
+                "createdAt": now_secs(),
+                "lastRunAt": null,
+                "lastStatus": null,
+                "lastError": null,
+            });
+            if let Err(e) = apply_fields(&mut record, &body) {
+                return bad_request(&e);
+            }
+            modify_tasks(|tasks| tasks.push(record.clone())).map_err(YetiError::Internal)?;
+
+            let id = record["id"].as_str().unwrap_or("").to_string();
+            crate::audit!(request, ctx, "cron.create", &id, json!({
+                "schedule": record["schedule"],
+                "task": record["task"],
+            }));
+
+            reply().code(201).json(view(&record))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "cron", "PUT", {
+            let id = ctx.require_id()?.to_string();
+            let body = request.json_value()?;
+            let updated = modify_tasks(|tasks| {
+                let Some(task) = tasks.iter_mut().find(|t| t["id"] == id.as_str()) else {
+                    return Ok(None);
+                };
+                let mut candidate = task.clone();
+                apply_fields(&mut candidate, &body)?;
+                *task = candidate.clone();
+                Ok(Some(candidate))
+            }).map_err(YetiError::Internal)?;
+            let task = match updated {
+                Ok(Some(task)) => task,
+                Ok(None) => return not_found(&format!("Scheduled task '{}' not found", id)),
+                Err(e) => return bad_request(&e),
+            };
+
+            let changed: Vec<&String> = body.as_object().map(|o| o.keys().collect()).unwrap_or_default();
+            crate::audit!(request, ctx, "cron.update", &id, json!({"fields": changed}));
+
+            reply().json(view(&task))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "cron", "DELETE", {
+            let id = ctx.require_id()?.to_string();
+            let removed = modify_tasks(|tasks| {
+                let before = tasks.len();
+                tasks.retain(|t| t["id"] != id.as_str());
+                tasks.len() != before
+            }).map_err(YetiError::Internal)?;
+            if !removed {
+                return not_found(&format!("Scheduled task '{}' not found", id));
+            }
+
+            crate::audit!(request, ctx, "cron.delete", &id, json!({}));
+
+            reply().json(json!({
+                "id": id,
+                "deleted": true,
+            }))
+        })
+    });
+}
+
+register_resource!(CronResource);
@@ -0,0 +1,266 @@
+//! Table Data Browser Resource
+//!
+//! Browse, filter, edit and delete records in any app's tables. Tables and
+//! their fields come from the app's schema (see `schemas::app_tables`), so
+//! only declared tables are reachable and edits are checked against the
+//! declared field types.
+//!
+//! | Method | Path                              | Description               |
+//! |--------|-----------------------------------|---------------------------|
+//! | GET    | /admin/data/{app_id}              | Tables with record counts |
+//! | GET    | /admin/data/{app_id}?table=T      | Page of records           |
+//! | GET    | /admin/data/{app_id}?table=T&id=X | Single record             |
+//! | PUT    | /admin/data/{app_id}?table=T      | Create/replace a record   |
+//! | DELETE | /admin/data/{app_id}?table=T&id=X | Delete a record           |
+//!
+//! Paging: `?limit=` (default 25, max 1000) and `?offset=`. Sorting:
+//! `?sort=field` or `?sort=-field`. Filtering: `?where=field:value,...` for
+//! exact matches and `?q=` for a case-insensitive substring over all fields.
+
+use yeti_core::prelude::*;
+
+pub type Data = DataResource;
+
+const DEFAULT_LIMIT: usize = 25;
+const MAX_LIMIT: usize = 1000;
+
+/// Look up a table declared in the app's schema
+fn table_schema(app_id: &str, table: &str) -> std::result::Result<serde_json::Value, YetiError> {
+    validate_identifier(app_id, "app_id")?;
+    let app_path = get_apps_directory().join(app_id);
+    if !app_path.is_dir() {
+        return Err(YetiError::Validation(format!("Application '{}' not found", app_id)));
+    }
+    crate::schemas::app_tables(&app_path)
+        .into_iter()
+        .find(|t| t.get("name").and_then(|v| v.as_str()) == Some(table))
+        .ok_or_else(|| YetiError::Validation(format!("Table '{}' is not declared by '{}'", table, app_id)))
+}
+
+/// Display form of a field value for filtering and sorting
+fn field_text(record: &serde_json::Value, field: &str) -> String {
+    match record.get(field) {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Parse `field:value,field:value`
+fn parse_where(filter: &str) -> Vec<(String, String)> {
+    filter
+        .split(',')
+        .filter_map(|clause| clause.split_once(':'))
+        .map(|(f, v)| (f.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+fn compare(a: &serde_json::Value, b: &serde_json::Value, field: &str) -> std::cmp::Ordering {
+    match (a.get(field).and_then(|v| v.as_f64()), b.get(field).and_then(|v| v.as_f64())) {
+        (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal),
+        _ => field_text(a, field).cmp(&field_text(b, field)),
+    }
+}
+
+/// Check (and coerce, for values edited as text) a record against the table's
+/// declared fields. Unknown fields and missing required (`!`) fields are errors.
+fn validate_record(schema: &serde_json::Value, record: &mut serde_json::Value) -> std::result::Result<(), String> {
+    let fields = schema.get("fields").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let obj = record.as_object_mut().ok_or("Record must be a JSON object")?;
+
+    for key in obj.keys() {
+        if !fields.iter().any(|f| f["name"] == key.as_str()) {
+            return Err(format!("Unknown field '{}'", key));
+        }
+    }
+
+    for field in &fields {
+        let name = field["name"].as_str().unwrap_or("");
+        let declared = field["type"].as_str().unwrap_or("");
+        let required = declared.ends_with('!');
+        let base = declared.trim_end_matches('!');
+
+        let value = match obj.get_mut(name) {
+            Some(serde_json::Value::Null) | None => {
+                if required {
+                    return Err(format!("Field '{}' is required", name));
+                }
+                continue;
+            }
+            Some(v) => v,
+        };
+
+        let coerced = match (base, &*value) {
+            ("Int", serde_json::Value::String(s)) => s.trim().parse::<i64>().ok().map(|n| json!(n)),
+            ("Int", v) if v.is_i64() || v.is_u64() => None,
+            ("Float", serde_json::Value::String(s)) => s.trim().parse::<f64>().ok().map(|n| json!(n)),
+            ("Float", v) if v.is_number() => None,
+            ("Boolean", serde_json::Value::String(s)) => s.trim().parse::<bool>().ok().map(|b| json!(b)),
+            ("Boolean", v) if v.is_boolean() => None,
+            ("String" | "ID", serde_json::Value::String(_)) => None,
+            ("ID", v) if v.is_number() => None,
+            (t, v) if t.starts_with('[') && v.is_array() => None,
+            ("Int" | "Float" | "Boolean" | "String" | "ID", _) => {
+                return Err(format!("Field '{}' must be {}", name, base));
+            }
+            _ => None, // custom or relationship types are passed through
+        };
+        match coerced {
+            Some(v) => *value = v,
+            None if matches!(base, "Int" | "Float" | "Boolean") && value.is_string() => {
+                return Err(format!("Field '{}' must be {}", name, base));
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct DataResource;
+
+impl Resource for DataResource {
+    fn name(&self) -> &str {
+        "data"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "data", "GET", {
+            let app_id = ctx.require_id()?.to_string();
+            let query = request.uri().query().unwrap_or("");
+
+            // No table: list the app's tables with record counts
+            let Some(table_name) = parse_query_param(query, "table") else {
+                validate_identifier(&app_id, "app_id")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                let mut tables = crate::schemas::app_tables(&app_path);
+                for table in &mut tables {
+                    let name = table["name"].as_str().unwrap_or("").to_string();
+                    let count = match ctx.get_table(&name) {
+                        Ok(t) => t.scan_all().await.ok().map(|rows| rows.len()),
+                        Err(_) => None,
+                    };
+                    table["count"] = json!(count);
+                }
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "tables": tables,
+                }));
+            };
+
+            let schema = table_schema(&app_id, &table_name)?;
+            let table = ctx.get_table(&table_name)?;
+
+            if let Some(id) = parse_query_param(query, "id") {
+                return match table.get_by_id(&id).await? {
+                    Some(record) => reply().json(record),
+                    None => not_found(&format!("Record '{}' not found in {}", id, table_name)),
+                };
+            }
+
+            let limit = parse_query_param(query, "limit")
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_LIMIT)
+                .min(MAX_LIMIT);
+            let offset = parse_query_param(query, "offset")
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(0);
+            let filters = parse_query_param(query, "where").map(|w| parse_where(&w)).unwrap_or_default();
+            let search = parse_query_param(query, "q").map(|q| q.to_lowercase());
+
+            let mut records: Vec<serde_json::Value> = table
+                .scan_all()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|r| filters.iter().all(|(f, v)| field_text(r, f) == *v))
+                .filter(|r| {
+                    search.as_ref().is_none_or(|q| {
+                        r.as_object().is_some_and(|obj| {
+                            obj.keys().any(|k| field_text(r, k).to_lowercase().contains(q.as_str()))
+                        })
+                    })
+                })
+                .collect();
+
+            match parse_query_param(query, "sort") {
+                Some(sort) => match sort.strip_prefix('-') {
+                    Some(field) => records.sort_by(|a, b| compare(b, a, field)),
+                    None => records.sort_by(|a, b| compare(a, b, &sort)),
+                },
+                None => records.sort_by(|a, b| compare(a, b, "id")),
+            }
+
+            let total = records.len();
+            let page: Vec<serde_json::Value> = records.into_iter().skip(offset).take(limit).collect();
+
+            reply().json(json!({
+                "app_id": app_id,
+                "table": table_name,
+                "fields": schema["fields"],
+                "total": total,
+                "limit": limit,
+                "offset": offset,
+                "data": page,
+            }))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "data", "PUT", {
+            let app_id = ctx.require_id()?.to_string();
+            let query = request.uri().query().unwrap_or("");
+            let table_name = parse_required_query_param(query, "table")?;
+            let schema = table_schema(&app_id, &table_name)?;
+
+            let mut record = request.json_value()?;
+            let id = match record.get("id") {
+                Some(serde_json::Value::String(s)) if !s.is_empty() => s.clone(),
+                Some(v) if v.is_number() => v.to_string(),
+                _ => return bad_request("Record must have an 'id'"),
+            };
+            if let Err(e) = validate_record(&schema, &mut record) {
+                return bad_request(&e);
+            }
+
+            let table = ctx.get_table(&table_name)?;
+            let existed = table.get_by_id(&id).await?.is_some();
+            table.put(record.clone()).await?;
+
+            crate::audit!(request, ctx, if existed { "data.update" } else { "data.create" },
+                &format!("{}/{}/{}", app_id, table_name, id), json!({}));
+
+            reply().code(if existed { 200 } else { 201 }).json(record)
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "data", "DELETE", {
+            let app_id = ctx.require_id()?.to_string();
+            let query = request.uri().query().unwrap_or("");
+            let table_name = parse_required_query_param(query, "table")?;
+            let id = parse_required_query_param(query, "id")?;
+            table_schema(&app_id, &table_name)?;
+
+            let table = ctx.get_table(&table_name)?;
+            if table.get_by_id(&id).await?.is_none() {
+                return not_found(&format!("Record '{}' not found in {}", id, table_name));
+            }
+            table.delete(&id).await?;
+
+            crate::audit!(request, ctx, "data.delete", &format!("{}/{}/{}", app_id, table_name, id), json!({}));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "table": table_name,
+                "id": id,
+                "deleted": true,
+            }))
+        })
+    });
+}
+
+register_resource!(DataResource);
@@ -0,0 +1,345 @@
+//! Domain Mapping Resource
+//!
+//! Maps hostnames and path prefixes to apps (e.g. `shop.example.com` →
+//! `my-shop`), so an app can be served from its own domain.
+//!
+//! | Method | Path                       | Description                           |
+//! |--------|----------------------------|---------------------------------------|
+//! | GET    | /admin/domains             | All mappings plus detected conflicts  |
+//! | GET    | /admin/domains?app={app_id}| Mappings of one app                   |
+//! | POST   | /admin/domains             | Add a mapping                         |
+//! | PUT    | /admin/domains/{id}        | Change a mapping                      |
+//! | DELETE | /admin/domains/{id}        | Remove a mapping                      |
+//!
+//! A mapping is `{"host": "shop.example.com", "pathPrefix": "/", "app":
+//! "my-shop"}`. `host` may be omitted (any host) or start with `*.` for
+//! subdomains; `pathPrefix` defaults to `/`. Its id is derived from host and
+//! prefix (`shop-example-com`, `any-shop`).
+//!
+//! Mappings are the `domains:` section of the server config
+//! (`yeti-config.yaml`), written atomically like the CORS policy. Adding or
+//! changing a mapping is rejected with 409 when another mapping already
+//! claims the same host and prefix, or when a host-less prefix is another
+//! app's route prefix. App details (`GET /admin/apps/{id}`) list the app's
+//! mappings.
+
+use yeti_core::prelude::*;
+
+pub type Domains = DomainsResource;
+
+fn section(config: &serde_yaml::Value) -> Vec<serde_json::Value> {
+    config
+        .get("domains")
+        .and_then(|v| serde_json::to_value(v).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|m| normalize(&m))
+        .collect()
+}
+
+fn store(config: &mut serde_yaml::Value, mappings: &[serde_json::Value]) -> std::result::Result<(), String> {
+    let stored: Vec<serde_json::Value> = mappings
+        .iter()
+        .map(|m| {
+            let mut m = m.clone();
+            if let Some(o) = m.as_object_mut() {
+                o.remove("id");
+                o.retain(|_, v| !v.is_null());
+            }
+            m
+        })
+        .collect();
+    let value = serde_yaml::to_value(&stored).map_err(|e| format!("Failed to convert mappings: {}", e))?;
+    config
+        .as_mapping_mut()
+        .ok_or("Server config is not a mapping")?
+        .insert(serde_yaml::Value::String("domains".to_string()), value);
+    Ok(())
+}
+
+/// Mapping with defaults applied and its id
+fn normalize(mapping: &serde_json::Value) -> serde_json::Value {
+    let host = mapping.get("host").and_then(|v| v.as_str()).map(|h| h.trim().to_lowercase()).filter(|h| !h.is_empty());
+    let prefix = mapping.get("pathPrefix").and_then(|v| v.as_str()).unwrap_or("/").trim();
+    let prefix = match prefix.trim_end_matches('/') {
+        "" => "/".to_string(),
+        p => p.to_string(),
+    };
+    let app = mapping.get("app").and_then(|v| v.as_str()).unwrap_or("");
+    json!({
+        "id": mapping_id(host.as_deref(), &prefix),
+        "host": host,
+        "pathPrefix": prefix,
+        "app": app,
+    })
+}
+
+fn mapping_id(host: Option<&str>, prefix: &str) -> String {
+    let raw = format!("{}{}", host.unwrap_or("any"), prefix);
+    let id: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    id.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+fn validate(mapping: &serde_json::Value) -> std::result::Result<(), String> {
+    if let Some(host) = mapping["host"].as_str() {
+        let bare = host.strip_prefix("*.").unwrap_or(host);
+        let valid = !bare.is_empty()
+            && bare.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if !valid {
+            return Err(format!("Invalid host '{}'", host));
+        }
+    }
+    let prefix = mapping["pathPrefix"].as_str().unwrap_or("");
+    if !prefix.starts_with('/') || prefix.contains(char::is_whitespace) || prefix.split('/').any(|s| s == "..") {
+        return Err(format!("Invalid pathPrefix '{}'", prefix));
+    }
+    let app = mapping["app"].as_str().unwrap_or("");
+    if app.is_empty() {
+        return Err("'app' is required".to_string());
+    }
+    if !get_apps_directory().join(app).join("config.yaml").is_file() {
+        return Err(format!("Application '{}' not found", app));
+    }
+    Ok(())
+}
+
+/// Route prefix of every app: `route_prefix` or `/{app_id}`
+fn route_prefixes() -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir(get_apps_directory()) else { return Vec::new() };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let app_id = entry.file_name().to_str()?.to_string();
+            if app_id.starts_with('.') {
+                return None;
+            }
+            let content = std::fs::read_to_string(entry.path().join("config.yaml")).ok()?;
+            let config: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+            let prefix = config
+                .get("route_prefix")
+                .and_then(|v| v.as_str())
+                .map(|p| p.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| format!("/{}", app_id));
+            Some((app_id, prefix))
+        })
+        .collect()
+}
+
+/// Why `mapping` conflicts with the other mappings or app routes
+fn conflicts_of<'a>(
+    mapping: &serde_json::Value,
+    others: impl Iterator<Item = &'a serde_json::Value>,
+    prefixes: &[(String, String)],
+) -> Vec<serde_json::Value> {
+    let mut conflicts = Vec::new();
+    for other in others {
+        let reason = if other["host"] == mapping["host"] && other["pathPrefix"] == mapping["pathPrefix"] {
+            format!("Same host and prefix as the mapping to app '{}'", other["app"].as_str().unwrap_or(""))
+        } else if other["id"] == mapping["id"] {
+            format!("Id '{}' is already used by the mapping to app '{}'",
+                other["id"].as_str().unwrap_or(""), other["app"].as_str().unwrap_or(""))
+        } else {
+            continue;
+        };
+        conflicts.push(json!({
+            "id": mapping["id"],
+            "reason": reason,
+        }));
+    }
+    if mapping["host"].is_null() {
+        let prefix = mapping["pathPrefix"].as_str().unwrap_or("");
+        let app = mapping["app"].as_str().unwrap_or("");
+        for (app_id, route_prefix) in prefixes {
+            if app_id != app && route_prefix == prefix {
+                conflicts.push(json!({
+                    "id": mapping["id"],
+                    "reason": format!("Prefix '{}' is the route prefix of app '{}'", prefix, app_id),
+                }));
+            }
+        }
+    }
+    conflicts
+}
+
+/// Mappings that point at `app_id`, for the app detail
+pub fn for_app(app_id: &str) -> Vec<serde_json::Value> {
+    crate::cors::read_server_config()
+        .map(|config| section(&config))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m["app"] == app_id)
+        .collect()
+}
+
+/// Point mappings of `old` at `new` after an app rename; returns how many moved
+pub fn rename_app(old: &str, new: &str) -> std::result::Result<usize, String> {
+    crate::cors::update_server_config(|config| {
+        let mut mappings = section(config);
+        let mut moved = 0;
+        for mapping in mappings.iter_mut().filter(|m| m["app"] == old) {
+            mapping["app"] = json!(new);
+            moved += 1;
+        }
+        if moved > 0 {
+            store(config, &mappings)?;
+        }
+        Ok(moved)
+    })
+}
+
+/// Validate, check for conflicts and save `mapping` in place of `replaced`
+fn save(
+    mapping: serde_json::Value,
+    replaced: Option<&str>,
+) -> std::result::Result<std::result::Result<(), Vec<serde_json::Value>>, YetiError> {
+    if let Err(e) = validate(&mapping) {
+        return Err(YetiError::Validation(e));
+    }
+    let prefixes = route_prefixes();
+    crate::cors::update_server_config(|config| {
+        let mut mappings = section(config);
+        if let Some(id) = replaced {
+            mappings.retain(|m| m["id"] != id);
+        }
+        let conflicts = conflicts_of(&mapping, mappings.iter(), &prefixes);
+        if !conflicts.is_empty() {
+            return Ok(Err(conflicts));
+        }
+        mappings.push(mapping);
+        mappings.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+        store(config, &mappings)?;
+        Ok(Ok(()))
+    })
+    .map_err(YetiError::Internal)
+}
+
+#[derive(Default)]
+pub struct DomainsResource;
+
+impl Resource for DomainsResource {
+    fn name(&self) -> &str {
+        "domains"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "domains", "GET", {
+            let config = crate::cors::read_server_config().map_err(YetiError::Internal)?;
+            let mappings = section(&config);
+
+            if let Some(id) = ctx.path_id() {
+                return match mappings.into_iter().find(|m| m["id"] == id) {
+                    Some(mapping) => reply().json(mapping),
+                    None => not_found(&format!("Domain mapping '{}' not found", id)),
+                };
+            }
+
+            let query = request.uri().query().unwrap_or("");
+            if let Some(app) = parse_query_param(query, "app") {
+                let list: Vec<serde_json::Value> = mappings.into_iter().filter(|m| m["app"] == app.as_str()).collect();
+                return reply().json(json!(list));
+            }
+
+            let prefixes = route_prefixes();
+            let conflicts: Vec<serde_json::Value> = mappings
+                .iter()
+                .enumerate()
+                .flat_map(|(i, m)| {
+                    let others = mappings.iter().enumerate().filter(move |(j, _)| *j != i).map(|(_, o)| o);
+                    conflicts_of(m, others, &prefixes)
+                })
+                .collect();
+            reply().json(json!({
+                "mappings": mappings,
+                "conflicts": conflicts,
+            }))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "domains", "POST", {
+            let body = request.json_value()?;
+            let mapping = normalize(&body);
+
+            if let Err(conflicts) = save(mapping.clone(), None)? {
+                return reply().code(409).json(json!({
+                    "error": "Domain mapping conflicts with existing routing",
+                    "conflicts": conflicts,
+                }));
+            }
+
+            let id = mapping["id"].as_str().unwrap_or("").to_string();
+            crate::audit!(request, ctx, "domain.create", &id, mapping.clone());
+
+            reply().code(201).json(mapping)
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "domains", "PUT", {
+            let id = ctx.require_id()?.to_string();
+            let config = crate::cors::read_server_config().map_err(YetiError::Internal)?;
+            let mappings = section(&config);
+            let Some(current) = mappings.iter().find(|m| m["id"] == id.as_str()).cloned() else {
+                return not_found(&format!("Domain mapping '{}' not found", id));
+            };
+
+            let body = request.json_value()?;
+            let mut merged = current.clone();
+            for key in ["host", "pathPrefix", "app"] {
+                if let Some(value) = body.get(key) {
+                    merged[key] = value.clone();
+                }
+            }
+            let mapping = normalize(&merged);
+
+            if let Err(conflicts) = save(mapping.clone(), Some(&id))? {
+                return reply().code(409).json(json!({
+                    "error": "Domain mapping conflicts with existing routing",
+                    "conflicts": conflicts,
+                }));
+            }
+
+            crate::audit!(request, ctx, "domain.update", &id, json!({"from": current, "to": mapping}));
+
+            reply().json(mapping)
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "domains", "DELETE", {
+            let id = ctx.require_id()?.to_string();
+            let deleted = crate::cors::update_server_config(|config| {
+                let mut mappings = section(config);
+                let before = mappings.len();
+                mappings.retain(|m| m["id"] != id.as_str());
+                if mappings.len() == before {
+                    return Ok(false);
+                }
+                store(config, &mappings)?;
+                Ok(true)
+            })
+            .map_err(YetiError::Internal)?;
+            if !deleted {
+                return not_found(&format!("Domain mapping '{}' not found", id));
+            }
+
+            crate::audit!(request, ctx, "domain.delete", &id, json!({}));
+
+            reply().json(json!({
+                "id": id,
+                "deleted": true,
+            }))
+        })
+    });
+}
+
+register_resource!(DomainsResource);
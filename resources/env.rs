@@ -0,0 +1,340 @@
+//! App Environment Variable Resource
+//!
+//! View and edit the environment variables injected into an app at load time,
+//! with a reload trigger so changes apply without editing raw YAML.
+//!
+//! | Method | Path                             | Description                       |
+//! |--------|----------------------------------|-----------------------------------|
+//! | GET    | /admin/env/{app_id}              | List variables (secrets masked)   |
+//! | PUT    | /admin/env/{app_id}              | Set variables                     |
+//! | DELETE | /admin/env/{app_id}?name=X       | Remove a variable                 |
+//! | POST   | /admin/env/{app_id}/reload       | Reload the app to apply changes   |
+//!
+//! Plain variables live in the `env:` map of the app's config.yaml. Sensitive
+//! ones (`"secret": true`) go to the secrets store, a 0600 JSON file per scope
+//! under the keys directory, and only their names are listed in config.yaml
+//! under `env_secrets:`, so the values never land in the app's git repo. App
+//! scopes live under `secrets/apps/`, apart from the admin's own scopes
+//! (`instances`, `webhooks`, `git-credentials`), so no app id can reach those.
+//!
+//! PUT body: `{"vars": [{"name": "API_URL", "value": "https://..."},
+//! {"name": "API_TOKEN", "value": "...", "secret": true}], "reload": true}`
+
+use std::path::{Path, PathBuf};
+use yeti_core::prelude::*;
+
+pub type Env = EnvResource;
+
+const MASK: &str = "********";
+
+// ── Secrets store ──
+
+fn secrets_path(scope: &str) -> PathBuf {
+    get_keys_directory().join("secrets").join(format!("{}.json", scope))
+}
+
+/// Scopes used by the admin itself, which app secrets were once stored beside
+const INTERNAL_SCOPES: &[&str] = &["instances", "webhooks", "git-credentials"];
+
+/// The secrets scope of an app
+fn app_scope(app_id: &str) -> String {
+    format!("apps/{}", app_id)
+}
+
+/// An app's secrets; falls back to the file of the flat layout app secrets
+/// used before they were namespaced
+pub fn read_app_secrets(app_id: &str) -> serde_json::Map<String, serde_json::Value> {
+    let scope = app_scope(app_id);
+    if !secrets_path(&scope).exists() && !INTERNAL_SCOPES.contains(&app_id) {
+        return read_secrets(app_id);
+    }
+    read_secrets(&scope)
+}
+
+/// Replace an app's secrets, dropping any file left from the flat layout
+pub fn write_app_secrets(app_id: &str, secrets: &serde_json::Map<String, serde_json::Value>) -> std::result::Result<(), String> {
+    write_secrets(&app_scope(app_id), secrets)?;
+    if !INTERNAL_SCOPES.contains(&app_id) {
+        let _ = std::fs::remove_file(secrets_path(app_id));
+    }
+    Ok(())
+}
+
+/// All secrets in an internal scope (e.g. "instances"); apps use `read_app_secrets`
+pub fn read_secrets(scope: &str) -> serde_json::Map<String, serde_json::Value> {
+    std::fs::read_to_string(secrets_path(scope))
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+pub fn read_secret(scope: &str, name: &str) -> Option<String> {
+    read_secrets(scope).get(name).and_then(|v| v.as_str()).map(String::from)
+}
+
+/// Replace a scope's secrets; the directory is 0700 and the file 0600.
+pub fn write_secrets(scope: &str, secrets: &serde_json::Map<String, serde_json::Value>) -> std::result::Result<(), String> {
+    let path = secrets_path(scope);
+    let dir = path.parent().expect("secrets path has a parent");
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create secrets directory: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for dir in [dir, get_keys_directory().join("secrets").as_path()] {
+            let _ = std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700));
+        }
+    }
+    if secrets.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return Ok(());
+    }
+    let content = serde_json::to_string_pretty(secrets).map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write secrets: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to set secrets permissions: {}", e))?;
+    }
+    Ok(())
+}
+
+// ── config.yaml ──
+
+fn read_config(config_path: &Path) -> std::result::Result<serde_yaml::Value, YetiError> {
+    let content = std::fs::read_to_string(config_path)
+        .map_err(|e| YetiError::Internal(format!("Failed to read config: {}", e)))?;
+    serde_yaml::from_str(&content).map_err(|e| YetiError::Internal(format!("Failed to parse config: {}", e)))
+}
+
+fn write_config(config_path: &Path, config: &serde_yaml::Value) -> std::result::Result<(), YetiError> {
+    let content = serde_yaml::to_string(config)
+        .map_err(|e| YetiError::Internal(format!("Failed to serialize config: {}", e)))?;
+    std::fs::write(config_path, content).map_err(|e| YetiError::Internal(format!("Failed to write config: {}", e)))
+}
+
+/// The `env:` map and `env_secrets:` list of a config
+fn env_sections(config: &serde_yaml::Value) -> (serde_yaml::Mapping, Vec<String>) {
+    let env = config.get("env").and_then(|v| v.as_mapping()).cloned().unwrap_or_default();
+    let secrets = config
+        .get("env_secrets")
+        .and_then(|v| v.as_sequence())
+        .map(|s| s.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    (env, secrets)
+}
+
+fn set_env_sections(config: &mut serde_yaml::Value, env: serde_yaml::Mapping, mut secrets: Vec<String>) {
+    let Some(map) = config.as_mapping_mut() else { return };
+    let env_key = serde_yaml::Value::String("env".to_string());
+    let secrets_key = serde_yaml::Value::String("env_secrets".to_string());
+    if env.is_empty() {
+        map.remove(&env_key);
+    } else {
+        map.insert(env_key, serde_yaml::Value::Mapping(env));
+    }
+    secrets.sort();
+    secrets.dedup();
+    if secrets.is_empty() {
+        map.remove(&secrets_key);
+    } else {
+        map.insert(secrets_key, serde_yaml::Value::Sequence(
+            secrets.into_iter().map(serde_yaml::Value::String).collect(),
+        ));
+    }
+}
+
+/// Environment variable names: letters, digits and underscores, not starting with a digit
+fn validate_var_name(name: &str) -> std::result::Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid variable name '{}'", name))
+    }
+}
+
+/// Ask the runtime to reload an app by bumping its config.yaml modification
+/// time, which the config watcher picks up.
+pub fn reload_app(app_path: &Path) -> std::result::Result<(), String> {
+    let config_path = app_path.join("config.yaml");
+    std::fs::File::options()
+        .append(true)
+        .open(&config_path)
+        .and_then(|f| f.set_modified(std::time::SystemTime::now()))
+        .map_err(|e| format!("Failed to touch config.yaml: {}", e))
+}
+
+fn app_config_path(app_id: &str) -> std::result::Result<PathBuf, YetiError> {
+    validate_identifier(app_id, "app_id")?;
+    let config_path = get_apps_directory().join(app_id).join("config.yaml");
+    if !config_path.exists() {
+        return Err(YetiError::Validation(format!("Application '{}' not found", app_id)));
+    }
+    Ok(config_path)
+}
+
+fn yaml_to_string(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) => s.clone(),
+        serde_yaml::Value::Null => String::new(),
+        other => serde_yaml::to_string(other).map(|s| s.trim().to_string()).unwrap_or_default(),
+    }
+}
+
+#[derive(Default)]
+pub struct EnvResource;
+
+impl Resource for EnvResource {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "env", "GET", {
+            let app_id = ctx.require_id()?.to_string();
+            let config = read_config(&app_config_path(&app_id)?)?;
+            let (env, secret_names) = env_sections(&config);
+            let secrets = read_app_secrets(&app_id);
+
+            let mut vars: Vec<serde_json::Value> = env
+                .iter()
+                .filter_map(|(k, v)| k.as_str().map(|k| json!({
+                    "name": k,
+                    "value": yaml_to_string(v),
+                    "secret": false,
+                })))
+                .collect();
+            vars.extend(secret_names.iter().map(|name| json!({
+                "name": name,
+                "value": MASK,
+                "secret": true,
+                "set": secrets.contains_key(name),
+            })));
+            vars.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "vars": vars,
+            }))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "env", "PUT", {
+            let app_id = ctx.require_id()?.to_string();
+            let config_path = app_config_path(&app_id)?;
+            let body = request.json_value()?;
+            let updates = body.get("vars").and_then(|v| v.as_array()).cloned()
+                .ok_or_else(|| YetiError::Validation("Missing 'vars' array".to_string()))?;
+
+            let mut config = read_config(&config_path)?;
+            let (mut env, mut secret_names) = env_sections(&config);
+            let mut secrets = read_app_secrets(&app_id);
+            let mut changed = Vec::new();
+
+            for var in &updates {
+                let name = var.require_str("name")?;
+                validate_var_name(&name).map_err(YetiError::Validation)?;
+                let value = match var.get("value") {
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(serde_json::Value::Null) | None => String::new(),
+                    Some(other) => other.to_string(),
+                };
+                let key = serde_yaml::Value::String(name.clone());
+                if var.get("secret").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    env.remove(&key);
+                    // Re-submitting the mask keeps the stored value
+                    if value != MASK {
+                        secrets.insert(name.clone(), json!(value));
+                    }
+                    secret_names.push(name.clone());
+                } else {
+                    secrets.remove(&name);
+                    secret_names.retain(|n| *n != name);
+                    env.insert(key, serde_yaml::Value::String(value));
+                }
+                changed.push(name);
+            }
+
+            write_app_secrets(&app_id, &secrets).map_err(YetiError::Internal)?;
+            set_env_sections(&mut config, env, secret_names);
+            write_config(&config_path, &config)?;
+
+            let reload = body.get("reload").and_then(|v| v.as_bool()).unwrap_or(false);
+            if reload {
+                if let Some(app_path) = config_path.parent() {
+                    reload_app(app_path).map_err(YetiError::Internal)?;
+                }
+            }
+
+            crate::audit!(request, ctx, "env.update", &app_id, json!({"names": changed, "reload": reload}));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "updated": changed,
+                "reloaded": reload,
+            }))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "env", "DELETE", {
+            let app_id = ctx.require_id()?.to_string();
+            let config_path = app_config_path(&app_id)?;
+            let query = request.uri().query().unwrap_or("");
+            let name = parse_required_query_param(query, "name")?;
+
+            let mut config = read_config(&config_path)?;
+            let (mut env, mut secret_names) = env_sections(&config);
+            let mut secrets = read_app_secrets(&app_id);
+
+            let in_env = env.remove(serde_yaml::Value::String(name.clone())).is_some();
+            let was_secret = secret_names.contains(&name);
+            if !in_env && !was_secret {
+                return not_found(&format!("Variable '{}' not set for '{}'", name, app_id));
+            }
+            secret_names.retain(|n| *n != name);
+            secrets.remove(&name);
+
+            write_app_secrets(&app_id, &secrets).map_err(YetiError::Internal)?;
+            set_env_sections(&mut config, env, secret_names);
+            write_config(&config_path, &config)?;
+
+            crate::audit!(request, ctx, "env.delete", &app_id, json!({"name": name}));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "name": name,
+                "deleted": true,
+            }))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "env", "POST", {
+            // POST /admin/env/{app_id}/reload
+            let uri_path = request.uri().path().trim_end_matches('/');
+            let Some(prefix) = uri_path.strip_suffix("/reload") else {
+                return bad_request("Use POST /env/{app_id}/reload");
+            };
+            let app_id = prefix.rsplit('/').next().unwrap_or("").to_string();
+            let config_path = app_config_path(&app_id)?;
+            if let Some(app_path) = config_path.parent() {
+                reload_app(app_path).map_err(YetiError::Internal)?;
+            }
+
+            crate::audit!(request, ctx, "app.reload", &app_id, json!({}));
+
+            reply().json(json!({
+                "app_id": app_id,
+                "reloaded": true,
+            }))
+        })
+    });
+}
+
+register_resource!(EnvResource);
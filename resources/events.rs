@@ -0,0 +1,186 @@
+//! Admin Event Stream Resource
+//!
+//! Admin happenings (apps created/deleted, files saved, pulls completed,
+//! benchmarks started/finished, runner progress) are written to the
+//! AdminEvent table, so the dashboard subscribes to one stream instead of
+//! polling several endpoints.
+//!
+//! | Method | Path                           | Description                        |
+//! |--------|--------------------------------|------------------------------------|
+//! | GET    | /admin/events                  | Recent events, oldest first        |
+//! | GET    | /admin/events?after={id}       | Events after a cursor (catch-up)   |
+//! | GET    | /admin/AdminEvent?stream=sse   | Live SSE stream (`update` events)  |
+//!
+//! `?kind=` filters by kind, exact or by prefix ending in `.` (`app.`);
+//! `?limit=` caps the result (default 100, newest kept).
+//!
+//! Events are kept for `RETENTION_DAYS` and capped at `MAX_EVENTS`; `publish!`
+//! prunes the table at most every `PRUNE_INTERVAL_SECS`.
+//!
+//! Every `audit!` call also publishes an event named after its action; other
+//! handlers use `publish!`:
+//!
+//! ```ignore
+//! crate::publish!(ctx, "benchmark.progress", &test, json!({"rps": 1200}));
+//! ```
+//!
+//! Background tasks without a `ctx` put `event(..)` into a table handle taken
+//! beforehand and pass it to `webhooks::dispatch`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use yeti_core::prelude::*;
+
+pub type Events = EventsResource;
+
+const DEFAULT_LIMIT: usize = 100;
+
+const RETENTION_DAYS: u64 = 3;
+const MAX_EVENTS: usize = 10_000;
+const PRUNE_INTERVAL_SECS: u64 = 300;
+
+/// Write an AdminEvent record and hand it to subscribed webhooks; failures
+/// are logged and otherwise ignored.
+#[macro_export]
+macro_rules! publish {
+    ($ctx:expr, $kind:expr, $target:expr, $data:expr) => {{
+        let event = $crate::events::event($kind, $target, $data);
+        if !$crate::webhooks::loaded() {
+            if let Ok(table) = $ctx.get_table("Webhook") {
+                $crate::webhooks::load(table.scan_all().await.unwrap_or_default());
+            }
+        }
+        $crate::webhooks::dispatch(&event);
+        if let Ok(table) = $ctx.get_table("AdminEvent") {
+            if let Err(e) = table.put(event).await {
+                yeti_log!(warn, "Failed to publish {} event: {}", $kind, e);
+            }
+            if $crate::events::prune_due() {
+                for id in $crate::events::expired(table.scan_all().await.unwrap_or_default()) {
+                    if let Err(e) = table.delete(&id).await {
+                        yeti_log!(warn, "Failed to prune event {}: {}", id, e);
+                        break;
+                    }
+                }
+            }
+        }
+    }};
+}
+
+/// Whether `PRUNE_INTERVAL_SECS` passed since the last prune; claims the slot
+pub fn prune_due() -> bool {
+    static LAST_PRUNE: AtomicU64 = AtomicU64::new(0);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let last = LAST_PRUNE.load(Ordering::Relaxed);
+    now.saturating_sub(last) >= PRUNE_INTERVAL_SECS
+        && LAST_PRUNE.compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+}
+
+/// Ids of events older than `RETENTION_DAYS` or beyond the newest `MAX_EVENTS`
+pub fn expired(events: Vec<serde_json::Value>) -> Vec<String> {
+    let cutoff = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+        - (RETENTION_DAYS * 86400) as f64;
+    let mut ids: Vec<(String, f64)> = events
+        .iter()
+        .filter_map(|e| {
+            let id = e.get("id").and_then(|v| v.as_str())?;
+            Some((id.to_string(), e.get("timestamp").and_then(|v| v.as_f64()).unwrap_or(0.0)))
+        })
+        .collect();
+    ids.sort_by(|a, b| b.0.cmp(&a.0));
+    ids.into_iter()
+        .enumerate()
+        .filter(|(i, (_, timestamp))| *i >= MAX_EVENTS || *timestamp < cutoff)
+        .map(|(_, (id, _))| id)
+        .collect()
+}
+
+/// Build an AdminEvent record. Ids sort by time, so they double as the
+/// `?after=` cursor.
+pub fn event(kind: &str, target: &str, data: serde_json::Value) -> serde_json::Value {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let seq = SEQ.fetch_add(1, Ordering::Relaxed) % 10_000;
+    json!({
+        "id": format!("{:013}-{:04}", now.as_millis(), seq),
+        "kind": kind,
+        "target": target,
+        "timestamp": now.as_secs_f64(),
+        "data": if data.is_null() { None } else { Some(data.to_string()) },
+    })
+}
+
+pub fn matches_kind(filter: &str, kind: &str) -> bool {
+    if filter.ends_with('.') {
+        kind.starts_with(filter)
+    } else {
+        kind == filter
+    }
+}
+
+#[derive(Default)]
+pub struct EventsResource;
+
+impl Resource for EventsResource {
+    fn name(&self) -> &str {
+        "events"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "events", "GET", {
+            let query = request.uri().query().unwrap_or("");
+            let after = parse_query_param(query, "after");
+            let kind = parse_query_param(query, "kind");
+            let limit = parse_query_param(query, "limit")
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_LIMIT);
+
+            let events = match ctx.get_table("AdminEvent") {
+                Ok(table) => table.scan_all().await.unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+
+            let id_of = |e: &serde_json::Value| e.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            let mut events: Vec<serde_json::Value> = events
+                .into_iter()
+                .filter(|e| after.as_ref().is_none_or(|a| id_of(e) > *a))
+                .filter(|e| {
+                    kind.as_ref().is_none_or(|k| {
+                        matches_kind(k, e.get("kind").and_then(|v| v.as_str()).unwrap_or(""))
+                    })
+                })
+                .collect();
+            events.sort_by_key(|e| id_of(e));
+            if events.len() > limit {
+                events.drain(..events.len() - limit);
+            }
+
+            let events: Vec<serde_json::Value> = events
+                .into_iter()
+                .map(|mut e| {
+                    if let Some(data) = e.get("data").and_then(|v| v.as_str()) {
+                        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(data) {
+                            e["data"] = parsed;
+                        }
+                    }
+                    e
+                })
+                .collect();
+            let cursor = events.last().map(|e| id_of(e)).or(after);
+
+            reply().json(json!({
+                "events": events,
+                "cursor": cursor,
+            }))
+        })
+    });
+}
+
+register_resource!(EventsResource);
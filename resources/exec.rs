@@ -0,0 +1,353 @@
+//! Restricted Command Execution Resource
+//!
+//! Runs allow-listed commands in an app directory, for the long tail of
+//! operations the structured APIs don't cover yet.
+//!
+//! | Method | Path                            | Description                          |
+//! |--------|---------------------------------|--------------------------------------|
+//! | POST   | /admin/exec/{app_id}            | Start a command (202)                |
+//! | POST   | /admin/exec/{app_id}?wait=true  | Run to completion and return output  |
+//! | GET    | /admin/exec                     | Recent sessions (`?app=` to filter)  |
+//! | GET    | /admin/exec/{id}                | Session with its full output         |
+//! | DELETE | /admin/exec/{id}                | Kill a running command               |
+//!
+//! POST body: `{"command": "git", "args": ["log", "--oneline", "-20"],
+//! "timeoutSecs": 60}`. Only `git`, `cargo`, `ls` and `tail` are allowed, each
+//! with its own subcommand/option rules (see `validate_command`). Commands run
+//! without a shell in the app directory, and no argument may be an absolute
+//! path, start with `~` or contain a `..` component. Forbidden options are
+//! also caught abbreviated or stuck to a value, so pass values of short
+//! options as separate arguments (`["commit", "-m", "msg"]`).
+//!
+//! Output is streamed line by line into the ExecOutput table; subscribe with
+//! `GET /admin/ExecOutput?stream=ws` and filter on `sessionId`. Each session
+//! (actor, command line, status, exit code, last lines) is an ExecSession
+//! record, and starting or killing a command is written to the audit log.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::sync::{Mutex, OnceLock};
+use yeti_core::prelude::*;
+
+pub type Exec = ExecResource;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const MAX_TIMEOUT_SECS: u64 = 600;
+/// Lines written to ExecOutput per session; the rest is counted but dropped
+const MAX_OUTPUT_LINES: u64 = 10_000;
+/// Lines kept on the ExecSession record
+const TAIL_LINES: usize = 200;
+
+const GIT_SUBCOMMANDS: &[&str] = &[
+    "status", "log", "diff", "show", "branch", "checkout", "switch", "fetch", "pull", "push",
+    "add", "rm", "mv", "commit", "reset", "restore", "stash", "tag", "merge", "rebase",
+    "cherry-pick", "revert", "rev-parse", "blame", "ls-files", "shortlog", "describe", "remote",
+];
+/// Options that run other programs or leave the app's repository
+const GIT_FORBIDDEN: &[&str] = &[
+    "-c", "-C", "--exec", "-x", "--upload-pack", "--receive-pack", "--git-dir", "--work-tree",
+    "--config-env", "--exec-path", "--template", "--output", "--ext-diff", "--textconv",
+];
+const CARGO_SUBCOMMANDS: &[&str] = &[
+    "check", "build", "test", "clippy", "fmt", "tree", "metadata", "update", "clean", "doc", "bench",
+];
+/// Real git options that are also prefixes of forbidden ones
+const GIT_EXACT: &[&str] = &["--text"];
+const CARGO_FORBIDDEN: &[&str] = &["--config", "-Z", "--manifest-path", "--target-dir", "--config-file"];
+
+/// Running sessions: id → (pid, reason it was stopped early)
+fn running() -> &'static Mutex<HashMap<String, (u32, Option<&'static str>)>> {
+    static RUNNING: OnceLock<Mutex<HashMap<String, (u32, Option<&'static str>)>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Whether `arg` is, or would be read as, one of the `forbidden` options:
+/// git and clap accept abbreviated long options (`--upload-pa=cmd`) and short
+/// options stuck to a value or in a cluster (`-x"sh -c …"`, `-ix`). `exact`
+/// lists full option names that are allowed although they abbreviate a
+/// forbidden one.
+fn is_forbidden(arg: &str, forbidden: &[&str], exact: &[&str]) -> bool {
+    if let Some(long) = arg.strip_prefix("--") {
+        let name = long.split('=').next().unwrap_or(long);
+        if name.is_empty() || exact.iter().any(|e| e.strip_prefix("--") == Some(name)) {
+            return false;
+        }
+        return forbidden.iter().filter_map(|f| f.strip_prefix("--")).any(|f| f.starts_with(name));
+    }
+    let Some(cluster) = arg.strip_prefix('-') else { return false };
+    forbidden
+        .iter()
+        .filter(|f| !f.starts_with("--"))
+        .filter_map(|f| f.strip_prefix('-'))
+        .any(|flag| cluster.contains(flag))
+}
+
+/// Arguments (and `--opt=value` values) must stay inside the app directory
+fn check_path(arg: &str) -> std::result::Result<(), String> {
+    let value = arg.split_once('=').map_or(arg, |(_, v)| v);
+    for candidate in [arg, value] {
+        if candidate.starts_with('/') || candidate.starts_with('~') || candidate.split('/').any(|c| c == "..") {
+            return Err(format!("Argument '{}' leaves the app directory", arg));
+        }
+    }
+    Ok(())
+}
+
+/// Allow-list rules per command
+fn validate_command(command: &str, args: &[String]) -> std::result::Result<(), String> {
+    for arg in args {
+        check_path(arg)?;
+        if arg.contains('\0') {
+            return Err("Arguments may not contain NUL bytes".to_string());
+        }
+    }
+    let first = args.iter().find(|a| !a.starts_with('-')).map(String::as_str);
+    match command {
+        "git" => {
+            let sub = first.ok_or("git needs a subcommand")?;
+            if !GIT_SUBCOMMANDS.contains(&sub) {
+                return Err(format!("git {} is not allowed (allowed: {})", sub, GIT_SUBCOMMANDS.join(", ")));
+            }
+            if let Some(arg) = args.iter().find(|a| is_forbidden(a, GIT_FORBIDDEN, GIT_EXACT)) {
+                return Err(format!("git option '{}' is not allowed", arg));
+            }
+            if sub == "remote" && args.iter().any(|a| ["add", "set-url", "rename"].contains(&a.as_str())) {
+                return Err("Changing remotes is not allowed; use the repos API".to_string());
+            }
+        }
+        "cargo" => {
+            let sub = first.ok_or("cargo needs a subcommand")?;
+            if !CARGO_SUBCOMMANDS.contains(&sub) {
+                return Err(format!("cargo {} is not allowed (allowed: {})", sub, CARGO_SUBCOMMANDS.join(", ")));
+            }
+            if let Some(arg) = args.iter().find(|a| is_forbidden(a, CARGO_FORBIDDEN, &[])) {
+                return Err(format!("cargo option '{}' is not allowed", arg));
+            }
+        }
+        "ls" | "tail" => {}
+        other => return Err(format!("Command '{}' is not allowed (allowed: git, cargo, ls, tail)", other)),
+    }
+    Ok(())
+}
+
+/// Read a child stream line by line into the channel
+fn pump(stream: impl std::io::Read + Send + 'static, name: &'static str, tx: tokio::sync::mpsc::UnboundedSender<(&'static str, String)>) {
+    tokio::task::spawn_blocking(move || {
+        for line in std::io::BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if tx.send((name, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[derive(Default)]
+pub struct ExecResource;
+
+impl Resource for ExecResource {
+    fn name(&self) -> &str {
+        "exec"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "exec", "GET", {
+            let sessions = ctx.get_table("ExecSession")?;
+
+            if let Some(id) = ctx.path_id() {
+                let Some(mut session) = sessions.get_by_id(id).await? else {
+                    return not_found(&format!("Exec session '{}' not found", id));
+                };
+                let mut lines: Vec<serde_json::Value> = ctx.get_table("ExecOutput")?
+                    .scan_all()
+                    .await?
+                    .into_iter()
+                    .filter(|l| l["sessionId"] == id)
+                    .collect();
+                lines.sort_by_key(|l| l["seq"].as_u64().unwrap_or(0));
+                session["output"] = json!(lines);
+                return reply().json(session);
+            }
+
+            let query = request.uri().query().unwrap_or("");
+            let app = parse_query_param(query, "app");
+            let mut list: Vec<serde_json::Value> = sessions
+                .scan_all()
+                .await?
+                .into_iter()
+                .filter(|s| app.as_ref().is_none_or(|a| s["appId"] == a.as_str()))
+                .collect();
+            list.sort_by(|a, b| b["startedAt"].as_f64().partial_cmp(&a["startedAt"].as_f64())
+                .unwrap_or(std::cmp::Ordering::Equal));
+            list.truncate(50);
+            reply().json(json!(list))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "exec", "POST", {
+            let app_id = ctx.require_id()?.to_string();
+            validate_identifier(&app_id, "app_id")?;
+            let app_path = get_apps_directory().join(&app_id);
+            if !app_path.is_dir() {
+                return not_found(&format!("Application '{}' not found", app_id));
+            }
+
+            let body = request.json_value()?;
+            let command = body.require_str("command")?;
+            let args: Vec<String> = match body.get("args") {
+                None => Vec::new(),
+                Some(v) => match v.as_array().and_then(|a| a.iter().map(|s| s.as_str().map(String::from)).collect()) {
+                    Some(args) => args,
+                    None => return bad_request("'args' must be an array of strings"),
+                },
+            };
+            if let Err(e) = validate_command(&command, &args) {
+                return bad_request(&e);
+            }
+            let timeout = body.get("timeoutSecs").and_then(|v| v.as_u64()).unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(1, MAX_TIMEOUT_SECS);
+            let wait = parse_query_param(request.uri().query().unwrap_or(""), "wait").is_some_and(|v| v == "true");
+            let owner = crate::actor!(request);
+
+            let mut cmd = std::process::Command::new(&command);
+            cmd.args(&args)
+                .current_dir(&app_path)
+                .env("GIT_TERMINAL_PROMPT", "0")
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped());
+            let mut child = crate::processes::spawn("exec", &owner, &mut cmd)
+                .map_err(|e| YetiError::Internal(format!("Failed to run {}: {}", command, e)))?;
+            let pid = child.id();
+
+            let started = now_secs();
+            let id = format!("{:x}{:x}", (started * 1000.0) as u64, pid);
+            let mut session = json!({
+                "id": id,
+                "appId": app_id,
+                "actor": owner,
+                "command": command,
+                "args": args,
+                "status": "running",
+                "pid": pid,
+                "startedAt": started,
+                "timeoutSecs": timeout,
+            });
+            let sessions = ctx.get_table("ExecSession")?;
+            sessions.put(session.clone()).await?;
+            let output_table = ctx.get_table("ExecOutput").ok();
+            running().lock().unwrap().insert(id.clone(), (pid, None));
+
+            crate::audit!(request, ctx, "exec.start", &app_id, json!({
+                "session": id,
+                "command": command,
+                "args": args,
+            }));
+
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            if let Some(stdout) = child.stdout.take() {
+                pump(stdout, "stdout", tx.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                pump(stderr, "stderr", tx);
+            }
+
+            // Timeout: stop the command if it is still ours
+            let timeout_id = id.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_secs(timeout)).await;
+                if let Some((pid, reason)) = running().lock().unwrap().get_mut(&timeout_id) {
+                    *reason = Some("timeout");
+                    crate::processes::terminate(*pid, false);
+                }
+            });
+
+            let session_id = id.clone();
+            let initial = session.clone();
+            let finished = tokio::spawn(async move {
+                let mut session = initial;
+                let mut tail: std::collections::VecDeque<String> = std::collections::VecDeque::new();
+                let mut seq: u64 = 0;
+                while let Some((stream, line)) = rx.recv().await {
+                    seq += 1;
+                    if seq <= MAX_OUTPUT_LINES {
+                        if let Some(table) = &output_table {
+                            let _ = table.put(json!({
+                                "id": format!("{}-{:06}", session_id, seq),
+                                "sessionId": session_id,
+                                "seq": seq,
+                                "stream": stream,
+                                "line": line,
+                                "timestamp": now_secs(),
+                            })).await;
+                        }
+                    }
+                    tail.push_back(line);
+                    if tail.len() > TAIL_LINES {
+                        tail.pop_front();
+                    }
+                }
+
+                let status = tokio::task::spawn_blocking(move || child.wait()).await;
+                crate::processes::reaped(pid);
+                let reason = running().lock().unwrap().remove(&session_id).and_then(|(_, reason)| reason);
+                let exit_code = match &status {
+                    Ok(Ok(s)) => s.code(),
+                    _ => None,
+                };
+                session["status"] = json!(match (reason, exit_code) {
+                    (Some(reason), _) => reason,
+                    (None, Some(0)) => "succeeded",
+                    (None, _) => "failed",
+                });
+                session["exitCode"] = json!(exit_code);
+                session["finishedAt"] = json!(now_secs());
+                session["lines"] = json!(seq);
+                session["truncated"] = json!(seq > MAX_OUTPUT_LINES);
+                session["tail"] = json!(tail.into_iter().collect::<Vec<_>>().join("\n"));
+                let _ = sessions.put(session.clone()).await;
+                session
+            });
+
+            if wait {
+                session = finished
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Exec task failed: {}", e)))?;
+                return reply().json(session);
+            }
+            session["stream"] = json!("/admin/ExecOutput?stream=ws");
+            reply().code(202).json(session)
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "exec", "DELETE", {
+            let id = ctx.require_id()?.to_string();
+            let pid = {
+                let mut running = running().lock().unwrap();
+                let Some((pid, reason)) = running.get_mut(&id) else {
+                    return not_found(&format!("Exec session '{}' is not running", id));
+                };
+                *reason = Some("killed");
+                *pid
+            };
+            let signalled = crate::processes::terminate(pid, false);
+
+            crate::audit!(request, ctx, "exec.kill", &id, json!({"pid": pid}));
+
+            reply().json(json!({
+                "id": id,
+                "killed": signalled,
+            }))
+        })
+    });
+}
+
+register_resource!(ExecResource);
@@ -0,0 +1,442 @@
+//! Extension Installer Resource
+//!
+//! Lists the extension apps published in a registry index, installs them into
+//! the apps directory and tells when an installed one has a newer version.
+//!
+//! | Method | Path                               | Description                           |
+//! |--------|------------------------------------|---------------------------------------|
+//! | GET    | /admin/extensions                  | Registry entries with install state   |
+//! | GET    | /admin/extensions?installed=true   | Installed extensions only             |
+//! | GET    | /admin/extensions/{name}           | Single extension                      |
+//! | POST   | /admin/extensions/{name}           | Install from the registry             |
+//! | PUT    | /admin/extensions/{name}           | Update to the registry version        |
+//! | PUT    | /admin/extensions                  | Set the registry index URL            |
+//! | DELETE | /admin/extensions/{name}           | Uninstall                             |
+//!
+//! The index URL is `extensionRegistry` in the server config
+//! (`yeti-config.yaml`), falling back to `YETI_EXTENSION_REGISTRY`. The index is
+//! JSON, either an array or `{"extensions": [...]}`:
+//!
+//! ```json
+//! [{"name": "yeti-auth", "version": "1.4.0", "description": "OAuth and JWT auth",
+//!   "repo": "https://github.com/yetirocks/yeti-auth.git", "ref": "v1.4.0"}]
+//! ```
+//!
+//! An entry is installed by cloning `repo` (at `ref`, default branch
+//! otherwise) or by downloading `archive`, a tar.gz laid out as `{name}/...`
+//! like promotion archives, checked against `sha256` when given. The result
+//! must be an app with `extension: true` in its config.yaml. Installed versions
+//! are tracked in `admin-extensions.json` in the root directory; extensions
+//! installed by hand report the `version` of their config.yaml.
+
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use yeti_core::prelude::*;
+
+pub type Extensions = ExtensionsResource;
+
+const REGISTRY_KEY: &str = "extensionRegistry";
+const INSTALLED_FILE: &str = "admin-extensions.json";
+const FETCH_TIMEOUT_SECS: &str = "30";
+const DOWNLOAD_TIMEOUT_SECS: &str = "300";
+
+fn registry_url() -> Option<String> {
+    crate::cors::read_server_config()
+        .ok()
+        .and_then(|c| c.get(REGISTRY_KEY).and_then(|v| v.as_str()).map(String::from))
+        .or_else(|| std::env::var("YETI_EXTENSION_REGISTRY").ok())
+        .filter(|s| !s.is_empty())
+}
+
+/// Fetch and parse the registry index (a URL or a local path)
+fn fetch_index(url: &str, owner: &str) -> std::result::Result<Vec<serde_json::Value>, String> {
+    let content = if url.starts_with("https://") || url.starts_with("http://") {
+        let mut cmd = std::process::Command::new("curl");
+        cmd.args(["-sSfL", "--max-time", FETCH_TIMEOUT_SECS, url]);
+        let output = crate::processes::output("extension", owner, &mut cmd)
+            .map_err(|e| format!("Failed to run curl: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("Failed to fetch registry: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        String::from_utf8_lossy(&output.stdout).to_string()
+    } else {
+        std::fs::read_to_string(url.strip_prefix("file://").unwrap_or(url))
+            .map_err(|e| format!("Failed to read registry: {}", e))?
+    };
+    let index: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("Registry is not valid JSON: {}", e))?;
+    let entries = index
+        .get("extensions")
+        .unwrap_or(&index)
+        .as_array()
+        .cloned()
+        .ok_or("Registry must be an array or {\"extensions\": [...]}")?;
+    Ok(entries
+        .into_iter()
+        .filter(|e| e["name"].as_str().is_some_and(|n| validate_identifier(n, "name").is_ok()))
+        .collect())
+}
+
+fn installed_path() -> PathBuf {
+    get_root_directory().join(INSTALLED_FILE)
+}
+
+fn read_installed() -> serde_json::Map<String, serde_json::Value> {
+    std::fs::read_to_string(installed_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn write_installed(installed: &serde_json::Map<String, serde_json::Value>) -> std::result::Result<(), String> {
+    let path = installed_path();
+    let tmp = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(installed).map_err(|e| format!("Failed to serialize: {}", e))?;
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", INSTALLED_FILE, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", INSTALLED_FILE, e))
+}
+
+fn app_config(app_path: &Path) -> Option<serde_yaml::Value> {
+    let content = std::fs::read_to_string(app_path.join("config.yaml")).ok()?;
+    serde_yaml::from_str(&content).ok()
+}
+
+fn is_extension(app_path: &Path) -> bool {
+    app_config(app_path)
+        .and_then(|c| c.get("extension").and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+/// Installed extension apps: name → version (tracked, else from config.yaml)
+fn installed_extensions() -> Vec<(String, Option<String>)> {
+    let tracked = read_installed();
+    let Ok(entries) = std::fs::read_dir(get_apps_directory()) else { return Vec::new() };
+    let mut list: Vec<(String, Option<String>)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if name.starts_with('.') || !is_extension(&entry.path()) {
+                return None;
+            }
+            let version = tracked
+                .get(&name)
+                .and_then(|t| t["version"].as_str().map(String::from))
+                .or_else(|| {
+                    app_config(&entry.path())
+                        .and_then(|c| c.get("version").and_then(|v| v.as_str()).map(String::from))
+                });
+            Some((name, version))
+        })
+        .collect();
+    list.sort();
+    list
+}
+
+/// Compare dotted versions numerically (`1.10.0` > `1.9.2`, leading `v` ignored)
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .map_while(|p| p.parse().ok())
+            .collect()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+fn view(entry: Option<&serde_json::Value>, name: &str, installed: Option<&Option<String>>) -> serde_json::Value {
+    let latest = entry.and_then(|e| e["version"].as_str());
+    let installed_version = installed.and_then(|v| v.as_deref());
+    json!({
+        "name": name,
+        "version": latest,
+        "description": entry.and_then(|e| e["description"].as_str()),
+        "source": entry.and_then(|e| e["repo"].as_str().or(e["archive"].as_str())),
+        "inRegistry": entry.is_some(),
+        "installed": installed.is_some(),
+        "installedVersion": installed_version,
+        "updateAvailable": match (latest, installed_version) {
+            (Some(latest), Some(current)) => compare_versions(latest, current) == Ordering::Greater,
+            _ => false,
+        },
+    })
+}
+
+/// Clone or download an entry into the hidden staging directory `staging`;
+/// returns the directory holding the fetched app
+fn fetch_entry(entry: &serde_json::Value, name: &str, staging: &Path, owner: &str) -> std::result::Result<PathBuf, String> {
+    if let Some(repo) = entry["repo"].as_str() {
+        if !repo.starts_with("git@") && !repo.starts_with("https://") {
+            return Err("'repo' must start with 'git@' or 'https://'".to_string());
+        }
+        let staging_str = staging.to_string_lossy().to_string();
+        let mut args = vec!["clone", "--depth", "1"];
+        if let Some(git_ref) = entry["ref"].as_str() {
+            args.extend(["--branch", git_ref]);
+        }
+        args.extend([repo, staging_str.as_str()]);
+        crate::repos::run_git(&args, None, None, owner)?;
+        return Ok(staging.to_path_buf());
+    }
+
+    let url = entry["archive"].as_str().ok_or("Registry entry has neither 'repo' nor 'archive'")?;
+    std::fs::create_dir_all(staging).map_err(|e| format!("Failed to create staging dir: {}", e))?;
+    let archive_path = staging.join("extension.tar.gz");
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["-sSfL", "--max-time", DOWNLOAD_TIMEOUT_SECS, "-o"]).arg(&archive_path).arg(url);
+    let output = crate::processes::output("extension", owner, &mut cmd)
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Download failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    if let Some(expected) = entry["sha256"].as_str() {
+        let bytes = std::fs::read(&archive_path).map_err(|e| format!("Failed to read download: {}", e))?;
+        let actual: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("Checksum mismatch: expected {}, got {}", expected, actual));
+        }
+    }
+    crate::promote::check_entries(&archive_path, name, owner)?;
+
+    let unpacked = staging.join("unpacked");
+    std::fs::create_dir_all(&unpacked).map_err(|e| format!("Failed to create staging dir: {}", e))?;
+    let output = crate::processes::output("extension", owner, std::process::Command::new("tar")
+        .arg("-xzf").arg(&archive_path)
+        .arg("--no-same-owner")
+        .arg("-C").arg(&unpacked))
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(unpacked.join(name))
+}
+
+/// Fetch an entry and swap it in as `apps/{name}`, keeping the previous tree
+/// until the new one is in place
+fn install(entry: &serde_json::Value, name: &str, replace: bool, owner: &str) -> std::result::Result<(), String> {
+    let apps_dir = get_apps_directory();
+    let target = apps_dir.join(name);
+    // Staged inside the apps directory (hidden) so the swap is a plain rename
+    let staging = apps_dir.join(format!(".extension-{}", name));
+    let _ = std::fs::remove_dir_all(&staging);
+    let result = (|| {
+        let fetched = fetch_entry(entry, name, &staging, owner)?;
+        if !is_extension(&fetched) {
+            return Err(format!("'{}' is not an extension app (config.yaml needs 'extension: true')", name));
+        }
+        let previous = apps_dir.join(format!(".extension-{}-previous", name));
+        let _ = std::fs::remove_dir_all(&previous);
+        if replace {
+            std::fs::rename(&target, &previous).map_err(|e| format!("Failed to move installed version: {}", e))?;
+        }
+        if let Err(e) = std::fs::rename(&fetched, &target) {
+            if replace {
+                let _ = std::fs::rename(&previous, &target);
+            }
+            return Err(format!("Failed to install extension: {}", e));
+        }
+        let _ = std::fs::remove_dir_all(&previous);
+        Ok(())
+    })();
+    let _ = std::fs::remove_dir_all(&staging);
+    result?;
+
+    let mut installed = read_installed();
+    installed.insert(name.to_string(), json!({
+        "version": entry["version"],
+        "source": entry["repo"].as_str().or(entry["archive"].as_str()),
+        "installedAt": std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0),
+    }));
+    write_installed(&installed)?;
+
+    if let Err(e) = crate::env::reload_app(&target) {
+        yeti_log!(warn, "Installed extension '{}' but reload failed: {}", name, e);
+    }
+    Ok(())
+}
+
+/// The registry entry for `name`, fetched on a blocking thread
+async fn registry_entry(name: &str, owner: &str) -> std::result::Result<Option<serde_json::Value>, String> {
+    let url = registry_url().ok_or("No extension registry configured (set extensionRegistry)")?;
+    let owner = owner.to_string();
+    let entries = tokio::task::spawn_blocking(move || fetch_index(&url, &owner))
+        .await
+        .map_err(|e| format!("Registry task failed: {}", e))??;
+    Ok(entries.into_iter().find(|e| e["name"] == name))
+}
+
+#[derive(Default)]
+pub struct ExtensionsResource;
+
+impl Resource for ExtensionsResource {
+    fn name(&self) -> &str {
+        "extensions"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "extensions", "GET", {
+            let owner = crate::actor!(request);
+            let installed = installed_extensions();
+            let query = request.uri().query().unwrap_or("");
+            let installed_only = parse_query_param(query, "installed").is_some_and(|v| v == "true");
+
+            // A missing registry still lists installed extensions
+            let (entries, registry_error) = match registry_url() {
+                Some(url) if !installed_only => {
+                    let fetch_owner = owner.clone();
+                    match tokio::task::spawn_blocking(move || fetch_index(&url, &fetch_owner)).await {
+                        Ok(Ok(entries)) => (entries, None),
+                        Ok(Err(e)) => (Vec::new(), Some(e)),
+                        Err(e) => (Vec::new(), Some(format!("Registry task failed: {}", e))),
+                    }
+                }
+                Some(_) => (Vec::new(), None),
+                None => (Vec::new(), (!installed_only).then(|| "No extension registry configured".to_string())),
+            };
+            let installed_version = |name: &str| installed.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+
+            if let Some(name) = ctx.path_id() {
+                let entry = entries.iter().find(|e| e["name"] == name);
+                if entry.is_none() && installed_version(name).is_none() {
+                    return not_found(&format!("Extension '{}' not found", name));
+                }
+                return reply().json(view(entry, name, installed_version(name)));
+            }
+
+            let mut list: Vec<serde_json::Value> = Vec::new();
+            if !installed_only {
+                for entry in &entries {
+                    let name = entry["name"].as_str().unwrap_or("");
+                    list.push(view(Some(entry), name, installed_version(name)));
+                }
+            }
+            for (name, version) in &installed {
+                if installed_only || !entries.iter().any(|e| e["name"] == name.as_str()) {
+                    let entry = entries.iter().find(|e| e["name"] == name.as_str());
+                    list.push(view(entry, name, Some(version)));
+                }
+            }
+            list.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+            reply().json(json!({
+                "registry": registry_url(),
+                "registryError": registry_error,
+                "extensions": list,
+            }))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "extensions", "POST", {
+            let name = ctx.require_id()?.to_string();
+            validate_identifier(&name, "name")?;
+            let owner = crate::actor!(request);
+            if get_apps_directory().join(&name).exists() {
+                return bad_request(&format!("Application '{}' already exists (use PUT to update)", name));
+            }
+            let Some(entry) = registry_entry(&name, &owner).await.map_err(YetiError::Validation)? else {
+                return not_found(&format!("Extension '{}' is not in the registry", name));
+            };
+
+            let install_entry = entry.clone();
+            let install_name = name.clone();
+            let install_owner = owner.clone();
+            tokio::task::spawn_blocking(move || install(&install_entry, &install_name, false, &install_owner))
+                .await
+                .map_err(|e| YetiError::Internal(format!("Install task failed: {}", e)))?
+                .map_err(YetiError::Internal)?;
+
+            crate::audit!(request, ctx, "extension.install", &name, json!({"version": entry["version"]}));
+
+            reply().code(201).json(view(Some(&entry), &name, Some(&entry["version"].as_str().map(String::from))))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "extensions", "PUT", {
+            let owner = crate::actor!(request);
+
+            // PUT /admin/extensions: set the registry
+            let Some(name) = ctx.path_id() else {
+                let body = request.json_value()?;
+                let registry = body.require_str("registry")?;
+                crate::cors::update_server_config(|config| {
+                    config
+                        .as_mapping_mut()
+                        .ok_or("Server config is not a mapping")?
+                        .insert(serde_yaml::Value::String(REGISTRY_KEY.to_string()), serde_yaml::Value::String(registry.clone()));
+                    Ok(())
+                })
+                .map_err(YetiError::Internal)?;
+                crate::audit!(request, ctx, "extension.registry", "server", json!({"registry": registry}));
+                return reply().json(json!({"registry": registry}));
+            };
+
+            // PUT /admin/extensions/{name}: update
+            let name = name.to_string();
+            validate_identifier(&name, "name")?;
+            let Some((_, current)) = installed_extensions().into_iter().find(|(n, _)| *n == name) else {
+                return not_found(&format!("Extension '{}' is not installed", name));
+            };
+            let Some(entry) = registry_entry(&name, &owner).await.map_err(YetiError::Validation)? else {
+                return not_found(&format!("Extension '{}' is not in the registry", name));
+            };
+            let latest = entry["version"].as_str().unwrap_or("");
+            let newer = current.as_deref().is_none_or(|c| compare_versions(latest, c) == Ordering::Greater);
+            let force = parse_query_param(request.uri().query().unwrap_or(""), "force").is_some_and(|v| v == "true");
+            if !newer && !force {
+                return reply().json(json!({
+                    "name": name,
+                    "updated": false,
+                    "installedVersion": current,
+                    "version": latest,
+                }));
+            }
+
+            let install_entry = entry.clone();
+            let install_name = name.clone();
+            let install_owner = owner.clone();
+            tokio::task::spawn_blocking(move || install(&install_entry, &install_name, true, &install_owner))
+                .await
+                .map_err(|e| YetiError::Internal(format!("Update task failed: {}", e)))?
+                .map_err(YetiError::Internal)?;
+
+            crate::audit!(request, ctx, "extension.update", &name, json!({"from": current, "to": latest}));
+
+            reply().json(json!({
+                "name": name,
+                "updated": true,
+                "previousVersion": current,
+                "version": latest,
+            }))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "extensions", "DELETE", {
+            let name = ctx.require_id()?.to_string();
+            validate_identifier(&name, "name")?;
+            let app_path = get_apps_directory().join(&name);
+            if !app_path.is_dir() || !is_extension(&app_path) {
+                return not_found(&format!("Extension '{}' is not installed", name));
+            }
+            std::fs::remove_dir_all(&app_path)
+                .map_err(|e| YetiError::Internal(format!("Failed to remove extension: {}", e)))?;
+            let mut installed = read_installed();
+            if installed.remove(&name).is_some() {
+                write_installed(&installed).map_err(YetiError::Internal)?;
+            }
+
+            crate::audit!(request, ctx, "extension.uninstall", &name, json!({}));
+
+            reply().json(json!({
+                "name": name,
+                "deleted": true,
+            }))
+        })
+    });
+}
+
+register_resource!(ExtensionsResource);
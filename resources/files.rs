@@ -2,15 +2,41 @@
 //!
 //! REST API for browsing and editing application files.
 //!
-//! | Method | Path                                           | Description        |
-//! |--------|------------------------------------------------|--------------------|
-//! | GET    | /yeti-applications/files?app={id}&path=/       | List directory      |
-//! | GET    | /yeti-applications/files?app={id}&path=/f.rs   | Read file as text   |
-//! | PUT    | /yeti-applications/files                       | Update file         |
-//! | POST   | /yeti-applications/files                       | Create file         |
-//! | DELETE | /yeti-applications/files?app={id}&path=/file   | Delete file         |
-
-use std::path::PathBuf;
+//! | Method | Path                                            | Description        |
+//! |--------|-------------------------------------------------|--------------------|
+//! | GET    | /yeti-applications/files?app={id}&path=/        | List directory     |
+//! | GET    | /yeti-applications/files?app={id}&path=/f.rs    | Read file as text  |
+//! | PUT    | /yeti-applications/files                        | Update file        |
+//! | POST   | /yeti-applications/files                        | Create file or dir |
+//! | DELETE | /yeti-applications/files?app={id}&path=/file    | Delete file        |
+//! | GET    | /yeti-applications/files/history?app={id}&path= | Commits of a file  |
+//! | GET    | /yeti-applications/files/at?app={id}&path=&ref= | File at a revision |
+//!
+//! POST with `"type": "directory"` (and no `content`) creates a directory,
+//! parents included. DELETE removes an empty directory; one with contents
+//! needs `?recursive=true` (409 otherwise). The app root cannot be deleted.
+//!
+//! Binary files (images, fonts, wasm) are transferred as base64: GET with
+//! `?encoding=base64` returns `content` base64-encoded, and POST/PUT decode it
+//! with `?encoding=base64` or `"encoding": "base64"` in the body. Reads carry a
+//! `contentType` guessed from the extension. Base64 reads and all writes are
+//! capped at `fileMaxBytes` in the server config (`yeti-config.yaml`, default
+//! `DEFAULT_MAX_BYTES`); larger transfers get 413.
+//!
+//! Large files can be read in slices with `?offset=&length=` or a `Range:
+//! bytes=start-end` header (`bytes=-n` for the last n bytes). A slice is at
+//! most `fileMaxBytes` (`DEFAULT_RANGE_BYTES` when no length is given); text
+//! slices are trimmed to whole UTF-8 characters. The response carries the
+//! actual `offset` and `length`, the file's total `size`, `truncated` and,
+//! when more follows, `nextOffset`.
+//!
+//! For apps that are git repositories, history lists the commits touching a
+//! file (following renames, `?limit=&offset=`), newest first, and `at` reads
+//! it as of `ref` (a commit, branch, tag or `HEAD~1`; default `HEAD`) in the
+//! same shape as a normal read, `?encoding=base64` included.
+
+use base64::Engine;
+use std::path::{Path, PathBuf};
 use yeti_core::prelude::*;
 
 pub type Files = FilesResource;
@@ -18,6 +44,189 @@ pub type Files = FilesResource;
 #[derive(Default)]
 pub struct FilesResource;
 
+/// Transfer limit when the server config sets no `fileMaxBytes`
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_BYTES_KEY: &str = "fileMaxBytes";
+
+fn max_bytes() -> u64 {
+    crate::cors::read_server_config()
+        .ok()
+        .and_then(|c| c.get(MAX_BYTES_KEY).and_then(|v| v.as_u64()))
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// 413 body for a transfer over the limit
+fn too_large(size: u64, limit: u64) -> serde_json::Value {
+    json!({
+        "error": format!("File is {} bytes, over the {}-byte limit", size, limit),
+        "size": size,
+        "limit": limit,
+    })
+}
+
+/// Content type guessed from the file extension
+fn content_type(path: &Path, is_text: bool) -> &'static str {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "json" => "application/json",
+        "js" | "mjs" => "text/javascript",
+        "css" => "text/css",
+        "html" | "htm" => "text/html",
+        "md" => "text/markdown",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "graphql" => "application/graphql",
+        "rs" => "text/x-rust",
+        _ if is_text => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Whether `encoding` (query string, else body) asks for base64
+fn wants_base64(query: &str, body: Option<&serde_json::Value>) -> Result<bool> {
+    let encoding = parse_query_param(query, "encoding")
+        .or_else(|| body.and_then(|b| b.get("encoding")).and_then(|v| v.as_str()).map(String::from));
+    match encoding.as_deref() {
+        None | Some("utf8") | Some("utf-8") => Ok(false),
+        Some("base64") => Ok(true),
+        Some(other) => Err(YetiError::Validation(format!("Unknown encoding '{}'; use 'utf8' or 'base64'", other))),
+    }
+}
+
+/// File content from a POST/PUT body, decoded when base64
+fn body_content(query: &str, body: &serde_json::Value) -> Result<Vec<u8>> {
+    let content = body.require_str("content")?;
+    if wants_base64(query, Some(body))? {
+        base64::engine::general_purpose::STANDARD
+            .decode(content.trim())
+            .map_err(|e| YetiError::Validation(format!("Invalid base64 content: {}", e)))
+    } else {
+        Ok(content.into_bytes())
+    }
+}
+
+/// Slice returned by a ranged read without a length
+const DEFAULT_RANGE_BYTES: u64 = 256 * 1024;
+
+/// (offset, length) asked for by `?offset=&length=`, else a `Range` header;
+/// None for a whole-file read
+fn requested_range(query: &str, range_header: Option<&str>, size: u64) -> std::result::Result<Option<(u64, u64)>, String> {
+    let number = |name: &str, value: &str| value.trim().parse::<u64>().map_err(|_| format!("Invalid {} '{}'", name, value));
+    let offset = parse_query_param(query, "offset");
+    let length = parse_query_param(query, "length");
+    let (start, len) = if offset.is_some() || length.is_some() {
+        let start = offset.map(|v| number("offset", &v)).transpose()?.unwrap_or(0);
+        let len = length.map(|v| number("length", &v)).transpose()?;
+        (start, len)
+    } else if let Some(header) = range_header {
+        let spec = header.trim().strip_prefix("bytes=").ok_or_else(|| format!("Unsupported range '{}'", header))?;
+        if spec.contains(',') {
+            return Err("Multiple ranges are not supported".to_string());
+        }
+        let (from, to) = spec.split_once('-').ok_or_else(|| format!("Invalid range '{}'", header))?;
+        match (from.trim(), to.trim()) {
+            ("", suffix) => {
+                let n = number("range", suffix)?.min(size);
+                (size - n, Some(n))
+            }
+            (from, "") => (number("range", from)?, None),
+            (from, to) => {
+                let (from, to) = (number("range", from)?, number("range", to)?);
+                if to < from {
+                    return Err(format!("Invalid range '{}'", header));
+                }
+                // `to` may run past the end of the file; clamp before computing the length
+                (from, Some(to.min(size.saturating_sub(1)).saturating_sub(from).saturating_add(1)))
+            }
+        }
+    } else {
+        return Ok(None);
+    };
+    if start > size {
+        return Err(format!("Offset {} is past the end of the file ({} bytes)", start, size));
+    }
+    let len = len.unwrap_or(DEFAULT_RANGE_BYTES).min(max_bytes()).min(size - start);
+    Ok(Some((start, len)))
+}
+
+/// Read `len` bytes at `offset`
+fn read_range(path: &Path, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    use std::io::{Read, Seek};
+    let mut file = std::fs::File::open(path)?;
+    file.seek(std::io::SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Trim a text slice to whole UTF-8 characters: continuation bytes at the
+/// start (when it starts mid-file) and a cut character at the end; returns
+/// the number of bytes dropped from the start
+fn align_utf8(bytes: &mut Vec<u8>, mid_start: bool, mid_end: bool) -> usize {
+    let skip = if mid_start { bytes.iter().take(3).take_while(|b| (**b & 0xC0) == 0x80).count() } else { 0 };
+    bytes.drain(..skip);
+    if mid_end {
+        if let Err(e) = std::str::from_utf8(bytes) {
+            if e.error_len().is_none() {
+                bytes.truncate(e.valid_up_to());
+            }
+        }
+    }
+    skip
+}
+
+/// Commits listed by history when no `limit` is given
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+const MAX_HISTORY_LIMIT: usize = 500;
+
+/// Add the content of a read to `file`: text, or base64 when asked for;
+/// Err when the file is not UTF-8 and base64 was not asked for
+fn add_content(file: &mut serde_json::Value, path: &Path, content: Vec<u8>, base64: bool) -> std::result::Result<(), String> {
+    file["size"] = json!(content.len());
+    if base64 {
+        file["encoding"] = json!("base64");
+        file["contentType"] = json!(content_type(path, std::str::from_utf8(&content).is_ok()));
+        file["content"] = json!(base64::engine::general_purpose::STANDARD.encode(&content));
+        return Ok(());
+    }
+    let text = String::from_utf8(content).map_err(|_| "File is not valid UTF-8 text; read it with ?encoding=base64".to_string())?;
+    file["encoding"] = json!("utf8");
+    file["contentType"] = json!(content_type(path, true));
+    file["content"] = json!(text);
+    Ok(())
+}
+
+/// App directory and repo-relative path for the git-backed views
+fn git_file(query: &str) -> Result<(String, PathBuf, String)> {
+    let app_id = parse_required_query_param(query, "app")?;
+    validate_identifier(&app_id, "app")?;
+    let rel_path = parse_required_query_param(query, "path")?;
+    let clean_path = rel_path.trim_start_matches('/').to_string();
+    crate::repos::validate_pathspec(&clean_path).map_err(YetiError::Validation)?;
+    let app_path = get_root_directory().join("applications").join(&app_id);
+    if !app_path.is_dir() {
+        return Err(YetiError::Validation(format!("Application '{}' not found", app_id)));
+    }
+    if !app_path.join(".git").exists() {
+        return Err(YetiError::Validation(format!("Application '{}' is not a git repository", app_id)));
+    }
+    Ok((app_id, app_path, clean_path))
+}
+
 /// Validate and resolve a file path within an app directory.
 /// Returns the canonical path if safe, or an error if path traversal is detected.
 fn resolve_safe_path(app_id: &str, rel_path: &str) -> Result<PathBuf> {
@@ -39,151 +248,329 @@ impl Resource for FilesResource {
     }
 
     get!(request, _ctx, {
-        let query = request.uri().query().unwrap_or("");
-        let app_id = parse_required_query_param(query, "app")?;
-        let rel_path = parse_query_param(query, "path")
-            .unwrap_or_else(|| "/".to_string());
-
-        let safe_path = resolve_safe_path(&app_id, &rel_path)?;
-
-        // Directory listing
-        if safe_path.is_dir() {
-            let entries = std::fs::read_dir(&safe_path)
-                .map_err(|e| YetiError::Internal(format!("Cannot read directory: {}", e)))?;
-
-            let mut items: Vec<serde_json::Value> = Vec::new();
-            for entry in entries.flatten() {
-                let meta = entry.metadata().ok();
-                let name = entry.file_name().to_string_lossy().to_string();
-                let is_dir = meta.as_ref().map_or(false, |m| m.is_dir());
-                let size = meta.as_ref().map_or(0, |m| m.len());
-
-                items.push(json!({
-                    "name": name,
-                    "type": if is_dir { "directory" } else { "file" },
-                    "size": size,
+        crate::metered!(request, "files", "GET", {
+            let query = request.uri().query().unwrap_or("");
+            let uri_path = request.uri().path().trim_end_matches('/');
+
+            // GET /files/history
+            if uri_path.ends_with("/files/history") {
+                let (app_id, app_path, path) = git_file(query)?;
+                let owner = crate::actor!(request);
+                let limit = parse_query_param(query, "limit")
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .unwrap_or(DEFAULT_HISTORY_LIMIT)
+                    .clamp(1, MAX_HISTORY_LIMIT);
+                let offset = parse_query_param(query, "offset").and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+                let (limit_arg, skip_arg) = (format!("-n{}", limit), format!("--skip={}", offset));
+                let output = crate::repos::run_git(
+                    &["log", "--follow", "--name-status", crate::repos::LOG_FORMAT, &limit_arg, &skip_arg, "--", &path],
+                    Some(&app_path),
+                    None,
+                    &owner,
+                )
+                .map_err(YetiError::Internal)?;
+                let commits = crate::repos::parse_log(&output);
+
+                return reply().json(json!({
+                    "app": app_id,
+                    "path": format!("/{}", path),
+                    "limit": limit,
+                    "offset": offset,
+                    "commits": commits,
                 }));
             }
 
-            items.sort_by(|a, b| {
-                let a_type = a["type"].as_str().unwrap_or("");
-                let b_type = b["type"].as_str().unwrap_or("");
-                let a_name = a["name"].as_str().unwrap_or("");
-                let b_name = b["name"].as_str().unwrap_or("");
-                // Directories first, then alphabetical
-                b_type.cmp(a_type).then(a_name.cmp(b_name))
-            });
+            // GET /files/at
+            if uri_path.ends_with("/files/at") {
+                let (app_id, app_path, path) = git_file(query)?;
+                let owner = crate::actor!(request);
+                let git_ref = parse_query_param(query, "ref").unwrap_or_else(|| "HEAD".to_string());
+                if git_ref.is_empty() || git_ref.starts_with('-') || git_ref.contains(char::is_whitespace) || git_ref.contains(':') {
+                    return bad_request(&format!("Invalid ref '{}'", git_ref));
+                }
+                let Ok(commit) = crate::repos::run_git(
+                    &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", git_ref)],
+                    Some(&app_path),
+                    None,
+                    &owner,
+                ) else {
+                    return not_found(&format!("Revision '{}' not found", git_ref));
+                };
+                let commit = commit.trim().to_string();
+                let object = format!("{}:{}", commit, path);
+                let size = crate::repos::run_git(&["cat-file", "-s", &object], Some(&app_path), None, &owner)
+                    .ok()
+                    .and_then(|s| s.trim().parse::<u64>().ok());
+                let Some(size) = size else {
+                    return not_found(&format!("'/{}' does not exist at {}", path, git_ref));
+                };
+                let base64 = wants_base64(query, None)?;
+                let limit = max_bytes();
+                if base64 && size > limit {
+                    return reply().code(413).json(too_large(size, limit));
+                }
+                let content = crate::repos::run_git_bytes(&["cat-file", "blob", &object], Some(&app_path), None, &owner)
+                    .map_err(YetiError::Internal)?;
+                crate::metrics::record_file("read");
+
+                let mut file = json!({
+                    "app": app_id,
+                    "path": format!("/{}", path),
+                    "type": "file",
+                    "ref": git_ref,
+                    "commit": commit,
+                });
+                if let Err(e) = add_content(&mut file, Path::new(&path), content, base64) {
+                    return bad_request(&e);
+                }
+                return reply().json(file);
+            }
+
+            let app_id = parse_required_query_param(query, "app")?;
+            let rel_path = parse_query_param(query, "path")
+                .unwrap_or_else(|| "/".to_string());
 
-            return reply().json(json!({
-                "app": app_id,
-                "path": rel_path,
-                "type": "directory",
-                "entries": items,
-            }));
-        }
+            let safe_path = resolve_safe_path(&app_id, &rel_path)?;
+
+            // Directory listing
+            if safe_path.is_dir() {
+                let entries = std::fs::read_dir(&safe_path)
+                    .map_err(|e| YetiError::Internal(format!("Cannot read directory: {}", e)))?;
+                crate::metrics::record_file("list");
+
+                let mut items: Vec<serde_json::Value> = Vec::new();
+                for entry in entries.flatten() {
+                    let meta = entry.metadata().ok();
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let is_dir = meta.as_ref().map_or(false, |m| m.is_dir());
+                    let size = meta.as_ref().map_or(0, |m| m.len());
 
-        // File read
-        if safe_path.is_file() {
-            let content = std::fs::read(&safe_path)
-                .map_err(|e| YetiError::Internal(format!("Cannot read file: {}", e)))?;
+                    items.push(json!({
+                        "name": name,
+                        "type": if is_dir { "directory" } else { "file" },
+                        "size": size,
+                    }));
+                }
+
+                items.sort_by(|a, b| {
+                    let a_type = a["type"].as_str().unwrap_or("");
+                    let b_type = b["type"].as_str().unwrap_or("");
+                    let a_name = a["name"].as_str().unwrap_or("");
+                    let b_name = b["name"].as_str().unwrap_or("");
+                    // Directories first, then alphabetical
+                    b_type.cmp(a_type).then(a_name.cmp(b_name))
+                });
+
+                return reply().json(json!({
+                    "app": app_id,
+                    "path": rel_path,
+                    "type": "directory",
+                    "entries": items,
+                }));
+            }
 
-            // Check if content is valid UTF-8
-            match String::from_utf8(content) {
-                Ok(text) => {
-                    let size = safe_path.metadata().map(|m| m.len()).unwrap_or(0);
-                    return reply().json(json!({
+            // File read
+            if safe_path.is_file() {
+                let base64 = wants_base64(query, None)?;
+                let size = safe_path.metadata().map(|m| m.len()).unwrap_or(0);
+
+                // Ranged read
+                let range_header = request.headers().get("range").and_then(|v| v.to_str().ok());
+                let range = match requested_range(query, range_header, size) {
+                    Ok(range) => range,
+                    Err(e) => return reply().code(416).json(json!({"error": e, "size": size})),
+                };
+                if let Some((offset, len)) = range {
+                    let mut content = read_range(&safe_path, offset, len)
+                        .map_err(|e| YetiError::Internal(format!("Cannot read file: {}", e)))?;
+                    crate::metrics::record_file("read");
+                    let start = if base64 {
+                        offset
+                    } else {
+                        offset + align_utf8(&mut content, offset > 0, offset + len < size) as u64
+                    };
+                    let end = start + content.len() as u64;
+
+                    let mut file = json!({
                         "app": app_id,
                         "path": rel_path,
                         "type": "file",
-                        "content": text,
-                        "size": size,
-                    }));
+                    });
+                    if let Err(e) = add_content(&mut file, &safe_path, content, base64) {
+                        return bad_request(&e);
+                    }
+                    file["size"] = json!(size);
+                    file["offset"] = json!(start);
+                    file["length"] = json!(end - start);
+                    file["truncated"] = json!(start > 0 || end < size);
+                    if end < size {
+                        file["nextOffset"] = json!(end);
+                    }
+                    return reply().json(file);
+                }
+
+                let limit = max_bytes();
+                if base64 && size > limit {
+                    return reply().code(413).json(too_large(size, limit));
                 }
-                Err(_) => {
-                    return bad_request("File is not valid UTF-8 text");
+                let content = std::fs::read(&safe_path)
+                    .map_err(|e| YetiError::Internal(format!("Cannot read file: {}", e)))?;
+                crate::metrics::record_file("read");
+
+                let mut file = json!({
+                    "app": app_id,
+                    "path": rel_path,
+                    "type": "file",
+                });
+                if let Err(e) = add_content(&mut file, &safe_path, content, base64) {
+                    return bad_request(&e);
                 }
+                return reply().json(file);
             }
-        }
 
-        not_found(&format!("Path '{}' not found in app '{}'", rel_path, app_id))
+            not_found(&format!("Path '{}' not found in app '{}'", rel_path, app_id))
+        })
     });
 
-    post!(request, _ctx, {
-        let body = request.json_value()?;
-        let app_id = body.require_str("app")?;
-        let rel_path = body.require_str("path")?;
-        let content = body.require_str("content")?;
+    post!(request, ctx, {
+        crate::metered!(request, "files", "POST", {
+            let body = request.json_value()?;
+            let app_id = body.require_str("app")?;
+            let rel_path = body.require_str("path")?;
+
+            // Create a directory (and any missing parents)
+            match body.get("type").and_then(|v| v.as_str()) {
+                None | Some("file") => {}
+                Some("directory") => {
+                    let safe_path = resolve_safe_path(&app_id, &rel_path)?;
+                    if safe_path.exists() {
+                        return bad_request(&format!("'{}' already exists", rel_path));
+                    }
+                    std::fs::create_dir_all(&safe_path)
+                        .map_err(|e| YetiError::Internal(format!("Failed to create directory: {}", e)))?;
+
+                    crate::metrics::record_file("mkdir");
+                    crate::audit!(request, ctx, "file.mkdir", &format!("{}:{}", app_id, rel_path), json!({}));
+
+                    return reply().code(201).json(json!({
+                        "app": app_id,
+                        "path": rel_path,
+                        "type": "directory",
+                        "created": true,
+                    }));
+                }
+                Some(other) => return bad_request(&format!("Unknown type '{}'; use 'file' or 'directory'", other)),
+            }
 
-        let safe_path = resolve_safe_path(&app_id, &rel_path)?;
+            let content = body_content(request.uri().query().unwrap_or(""), &body)?;
+            let limit = max_bytes();
+            if content.len() as u64 > limit {
+                return reply().code(413).json(too_large(content.len() as u64, limit));
+            }
 
-        if safe_path.exists() {
-            return bad_request(&format!("File '{}' already exists, use PUT to update", rel_path));
-        }
+            let safe_path = resolve_safe_path(&app_id, &rel_path)?;
 
-        // Create parent directories if needed
-        if let Some(parent) = safe_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| YetiError::Internal(format!("Failed to create directories: {}", e)))?;
-        }
+            if safe_path.exists() {
+                return bad_request(&format!("File '{}' already exists, use PUT to update", rel_path));
+            }
+
+            // Create parent directories if needed
+            if let Some(parent) = safe_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| YetiError::Internal(format!("Failed to create directories: {}", e)))?;
+            }
+
+            std::fs::write(&safe_path, &content)
+                .map_err(|e| YetiError::Internal(format!("Failed to write file: {}", e)))?;
 
-        std::fs::write(&safe_path, &content)
-            .map_err(|e| YetiError::Internal(format!("Failed to write file: {}", e)))?;
+            crate::metrics::record_file("create");
+            crate::audit!(request, ctx, "file.create", &format!("{}:{}", app_id, rel_path), json!({"size": content.len()}));
 
-        reply().code(201).json(json!({
-            "app": app_id,
-            "path": rel_path,
-            "created": true,
-            "size": content.len(),
-        }))
+            reply().code(201).json(json!({
+                "app": app_id,
+                "path": rel_path,
+                "created": true,
+                "size": content.len(),
+            }))
+        })
     });
 
-    put!(request, _ctx, {
-        let body = request.json_value()?;
-        let app_id = body.require_str("app")?;
-        let rel_path = body.require_str("path")?;
-        let content = body.require_str("content")?;
+    put!(request, ctx, {
+        crate::metered!(request, "files", "PUT", {
+            let body = request.json_value()?;
+            let app_id = body.require_str("app")?;
+            let rel_path = body.require_str("path")?;
+            let content = body_content(request.uri().query().unwrap_or(""), &body)?;
+            let limit = max_bytes();
+            if content.len() as u64 > limit {
+                return reply().code(413).json(too_large(content.len() as u64, limit));
+            }
 
-        let safe_path = resolve_safe_path(&app_id, &rel_path)?;
+            let safe_path = resolve_safe_path(&app_id, &rel_path)?;
 
-        if !safe_path.exists() {
-            return not_found(&format!("File '{}' not found in app '{}'", rel_path, app_id));
-        }
+            if !safe_path.exists() {
+                return not_found(&format!("File '{}' not found in app '{}'", rel_path, app_id));
+            }
+
+            std::fs::write(&safe_path, &content)
+                .map_err(|e| YetiError::Internal(format!("Failed to write file: {}", e)))?;
 
-        std::fs::write(&safe_path, &content)
-            .map_err(|e| YetiError::Internal(format!("Failed to write file: {}", e)))?;
+            crate::metrics::record_file("update");
+            crate::audit!(request, ctx, "file.update", &format!("{}:{}", app_id, rel_path), json!({"size": content.len()}));
 
-        reply().json(json!({
-            "app": app_id,
-            "path": rel_path,
-            "updated": true,
-            "size": content.len(),
-        }))
+            reply().json(json!({
+                "app": app_id,
+                "path": rel_path,
+                "updated": true,
+                "size": content.len(),
+            }))
+        })
     });
 
-    delete!(request, _ctx, {
-        let query = request.uri().query().unwrap_or("");
-        let app_id = parse_required_query_param(query, "app")?;
-        let rel_path = parse_required_query_param(query, "path")?;
+    delete!(request, ctx, {
+        crate::metered!(request, "files", "DELETE", {
+            let query = request.uri().query().unwrap_or("");
+            let app_id = parse_required_query_param(query, "app")?;
+            let rel_path = parse_required_query_param(query, "path")?;
 
-        let safe_path = resolve_safe_path(&app_id, &rel_path)?;
+            let safe_path = resolve_safe_path(&app_id, &rel_path)?;
 
-        if !safe_path.exists() {
-            return not_found(&format!("Path '{}' not found in app '{}'", rel_path, app_id));
-        }
+            if !safe_path.exists() {
+                return not_found(&format!("Path '{}' not found in app '{}'", rel_path, app_id));
+            }
 
-        if safe_path.is_dir() {
-            std::fs::remove_dir_all(&safe_path)
-                .map_err(|e| YetiError::Internal(format!("Failed to remove directory: {}", e)))?;
-        } else {
-            std::fs::remove_file(&safe_path)
-                .map_err(|e| YetiError::Internal(format!("Failed to remove file: {}", e)))?;
-        }
+            if safe_path.is_dir() {
+                // Compare resolved paths: `.`, `/./` or a link to the app root name it too
+                let app_root = resolve_safe_path(&app_id, "")?;
+                if safe_path.canonicalize().is_ok_and(|p| p == app_root) {
+                    return bad_request("Cannot delete the app directory; delete the app instead");
+                }
+                let empty = std::fs::read_dir(&safe_path).map(|mut d| d.next().is_none()).unwrap_or(false);
+                if empty {
+                    std::fs::remove_dir(&safe_path)
+                        .map_err(|e| YetiError::Internal(format!("Failed to remove directory: {}", e)))?;
+                } else if parse_query_param(query, "recursive").as_deref() == Some("true") {
+                    std::fs::remove_dir_all(&safe_path)
+                        .map_err(|e| YetiError::Internal(format!("Failed to remove directory: {}", e)))?;
+                } else {
+                    return reply().code(409).json(json!({
+                        "error": format!("Directory '{}' is not empty; retry with ?recursive=true", rel_path),
+                    }));
+                }
+            } else {
+                std::fs::remove_file(&safe_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to remove file: {}", e)))?;
+            }
+
+            crate::metrics::record_file("delete");
+            crate::audit!(request, ctx, "file.delete", &format!("{}:{}", app_id, rel_path), json!({}));
 
-        reply().json(json!({
-            "app": app_id,
-            "path": rel_path,
-            "deleted": true,
-        }))
+            reply().json(json!({
+                "app": app_id,
+                "path": rel_path,
+                "deleted": true,
+            }))
+        })
     });
 }
 
@@ -0,0 +1,360 @@
+//! Remote Instance Resource
+//!
+//! Registry of other Yeti servers, with read-only views proxied from their
+//! admin APIs so one admin UI can oversee a small fleet.
+//!
+//! | Method | Path                                   | Description                       |
+//! |--------|----------------------------------------|-----------------------------------|
+//! | GET    | /admin/instances                       | List instances                    |
+//! | GET    | /admin/instances?probe=true            | ...with reachability and latency  |
+//! | GET    | /admin/instances/{id}                  | Single instance                   |
+//! | GET    | /admin/instances/{id}/apps             | Remote app list                   |
+//! | GET    | /admin/instances/{id}/health           | Remote host vitals (`/system`)    |
+//! | GET    | /admin/instances/{id}/bestresults      | Remote best benchmark results     |
+//! | POST   | /admin/instances                       | Register an instance              |
+//! | PUT    | /admin/instances/{id}                  | Update url/description/credentials|
+//! | DELETE | /admin/instances/{id}                  | Remove an instance                |
+//!
+//! POST body: `{"id": "staging", "url": "https://staging.example.com:9996",
+//! "token": "..."}` (or `"username"` / `"password"` for basic auth). The
+//! credentials are kept in the secrets store (`instances` scope) and never
+//! returned. The query string of a view request is passed through, e.g.
+//! `/instances/staging/bestresults?metric=p95`.
+//!
+//! TLS certificates are verified. For an instance with a private CA set
+//! `"caCert": "/etc/yeti/staging-ca.pem"`; `"insecure": true` turns
+//! verification off for that instance only (its credentials can then be
+//! intercepted).
+//!
+//! `POST /admin/promote/{app_id}` accepts `{"instance": "staging"}` in place
+//! of a url and token.
+
+use base64::Engine;
+use std::path::Path;
+use yeti_core::prelude::*;
+
+pub type Instances = InstancesResource;
+
+const SECRETS_SCOPE: &str = "instances";
+const TIMEOUT_SECS: &str = "10";
+
+/// Read-only views: name → remote admin path
+const VIEWS: &[(&str, &str)] = &[
+    ("apps", "/admin/apps"),
+    ("health", "/admin/system"),
+    ("bestresults", "/admin/bestresults"),
+];
+
+/// The stored `Authorization` header value for an instance
+pub fn authorization(id: &str) -> Option<String> {
+    crate::env::read_secret(SECRETS_SCOPE, id)
+}
+
+/// Authorization header value from a request body's credentials, if any
+fn credentials(body: &serde_json::Value) -> std::result::Result<Option<String>, String> {
+    if let Some(token) = body.get("token").and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
+        return Ok(Some(format!("Bearer {}", token)));
+    }
+    match (body.get("username").and_then(|v| v.as_str()), body.get("password").and_then(|v| v.as_str())) {
+        (Some(user), Some(password)) => Ok(Some(format!("Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password))))),
+        (None, None) => Ok(None),
+        _ => Err("Basic auth needs both 'username' and 'password'".to_string()),
+    }
+}
+
+fn set_credentials(id: &str, authorization: Option<&str>) -> std::result::Result<(), YetiError> {
+    let mut secrets = crate::env::read_secrets(SECRETS_SCOPE);
+    match authorization {
+        Some(a) => secrets.insert(id.to_string(), json!(a)),
+        None => secrets.remove(id),
+    };
+    crate::env::write_secrets(SECRETS_SCOPE, &secrets).map_err(YetiError::Internal)
+}
+
+fn validate_url(url: &str) -> std::result::Result<(), String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or("'url' must start with http:// or https://")?;
+    if rest.is_empty() || url.contains(char::is_whitespace) {
+        return Err(format!("Invalid url '{}'", url));
+    }
+    Ok(())
+}
+
+/// `caCert` / `insecure` of a request body, validated
+fn tls_settings(body: &serde_json::Value) -> std::result::Result<Vec<(&'static str, serde_json::Value)>, String> {
+    let mut settings = Vec::new();
+    if let Some(ca) = body.get("caCert") {
+        match ca {
+            serde_json::Value::Null => settings.push(("caCert", serde_json::Value::Null)),
+            serde_json::Value::String(path) if path.starts_with('/') && Path::new(path).is_file() => {
+                settings.push(("caCert", json!(path)));
+            }
+            _ => return Err("'caCert' must be the absolute path of a CA certificate file, or null".to_string()),
+        }
+    }
+    if let Some(insecure) = body.get("insecure") {
+        let insecure = insecure.as_bool().ok_or("'insecure' must be a boolean")?;
+        settings.push(("insecure", json!(insecure)));
+    }
+    Ok(settings)
+}
+
+/// curl TLS options for an instance record: its CA file, and `-k` only when
+/// the instance is explicitly marked `insecure`
+pub fn tls_args(record: &serde_json::Value) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(ca) = record["caCert"].as_str() {
+        args.extend(["--cacert".to_string(), ca.to_string()]);
+    }
+    if record["insecure"].as_bool().unwrap_or(false) {
+        args.push("-k".to_string());
+    }
+    args
+}
+
+/// GET a remote admin path; returns the HTTP status, body and latency.
+/// Credentials are passed in a header file, never on the command line.
+pub fn remote_get(
+    base_url: &str,
+    path: &str,
+    authorization: Option<&str>,
+    tls: &[String],
+    owner: &str,
+) -> std::result::Result<(u16, String, f64), String> {
+    let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["-s", "--max-time", TIMEOUT_SECS, "-w", "\n%{http_code}"]).args(tls);
+
+    let header_path = match authorization {
+        Some(auth) => Some(crate::processes::header_file("instances", auth)?),
+        None => None,
+    };
+    if let Some(header_path) = &header_path {
+        cmd.arg("-H").arg(format!("@{}", header_path.display()));
+    }
+    cmd.arg(&url);
+
+    let started = std::time::Instant::now();
+    let output = crate::processes::output("instance", owner, &mut cmd);
+    if let Some(header_path) = &header_path {
+        let _ = std::fs::remove_file(header_path);
+    }
+    let output = output.map_err(|e| format!("Failed to run curl: {}", e))?;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    if !output.status.success() {
+        return Err(format!("{} unreachable (curl exit code {})", url, output.status.code().unwrap_or(-1)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", stdout.as_ref()));
+    let code = code.trim().parse().map_err(|_| "No HTTP status in response".to_string())?;
+    Ok((code, body.to_string(), latency_ms))
+}
+
+/// Instance record as returned by the API (credential presence only)
+fn view(mut record: serde_json::Value) -> serde_json::Value {
+    let id = record["id"].as_str().unwrap_or("").to_string();
+    record["hasCredentials"] = json!(authorization(&id).is_some());
+    record
+}
+
+/// Probe an instance's admin API
+async fn probe(record: &serde_json::Value, owner: &str) -> serde_json::Value {
+    let id = record["id"].as_str().unwrap_or("").to_string();
+    let url = record["url"].as_str().unwrap_or("").to_string();
+    let owner = owner.to_string();
+    let tls = tls_args(record);
+    let result = tokio::task::spawn_blocking(move || remote_get(&url, "/admin/system", authorization(&id).as_deref(), &tls, &owner))
+        .await
+        .unwrap_or_else(|e| Err(format!("Probe failed: {}", e)));
+    match result {
+        Ok((code, _, latency)) => json!({
+            "reachable": (200..300).contains(&code),
+            "status": code,
+            "latencyMs": latency,
+        }),
+        Err(e) => json!({
+            "reachable": false,
+            "error": e,
+        }),
+    }
+}
+
+#[derive(Default)]
+pub struct InstancesResource;
+
+impl Resource for InstancesResource {
+    fn name(&self) -> &str {
+        "instances"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "instances", "GET", {
+            let table = ctx.get_table("Instance")?;
+            let owner = crate::actor!(request);
+            let query = request.uri().query().unwrap_or("");
+
+            // GET /admin/instances/{id}/{view}
+            let uri_path = request.uri().path().trim_end_matches('/');
+            if let Some((remote_path, id)) = VIEWS.iter().find_map(|(name, remote_path)| {
+                let prefix = uri_path.strip_suffix(&format!("/{}", name))?;
+                let id = prefix.rsplit('/').next()?;
+                (id != "instances").then(|| (*remote_path, id.to_string()))
+            }) {
+                let Some(record) = table.get_by_id(&id).await? else {
+                    return not_found(&format!("Instance '{}' not found", id));
+                };
+                let url = record["url"].as_str().unwrap_or("").to_string();
+                let path = if query.is_empty() { remote_path.to_string() } else { format!("{}?{}", remote_path, query) };
+                let tls = tls_args(&record);
+                let result = tokio::task::spawn_blocking(move || remote_get(&url, &path, authorization(&id).as_deref(), &tls, &owner))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Proxy task failed: {}", e)))?;
+                return match result {
+                    Ok((code, body, _)) => {
+                        let body: serde_json::Value = serde_json::from_str(&body).unwrap_or(json!(body));
+                        if (200..300).contains(&code) {
+                            reply().json(body)
+                        } else {
+                            reply().code(502).json(json!({
+                                "error": format!("Instance returned HTTP {}", code),
+                                "remote": body,
+                            }))
+                        }
+                    }
+                    Err(e) => reply().code(502).json(json!({"error": e})),
+                };
+            }
+
+            if let Some(id) = ctx.path_id() {
+                return match table.get_by_id(id).await? {
+                    Some(record) => {
+                        let mut instance = view(record.clone());
+                        instance["probe"] = probe(&record, &owner).await;
+                        reply().json(instance)
+                    }
+                    None => not_found(&format!("Instance '{}' not found", id)),
+                };
+            }
+
+            let probe_all = parse_query_param(query, "probe").is_some_and(|v| v == "true");
+            let mut instances = Vec::new();
+            for record in table.scan_all().await? {
+                let mut instance = view(record.clone());
+                if probe_all {
+                    instance["probe"] = probe(&record, &owner).await;
+                }
+                instances.push(instance);
+            }
+            instances.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+            reply().json(json!(instances))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "instances", "POST", {
+            let body = request.json_value()?;
+            let id = body.require_str("id")?;
+            validate_identifier(&id, "id")?;
+            let url = body.require_str("url")?;
+            if let Err(e) = validate_url(&url) {
+                return bad_request(&e);
+            }
+            let auth = match credentials(&body) {
+                Ok(auth) => auth,
+                Err(e) => return bad_request(&e),
+            };
+            let tls = match tls_settings(&body) {
+                Ok(tls) => tls,
+                Err(e) => return bad_request(&e),
+            };
+
+            let table = ctx.get_table("Instance")?;
+            if table.get_by_id(&id).await?.is_some() {
+                return bad_request(&format!("Instance '{}' already exists", id));
+            }
+            let mut record = json!({
+                "id": id,
+                "url": url.trim_end_matches('/'),
+                "description": body.get("description").and_then(|v| v.as_str()).unwrap_or(""),
+                "createdAt": std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0),
+            });
+            for (key, value) in tls {
+                record[key] = value;
+            }
+            set_credentials(&id, auth.as_deref())?;
+            table.put(record.clone()).await?;
+
+            crate::audit!(request, ctx, "instance.create", &id, json!({"url": record["url"]}));
+
+            reply().code(201).json(view(record))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "instances", "PUT", {
+            let id = ctx.require_id()?.to_string();
+            let table = ctx.get_table("Instance")?;
+            let Some(mut record) = table.get_by_id(&id).await? else {
+                return not_found(&format!("Instance '{}' not found", id));
+            };
+
+            let body = request.json_value()?;
+            if let Some(url) = body.get("url") {
+                let url = url.as_str().unwrap_or("");
+                if let Err(e) = validate_url(url) {
+                    return bad_request(&e);
+                }
+                record["url"] = json!(url.trim_end_matches('/'));
+            }
+            if let Some(description) = body.get("description").and_then(|v| v.as_str()) {
+                record["description"] = json!(description);
+            }
+            match tls_settings(&body) {
+                Ok(tls) => {
+                    for (key, value) in tls {
+                        record[key] = value;
+                    }
+                }
+                Err(e) => return bad_request(&e),
+            }
+            match credentials(&body) {
+                Ok(Some(auth)) => set_credentials(&id, Some(&auth))?,
+                Ok(None) => {}
+                Err(e) => return bad_request(&e),
+            }
+            table.put(record.clone()).await?;
+
+            let changed: Vec<&String> = body.as_object().map(|o| o.keys().collect()).unwrap_or_default();
+            crate::audit!(request, ctx, "instance.update", &id, json!({"fields": changed}));
+
+            reply().json(view(record))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "instances", "DELETE", {
+            let id = ctx.require_id()?.to_string();
+            let table = ctx.get_table("Instance")?;
+            if table.get_by_id(&id).await?.is_none() {
+                return not_found(&format!("Instance '{}' not found", id));
+            }
+            table.delete(&id).await?;
+            set_credentials(&id, None)?;
+
+            crate::audit!(request, ctx, "instance.delete", &id, json!({}));
+
+            reply().json(json!({
+                "id": id,
+                "deleted": true,
+            }))
+        })
+    });
+}
+
+register_resource!(InstancesResource);
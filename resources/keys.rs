@@ -1,6 +1,6 @@
 //! SSH Deploy Key Management Resource
 //!
-//! REST API for managing ED25519 SSH keypairs for git authentication.
+//! REST API for managing SSH keypairs for git authentication.
 //!
 //! | Method | Path                                | Description              |
 //! |--------|-------------------------------------|--------------------------|
@@ -8,7 +8,26 @@
 //! | GET    | /yeti-applications/keys/{name}      | Get single key (pub)     |
 //! | POST   | /yeti-applications/keys             | Generate new keypair     |
 //! | DELETE | /yeti-applications/keys/{name}      | Remove keypair           |
-
+//!
+//! POST body: `{"name": "deploy", "type": "rsa-4096"}`. `type` is one of
+//! `KEY_TYPES` and defaults to `ed25519`; RSA and ECDSA are for git servers
+//! that still reject ed25519. Keys are listed with their `type` and SHA256
+//! `fingerprint` (as `ssh-keygen -l` prints it), read from the public key.
+//!
+//! Every git command run with a key records its `last_used` time and the app
+//! it ran for in `key-usage.json` in the root directory; the list shows both
+//! (`apps` holds each app with its last use), so keys nobody uses any more
+//! can be found and rotated.
+//!
+//! An app can have a default deploy key (set with `POST /admin/repos/key/{app_id}`,
+//! or by cloning with a `key`), kept in `app-keys.json` in the root directory
+//! and used by pull, checkout and push when no `key` is passed. Keys show the
+//! apps they are the default for under `defaultFor`; deleting one of those is
+//! refused (409) unless `?force=true`.
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
 use yeti_core::prelude::*;
 
 pub type Keys = KeysResource;
@@ -16,6 +35,172 @@ pub type Keys = KeysResource;
 #[derive(Default)]
 pub struct KeysResource;
 
+/// Key types POST accepts, with their `ssh-keygen` arguments
+const KEY_TYPES: &[(&str, &[&str])] = &[
+    ("ed25519", &["-t", "ed25519"]),
+    ("rsa-4096", &["-t", "rsa", "-b", "4096"]),
+    ("ecdsa-p256", &["-t", "ecdsa", "-b", "256"]),
+];
+
+const USAGE_FILE: &str = "key-usage.json";
+
+fn usage_path() -> PathBuf {
+    get_root_directory().join(USAGE_FILE)
+}
+
+/// Serializes read-modify-write of the usage and app-key files
+static USAGE_LOCK: Mutex<()> = Mutex::new(());
+
+/// `{key: {"lastUsed": secs, "apps": {app_id: secs}}}`
+fn read_usage() -> serde_json::Map<String, serde_json::Value> {
+    std::fs::read_to_string(usage_path())
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+fn modify_usage(f: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>)) -> std::result::Result<(), String> {
+    let _guard = USAGE_LOCK.lock().unwrap();
+    let mut usage = read_usage();
+    f(&mut usage);
+    let path = usage_path();
+    let tmp = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(&usage).map_err(|e| format!("Failed to serialize key usage: {}", e))?;
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", USAGE_FILE, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", USAGE_FILE, e))
+}
+
+/// Record that `key_name` was used, for `app_id` when known
+pub fn record_use(key_name: &str, app_id: Option<&str>) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let result = modify_usage(|usage| {
+        let entry = usage.entry(key_name.to_string()).or_insert_with(|| json!({"apps": {}}));
+        entry["lastUsed"] = json!(now);
+        if let Some(app_id) = app_id {
+            entry["apps"][app_id] = json!(now);
+        }
+    });
+    if let Err(e) = result {
+        yeti_log!(warn, "Failed to record use of key '{}': {}", key_name, e);
+    }
+}
+
+/// Usage fields of a key for the list and detail views
+fn usage_fields(usage: &serde_json::Map<String, serde_json::Value>, name: &str) -> (serde_json::Value, Vec<serde_json::Value>) {
+    let Some(entry) = usage.get(name) else { return (serde_json::Value::Null, Vec::new()) };
+    let mut apps: Vec<serde_json::Value> = entry["apps"]
+        .as_object()
+        .map(|apps| apps.iter().map(|(app, at)| json!({"app_id": app, "lastUsed": at})).collect())
+        .unwrap_or_default();
+    apps.sort_by(|a, b| a["app_id"].as_str().cmp(&b["app_id"].as_str()));
+    (entry["lastUsed"].clone(), apps)
+}
+
+const DEFAULTS_FILE: &str = "app-keys.json";
+
+/// `{app_id: key_name}`
+fn read_defaults() -> serde_json::Map<String, serde_json::Value> {
+    std::fs::read_to_string(get_root_directory().join(DEFAULTS_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+fn modify_defaults(f: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>)) -> std::result::Result<(), String> {
+    let _guard = USAGE_LOCK.lock().unwrap();
+    let mut defaults = read_defaults();
+    f(&mut defaults);
+    let path = get_root_directory().join(DEFAULTS_FILE);
+    let tmp = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(&defaults).map_err(|e| format!("Failed to serialize app keys: {}", e))?;
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", DEFAULTS_FILE, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", DEFAULTS_FILE, e))
+}
+
+/// The default deploy key of `app_id`, if one is set
+pub fn default_for(app_id: &str) -> Option<String> {
+    read_defaults().get(app_id).and_then(|v| v.as_str()).map(String::from)
+}
+
+/// Set (or with None, clear) the default deploy key of `app_id`
+pub fn set_default(app_id: &str, key_name: Option<&str>) -> std::result::Result<(), String> {
+    if let Some(name) = key_name {
+        if !get_keys_directory().join(name).exists() {
+            return Err(format!("SSH key '{}' not found", name));
+        }
+    }
+    modify_defaults(|defaults| match key_name {
+        Some(name) => {
+            defaults.insert(app_id.to_string(), json!(name));
+        }
+        None => {
+            defaults.remove(app_id);
+        }
+    })
+}
+
+/// Move an app's default key to its new id after a rename
+pub fn rename_app(old: &str, new: &str) -> std::result::Result<(), String> {
+    if read_defaults().contains_key(old) {
+        modify_defaults(|defaults| {
+            if let Some(key) = defaults.remove(old) {
+                defaults.insert(new.to_string(), key);
+            }
+        })?;
+    }
+    Ok(())
+}
+
+/// Apps whose default key is `key_name`
+fn defaulting_apps(defaults: &serde_json::Map<String, serde_json::Value>, key_name: &str) -> Vec<String> {
+    let mut apps: Vec<String> = defaults.iter().filter(|(_, k)| *k == key_name).map(|(app, _)| app.clone()).collect();
+    apps.sort();
+    apps
+}
+
+/// Bit length of the modulus of an `ssh-rsa` key blob
+fn rsa_bits(blob: &[u8]) -> Option<usize> {
+    // string "ssh-rsa", mpint e, mpint n
+    let mut rest = blob;
+    let mut field = || {
+        let len = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let value = rest.get(4..4 + len)?;
+        rest = &rest[4 + len..];
+        Some(value)
+    };
+    let (_, _, n) = (field()?, field()?, field()?);
+    let n: Vec<u8> = n.iter().copied().skip_while(|b| *b == 0).collect();
+    let first = *n.first()?;
+    Some(n.len() * 8 - first.leading_zeros() as usize)
+}
+
+/// Type (as POST names it) and SHA256 fingerprint of an OpenSSH public key line
+fn describe(public_key: &str) -> serde_json::Value {
+    let mut parts = public_key.split_whitespace();
+    let algorithm = parts.next().unwrap_or("");
+    let Some(blob) = parts.next().and_then(|b| base64::engine::general_purpose::STANDARD.decode(b).ok()) else {
+        return json!({"type": null, "fingerprint": null});
+    };
+    let key_type = match algorithm {
+        "ssh-ed25519" => "ed25519".to_string(),
+        "ssh-rsa" => rsa_bits(&blob).map_or("rsa".to_string(), |bits| format!("rsa-{}", bits)),
+        "ecdsa-sha2-nistp256" => "ecdsa-p256".to_string(),
+        "ecdsa-sha2-nistp384" => "ecdsa-p384".to_string(),
+        "ecdsa-sha2-nistp521" => "ecdsa-p521".to_string(),
+        other => other.to_string(),
+    };
+    let digest = Sha256::digest(&blob);
+    json!({
+        "type": key_type,
+        "fingerprint": format!("SHA256:{}", base64::engine::general_purpose::STANDARD_NO_PAD.encode(digest)),
+    })
+}
+
 /// Ensure keys directory exists with 0700 permissions
 fn ensure_get_keys_directory() -> std::result::Result<PathBuf, String> {
     let dir = get_keys_directory();
@@ -45,139 +230,191 @@ impl Resource for KeysResource {
         "keys"
     }
 
-    get!(_request, ctx, {
-        let dir = get_keys_directory();
-
-        // Single key by path ID
-        if let Some(key_name) = ctx.path_id() {
-            validate_identifier(key_name, "key name")?;
-
-            let pub_path = dir.join(format!("{}.pub", key_name));
-            if !pub_path.exists() {
-                return not_found(&format!("Key '{}' not found", key_name));
-            }
+    get!(request, ctx, {
+        crate::metered!(request, "keys", "GET", {
+            let dir = get_keys_directory();
 
-            let public_key = read_pub_key(&dir, key_name)
-                .map_err(|e| YetiError::Internal(e))?;
+            // Single key by path ID
+            if let Some(key_name) = ctx.path_id() {
+                validate_identifier(key_name, "key name")?;
 
-            return reply().json(json!({
-                "name": key_name,
-                "public_key": public_key,
-            }));
-        }
-
-        // List all keys
-        let mut keys = Vec::new();
-        if dir.is_dir() {
-            let entries = std::fs::read_dir(&dir)
-                .map_err(|e| YetiError::Internal(format!("Cannot read keys dir: {}", e)))?;
-
-            for entry in entries.flatten() {
-                let file_name = entry.file_name().to_string_lossy().to_string();
-                if !file_name.ends_with(".pub") {
-                    continue;
+                let pub_path = dir.join(format!("{}.pub", key_name));
+                if !pub_path.exists() {
+                    return not_found(&format!("Key '{}' not found", key_name));
                 }
-                let name = file_name.strip_suffix(".pub").unwrap().to_string();
-
-                let public_key = read_pub_key(&dir, &name).unwrap_or_default();
 
-                let created = entry.metadata()
-                    .ok()
-                    .and_then(|m| m.created().ok())
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
+                let public_key = read_pub_key(&dir, key_name)
+                    .map_err(|e| YetiError::Internal(e))?;
 
-                keys.push(json!({
-                    "name": name,
+                let info = describe(&public_key);
+                let (last_used, apps) = usage_fields(&read_usage(), key_name);
+                return reply().json(json!({
+                    "name": key_name,
                     "public_key": public_key,
-                    "created": created,
+                    "type": info["type"],
+                    "fingerprint": info["fingerprint"],
+                    "last_used": last_used,
+                    "apps": apps,
+                    "defaultFor": defaulting_apps(&read_defaults(), key_name),
                 }));
             }
-        }
 
-        keys.sort_by(|a, b| {
-            let a_name = a["name"].as_str().unwrap_or("");
-            let b_name = b["name"].as_str().unwrap_or("");
-            a_name.cmp(b_name)
-        });
+            // List all keys
+            let usage = read_usage();
+            let defaults = read_defaults();
+            let mut keys = Vec::new();
+            if dir.is_dir() {
+                let entries = std::fs::read_dir(&dir)
+                    .map_err(|e| YetiError::Internal(format!("Cannot read keys dir: {}", e)))?;
+
+                for entry in entries.flatten() {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    if !file_name.ends_with(".pub") {
+                        continue;
+                    }
+                    let name = file_name.strip_suffix(".pub").unwrap().to_string();
+
+                    let public_key = read_pub_key(&dir, &name).unwrap_or_default();
+
+                    let created = entry.metadata()
+                        .ok()
+                        .and_then(|m| m.created().ok())
+                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+
+                    let info = describe(&public_key);
+                    let (last_used, apps) = usage_fields(&usage, &name);
+                    keys.push(json!({
+                        "name": name,
+                        "public_key": public_key,
+                        "type": info["type"],
+                        "fingerprint": info["fingerprint"],
+                        "created": created,
+                        "last_used": last_used,
+                        "apps": apps,
+                        "defaultFor": defaulting_apps(&defaults, &name),
+                    }));
+                }
+            }
 
-        reply().json(json!(keys))
+            keys.sort_by(|a, b| {
+                let a_name = a["name"].as_str().unwrap_or("");
+                let b_name = b["name"].as_str().unwrap_or("");
+                a_name.cmp(b_name)
+            });
+
+            reply().json(json!(keys))
+        })
     });
 
-    post!(request, _ctx, {
-        let body = request.json_value()?;
-        let name = body.require_str("name")?;
+    post!(request, ctx, {
+        crate::metered!(request, "keys", "POST", {
+            let body = request.json_value()?;
+            let name = body.require_str("name")?;
 
-        validate_identifier(&name, "key name")?;
+            validate_identifier(&name, "key name")?;
+            let key_type = body.get("type").and_then(|v| v.as_str()).unwrap_or("ed25519").to_string();
+            let Some((_, keygen_args)) = KEY_TYPES.iter().find(|(t, _)| *t == key_type) else {
+                let types: Vec<&str> = KEY_TYPES.iter().map(|(t, _)| *t).collect();
+                return bad_request(&format!("Unknown key type '{}'; use one of: {}", key_type, types.join(", ")));
+            };
 
-        let dir = ensure_get_keys_directory()
-            .map_err(|e| YetiError::Internal(e))?;
+            let dir = ensure_get_keys_directory()
+                .map_err(|e| YetiError::Internal(e))?;
 
-        let key_path = dir.join(&name);
-        let pub_path = dir.join(format!("{}.pub", name));
+            let key_path = dir.join(&name);
+            let pub_path = dir.join(format!("{}.pub", name));
 
-        if key_path.exists() || pub_path.exists() {
-            return bad_request(&format!("Key '{}' already exists", name));
-        }
+            if key_path.exists() || pub_path.exists() {
+                return bad_request(&format!("Key '{}' already exists", name));
+            }
 
-        // Generate ED25519 keypair via ssh-keygen
-        let output = std::process::Command::new("ssh-keygen")
-            .args([
-                "-t", "ed25519",
-                "-f", &key_path.to_string_lossy(),
-                "-N", "",
-                "-C", &format!("yeti-deploy-key-{}", name),
-            ])
-            .output()
-            .map_err(|e| YetiError::Internal(format!("Failed to run ssh-keygen: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(YetiError::Internal(format!("ssh-keygen failed: {}", stderr)));
-        }
+            // Generate the keypair via ssh-keygen
+            let output = std::process::Command::new("ssh-keygen")
+                .args(*keygen_args)
+                .args([
+                    "-f", &key_path.to_string_lossy(),
+                    "-N", "",
+                    "-C", &format!("yeti-deploy-key-{}", name),
+                ])
+                .output()
+                .map_err(|e| YetiError::Internal(format!("Failed to run ssh-keygen: {}", e)))?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(YetiError::Internal(format!("ssh-keygen failed: {}", stderr)));
+            }
 
-        // Set private key to 0600
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let _ = std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600));
-        }
+            // Set private key to 0600
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let _ = std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600));
+            }
+
+            let public_key = read_pub_key(&dir, &name)
+                .map_err(|e| YetiError::Internal(e))?;
 
-        let public_key = read_pub_key(&dir, &name)
-            .map_err(|e| YetiError::Internal(e))?;
+            crate::audit!(request, ctx, "key.create", &name, json!({"type": key_type}));
 
-        reply().code(201).json(json!({
-            "name": name,
-            "public_key": public_key,
-            "created": true,
-        }))
+            let info = describe(&public_key);
+            reply().code(201).json(json!({
+                "name": name,
+                "public_key": public_key,
+                "type": key_type,
+                "fingerprint": info["fingerprint"],
+                "created": true,
+            }))
+        })
     });
 
-    delete!(_request, ctx, {
-        let key_name = ctx.require_id()?.to_string();
+    delete!(request, ctx, {
+        crate::metered!(request, "keys", "DELETE", {
+            let key_name = ctx.require_id()?.to_string();
 
-        validate_identifier(&key_name, "key name")?;
+            validate_identifier(&key_name, "key name")?;
 
-        let dir = get_keys_directory();
-        let key_path = dir.join(&key_name);
-        let pub_path = dir.join(format!("{}.pub", &key_name));
+            let dir = get_keys_directory();
+            let key_path = dir.join(&key_name);
+            let pub_path = dir.join(format!("{}.pub", &key_name));
 
-        if !key_path.exists() && !pub_path.exists() {
-            return not_found(&format!("Key '{}' not found", key_name));
-        }
+            if !key_path.exists() && !pub_path.exists() {
+                return not_found(&format!("Key '{}' not found", key_name));
+            }
 
-        // Remove both private and public key files
-        if key_path.exists() {
-            std::fs::remove_file(&key_path)
-                .map_err(|e| YetiError::Internal(format!("Failed to remove private key: {}", e)))?;
-        }
-        if pub_path.exists() {
-            std::fs::remove_file(&pub_path)
-                .map_err(|e| YetiError::Internal(format!("Failed to remove public key: {}", e)))?;
-        }
+            let default_for = defaulting_apps(&read_defaults(), &key_name);
+            let force = parse_query_param(request.uri().query().unwrap_or(""), "force").as_deref() == Some("true");
+            if !default_for.is_empty() && !force {
+                return reply().code(409).json(json!({
+                    "error": format!("Key '{}' is the default key of some apps; retry with ?force=true", key_name),
+                    "defaultFor": default_for,
+                }));
+            }
+
+            // Remove both private and public key files
+            if key_path.exists() {
+                std::fs::remove_file(&key_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to remove private key: {}", e)))?;
+            }
+            if pub_path.exists() {
+                std::fs::remove_file(&pub_path)
+                    .map_err(|e| YetiError::Internal(format!("Failed to remove public key: {}", e)))?;
+            }
+
+            if let Err(e) = modify_usage(|usage| {
+                usage.remove(&key_name);
+            }) {
+                yeti_log!(warn, "Failed to clear usage of key '{}': {}", key_name, e);
+            }
+            if let Err(e) = modify_defaults(|defaults| defaults.retain(|_, k| *k != key_name.as_str())) {
+                yeti_log!(warn, "Failed to clear key '{}' as an app default: {}", key_name, e);
+            }
+
+            crate::audit!(request, ctx, "key.delete", &key_name, json!({}));
 
-        reply().json(json!({"deleted": true, "name": key_name}))
+            reply().json(json!({"deleted": true, "name": key_name}))
+        })
     });
 }
 
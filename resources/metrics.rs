@@ -0,0 +1,208 @@
+//! Admin Metrics Resource
+//!
+//! Prometheus metrics for the admin plane itself, so it can be scraped and
+//! alerted on like any other service.
+//!
+//! | Method | Path           | Description                          |
+//! |--------|----------------|--------------------------------------|
+//! | GET    | /admin/metrics | Metrics in Prometheus text format    |
+//!
+//! | Metric                                       | Type      | Labels                 |
+//! |----------------------------------------------|-----------|------------------------|
+//! | `yeti_admin_requests_total`                  | counter   | `resource`, `method`   |
+//! | `yeti_admin_request_errors_total`            | counter   | `resource`, `method`   |
+//! | `yeti_admin_request_duration_seconds`        | histogram | `resource`             |
+//! | `yeti_admin_git_operation_duration_seconds`  | histogram | `operation`            |
+//! | `yeti_admin_git_operation_failures_total`    | counter   | `operation`            |
+//! | `yeti_admin_file_operations_total`           | counter   | `operation`            |
+//! | `yeti_admin_background_jobs`                 | gauge     | `kind`                 |
+//! | `yeti_admin_managed_processes`               | gauge     | `kind`                 |
+//!
+//! Handlers are wrapped in `metered!`, which counts the request, times it and
+//! counts it as an error when the handler returns `Err` (or the session was
+//! revoked, see `sessions`):
+//!
+//! ```ignore
+//! get!(request, ctx, {
+//!     crate::metered!(request, "apps", "GET", {
+//!         ...
+//!     })
+//! });
+//! ```
+//!
+//! Counters live in memory and reset when the server restarts.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use yeti_core::prelude::*;
+
+pub type Metrics = MetricsResource;
+
+/// Histogram bucket upper bounds, in seconds
+const BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+const HELP: &[(&str, &str, &str)] = &[
+    ("yeti_admin_requests_total", "counter", "Admin API requests handled"),
+    ("yeti_admin_request_errors_total", "counter", "Admin API requests that returned an error"),
+    ("yeti_admin_request_duration_seconds", "histogram", "Admin API request duration"),
+    ("yeti_admin_git_operation_duration_seconds", "histogram", "Duration of git commands run by the admin"),
+    ("yeti_admin_git_operation_failures_total", "counter", "Git commands that failed"),
+    ("yeti_admin_file_operations_total", "counter", "File browser operations"),
+    ("yeti_admin_background_jobs", "gauge", "Background jobs currently running"),
+    ("yeti_admin_managed_processes", "gauge", "Live child processes spawned by the admin"),
+];
+
+/// A metric series: name plus rendered label set
+type Series = (&'static str, String);
+
+#[derive(Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<Series, u64>,
+    histograms: BTreeMap<Series, Histogram>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Count a request to `resource`, its duration, and whether it failed.
+/// Also records the caller's session and answers 401 if it was revoked.
+/// Wrap a handler body in `metered!` rather than calling this directly.
+#[macro_export]
+macro_rules! metered {
+    ($request:expr, $resource:expr, $method:expr, { $($body:tt)* }) => {{
+        let started = std::time::Instant::now();
+        if let Err(e) = $crate::session_check!($request) {
+            $crate::metrics::record_request($resource, $method, false, started.elapsed());
+            return reply().code(401).json(json!({"error": e}));
+        }
+        let result = async { $($body)* }.await;
+        $crate::metrics::record_request($resource, $method, result.is_ok(), started.elapsed());
+        result
+    }};
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn inc(name: &'static str, pairs: &[(&str, &str)]) {
+    *registry().lock().unwrap().counters.entry((name, labels(pairs))).or_default() += 1;
+}
+
+pub fn observe(name: &'static str, pairs: &[(&str, &str)], elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    let mut registry = registry().lock().unwrap();
+    let histogram = registry.histograms.entry((name, labels(pairs))).or_default();
+    if histogram.buckets.is_empty() {
+        histogram.buckets = vec![0; BUCKETS.len()];
+    }
+    for (i, bound) in BUCKETS.iter().enumerate() {
+        if secs <= *bound {
+            histogram.buckets[i] += 1;
+        }
+    }
+    histogram.sum += secs;
+    histogram.count += 1;
+}
+
+pub fn record_request(resource: &str, method: &str, ok: bool, elapsed: Duration) {
+    inc("yeti_admin_requests_total", &[("resource", resource), ("method", method)]);
+    if !ok {
+        inc("yeti_admin_request_errors_total", &[("resource", resource), ("method", method)]);
+    }
+    observe("yeti_admin_request_duration_seconds", &[("resource", resource)], elapsed);
+}
+
+pub fn record_git(operation: &str, ok: bool, elapsed: Duration) {
+    observe("yeti_admin_git_operation_duration_seconds", &[("operation", operation)], elapsed);
+    if !ok {
+        inc("yeti_admin_git_operation_failures_total", &[("operation", operation)]);
+    }
+}
+
+pub fn record_file(operation: &str) {
+    inc("yeti_admin_file_operations_total", &[("operation", operation)]);
+}
+
+fn render() -> String {
+    let mut out = String::new();
+    // Gauges are sampled from the modules that own the jobs
+    let jobs = [
+        ("build", crate::builds::running_builds()),
+        ("benchmark", usize::from(crate::benchmarks::runner_busy())),
+        ("cron", crate::cron::running_tasks()),
+        ("webhook_delivery", crate::webhooks::pending_deliveries()),
+    ];
+    let processes = crate::processes::counts_by_kind();
+    let registry = registry().lock().unwrap();
+
+    for (name, kind, help) in HELP {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        match *name {
+            "yeti_admin_background_jobs" => {
+                for (job, value) in jobs {
+                    let _ = writeln!(out, "{}{{{}}} {}", name, labels(&[("kind", job)]), value);
+                }
+            }
+            "yeti_admin_managed_processes" => {
+                for (process, count) in &processes {
+                    let _ = writeln!(out, "{}{{{}}} {}", name, labels(&[("kind", process)]), count);
+                }
+            }
+            _ if *kind == "histogram" => {
+                for ((_, set), h) in registry.histograms.range((*name, String::new())..).take_while(|((n, _), _)| n == name) {
+                    let sep = if set.is_empty() { "" } else { "," };
+                    for (bound, count) in BUCKETS.iter().zip(&h.buckets) {
+                        let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, set, sep, bound, count);
+                    }
+                    let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, set, sep, h.count);
+                    let _ = writeln!(out, "{}_sum{{{}}} {}", name, set, h.sum);
+                    let _ = writeln!(out, "{}_count{{{}}} {}", name, set, h.count);
+                }
+            }
+            _ => {
+                for ((_, set), value) in registry.counters.range((*name, String::new())..).take_while(|((n, _), _)| n == name) {
+                    let _ = writeln!(out, "{}{{{}}} {}", name, set, value);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+pub struct MetricsResource;
+
+impl Resource for MetricsResource {
+    fn name(&self) -> &str {
+        "metrics"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "metrics", "GET", {
+            reply().text(render())
+        })
+    });
+}
+
+register_resource!(MetricsResource);
@@ -0,0 +1,356 @@
+//! Notification Center Resource
+//!
+//! One place for the problems the dashboard should surface: alerts raised by
+//! other subsystems, with severity, read/unread state and dismissal.
+//!
+//! | Method | Path                                  | Description                        |
+//! |--------|---------------------------------------|------------------------------------|
+//! | GET    | /admin/notifications                  | Open notifications, newest first   |
+//! | GET    | /admin/notifications?unread=true      | ...unread only                     |
+//! | GET    | /admin/notifications?severity=critical| ...of one severity                 |
+//! | GET    | /admin/notifications?all=true         | Including dismissed                |
+//! | PUT    | /admin/notifications/{id}             | Mark read/unread (`{"read": true}`)|
+//! | PUT    | /admin/notifications                  | Mark all read                      |
+//! | DELETE | /admin/notifications/{id}             | Dismiss                            |
+//! | DELETE | /admin/notifications                  | Dismiss every read notification    |
+//!
+//! Sources:
+//!
+//! | Kind                   | Severity          | Raised by                            |
+//! |------------------------|-------------------|--------------------------------------|
+//! | `benchmark.regression` | warning           | A run failing its SLA criteria       |
+//! | `schedule.failed`      | warning           | A failed scheduled task (e.g. pull)  |
+//! | `disk.low`             | warning, critical | Root filesystem ≥ 90% / ≥ 97% used   |
+//! | `cert.expiring`        | warning, critical | Certificate expiring in 30 days / expired |
+//! | `key.old`              | info              | SSH key older than a year            |
+//!
+//! Disk, certificate (`*.crt` / `*.pem` in the keys directory and `certs/` in the
+//! root directory) and key checks run every `CHECK_INTERVAL_SECS` from the
+//! scheduler and when notifications are listed. A notification that is raised
+//! again while still open is updated in place (`count`, `updatedAt`) and marked
+//! unread. Notifications are kept in `admin-notifications.json` in the root
+//! directory, up to `MAX_NOTIFICATIONS`.
+
+use chrono::{NaiveDateTime, Utc};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use yeti_core::prelude::*;
+
+pub type Notifications = NotificationsResource;
+
+const NOTIFICATIONS_FILE: &str = "admin-notifications.json";
+const MAX_NOTIFICATIONS: usize = 500;
+const CHECK_INTERVAL_SECS: f64 = 600.0;
+const DISK_WARNING_PERCENT: f64 = 90.0;
+const DISK_CRITICAL_PERCENT: f64 = 97.0;
+const CERT_WARNING_DAYS: f64 = 30.0;
+const KEY_MAX_AGE_DAYS: f64 = 365.0;
+const SEVERITIES: &[&str] = &["info", "warning", "critical"];
+
+fn store() -> &'static Mutex<Vec<serde_json::Value>> {
+    static STORE: OnceLock<Mutex<Vec<serde_json::Value>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(read_notifications()))
+}
+
+fn notifications_path() -> PathBuf {
+    get_root_directory().join(NOTIFICATIONS_FILE)
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn read_notifications() -> Vec<serde_json::Value> {
+    std::fs::read_to_string(notifications_path())
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+}
+
+/// Write via a temp file and rename so a crash never leaves a partial file
+fn write_notifications(notifications: &[serde_json::Value]) -> std::result::Result<(), String> {
+    let path = notifications_path();
+    let tmp = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(notifications).map_err(|e| format!("Failed to serialize notifications: {}", e))?;
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", NOTIFICATIONS_FILE, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", NOTIFICATIONS_FILE, e))
+}
+
+/// Read-modify-write under the store lock
+fn modify<T>(f: impl FnOnce(&mut Vec<serde_json::Value>) -> T) -> std::result::Result<T, String> {
+    let mut notifications = store().lock().unwrap();
+    let result = f(&mut notifications);
+    write_notifications(&notifications)?;
+    Ok(result)
+}
+
+fn is_open(n: &serde_json::Value) -> bool {
+    !n["dismissed"].as_bool().unwrap_or(false)
+}
+
+/// Raise a notification, or refresh the open one with the same kind and target
+pub fn notify(kind: &str, severity: &str, target: &str, title: &str, message: &str) {
+    let now = now_secs();
+    let result = modify(|notifications| {
+        if let Some(n) = notifications.iter_mut().find(|n| is_open(n) && n["kind"] == kind && n["target"] == target) {
+            n["severity"] = json!(severity);
+            n["title"] = json!(title);
+            n["message"] = json!(message);
+            n["updatedAt"] = json!(now);
+            n["count"] = json!(n["count"].as_u64().unwrap_or(1) + 1);
+            n["read"] = json!(false);
+            return;
+        }
+        notifications.push(json!({
+            "id": format!("{:x}{:04x}", (now * 1000.0) as u64, notifications.len() % 0x10000),
+            "kind": kind,
+            "severity": severity,
+            "target": target,
+            "title": title,
+            "message": message,
+            "createdAt": now,
+            "updatedAt": now,
+            "count": 1,
+            "read": false,
+            "dismissed": false,
+        }));
+        // Drop the oldest, dismissed ones first
+        while notifications.len() > MAX_NOTIFICATIONS {
+            let victim = notifications.iter().position(|n| !is_open(n)).unwrap_or(0);
+            notifications.remove(victim);
+        }
+    });
+    if let Err(e) = result {
+        yeti_log!(warn, "Failed to record notification '{}': {}", kind, e);
+    }
+}
+
+/// Dismiss the open notification of a kind and target once the problem is gone
+pub fn resolve(kind: &str, target: &str) {
+    let open = store().lock().unwrap().iter().any(|n| is_open(n) && n["kind"] == kind && n["target"] == target);
+    if !open {
+        return;
+    }
+    let _ = modify(|notifications| {
+        for n in notifications.iter_mut().filter(|n| is_open(n) && n["kind"] == kind && n["target"] == target) {
+            n["dismissed"] = json!(true);
+            n["updatedAt"] = json!(now_secs());
+        }
+    });
+}
+
+// ── Periodic checks ──
+
+fn check_disk() {
+    let disk = crate::system::disk(&get_root_directory());
+    let Some(used) = disk["usedPercent"].as_f64() else { return };
+    let path = disk["path"].as_str().unwrap_or("");
+    let severity = if used >= DISK_CRITICAL_PERCENT {
+        "critical"
+    } else if used >= DISK_WARNING_PERCENT {
+        "warning"
+    } else {
+        return resolve("disk.low", path);
+    };
+    let available_gb = disk["availableBytes"].as_f64().unwrap_or(0.0) / 1e9;
+    notify("disk.low", severity, path, "Low disk space",
+        &format!("{:.1}% used, {:.1} GB available on {}", used, available_gb, path));
+}
+
+/// `notAfter` of a certificate file, as Unix seconds
+fn cert_not_after(path: &std::path::Path) -> Option<f64> {
+    let output = std::process::Command::new("openssl")
+        .args(["x509", "-enddate", "-noout", "-in"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let date = stdout.trim().strip_prefix("notAfter=")?;
+    let date = date.trim_end_matches(" GMT").split_whitespace().collect::<Vec<_>>().join(" ");
+    NaiveDateTime::parse_from_str(&date, "%b %d %H:%M:%S %Y")
+        .ok()
+        .map(|d| d.and_utc().timestamp() as f64)
+}
+
+fn check_certs(now: f64) {
+    let dirs = [get_keys_directory(), get_root_directory().join("certs")];
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.extension().is_some_and(|ext| ext == "crt" || ext == "pem") {
+                continue;
+            }
+            // Private keys and other PEM files have no notAfter
+            let Some(not_after) = cert_not_after(&path) else { continue };
+            let target = path.to_string_lossy().to_string();
+            let days = (not_after - now) / 86400.0;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if days <= 0.0 {
+                notify("cert.expiring", "critical", &target, "Certificate expired",
+                    &format!("{} expired {:.0} days ago", name, -days));
+            } else if days <= CERT_WARNING_DAYS {
+                notify("cert.expiring", "warning", &target, "Certificate expiring",
+                    &format!("{} expires in {:.0} days", name, days));
+            } else {
+                resolve("cert.expiring", &target);
+            }
+        }
+    }
+}
+
+fn check_keys(now: f64) {
+    let Ok(entries) = std::fs::read_dir(get_keys_directory()) else { return };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file_name.strip_suffix(".pub") else { continue };
+        let Some(created) = entry.metadata()
+            .ok()
+            .and_then(|m| m.created().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64())
+        else {
+            continue;
+        };
+        let days = (now - created) / 86400.0;
+        if days > KEY_MAX_AGE_DAYS {
+            notify("key.old", "info", name, "SSH key due for rotation",
+                &format!("Deploy key '{}' was created {:.0} days ago", name, days));
+        }
+    }
+}
+
+/// Run the disk, certificate and key checks, at most every `CHECK_INTERVAL_SECS`
+pub fn run_checks() {
+    static LAST_CHECK: Mutex<f64> = Mutex::new(0.0);
+    let now = now_secs();
+    {
+        let mut last = LAST_CHECK.lock().unwrap();
+        if now - *last < CHECK_INTERVAL_SECS {
+            return;
+        }
+        *last = now;
+    }
+    check_disk();
+    check_certs(now);
+    check_keys(now);
+}
+
+#[derive(Default)]
+pub struct NotificationsResource;
+
+impl Resource for NotificationsResource {
+    fn name(&self) -> &str {
+        "notifications"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "notifications", "GET", {
+            tokio::task::spawn_blocking(run_checks)
+                .await
+                .map_err(|e| YetiError::Internal(format!("Checks failed: {}", e)))?;
+
+            let query = request.uri().query().unwrap_or("");
+            let flag = |key: &str| parse_query_param(query, key).is_some_and(|v| v == "true");
+            let (all, unread_only) = (flag("all"), flag("unread"));
+            let severity = parse_query_param(query, "severity");
+            if let Some(s) = &severity {
+                if !SEVERITIES.contains(&s.as_str()) {
+                    return bad_request(&format!("Unknown severity '{}' (use {})", s, SEVERITIES.join(", ")));
+                }
+            }
+
+            let notifications = store().lock().unwrap();
+            let unread = notifications.iter().filter(|n| is_open(n) && n["read"] == false).count();
+            let mut list: Vec<serde_json::Value> = notifications
+                .iter()
+                .filter(|n| all || is_open(n))
+                .filter(|n| !unread_only || n["read"] == false)
+                .filter(|n| severity.as_ref().is_none_or(|s| n["severity"] == s.as_str()))
+                .cloned()
+                .collect();
+            list.sort_by(|a, b| b["updatedAt"].as_f64().partial_cmp(&a["updatedAt"].as_f64())
+                .unwrap_or(std::cmp::Ordering::Equal));
+
+            reply().json(json!({
+                "unread": unread,
+                "notifications": list,
+            }))
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "notifications", "PUT", {
+            let body = request.json_value().unwrap_or_else(|_| json!({}));
+            let read = body.get("read").and_then(|v| v.as_bool()).unwrap_or(true);
+
+            let Some(id) = ctx.path_id() else {
+                let updated = modify(|notifications| {
+                    let mut updated = 0;
+                    for n in notifications.iter_mut().filter(|n| is_open(n) && n["read"] != read) {
+                        n["read"] = json!(read);
+                        updated += 1;
+                    }
+                    updated
+                })
+                .map_err(YetiError::Internal)?;
+                return reply().json(json!({"updated": updated}));
+            };
+
+            let id = id.to_string();
+            let notification = modify(|notifications| {
+                notifications.iter_mut().find(|n| n["id"] == id.as_str()).map(|n| {
+                    n["read"] = json!(read);
+                    n.clone()
+                })
+            })
+            .map_err(YetiError::Internal)?;
+            match notification {
+                Some(n) => reply().json(n),
+                None => not_found(&format!("Notification '{}' not found", id)),
+            }
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "notifications", "DELETE", {
+            let now = now_secs();
+            let Some(id) = ctx.path_id() else {
+                let dismissed = modify(|notifications| {
+                    let mut dismissed = 0;
+                    for n in notifications.iter_mut().filter(|n| is_open(n) && n["read"] == true) {
+                        n["dismissed"] = json!(true);
+                        n["updatedAt"] = json!(now);
+                        dismissed += 1;
+                    }
+                    dismissed
+                })
+                .map_err(YetiError::Internal)?;
+                return reply().json(json!({"dismissed": dismissed}));
+            };
+
+            let id = id.to_string();
+            let found = modify(|notifications| {
+                notifications.iter_mut().find(|n| n["id"] == id.as_str()).map(|n| {
+                    n["dismissed"] = json!(true);
+                    n["updatedAt"] = json!(now);
+                })
+            })
+            .map_err(YetiError::Internal)?;
+            if found.is_none() {
+                return not_found(&format!("Notification '{}' not found", id));
+            }
+            reply().json(json!({
+                "id": id,
+                "dismissed": true,
+            }))
+        })
+    });
+}
+
+register_resource!(NotificationsResource);
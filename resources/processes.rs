@@ -0,0 +1,221 @@
+//! Managed Process Resource
+//!
+//! Inventory of the child processes the admin has spawned (benchmark
+//! binaries, cargo builds, git operations), with a way to terminate them.
+//!
+//! | Method | Path                              | Description                 |
+//! |--------|-----------------------------------|-----------------------------|
+//! | GET    | /admin/processes                  | List live managed processes |
+//! | DELETE | /admin/processes/{pid}            | SIGTERM a managed process   |
+//! | DELETE | /admin/processes/{pid}?force=true | SIGKILL instead             |
+//!
+//! Only processes in the inventory can be terminated. Resources spawn through
+//! `spawn` / `output` so every child is registered, and entries are dropped
+//! when the child is reaped (or found dead on listing). Listed command lines
+//! have URL credentials and the values of `-u`/`-H`-style options redacted.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output};
+use std::sync::{Mutex, OnceLock};
+use yeti_core::prelude::*;
+
+pub type Processes = ProcessesResource;
+
+#[derive(Clone)]
+struct ManagedProcess {
+    kind: String, // "benchmark", "build", "git", "cron", "webhook", "instance", "extension", "exec"
+    command: String,
+    started_at: f64,
+    owner: String,
+}
+
+fn inventory() -> &'static Mutex<BTreeMap<u32, ManagedProcess>> {
+    static INVENTORY: OnceLock<Mutex<BTreeMap<u32, ManagedProcess>>> = OnceLock::new();
+    INVENTORY.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Options whose value may be a credential
+const SECRET_OPTIONS: &[&str] = &["-u", "--user", "-H", "--header", "--password", "--token"];
+const REDACTED: &str = "[redacted]";
+
+/// Command line for the inventory and audit log, with URL credentials and the
+/// values of `SECRET_OPTIONS` (separate or `--opt=value`) redacted
+fn describe(cmd: &Command) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut hide_next = false;
+    for arg in std::iter::once(cmd.get_program()).chain(cmd.get_args()) {
+        let arg = arg.to_string_lossy();
+        if std::mem::take(&mut hide_next) {
+            words.push(REDACTED.to_string());
+            continue;
+        }
+        if SECRET_OPTIONS.contains(&arg.as_ref()) {
+            hide_next = true;
+            words.push(arg.to_string());
+        } else if let Some((option, _)) = arg.split_once('=').filter(|(o, _)| SECRET_OPTIONS.contains(o)) {
+            words.push(format!("{}={}", option, REDACTED));
+        } else {
+            words.push(crate::repos::redact_url(&arg));
+        }
+    }
+    words.join(" ")
+}
+
+/// Spawn `cmd` and record it in the inventory; call `reaped` once it has
+/// been waited on.
+pub fn spawn(kind: &str, owner: &str, cmd: &mut Command) -> std::io::Result<Child> {
+    let child = cmd.spawn()?;
+    inventory().lock().unwrap().insert(child.id(), ManagedProcess {
+        kind: kind.to_string(),
+        command: describe(cmd),
+        started_at: now_secs(),
+        owner: owner.to_string(),
+    });
+    Ok(child)
+}
+
+/// Run `cmd` to completion, listed in the inventory while it runs.
+pub fn output(kind: &str, owner: &str, cmd: &mut Command) -> std::io::Result<Output> {
+    cmd.stdout(std::process::Stdio::piped()).stderr(std::process::Stdio::piped());
+    let child = spawn(kind, owner, cmd)?;
+    let pid = child.id();
+    let output = child.wait_with_output();
+    reaped(pid);
+    output
+}
+
+/// Create `path` readable by the server user only (0600 from the start, never
+/// briefly world-readable); fails if it already exists.
+pub fn create_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// A new, uniquely named 0600 file under `cache/{dir}` holding an
+/// `Authorization` header for curl's `-H @file`, so credentials never appear
+/// on a command line. The caller removes it after use.
+pub fn header_file(dir: &str, authorization: &str) -> std::result::Result<PathBuf, String> {
+    let dir = get_cache_directory().join(dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    let path = dir.join(format!("{}.headers", crate::webhooks::random_hex(8)?));
+    create_private(&path, format!("Authorization: {}\n", authorization).as_bytes())
+        .map_err(|e| format!("Failed to write headers: {}", e))?;
+    Ok(path)
+}
+
+/// Drop a process from the inventory after it has exited.
+pub fn reaped(pid: u32) {
+    inventory().lock().unwrap().remove(&pid);
+}
+
+/// Number of inventoried processes per kind
+pub fn counts_by_kind() -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for process in inventory().lock().unwrap().values() {
+        *counts.entry(process.kind.clone()).or_default() += 1;
+    }
+    counts
+}
+
+/// Whether a process with this pid exists (`kill -0`).
+pub fn is_alive(pid: u32) -> bool {
+    signal(pid, "-0")
+}
+
+/// Send SIGTERM (or SIGKILL with `force`); returns whether it was delivered.
+pub fn terminate(pid: u32, force: bool) -> bool {
+    signal(pid, if force { "-KILL" } else { "-TERM" })
+}
+
+fn signal(pid: u32, sig: &str) -> bool {
+    Command::new("kill")
+        .arg(sig)
+        .arg(pid.to_string())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[derive(Default)]
+pub struct ProcessesResource;
+
+impl Resource for ProcessesResource {
+    fn name(&self) -> &str {
+        "processes"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "processes", "GET", {
+            let mut inventory = inventory().lock().unwrap();
+            inventory.retain(|pid, _| is_alive(*pid));
+
+            let now = now_secs();
+            let processes: Vec<serde_json::Value> = inventory
+                .iter()
+                .map(|(pid, p)| json!({
+                    "pid": pid,
+                    "kind": p.kind,
+                    "command": p.command,
+                    "startedAt": p.started_at,
+                    "runningSecs": now - p.started_at,
+                    "owner": p.owner,
+                }))
+                .collect();
+
+            reply().json(json!(processes))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "processes", "DELETE", {
+            let pid: u32 = ctx.require_id()?
+                .parse()
+                .map_err(|_| YetiError::Validation("pid must be a number".to_string()))?;
+            let query = request.uri().query().unwrap_or("");
+            let force = parse_query_param(query, "force").is_some_and(|v| v == "true");
+
+            let process = inventory().lock().unwrap().get(&pid).cloned();
+            let Some(process) = process else {
+                return not_found(&format!("No managed process with pid {}", pid));
+            };
+            if !terminate(pid, force) {
+                reaped(pid);
+                return not_found(&format!("Process {} has already exited", pid));
+            }
+
+            yeti_log!(info, "Terminated {} process {} ({}){}", process.kind, pid, process.command,
+                if force { " with SIGKILL" } else { "" });
+            crate::audit!(request, ctx, "process.terminate", &pid.to_string(), json!({
+                "kind": process.kind,
+                "command": process.command,
+                "force": force,
+            }));
+
+            reply().json(json!({
+                "pid": pid,
+                "kind": process.kind,
+                "signal": if force { "SIGKILL" } else { "SIGTERM" },
+                "terminated": true,
+            }))
+        })
+    });
+}
+
+register_resource!(ProcessesResource);
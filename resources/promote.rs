@@ -0,0 +1,316 @@
+//! App Promotion Resource
+//!
+//! Copies an app to another Yeti instance (dev → staging → prod) without
+//! shuffling archives by hand. The sending admin packs the app and POSTs it to
+//! the receiving admin's `/admin/promote`.
+//!
+//! | Method | Path                        | Description                            |
+//! |--------|-----------------------------|----------------------------------------|
+//! | POST   | /admin/promote/{app_id}     | Push an app to a remote admin          |
+//! | POST   | /admin/promote              | Receive a pushed app (remote side)     |
+//!
+//! Push body: `{"url": "https://staging:9996", "token": "...", "includeData":
+//! false, "overwrite": false}`, or `{"instance": "staging", ...}` for an
+//! instance registered under `/admin/instances` (its `caCert` / `insecure`
+//! apply). The remote certificate is verified unless an explicit `url` comes
+//! with `"insecure": true`. The archive is a tar.gz of the app directory
+//! without `target/`, `node_modules/` and `.git/`. With `includeData` every
+//! table declared by the app's schema is sent along and written on the remote
+//! once the app is installed.
+//!
+//! Receive body: `{"app_id": "...", "archive": "<base64 tar.gz>", "data":
+//! {"Table": [records]}, "overwrite": false}`. Archives may only contain paths
+//! under `{app_id}/`. An existing app is replaced only with `overwrite`; the
+//! archive is unpacked into a hidden staging directory, swapped in, and the
+//! app reloaded.
+
+use base64::Engine;
+use std::path::{Path, PathBuf};
+use yeti_core::prelude::*;
+
+pub type Promote = PromoteResource;
+
+const EXCLUDES: &[&str] = &["target", "node_modules", ".git"];
+const TIMEOUT_SECS: &str = "300";
+
+/// tar.gz of an app directory without build artifacts and git history
+pub fn archive_app(app_id: &str, owner: &str) -> std::result::Result<Vec<u8>, String> {
+    let apps_dir = get_apps_directory();
+    if !apps_dir.join(app_id).is_dir() {
+        return Err(format!("Application '{}' not found", app_id));
+    }
+    let mut cmd = std::process::Command::new("tar");
+    cmd.arg("-czf").arg("-");
+    for exclude in EXCLUDES {
+        cmd.arg(format!("--exclude={}/{}", app_id, exclude));
+    }
+    cmd.arg("-C").arg(&apps_dir).arg(app_id);
+
+    let output = crate::processes::output("archive", owner, &mut cmd)
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// Every entry must live under `{app_id}/` and must not climb out of it
+pub fn check_entries(archive: &Path, app_id: &str, owner: &str) -> std::result::Result<(), String> {
+    let output = crate::processes::output("archive", owner, std::process::Command::new("tar").arg("-tzf").arg(archive))
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !output.status.success() {
+        return Err("Archive is not a valid tar.gz".to_string());
+    }
+    let listing = String::from_utf8_lossy(&output.stdout);
+    for entry in listing.lines() {
+        let inside = entry == app_id || entry.starts_with(&format!("{}/", app_id));
+        if !inside || entry.split('/').any(|part| part == "..") {
+            return Err(format!("Archive entry '{}' is outside '{}/'", entry, app_id));
+        }
+    }
+    Ok(())
+}
+
+/// Unpack a pushed archive into the apps directory, replacing an existing
+/// app only if `overwrite`
+pub fn install_archive(app_id: &str, archive: &[u8], overwrite: bool, owner: &str) -> std::result::Result<bool, String> {
+    let apps_dir = get_apps_directory();
+    let target = apps_dir.join(app_id);
+    let existed = target.exists();
+    if existed && !overwrite {
+        return Err(format!("Application '{}' already exists (set overwrite to replace it)", app_id));
+    }
+
+    // Staged inside the apps directory (hidden) so the swap is a plain rename
+    let staging = apps_dir.join(format!(".promote-{}", app_id));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(&staging).map_err(|e| format!("Failed to create staging dir: {}", e))?;
+    let result = (|| {
+        let archive_path = staging.join("app.tar.gz");
+        std::fs::write(&archive_path, archive).map_err(|e| format!("Failed to write archive: {}", e))?;
+        check_entries(&archive_path, app_id, owner)?;
+
+        let unpacked = staging.join("unpacked");
+        std::fs::create_dir_all(&unpacked).map_err(|e| format!("Failed to create staging dir: {}", e))?;
+        let output = crate::processes::output("archive", owner, std::process::Command::new("tar")
+            .arg("-xzf").arg(&archive_path)
+            .arg("--no-same-owner")
+            .arg("-C").arg(&unpacked))
+            .map_err(|e| format!("Failed to run tar: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let new_app = unpacked.join(app_id);
+        if !new_app.join("config.yaml").is_file() {
+            return Err("Archive has no config.yaml".to_string());
+        }
+
+        // Keep the old tree until the new one is in place
+        let previous = staging.join("previous");
+        if existed {
+            std::fs::rename(&target, &previous).map_err(|e| format!("Failed to move existing app: {}", e))?;
+        }
+        if let Err(e) = std::fs::rename(&new_app, &target) {
+            if existed {
+                let _ = std::fs::rename(&previous, &target);
+            }
+            return Err(format!("Failed to install app: {}", e));
+        }
+        Ok(existed)
+    })();
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Send the promotion payload (via a temp file, it can be large)
+fn push(
+    url: &str,
+    authorization: &str,
+    tls: &[String],
+    payload: &serde_json::Value,
+    owner: &str,
+) -> std::result::Result<(u16, String), String> {
+    let dir = get_cache_directory().join("promote");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    let payload_path: PathBuf = dir.join(format!("{}.json", crate::webhooks::random_hex(8)?));
+    crate::processes::create_private(&payload_path, payload.to_string().as_bytes())
+        .map_err(|e| format!("Failed to write payload: {}", e))?;
+    // Credentials go in a header file so they never show up in the process list
+    let header_path = match crate::processes::header_file("promote", authorization) {
+        Ok(path) => path,
+        Err(e) => {
+            let _ = std::fs::remove_file(&payload_path);
+            return Err(e);
+        }
+    };
+
+    let endpoint = format!("{}/admin/promote", url.trim_end_matches('/'));
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["-s", "--max-time", TIMEOUT_SECS, "-X", "POST", "-w", "\n%{http_code}"])
+        .args(tls)
+        .args(["-H", "Content-Type: application/json"])
+        .arg("-H").arg(format!("@{}", header_path.display()))
+        .arg("--data-binary").arg(format!("@{}", payload_path.display()))
+        .arg(&endpoint);
+    let output = crate::processes::output("promote", owner, &mut cmd);
+    let _ = std::fs::remove_file(&payload_path);
+    let _ = std::fs::remove_file(&header_path);
+    let output = output.map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("{} unreachable (curl exit code {})", endpoint, output.status.code().unwrap_or(-1)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", stdout.as_ref()));
+    let code = code.trim().parse().map_err(|_| "No HTTP status in response".to_string())?;
+    Ok((code, body.to_string()))
+}
+
+#[derive(Default)]
+pub struct PromoteResource;
+
+impl Resource for PromoteResource {
+    fn name(&self) -> &str {
+        "promote"
+    }
+
+    post!(request, ctx, {
+        crate::metered!(request, "promote", "POST", {
+            let body = request.json_value()?;
+            let owner = crate::actor!(request);
+
+            // POST /admin/promote/{app_id}: push to a remote admin
+            if let Some(app_id) = ctx.path_id() {
+                let app_id = app_id.to_string();
+                validate_identifier(&app_id, "app_id")?;
+                // Either a registered instance or an explicit url + token
+                let (url, authorization, tls) = if let Some(instance) = body.get("instance").and_then(|v| v.as_str()) {
+                    let Some(record) = ctx.get_table("Instance")?.get_by_id(instance).await? else {
+                        return not_found(&format!("Instance '{}' not found", instance));
+                    };
+                    let Some(authorization) = crate::instances::authorization(instance) else {
+                        return bad_request(&format!("Instance '{}' has no credentials", instance));
+                    };
+                    (record["url"].as_str().unwrap_or("").to_string(), authorization, crate::instances::tls_args(&record))
+                } else {
+                    let url = body.require_str("url")?;
+                    if !url.starts_with("https://") && !url.starts_with("http://") {
+                        return bad_request("'url' must start with http:// or https://");
+                    }
+                    let insecure = body.get("insecure").and_then(|v| v.as_bool()).unwrap_or(false);
+                    let tls = if insecure { vec!["-k".to_string()] } else { Vec::new() };
+                    (url, format!("Bearer {}", body.require_str("token")?), tls)
+                };
+                let include_data = body.get("includeData").and_then(|v| v.as_bool()).unwrap_or(false);
+                let overwrite = body.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+
+                let archive = archive_app(&app_id, &owner).map_err(YetiError::Validation)?;
+                let mut data = serde_json::Map::new();
+                if include_data {
+                    for table in crate::schemas::app_tables(&get_apps_directory().join(&app_id)) {
+                        let Some(name) = table["name"].as_str() else { continue };
+                        if let Ok(t) = ctx.get_table(name) {
+                            data.insert(name.to_string(), json!(t.scan_all().await.unwrap_or_default()));
+                        }
+                    }
+                }
+                let records: usize = data.values().filter_map(|v| v.as_array()).map(|a| a.len()).sum();
+                let payload = json!({
+                    "app_id": app_id,
+                    "archive": base64::engine::general_purpose::STANDARD.encode(&archive),
+                    "data": data,
+                    "overwrite": overwrite,
+                });
+
+                let push_owner = owner.clone();
+                let push_url = url.clone();
+                let (code, response) = tokio::task::spawn_blocking(move || push(&push_url, &authorization, &tls, &payload, &push_owner))
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Push task failed: {}", e)))?
+                    .map_err(YetiError::Internal)?;
+                let remote: serde_json::Value = serde_json::from_str(&response).unwrap_or(json!(response));
+                if !(200..300).contains(&code) {
+                    return reply().code(502).json(json!({
+                        "error": format!("Remote admin returned HTTP {}", code),
+                        "remote": remote,
+                    }));
+                }
+
+                crate::audit!(request, ctx, "app.promote", &app_id, json!({
+                    "url": url,
+                    "includeData": include_data,
+                    "records": records,
+                    "bytes": archive.len(),
+                }));
+
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "url": url,
+                    "bytes": archive.len(),
+                    "records": records,
+                    "remote": remote,
+                }));
+            }
+
+            // POST /admin/promote: receive
+            let app_id = body.require_str("app_id")?;
+            validate_identifier(&app_id, "app_id")?;
+            let archive = base64::engine::general_purpose::STANDARD
+                .decode(body.require_str("archive")?)
+                .map_err(|e| YetiError::Validation(format!("'archive' is not valid base64: {}", e)))?;
+            let overwrite = body.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+
+            let replaced = match install_archive(&app_id, &archive, overwrite, &owner) {
+                Ok(replaced) => replaced,
+                Err(e) => return bad_request(&e),
+            };
+            let app_path = get_apps_directory().join(&app_id);
+            if let Err(e) = crate::env::reload_app(&app_path) {
+                yeti_log!(warn, "Promoted '{}' but reload failed: {}", app_id, e);
+            }
+
+            // Records are written where the table is available; a table of a
+            // brand-new app may only appear once the app has loaded
+            let mut tables = serde_json::Map::new();
+            if let Some(data) = body.get("data").and_then(|v| v.as_object()) {
+                let declared: Vec<String> = crate::schemas::app_tables(&app_path)
+                    .iter()
+                    .filter_map(|t| t["name"].as_str().map(String::from))
+                    .collect();
+                for (name, records) in data {
+                    let records = records.as_array().cloned().unwrap_or_default();
+                    let result = if !declared.contains(name) {
+                        json!({"error": "Table is not declared by the app's schema"})
+                    } else {
+                        match ctx.get_table(name) {
+                            Ok(table) => {
+                                let mut written = 0;
+                                for record in records {
+                                    if table.put(record).await.is_ok() {
+                                        written += 1;
+                                    }
+                                }
+                                json!({"written": written})
+                            }
+                            Err(e) => json!({"error": e.to_string()}),
+                        }
+                    };
+                    tables.insert(name.clone(), result);
+                }
+            }
+
+            crate::audit!(request, ctx, "app.receive", &app_id, json!({
+                "replaced": replaced,
+                "bytes": archive.len(),
+            }));
+
+            reply().code(if replaced { 200 } else { 201 }).json(json!({
+                "app_id": app_id,
+                "installed": true,
+                "replaced": replaced,
+                "tables": tables,
+            }))
+        })
+    });
+}
+
+register_resource!(PromoteResource);
@@ -2,22 +2,280 @@
 //!
 //! REST API for cloning repos and managing git operations on applications.
 //!
-//! | Method | Path                                          | Description              |
-//! |--------|-----------------------------------------------|--------------------------|
-//! | POST   | /yeti-applications/repos/check                | Check repo accessibility |
-//! | POST   | /yeti-applications/repos/clone                | Clone repo into apps/    |
-//! | POST   | /yeti-applications/repos/pull/{app_id}        | Pull latest for an app   |
-//! | GET    | /yeti-applications/repos/status/{app_id}      | Git status for an app    |
+//! | Method | Path                                           | Description               |
+//! |--------|------------------------------------------------|---------------------------|
+//! | POST   | /yeti-applications/repos/check                 | Check repo accessibility  |
+//! | POST   | /yeti-applications/repos/clone                 | Clone repo into apps/     |
+//! | GET    | /yeti-applications/repos/clone-status          | Recent clone jobs         |
+//! | GET    | /yeti-applications/repos/clone-status/{job_id} | Clone progress            |
+//! | POST   | /yeti-applications/repos/pull/{app_id}         | Pull latest for an app    |
+//! | GET    | /yeti-applications/repos/status/{app_id}       | Git status for an app     |
+//! | GET    | /yeti-applications/repos/branches/{app_id}     | Local and remote branches |
+//! | GET    | /yeti-applications/repos/diff/{app_id}         | Uncommitted changes       |
+//! | GET    | /yeti-applications/repos/log/{app_id}          | Commit history            |
+//! | POST   | /yeti-applications/repos/reset/{app_id}        | Reset to a ref            |
+//! | POST   | /yeti-applications/repos/discard/{app_id}      | Discard file changes      |
+//! | POST   | /yeti-applications/repos/checkout/{app_id}     | Switch branch             |
+//! | POST   | /yeti-applications/repos/commit/{app_id}       | Commit working changes    |
+//! | POST   | /yeti-applications/repos/push/{app_id}         | Push to origin            |
+//! | POST   | /yeti-applications/repos/key/{app_id}          | Set the default key       |
+//!
+//! Clone runs in the background and returns 202 with a `job_id`; poll
+//! clone-status for the phase ("Receiving objects", "Resolving deltas", ...),
+//! its percentage and object counts, the bytes received and an overall
+//! `percent`, or follow the `repo.clone.succeeded` / `repo.clone.failed` event
+//! published when it ends. `?wait=true` clones synchronously and returns 201 as
+//! before.
+//!
+//! Checkout body: `{"branch": "feature-x", "create": false, "key": "deploy"}`. A
+//! branch that only exists on origin gets a local tracking branch (origin is
+//! fetched first if needed); `create` starts a new branch from HEAD. The app is
+//! reloaded after switching.
+//!
+//! Commit body: `{"message": "Fix header", "author": "Jane <jane@example.com>",
+//! "files": ["src/lib.rs"]}`. Without `files` every change (including new and
+//! deleted files) is committed; `author` defaults to the git config, or to the
+//! calling user when the repo has no identity. Push body: `{"key": "deploy"}`;
+//! the current branch is pushed to origin and gets an upstream if it has none.
+//! A rejected (non-fast-forward) push returns 409.
+//!
+//! Pull, checkout and push use the app's default deploy key unless the body
+//! names a `key` (`"key": null` for none). Key body: `{"key": "deploy"}`, or
+//! `{"key": null}` to clear it; cloning with a `key` makes it the default.
+//! Status reports it as `deployKey`.
+//! `https://` remotes authenticate with the token stored for their host in
+//! `/admin/credentials` (see `credentials.rs`).
+//!
+//! Diff returns one entry per changed file (staged, unstaged and untracked)
+//! with its status, line counts and unified diff against HEAD; `?path=`
+//! narrows it to a file or directory. Diffs over `MAX_DIFF_BYTES` are cut off
+//! and flagged `truncated`.
+//!
+//! Log takes `?limit=` (default `DEFAULT_LOG_LIMIT`, at most `MAX_LOG_LIMIT`),
+//! `?offset=` and `?branch=` (any branch or ref, default HEAD). Parsed pages
+//! are cached per app and dropped as soon as the branch tip moves.
+//!
+//! Reset body: `{"mode": "soft" | "hard", "ref": "HEAD~1"}` (`ref` defaults to
+//! HEAD); discard body: `{"files": ["src/lib.rs"]}`, which restores tracked
+//! files from HEAD and deletes untracked ones. Both are two-step: the first
+//! call changes nothing and returns a preview plus a `confirm` token, and the
+//! same body with `"confirm": "<token>"` performs it. Tokens expire after
+//! `CONFIRM_TTL_SECS` and are void once HEAD or the working tree changes.
 
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use yeti_core::prelude::*;
 
+/// Per-file cap on diff text returned by the diff endpoint
+const MAX_DIFF_BYTES: usize = 256 * 1024;
+const DEFAULT_LOG_LIMIT: usize = 50;
+const MAX_LOG_LIMIT: usize = 500;
+/// One record per commit: `\x1e` starts a record, `\x1f` separates fields
+pub const LOG_FORMAT: &str = "--format=%x1e%H%x1f%P%x1f%an%x1f%ae%x1f%at%x1f%D%x1f%s";
+/// Cached log pages kept per app
+const MAX_CACHED_PAGES: usize = 32;
+const CONFIRM_TTL_SECS: f64 = 120.0;
+/// Finished clone jobs are forgotten after this long
+const CLONE_JOB_TTL_SECS: f64 = 3600.0;
+/// Non-progress output lines kept per clone job
+const MAX_CLONE_OUTPUT_LINES: usize = 200;
+
+// ── Clone jobs (in-memory, one entry per clone) ──
+
+#[derive(Clone)]
+struct CloneJob {
+    app_id: String,
+    url: String,
+    status: String, // "running", "succeeded", "failed"
+    phase: Option<String>,
+    phase_percent: Option<u64>,
+    objects: Option<(u64, u64)>,
+    received: Option<String>,
+    rate: Option<String>,
+    percent: u64,
+    started_at: f64,
+    finished_at: Option<f64>,
+    output: Vec<String>,
+    error: Option<String>,
+}
+
+impl CloneJob {
+    fn to_json(&self, job_id: &str) -> serde_json::Value {
+        json!({
+            "job_id": job_id,
+            "app_id": self.app_id,
+            "url": self.url,
+            "status": self.status,
+            "phase": self.phase,
+            "phasePercent": self.phase_percent,
+            "objects": self.objects.map(|(done, total)| json!({"done": done, "total": total})),
+            "received": self.received,
+            "rate": self.rate,
+            "percent": self.percent,
+            "startedAt": self.started_at,
+            "finishedAt": self.finished_at,
+            "output": self.output,
+            "error": self.error,
+        })
+    }
+
+    /// Apply one `git clone --progress` line
+    fn progress(&mut self, line: &str) {
+        let line = line.trim();
+        let text = line.strip_prefix("remote: ").unwrap_or(line);
+        let parsed = text.split_once(':').and_then(|(phase, rest)| {
+            let (pct, rest) = rest.split_once('%')?;
+            Some((phase.trim(), pct.trim().parse::<u64>().ok()?, rest))
+        });
+        let Some((phase, pct, rest)) = parsed else {
+            if !line.is_empty() {
+                self.output.push(line.to_string());
+                if self.output.len() > MAX_CLONE_OUTPUT_LINES {
+                    self.output.remove(0);
+                }
+            }
+            return;
+        };
+
+        // " (450/1000), 1.20 MiB | 512.00 KiB/s" or " (10/10), done."
+        let counts = rest.split_once('(').and_then(|(_, r)| r.split_once(')'));
+        self.objects = counts.and_then(|(c, _)| {
+            let (done, total) = c.split_once('/')?;
+            Some((done.trim().parse().ok()?, total.trim().parse().ok()?))
+        });
+        if let Some((_, transfer)) = counts {
+            let transfer = transfer.trim_start_matches(',').trim();
+            if let Some((received, rate)) = transfer.split_once('|') {
+                self.received = Some(received.trim().to_string());
+                self.rate = Some(rate.trim().trim_end_matches(", done.").to_string());
+            }
+        }
+        self.phase = Some(phase.to_string());
+        self.phase_percent = Some(pct);
+
+        // Rough overall progress: the server-side phases are quick, receiving
+        // dominates, then deltas and the checkout
+        let (start, span) = match phase {
+            "Enumerating objects" | "Counting objects" | "Compressing objects" => (0, 10),
+            "Receiving objects" => (10, 70),
+            "Resolving deltas" => (80, 15),
+            "Updating files" => (95, 5),
+            _ => (self.percent, 0),
+        };
+        self.percent = self.percent.max(start + span * pct.min(100) / 100);
+    }
+}
+
+fn clones() -> &'static Mutex<HashMap<String, CloneJob>> {
+    static CLONES: OnceLock<Mutex<HashMap<String, CloneJob>>> = OnceLock::new();
+    CLONES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn update_clone(job_id: &str, f: impl FnOnce(&mut CloneJob)) {
+    if let Some(job) = clones().lock().unwrap().get_mut(job_id) {
+        f(job);
+    }
+}
+
+/// Run `git clone --progress` for a registered job, feeding its stderr into the job
+fn run_clone(job_id: &str, url: &str, app_path: &std::path::Path, key: Option<&str>, owner: &str) -> std::result::Result<(), String> {
+    use std::io::Read;
+
+    let mut cmd = std::process::Command::new("git");
+    cmd.args(["clone", "--progress", url])
+        .arg(app_path)
+        .envs(crate::credentials::git_env())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped());
+    if let Some(key_name) = key {
+        cmd.env("GIT_SSH_COMMAND", git_ssh_command(key_name)?);
+        crate::keys::record_use(key_name, app_path.file_name().and_then(|n| n.to_str()));
+    }
+
+    let started = std::time::Instant::now();
+    let mut child = crate::processes::spawn("git", owner, &mut cmd)
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    // Progress lines end in '\r' while a phase is running and '\n' when it is done
+    let mut last_lines = Vec::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let mut pending = Vec::new();
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = stderr.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                if byte == b'\r' || byte == b'\n' {
+                    let line = String::from_utf8_lossy(&pending).to_string();
+                    pending.clear();
+                    if !line.trim().is_empty() {
+                        update_clone(job_id, |job| job.progress(&line));
+                        last_lines.push(line);
+                        if last_lines.len() > 5 {
+                            last_lines.remove(0);
+                        }
+                    }
+                } else {
+                    pending.push(byte);
+                }
+            }
+        }
+    }
+
+    let status = child.wait();
+    crate::processes::reaped(child.id());
+    let ok = status.as_ref().is_ok_and(|s| s.success());
+    crate::metrics::record_git("clone", ok, started.elapsed());
+    let status = status.map_err(|e| format!("Failed to wait for git: {}", e))?;
+    if !status.success() {
+        return Err(format!("git failed: {}", last_lines.join("\n")));
+    }
+    Ok(())
+}
+
+/// A previewed reset or discard waiting for its confirmation token
+struct PendingConfirm {
+    app_id: String,
+    /// The operation and its arguments, e.g. `reset:hard:<sha>`
+    action: String,
+    /// HEAD and working tree status at preview time
+    state: String,
+    expires: f64,
+}
+
+fn pending_confirms() -> &'static Mutex<HashMap<String, PendingConfirm>> {
+    static PENDING: OnceLock<Mutex<HashMap<String, PendingConfirm>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Parsed log pages per app: tip commit, total count and pages by (offset, limit)
+struct LogCache {
+    tip: String,
+    total: u64,
+    pages: HashMap<(usize, usize), Vec<serde_json::Value>>,
+}
+
+fn log_cache() -> &'static Mutex<HashMap<(String, String), LogCache>> {
+    static CACHE: OnceLock<Mutex<HashMap<(String, String), LogCache>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
 pub type Repos = ReposResource;
 
 #[derive(Default)]
 pub struct ReposResource;
 
 /// Validate git URL format (must start with git@ or https://)
-fn validate_git_url(url: &str) -> std::result::Result<(), String> {
+pub fn validate_git_url(url: &str) -> std::result::Result<(), String> {
     if url.starts_with("git@") || url.starts_with("https://") {
         Ok(())
     } else {
@@ -25,6 +283,17 @@ fn validate_git_url(url: &str) -> std::result::Result<(), String> {
     }
 }
 
+/// `url` without the `user:token@` of a `scheme://` URL, for the audit log,
+/// events, logs and job views
+pub fn redact_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else { return url.to_string() };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..authority_end].rfind('@') {
+        Some(at) => format!("{}://{}", scheme, &rest[at + 1..]),
+        None => url.to_string(),
+    }
+}
+
 /// Extract repo name from git URL
 /// e.g. git@github.com:org/my-app.git -> my-app
 /// e.g. https://github.com/org/my-app.git -> my-app
@@ -59,10 +328,327 @@ fn git_ssh_command(key_name: &str) -> std::result::Result<String, String> {
     ))
 }
 
-/// Run a git command, optionally with SSH key
-fn run_git(args: &[&str], cwd: Option<&std::path::Path>, key: Option<&str>) -> std::result::Result<String, String> {
+/// The app a git command works on: the first app directory among `cwd`, a
+/// `-C` directory or a clone destination
+fn git_app(args: &[&str], cwd: Option<&std::path::Path>) -> Option<String> {
+    let apps = get_apps_directory();
+    cwd.into_iter()
+        .chain(args.iter().map(|a| std::path::Path::new(*a)))
+        .find_map(|path| path.strip_prefix(&apps).ok()?.components().next())
+        .and_then(|c| c.as_os_str().to_str().map(String::from))
+}
+
+/// The git subcommand in `args`, skipping `-C <dir>` and other options
+fn git_operation(args: &[&str]) -> &str {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match *arg {
+            "-C" | "-c" => {
+                iter.next();
+            }
+            a if a.starts_with('-') => {}
+            a => return a,
+        }
+    }
+    "unknown"
+}
+
+/// Key for a remote operation on `app_id`: `key` from the body when given
+/// (null for none), else the app's default deploy key
+fn resolve_key(app_id: &str, body: &serde_json::Value) -> Option<String> {
+    match body.get("key") {
+        Some(key) => key.as_str().map(String::from),
+        None => crate::keys::default_for(app_id),
+    }
+}
+
+/// `{app_id}` following `/repos/{op}/` in the request path
+fn app_id_after(uri_path: &str, op: &str) -> std::result::Result<String, YetiError> {
+    let marker = format!("/repos/{}/", op);
+    let app_id = uri_path
+        .split_once(&marker)
+        .map(|(_, rest)| rest.trim_end_matches('/'))
+        .filter(|s| !s.is_empty() && !s.contains('/'))
+        .ok_or_else(|| YetiError::Validation(format!("App ID required in path (use /repos/{}/{{app_id}})", op)))?;
+    validate_identifier(app_id, "app_id")?;
+    Ok(app_id.to_string())
+}
+
+/// Branch names must be valid refs and must not look like options
+fn validate_branch(name: &str, owner: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || name.starts_with('-') {
+        return Err(format!("Invalid branch name '{}'", name));
+    }
+    run_git(&["check-ref-format", "--branch", name], None, None, owner)
+        .map(|_| ())
+        .map_err(|_| format!("Invalid branch name '{}'", name))
+}
+
+/// Pathspecs must be relative to the app, stay inside it and not use git magic
+pub fn validate_pathspec(path: &str) -> std::result::Result<(), String> {
+    if path.is_empty()
+        || path.starts_with('/')
+        || path.starts_with('-')
+        || path.starts_with(':')
+        || path.split('/').any(|c| c == "..")
+    {
+        return Err(format!("Invalid path '{}'", path));
+    }
+    Ok(())
+}
+
+/// `Name <email>` split into its parts
+fn parse_author(author: &str) -> Option<(&str, &str)> {
+    let (name, rest) = author.split_once('<')?;
+    let email = rest.strip_suffix('>')?.trim();
+    let name = name.trim();
+    if name.is_empty() || email.is_empty() || !email.contains('@') || author.contains('\n') {
+        return None;
+    }
+    Some((name, email))
+}
+
+/// Cut `text` to `MAX_DIFF_BYTES` on a char boundary; returns whether it was cut
+fn truncate_diff(text: &mut String) -> bool {
+    if text.len() <= MAX_DIFF_BYTES {
+        return false;
+    }
+    let mut end = MAX_DIFF_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    true
+}
+
+/// Split `git diff` output into one JSON entry per file
+fn split_diff(output: &str) -> Vec<serde_json::Value> {
+    let mut chunks: Vec<String> = Vec::new();
+    for line in output.split_inclusive('\n') {
+        match chunks.last_mut() {
+            Some(chunk) if !line.starts_with("diff --git ") => chunk.push_str(line),
+            _ => chunks.push(line.to_string()),
+        }
+    }
+
+    chunks
+        .into_iter()
+        .map(|mut diff| {
+            let (mut old_path, mut new_path) = (None, None);
+            let (mut additions, mut deletions) = (0u64, 0u64);
+            let mut status = "modified";
+            for line in diff.lines() {
+                if let Some(p) = line.strip_prefix("--- a/") {
+                    old_path = Some(p.to_string());
+                } else if let Some(p) = line.strip_prefix("+++ b/") {
+                    new_path = Some(p.to_string());
+                } else if let Some(p) = line.strip_prefix("rename from ") {
+                    old_path = Some(p.to_string());
+                    status = "renamed";
+                } else if let Some(p) = line.strip_prefix("rename to ") {
+                    new_path = Some(p.to_string());
+                } else if line.starts_with("new file mode") {
+                    status = "added";
+                } else if line.starts_with("deleted file mode") {
+                    status = "deleted";
+                } else if line.starts_with("Binary files") {
+                    if status == "modified" {
+                        status = "binary";
+                    }
+                } else if line.starts_with('+') && !line.starts_with("+++") {
+                    additions += 1;
+                } else if line.starts_with('-') && !line.starts_with("---") {
+                    deletions += 1;
+                }
+            }
+            // Binary and mode-only changes have no ---/+++ lines; fall back to the header
+            let header_path = diff
+                .lines()
+                .next()
+                .and_then(|h| h.strip_prefix("diff --git a/"))
+                .and_then(|h| h.rsplit_once(" b/"))
+                .map(|(_, p)| p.to_string());
+            let path = new_path.clone().or_else(|| old_path.clone()).or(header_path);
+            let truncated = truncate_diff(&mut diff);
+            json!({
+                "path": path,
+                "oldPath": if status == "renamed" { old_path } else { None },
+                "status": status,
+                "additions": additions,
+                "deletions": deletions,
+                "diff": diff,
+                "truncated": truncated,
+            })
+        })
+        .collect()
+}
+
+/// Diff entry for an untracked file, shown as an addition of its whole content
+fn untracked_diff(app_path: &std::path::Path, rel: &str) -> serde_json::Value {
+    let bytes = std::fs::read(app_path.join(rel)).unwrap_or_default();
+    let (mut diff, additions) = match String::from_utf8(bytes) {
+        Ok(text) if !text.contains('\0') => {
+            let lines: Vec<&str> = text.lines().collect();
+            let mut diff = format!("--- /dev/null\n+++ b/{}\n", rel);
+            if !lines.is_empty() {
+                diff.push_str(&format!("@@ -0,0 +1,{} @@\n", lines.len()));
+                for line in &lines {
+                    diff.push('+');
+                    diff.push_str(line);
+                    diff.push('\n');
+                }
+            }
+            (diff, lines.len() as u64)
+        }
+        _ => (format!("Binary file b/{} added\n", rel), 0),
+    };
+    let truncated = truncate_diff(&mut diff);
+    json!({
+        "path": rel,
+        "oldPath": null,
+        "status": "untracked",
+        "additions": additions,
+        "deletions": 0,
+        "diff": diff,
+        "truncated": truncated,
+    })
+}
+
+/// HEAD plus porcelain status, which any commit, checkout or edit changes
+fn repo_state(app_path: &str, owner: &str) -> std::result::Result<String, String> {
+    let head = run_git(&["-C", app_path, "rev-parse", "--verify", "--quiet", "HEAD"], None, None, owner).unwrap_or_default();
+    let status = run_git(&["-C", app_path, "status", "--porcelain", "--untracked-files=all"], None, None, owner)?;
+    Ok(format!("{}\n{}", head.trim(), status))
+}
+
+/// Issue a confirmation token for `action` on the current repo state
+fn issue_confirm(app_id: &str, action: &str, state: String) -> std::result::Result<String, String> {
+    let token = crate::webhooks::random_hex(16)?;
+    let now = now_secs();
+    let mut pending = pending_confirms().lock().unwrap();
+    pending.retain(|_, p| p.expires > now);
+    pending.insert(token.clone(), PendingConfirm {
+        app_id: app_id.to_string(),
+        action: action.to_string(),
+        state,
+        expires: now + CONFIRM_TTL_SECS,
+    });
+    Ok(token)
+}
+
+/// Consume a confirmation token; it must match the app, action and repo state
+fn take_confirm(token: &str, app_id: &str, action: &str, state: &str) -> std::result::Result<(), String> {
+    let pending = pending_confirms().lock().unwrap().remove(token);
+    match pending {
+        Some(p) if p.app_id == app_id && p.action == action && p.expires > now_secs() => {
+            if p.state == state {
+                Ok(())
+            } else {
+                Err("Repository changed since the preview; request a new confirmation".to_string())
+            }
+        }
+        _ => Err("Invalid or expired confirmation token".to_string()),
+    }
+}
+
+/// Parse `git log` output written with `LOG_FORMAT` and `--name-status`
+pub fn parse_log(output: &str) -> Vec<serde_json::Value> {
+    output
+        .split('\x1e')
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| {
+            let (header, files) = record.split_once('\n').unwrap_or((record, ""));
+            let fields: Vec<&str> = header.splitn(7, '\x1f').collect();
+            let [hash, parents, author, email, date, refs, subject] = fields[..] else { return None };
+            let files: Vec<serde_json::Value> = files
+                .lines()
+                .filter(|l| !l.is_empty())
+                .filter_map(|line| {
+                    // "M\tpath", "R100\told\tnew"
+                    let mut parts = line.split('\t');
+                    let status = parts.next()?;
+                    let first = parts.next()?;
+                    let status_name = match status.get(..1).unwrap_or("M") {
+                        "A" => "added",
+                        "D" => "deleted",
+                        "R" => "renamed",
+                        "C" => "copied",
+                        "T" => "typechange",
+                        _ => "modified",
+                    };
+                    Some(match parts.next() {
+                        Some(second) => json!({"path": second, "oldPath": first, "status": status_name}),
+                        None => json!({"path": first, "status": status_name}),
+                    })
+                })
+                .collect();
+            Some(json!({
+                "hash": hash,
+                "shortHash": &hash[..hash.len().min(7)],
+                "parents": parents.split_whitespace().collect::<Vec<_>>(),
+                "author": author,
+                "email": email,
+                "date": date.parse::<i64>().ok(),
+                "refs": refs.split(", ").filter(|r| !r.is_empty()).collect::<Vec<_>>(),
+                "subject": subject,
+                "filesChanged": files.len(),
+                "files": files,
+            }))
+        })
+        .collect()
+}
+
+/// Local and remote branches from `git for-each-ref`
+fn list_branches(app_path: &str, owner: &str) -> std::result::Result<(Vec<serde_json::Value>, Vec<serde_json::Value>), String> {
+    let format = "%(refname)%09%(refname:short)%09%(objectname:short)%09%(upstream:short)%09%(upstream:track)%09%(committerdate:unix)%09%(subject)";
+    let output = run_git(
+        &["-C", app_path, "for-each-ref", &format!("--format={}", format), "refs/heads", "refs/remotes"],
+        None,
+        None,
+        owner,
+    )?;
+    let (mut local, mut remote) = (Vec::new(), Vec::new());
+    for line in output.lines() {
+        let fields: Vec<&str> = line.splitn(7, '\t').collect();
+        let [refname, name, commit, upstream, track, date, subject] = fields[..] else { continue };
+        if refname.ends_with("/HEAD") {
+            continue;
+        }
+        let mut branch = json!({
+            "name": name,
+            "commit": commit,
+            "committedAt": date.parse::<i64>().ok(),
+            "subject": subject,
+        });
+        if refname.starts_with("refs/heads/") {
+            // track looks like "[ahead 1, behind 2]" or "[gone]"
+            let count = |what: &str| {
+                track.split(|c| c == '[' || c == ']' || c == ',')
+                    .filter_map(|part| part.trim().strip_prefix(what))
+                    .find_map(|n| n.trim().parse::<u64>().ok())
+                    .unwrap_or(0)
+            };
+            branch["upstream"] = json!(if upstream.is_empty() { None } else { Some(upstream) });
+            branch["ahead"] = json!(count("ahead"));
+            branch["behind"] = json!(count("behind"));
+            branch["upstreamGone"] = json!(track.contains("gone"));
+            local.push(branch);
+        } else {
+            remote.push(branch);
+        }
+    }
+    Ok((local, remote))
+}
+
+/// Run a git command, optionally with SSH key, on behalf of `owner`
+pub fn run_git(args: &[&str], cwd: Option<&std::path::Path>, key: Option<&str>, owner: &str) -> std::result::Result<String, String> {
+    run_git_bytes(args, cwd, key, owner).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// `run_git` returning stdout as raw bytes (file contents at a revision)
+pub fn run_git_bytes(args: &[&str], cwd: Option<&std::path::Path>, key: Option<&str>, owner: &str) -> std::result::Result<Vec<u8>, String> {
     let mut cmd = std::process::Command::new("git");
-    cmd.args(args);
+    cmd.args(args).envs(crate::credentials::git_env());
 
     if let Some(dir) = cwd {
         cmd.current_dir(dir);
@@ -71,19 +657,21 @@ fn run_git(args: &[&str], cwd: Option<&std::path::Path>, key: Option<&str>) -> s
     if let Some(key_name) = key {
         let ssh_cmd = git_ssh_command(key_name)?;
         cmd.env("GIT_SSH_COMMAND", &ssh_cmd);
+        crate::keys::record_use(key_name, git_app(args, cwd).as_deref());
     }
 
-    let output = cmd.output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let started = std::time::Instant::now();
+    let output = crate::processes::output("git", owner, &mut cmd);
+    crate::metrics::record_git(git_operation(args), output.as_ref().is_ok_and(|o| o.status.success()), started.elapsed());
+    let output = output.map_err(|e| format!("Failed to run git: {}", e))?;
 
     if !output.status.success() {
-        return Err(format!("git failed: {}", if stderr.is_empty() { &stdout } else { &stderr }));
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let detail = if stderr.is_empty() { String::from_utf8_lossy(&output.stdout).to_string() } else { stderr };
+        return Err(format!("git failed: {}", detail));
     }
 
-    Ok(stdout)
+    Ok(output.stdout)
 }
 
 impl Resource for ReposResource {
@@ -92,153 +680,236 @@ impl Resource for ReposResource {
     }
 
     get!(request, _ctx, {
-        // GET /repos/status/{app_id}
-        let uri_path = request.uri().path();
-        let app_id = if uri_path.contains("/repos/status/") {
-            uri_path
-                .rsplit('/')
-                .next()
-                .filter(|s| !s.is_empty())
-                .ok_or_else(|| YetiError::Validation("App ID required (use /repos/status/{app_id})".to_string()))?
-                .to_string()
-        } else {
-            return bad_request("Use /repos/status/{app_id}");
-        };
-
-        validate_identifier(&app_id, "app_id")?;
+        crate::metered!(request, "repos", "GET", {
+            let uri_path = request.uri().path();
+            let owner = crate::actor!(request);
 
-        let app_path = get_apps_directory().join(&app_id);
-        if !app_path.is_dir() {
-            return not_found(&format!("Application '{}' not found", app_id));
-        }
+            // GET /repos/clone-status[/{job_id}]
+            if uri_path.contains("/repos/clone-status") {
+                let job_id = uri_path
+                    .split_once("/repos/clone-status")
+                    .map(|(_, rest)| rest.trim_matches('/'))
+                    .filter(|s| !s.is_empty());
+                let jobs = clones().lock().unwrap();
+                return match job_id {
+                    Some(job_id) => match jobs.get(job_id) {
+                        Some(job) => reply().json(job.to_json(job_id)),
+                        None => not_found(&format!("Clone job '{}' not found", job_id)),
+                    },
+                    None => {
+                        let mut list: Vec<(&String, &CloneJob)> = jobs.iter().collect();
+                        list.sort_by(|a, b| b.1.started_at.partial_cmp(&a.1.started_at).unwrap_or(std::cmp::Ordering::Equal));
+                        reply().json(json!({
+                            "jobs": list.into_iter().map(|(id, job)| job.to_json(id)).collect::<Vec<_>>(),
+                        }))
+                    }
+                };
+            }
 
-        let is_git = app_path.join(".git").is_dir();
-        if !is_git {
-            return reply().json(json!({
-                "app_id": app_id,
-                "is_git": false,
-            }));
-        }
+            // GET /repos/branches/{app_id}
+            if uri_path.contains("/repos/branches/") {
+                let app_id = app_id_after(uri_path, "branches")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+                let app_path_str = app_path.to_string_lossy().to_string();
+                let current = run_git(&["-C", &app_path_str, "branch", "--show-current"], None, None, &owner)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                let (local, remote) = list_branches(&app_path_str, &owner).map_err(YetiError::Internal)?;
 
-        // Get branch name
-        let branch = run_git(&["-C", &app_path.to_string_lossy(), "branch", "--show-current"], None, None)
-            .unwrap_or_default()
-            .trim()
-            .to_string();
-
-        // Get remote URL
-        let remote_url = run_git(&["-C", &app_path.to_string_lossy(), "remote", "get-url", "origin"], None, None)
-            .unwrap_or_default()
-            .trim()
-            .to_string();
-
-        // Check if dirty
-        let status_output = run_git(&["-C", &app_path.to_string_lossy(), "status", "--porcelain"], None, None)
-            .unwrap_or_default();
-        let dirty = !status_output.trim().is_empty();
-
-        reply().json(json!({
-            "app_id": app_id,
-            "is_git": true,
-            "branch": branch,
-            "remote_url": remote_url,
-            "dirty": dirty,
-        }))
-    });
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "current": if current.is_empty() { None } else { Some(current) },
+                    "local": local,
+                    "remote": remote,
+                }));
+            }
 
-    post!(request, _ctx, {
-        let body = request.json_value()?;
-
-        // Parse the request URI to determine the operation
-        let uri_path = request.uri().path();
-
-        if uri_path.contains("/repos/check") {
-            // --- Check repo accessibility ---
-            let url = body.require_str("url")?;
-
-            validate_git_url(&url)
-                .map_err(|e| YetiError::Validation(e))?;
-
-            // Run git ls-remote with timeout to check public accessibility
-            let mut cmd = std::process::Command::new("git");
-            cmd.args(["ls-remote", "--exit-code", &url]);
-            cmd.env("GIT_TERMINAL_PROMPT", "0");
-            cmd.env("GIT_SSH_COMMAND", "ssh -o ConnectTimeout=5 -o StrictHostKeyChecking=accept-new -o BatchMode=yes");
-            cmd.stdout(std::process::Stdio::null());
-            cmd.stderr(std::process::Stdio::null());
-
-            let mut child = cmd.spawn()
-                .map_err(|e| YetiError::Internal(format!("Failed to run git: {}", e)))?;
-
-            let start = std::time::Instant::now();
-            let timeout = std::time::Duration::from_secs(10);
-            let is_public = loop {
-                match child.try_wait() {
-                    Ok(Some(status)) => break status.success(),
-                    Ok(None) => {
-                        if start.elapsed() > timeout {
-                            let _ = child.kill();
-                            break false;
-                        }
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                    }
-                    Err(_) => break false,
+            // GET /repos/diff/{app_id}?path=...
+            if uri_path.contains("/repos/diff/") {
+                let app_id = app_id_after(uri_path, "diff")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
                 }
-            };
-
-            reply().json(json!({
-                "url": url,
-                "public": is_public,
-            }))
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+                let query = request.uri().query().unwrap_or("");
+                let path = parse_query_param(query, "path")
+                    .map(|p| p.trim_start_matches('/').trim_end_matches('/').to_string())
+                    .filter(|p| !p.is_empty());
+                if let Some(p) = &path {
+                    validate_pathspec(p).map_err(YetiError::Validation)?;
+                }
+                let app_path_str = app_path.to_string_lossy().to_string();
+                let pathspec: Vec<&str> = path.iter().map(|p| p.as_str()).collect();
 
-        } else if uri_path.contains("/repos/clone") {
-            // --- Clone operation ---
-            let url = body.require_str("url")?;
+                // Against HEAD so staged and unstaged changes both show; a repo
+                // without commits yet only has the index to compare with
+                let has_head = run_git(&["-C", &app_path_str, "rev-parse", "--verify", "--quiet", "HEAD"], None, None, &owner).is_ok();
+                let mut diff_args = vec!["-C", app_path_str.as_str(), "-c", "core.quotePath=false", "diff", "--no-color", "--no-ext-diff", "-M"];
+                diff_args.push(if has_head { "HEAD" } else { "--cached" });
+                diff_args.push("--");
+                diff_args.extend(&pathspec);
+                let output = run_git(&diff_args, None, None, &owner).map_err(YetiError::Internal)?;
+                let mut files = split_diff(&output);
 
-            validate_git_url(&url)
-                .map_err(|e| YetiError::Validation(e))?;
+                let mut untracked_args = vec!["-C", app_path_str.as_str(), "-c", "core.quotePath=false", "ls-files", "--others", "--exclude-standard", "--"];
+                untracked_args.extend(&pathspec);
+                let untracked = run_git(&untracked_args, None, None, &owner).unwrap_or_default();
+                files.extend(untracked.lines().filter(|l| !l.is_empty()).map(|rel| untracked_diff(&app_path, rel)));
 
-            let app_id = body.get("app_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .or_else(|| extract_repo_name(&url))
-                .ok_or_else(|| YetiError::Validation("Cannot determine app_id from URL, please provide 'app_id'".to_string()))?;
+                let additions: u64 = files.iter().filter_map(|f| f["additions"].as_u64()).sum();
+                let deletions: u64 = files.iter().filter_map(|f| f["deletions"].as_u64()).sum();
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "path": path,
+                    "dirty": !files.is_empty(),
+                    "additions": additions,
+                    "deletions": deletions,
+                    "files": files,
+                }));
+            }
 
-            validate_identifier(&app_id, "app_id")?;
+            // GET /repos/log/{app_id}?limit=&offset=&branch=
+            if uri_path.contains("/repos/log/") {
+                let app_id = app_id_after(uri_path, "log")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+                let query = request.uri().query().unwrap_or("");
+                let limit = match parse_query_param(query, "limit") {
+                    Some(v) => match v.parse::<usize>() {
+                        Ok(n) if (1..=MAX_LOG_LIMIT).contains(&n) => n,
+                        _ => return bad_request(&format!("'limit' must be between 1 and {}", MAX_LOG_LIMIT)),
+                    },
+                    None => DEFAULT_LOG_LIMIT,
+                };
+                let offset = match parse_query_param(query, "offset") {
+                    Some(v) => match v.parse::<usize>() {
+                        Ok(n) => n,
+                        Err(_) => return bad_request("'offset' must be a non-negative integer"),
+                    },
+                    None => 0,
+                };
+                let branch = parse_query_param(query, "branch").filter(|b| !b.is_empty());
+                if let Some(b) = &branch {
+                    validate_branch(b, &owner).map_err(YetiError::Validation)?;
+                }
+                let rev = branch.clone().unwrap_or_else(|| "HEAD".to_string());
+                let app_path_str = app_path.to_string_lossy().to_string();
 
-            let app_path = get_apps_directory().join(&app_id);
-            if app_path.exists() {
-                return bad_request(&format!("Application '{}' already exists", app_id));
-            }
+                let tip = run_git(
+                    &["-C", &app_path_str, "rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)],
+                    None,
+                    None,
+                    &owner,
+                ).map(|t| t.trim().to_string());
+                let tip = match (tip, &branch) {
+                    (Ok(tip), _) => tip,
+                    (Err(_), Some(b)) => return not_found(&format!("Branch '{}' not found", b)),
+                    // No commits yet
+                    (Err(_), None) => {
+                        return reply().json(json!({
+                            "app_id": app_id,
+                            "branch": branch,
+                            "total": 0,
+                            "limit": limit,
+                            "offset": offset,
+                            "commits": [],
+                        }));
+                    }
+                };
 
-            let key = body.get("key").and_then(|v| v.as_str());
+                let cache_key = (app_id.clone(), rev.clone());
+                let cached = {
+                    let cache = log_cache().lock().unwrap();
+                    cache.get(&cache_key)
+                        .filter(|c| c.tip == tip)
+                        .map(|c| (c.total, c.pages.get(&(offset, limit)).cloned()))
+                };
 
-            // Run git clone
-            let app_path_str = app_path.to_string_lossy().to_string();
-            let args = vec!["clone", &url, &app_path_str];
+                let total = match cached {
+                    Some((total, _)) => total,
+                    None => run_git(&["-C", &app_path_str, "rev-list", "--count", &tip], None, None, &owner)
+                        .map_err(YetiError::Internal)?
+                        .trim()
+                        .parse::<u64>()
+                        .unwrap_or(0),
+                };
+                let commits = match cached.and_then(|(_, page)| page) {
+                    Some(page) => page,
+                    None => {
+                        let output = run_git(
+                            &[
+                                "-C", &app_path_str,
+                                "-c", "core.quotePath=false",
+                                "log",
+                                LOG_FORMAT,
+                                "--name-status",
+                                "--no-color",
+                                &format!("--skip={}", offset),
+                                &format!("--max-count={}", limit),
+                                &tip,
+                                "--",
+                            ],
+                            None,
+                            None,
+                            &owner,
+                        ).map_err(YetiError::Internal)?;
+                        let page = parse_log(&output);
 
-            let output = run_git(&args, None, key)
-                .map_err(|e| {
-                    // Clean up partial clone if it exists
-                    let _ = std::fs::remove_dir_all(&app_path);
-                    YetiError::Internal(e)
-                })?;
+                        let mut cache = log_cache().lock().unwrap();
+                        let entry = cache.entry(cache_key).or_insert_with(|| LogCache {
+                            tip: tip.clone(),
+                            total,
+                            pages: HashMap::new(),
+                        });
+                        if entry.tip != tip {
+                            *entry = LogCache { tip: tip.clone(), total, pages: HashMap::new() };
+                        }
+                        if entry.pages.len() >= MAX_CACHED_PAGES {
+                            entry.pages.clear();
+                        }
+                        entry.pages.insert((offset, limit), page.clone());
+                        page
+                    }
+                };
 
-            reply().code(201).json(json!({
-                "app_id": app_id,
-                "cloned": true,
-                "output": output.trim(),
-            }))
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "branch": branch,
+                    "head": tip,
+                    "total": total,
+                    "limit": limit,
+                    "offset": offset,
+                    "hasMore": (offset + commits.len()) < total as usize,
+                    "commits": commits,
+                }));
+            }
 
-        } else if uri_path.contains("/repos/pull/") {
-            // --- Pull operation ---
-            // Extract app_id from the URI path after /repos/pull/
-            let app_id = uri_path
-                .rsplit('/')
-                .next()
-                .filter(|s| !s.is_empty())
-                .ok_or_else(|| YetiError::Validation("App ID required in path (use /repos/pull/{app_id})".to_string()))?
-                .to_string();
+            // GET /repos/status/{app_id}
+            let app_id = if uri_path.contains("/repos/status/") {
+                uri_path
+                    .rsplit('/')
+                    .next()
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| YetiError::Validation("App ID required (use /repos/status/{app_id})".to_string()))?
+                    .to_string()
+            } else {
+                return bad_request("Use /repos/{status,branches,diff,log}/{app_id} or /repos/clone-status/{job_id}");
+            };
 
             validate_identifier(&app_id, "app_id")?;
 
@@ -246,26 +917,680 @@ impl Resource for ReposResource {
             if !app_path.is_dir() {
                 return not_found(&format!("Application '{}' not found", app_id));
             }
-            if !app_path.join(".git").is_dir() {
-                return bad_request(&format!("Application '{}' is not a git repository", app_id));
+
+            let is_git = app_path.join(".git").is_dir();
+            if !is_git {
+                return reply().json(json!({
+                    "app_id": app_id,
+                    "is_git": false,
+                }));
             }
 
-            let key = body.get("key").and_then(|v| v.as_str());
-            let app_path_str = app_path.to_string_lossy().to_string();
-            let args = vec!["-C", &app_path_str, "pull"];
+            // Get branch name
+            let branch = run_git(&["-C", &app_path.to_string_lossy(), "branch", "--show-current"], None, None, &owner)
+                .unwrap_or_default()
+                .trim()
+                .to_string();
 
-            let output = run_git(&args, None, key)
-                .map_err(|e| YetiError::Internal(e))?;
+            // Get remote URL
+            let remote_url = run_git(&["-C", &app_path.to_string_lossy(), "remote", "get-url", "origin"], None, None, &owner)
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+
+            // Check if dirty
+            let status_output = run_git(&["-C", &app_path.to_string_lossy(), "status", "--porcelain"], None, None, &owner)
+                .unwrap_or_default();
+            let dirty = !status_output.trim().is_empty();
 
             reply().json(json!({
                 "app_id": app_id,
-                "pulled": true,
-                "output": output.trim(),
+                "is_git": true,
+                "branch": branch,
+                "remote_url": remote_url,
+                "dirty": dirty,
+                "deployKey": crate::keys::default_for(&app_id),
             }))
+        })
+    });
 
-        } else {
-            bad_request("Unknown repos operation. Use /repos/clone or /repos/pull/{app_id}")
-        }
+    post!(request, ctx, {
+        crate::metered!(request, "repos", "POST", {
+            let body = request.json_value()?;
+            let owner = crate::actor!(request);
+
+            // Parse the request URI to determine the operation
+            let uri_path = request.uri().path();
+
+            if uri_path.trim_end_matches('/').ends_with("/repos/check") {
+                // --- Check repo accessibility ---
+                let url = body.require_str("url")?;
+
+                validate_git_url(&url)
+                    .map_err(|e| YetiError::Validation(e))?;
+
+                // Run git ls-remote with timeout to check public accessibility
+                let mut cmd = std::process::Command::new("git");
+                cmd.args(["ls-remote", "--exit-code", &url]);
+                cmd.env("GIT_TERMINAL_PROMPT", "0");
+                cmd.env("GIT_SSH_COMMAND", "ssh -o ConnectTimeout=5 -o StrictHostKeyChecking=accept-new -o BatchMode=yes");
+                cmd.stdout(std::process::Stdio::null());
+                cmd.stderr(std::process::Stdio::null());
+
+                let mut child = crate::processes::spawn("git", &owner, &mut cmd)
+                    .map_err(|e| YetiError::Internal(format!("Failed to run git: {}", e)))?;
+
+                let start = std::time::Instant::now();
+                let timeout = std::time::Duration::from_secs(10);
+                let is_public = loop {
+                    match child.try_wait() {
+                        Ok(Some(status)) => break status.success(),
+                        Ok(None) => {
+                            if start.elapsed() > timeout {
+                                let _ = child.kill();
+                                break false;
+                            }
+                            std::thread::sleep(std::time::Duration::from_millis(100));
+                        }
+                        Err(_) => break false,
+                    }
+                };
+                crate::processes::reaped(child.id());
+                crate::metrics::record_git("ls-remote", is_public, start.elapsed());
+
+                reply().json(json!({
+                    "url": url,
+                    "public": is_public,
+                }))
+
+            } else if uri_path.contains("/repos/clone") {
+                // --- Clone operation ---
+                let url = body.require_str("url")?;
+
+                validate_git_url(&url)
+                    .map_err(|e| YetiError::Validation(e))?;
+
+                let app_id = body.get("app_id")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .or_else(|| extract_repo_name(&url))
+                    .ok_or_else(|| YetiError::Validation("Cannot determine app_id from URL, please provide 'app_id'".to_string()))?;
+
+                validate_identifier(&app_id, "app_id")?;
+
+                let app_path = get_apps_directory().join(&app_id);
+                if app_path.exists() {
+                    return bad_request(&format!("Application '{}' already exists", app_id));
+                }
+
+                let key = body.get("key").and_then(|v| v.as_str());
+
+                let query = request.uri().query().unwrap_or("");
+                if parse_query_param(query, "wait").as_deref() != Some("true") {
+                    let job_id = {
+                        let now = now_secs();
+                        let mut jobs = clones().lock().unwrap();
+                        jobs.retain(|_, j| j.finished_at.is_none_or(|f| now - f < CLONE_JOB_TTL_SECS));
+                        if jobs.values().any(|j| j.app_id == app_id && j.status == "running") {
+                            return bad_request(&format!("A clone into '{}' is already running", app_id));
+                        }
+                        let job_id = format!("{}-{}", app_id, crate::webhooks::random_hex(4).map_err(YetiError::Internal)?);
+                        jobs.insert(job_id.clone(), CloneJob {
+                            app_id: app_id.clone(),
+                            url: redact_url(&url),
+                            status: "running".to_string(),
+                            phase: None,
+                            phase_percent: None,
+                            objects: None,
+                            received: None,
+                            rate: None,
+                            percent: 0,
+                            started_at: now,
+                            finished_at: None,
+                            output: Vec::new(),
+                            error: None,
+                        });
+                        job_id
+                    };
+
+                    crate::audit!(request, ctx, "repo.clone", &app_id, json!({"url": redact_url(&url), "job_id": job_id}));
+
+                    // The outcome is published once the clone ends, like the audited start
+                    let admin_events = ctx.get_table("AdminEvent").ok();
+                    let (job, url, key, owner, app) = (job_id.clone(), url.clone(), key.map(String::from), owner.clone(), app_id.clone());
+                    tokio::spawn(async move {
+                        let (clone_job, clone_url, target) = (job.clone(), url.clone(), app.clone());
+                        let result = tokio::task::spawn_blocking(move || {
+                            let mut result = run_clone(&clone_job, &clone_url, &app_path, key.as_deref(), &owner);
+                            if result.is_err() {
+                                let _ = std::fs::remove_dir_all(&app_path);
+                            } else if let Some(key) = key.as_deref() {
+                                result = crate::keys::set_default(&app, Some(key));
+                            }
+                            update_clone(&clone_job, |j| {
+                                j.finished_at = Some(now_secs());
+                                match &result {
+                                    Ok(()) => {
+                                        j.status = "succeeded".to_string();
+                                        j.percent = 100;
+                                    }
+                                    Err(e) => {
+                                        yeti_log!(warn, "Clone of {} failed: {}", redact_url(&clone_url), e);
+                                        j.status = "failed".to_string();
+                                        j.error = Some(e.clone());
+                                    }
+                                }
+                            });
+                            result
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(format!("Clone task failed: {}", e)));
+
+                        let (kind, error) = match result {
+                            Ok(()) => ("repo.clone.succeeded", None),
+                            Err(e) => ("repo.clone.failed", Some(e)),
+                        };
+                        let event = crate::events::event(kind, &target, json!({
+                            "url": redact_url(&url),
+                            "job_id": job,
+                            "error": error,
+                        }));
+                        crate::webhooks::dispatch(&event);
+                        if let Some(table) = &admin_events {
+                            let _ = table.put(event).await;
+                        }
+                    });
+
+                    return reply().code(202).json(json!({
+                        "app_id": app_id,
+                        "job_id": job_id,
+                        "status": "running",
+                    }));
+                }
+
+                // Run git clone
+                let app_path_str = app_path.to_string_lossy().to_string();
+                let args = vec!["clone", &url, &app_path_str];
+
+                let output = run_git(&args, None, key, &owner)
+                    .map_err(|e| {
+                        // Clean up partial clone if it exists
+                        let _ = std::fs::remove_dir_all(&app_path);
+                        YetiError::Internal(e)
+                    })?;
+                if let Some(key) = key {
+                    crate::keys::set_default(&app_id, Some(key)).map_err(YetiError::Internal)?;
+                }
+
+                crate::audit!(request, ctx, "repo.clone", &app_id, json!({"url": redact_url(&url)}));
+
+                reply().code(201).json(json!({
+                    "app_id": app_id,
+                    "cloned": true,
+                    "output": output.trim(),
+                }))
+
+            } else if uri_path.contains("/repos/pull/") {
+                // --- Pull operation ---
+                // Extract app_id from the URI path after /repos/pull/
+                let app_id = uri_path
+                    .rsplit('/')
+                    .next()
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| YetiError::Validation("App ID required in path (use /repos/pull/{app_id})".to_string()))?
+                    .to_string();
+
+                validate_identifier(&app_id, "app_id")?;
+
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+
+                let key = resolve_key(&app_id, &body);
+                let app_path_str = app_path.to_string_lossy().to_string();
+                let args = vec!["-C", &app_path_str, "pull"];
+
+                let output = run_git(&args, None, key.as_deref(), &owner)
+                    .map_err(|e| YetiError::Internal(e))?;
+
+                crate::audit!(request, ctx, "repo.pull", &app_id, json!({}));
+
+                reply().json(json!({
+                    "app_id": app_id,
+                    "pulled": true,
+                    "output": output.trim(),
+                }))
+
+            } else if uri_path.contains("/repos/checkout/") {
+                // --- Checkout operation ---
+                let app_id = app_id_after(uri_path, "checkout")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+
+                let branch = body.require_str("branch")?;
+                validate_branch(&branch, &owner).map_err(YetiError::Validation)?;
+                let create = body.get("create").and_then(|v| v.as_bool()).unwrap_or(false);
+                let key = resolve_key(&app_id, &body);
+                let key = key.as_deref();
+                let app_path_str = app_path.to_string_lossy().to_string();
+
+                let has_ref = |refname: &str| {
+                    run_git(&["-C", &app_path_str, "show-ref", "--verify", "--quiet", refname], None, None, &owner).is_ok()
+                };
+                let local_ref = format!("refs/heads/{}", branch);
+                let remote_ref = format!("refs/remotes/origin/{}", branch);
+                let remote_branch = format!("origin/{}", branch);
+
+                // Fetch only when the branch is not known locally yet
+                if !has_ref(&local_ref) && !has_ref(&remote_ref) && !create {
+                    run_git(&["-C", &app_path_str, "fetch", "origin"], None, key, &owner)
+                        .map_err(YetiError::Internal)?;
+                }
+                let (args, created, tracking): (Vec<&str>, bool, Option<&str>) = if has_ref(&local_ref) {
+                    (vec!["-C", &app_path_str, "checkout", &branch], false, None)
+                } else if has_ref(&remote_ref) {
+                    (vec!["-C", &app_path_str, "checkout", "-b", &branch, "--track", &remote_branch], true, Some(&remote_branch))
+                } else if create {
+                    (vec!["-C", &app_path_str, "checkout", "-b", &branch], true, None)
+                } else {
+                    return not_found(&format!("Branch '{}' not found locally or on origin (set create to start it)", branch));
+                };
+
+                let output = run_git(&args, None, None, &owner)
+                    .map_err(YetiError::Internal)?;
+
+                if let Err(e) = crate::env::reload_app(&app_path) {
+                    yeti_log!(warn, "Switched '{}' to {} but reload failed: {}", app_id, branch, e);
+                }
+
+                crate::audit!(request, ctx, "repo.checkout", &app_id, json!({
+                    "branch": branch,
+                    "created": created,
+                }));
+
+                reply().json(json!({
+                    "app_id": app_id,
+                    "branch": branch,
+                    "created": created,
+                    "tracking": tracking,
+                    "output": output.trim(),
+                }))
+
+            } else if uri_path.contains("/repos/commit/") {
+                // --- Commit operation ---
+                let app_id = app_id_after(uri_path, "commit")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+
+                let message = body.require_str("message")?;
+                if message.trim().is_empty() {
+                    return bad_request("'message' must not be empty");
+                }
+                let files: Vec<String> = match body.get("files") {
+                    None | Some(serde_json::Value::Null) => Vec::new(),
+                    Some(serde_json::Value::Array(items)) => {
+                        let files: Option<Vec<String>> = items.iter().map(|v| v.as_str().map(String::from)).collect();
+                        let Some(files) = files else { return bad_request("'files' must be an array of paths") };
+                        for file in &files {
+                            validate_pathspec(file).map_err(YetiError::Validation)?;
+                        }
+                        files
+                    }
+                    Some(_) => return bad_request("'files' must be an array of paths"),
+                };
+                let author = match body.get("author").and_then(|v| v.as_str()) {
+                    Some(author) => match parse_author(author) {
+                        Some(_) => Some(author.to_string()),
+                        None => return bad_request("'author' must look like 'Name <email>'"),
+                    },
+                    None => None,
+                };
+                let app_path_str = app_path.to_string_lossy().to_string();
+
+                // Stage the requested files (or everything), then commit only those
+                let mut add_args = vec!["-C", app_path_str.as_str(), "add", "-A", "--"];
+                add_args.extend(files.iter().map(|f| f.as_str()));
+                run_git(&add_args, None, None, &owner).map_err(YetiError::Internal)?;
+
+                // Only the requested files count: other staged changes are not committed
+                let mut diff_args = vec!["-C", app_path_str.as_str(), "diff", "--cached", "--name-only", "--"];
+                diff_args.extend(files.iter().map(|f| f.as_str()));
+                let staged = run_git(&diff_args, None, None, &owner).map_err(YetiError::Internal)?;
+                if staged.trim().is_empty() {
+                    return bad_request(&format!("Nothing to commit in '{}'", app_id));
+                }
+
+                // Without a configured identity git refuses to commit, so fall
+                // back to the author or the calling user as committer
+                let has_identity = run_git(&["-C", &app_path_str, "config", "user.email"], None, None, &owner).is_ok();
+                let identity = if has_identity {
+                    Vec::new()
+                } else {
+                    let (name, email) = author
+                        .as_deref()
+                        .and_then(parse_author)
+                        .map(|(n, e)| (n.to_string(), e.to_string()))
+                        .unwrap_or_else(|| (owner.to_string(), format!("{}@yeti.local", owner)));
+                    vec!["-c".to_string(), format!("user.name={}", name), "-c".to_string(), format!("user.email={}", email)]
+                };
+
+                let mut commit_args = vec!["-C", app_path_str.as_str()];
+                commit_args.extend(identity.iter().map(|a| a.as_str()));
+                commit_args.extend(["commit", "-m", message.as_str()]);
+                let author_arg = author.as_ref().map(|a| format!("--author={}", a));
+                if let Some(arg) = &author_arg {
+                    commit_args.push(arg);
+                }
+                if !files.is_empty() {
+                    commit_args.push("--");
+                    commit_args.extend(files.iter().map(|f| f.as_str()));
+                }
+                let output = run_git(&commit_args, None, None, &owner).map_err(YetiError::Internal)?;
+
+                let commit = run_git(&["-C", &app_path_str, "rev-parse", "HEAD"], None, None, &owner)
+                    .map_err(YetiError::Internal)?
+                    .trim()
+                    .to_string();
+                let changed: Vec<&str> = staged.lines().filter(|l| !l.is_empty()).collect();
+
+                crate::audit!(request, ctx, "repo.commit", &app_id, json!({
+                    "commit": commit,
+                    "message": message,
+                    "files": changed,
+                }));
+
+                reply().code(201).json(json!({
+                    "app_id": app_id,
+                    "commit": commit,
+                    "files": changed,
+                    "output": output.trim(),
+                }))
+
+            } else if uri_path.contains("/repos/push/") {
+                // --- Push operation ---
+                let app_id = app_id_after(uri_path, "push")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+
+                let key = resolve_key(&app_id, &body);
+                let key = key.as_deref();
+                let app_path_str = app_path.to_string_lossy().to_string();
+                let branch = run_git(&["-C", &app_path_str, "branch", "--show-current"], None, None, &owner)
+                    .map_err(YetiError::Internal)?
+                    .trim()
+                    .to_string();
+                if branch.is_empty() {
+                    return bad_request(&format!("'{}' is in detached HEAD state; check out a branch first", app_id));
+                }
+                let has_upstream = run_git(
+                    &["-C", &app_path_str, "rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+                    None,
+                    None,
+                    &owner,
+                ).is_ok();
+
+                let mut args = vec!["-C", app_path_str.as_str(), "push"];
+                if !has_upstream {
+                    args.push("--set-upstream");
+                }
+                args.extend(["origin", branch.as_str()]);
+
+                let output = match run_git(&args, None, key, &owner) {
+                    Ok(output) => output,
+                    Err(e) if e.contains("[rejected]") || e.contains("non-fast-forward") || e.contains("fetch first") => {
+                        return reply().code(409).json(json!({
+                            "error": format!("Push of '{}' was rejected; pull first", branch),
+                            "output": e,
+                        }));
+                    }
+                    Err(e) => return Err(YetiError::Internal(e)),
+                };
+
+                crate::audit!(request, ctx, "repo.push", &app_id, json!({
+                    "branch": branch,
+                    "setUpstream": !has_upstream,
+                }));
+
+                reply().json(json!({
+                    "app_id": app_id,
+                    "branch": branch,
+                    "pushed": true,
+                    "setUpstream": !has_upstream,
+                    "output": output.trim(),
+                }))
+
+            } else if uri_path.contains("/repos/reset/") {
+                // --- Reset operation (two-step) ---
+                let app_id = app_id_after(uri_path, "reset")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+
+                let mode = body.require_str("mode")?;
+                if mode != "soft" && mode != "hard" {
+                    return bad_request("'mode' must be 'soft' or 'hard'");
+                }
+                let git_ref = body.get("ref").and_then(|v| v.as_str()).unwrap_or("HEAD").to_string();
+                if git_ref.is_empty()
+                    || git_ref.starts_with('-')
+                    || !git_ref.chars().all(|c| c.is_ascii_alphanumeric() || "._/~^-@".contains(c))
+                {
+                    return bad_request(&format!("Invalid ref '{}'", git_ref));
+                }
+                let app_path_str = app_path.to_string_lossy().to_string();
+
+                let target = match run_git(
+                    &["-C", &app_path_str, "rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", git_ref)],
+                    None,
+                    None,
+                    &owner,
+                ) {
+                    Ok(sha) => sha.trim().to_string(),
+                    Err(_) => return not_found(&format!("Ref '{}' not found", git_ref)),
+                };
+                let action = format!("reset:{}:{}", mode, target);
+                let state = repo_state(&app_path_str, &owner).map_err(YetiError::Internal)?;
+
+                let Some(token) = body.get("confirm").and_then(|v| v.as_str()) else {
+                    // Preview: commits moved off the branch and changes that would be lost
+                    let dropped = run_git(&["-C", &app_path_str, "log", "--format=%h %s", &format!("{}..HEAD", target)], None, None, &owner)
+                        .unwrap_or_default();
+                    let discarded = if mode == "hard" {
+                        run_git(&["-C", &app_path_str, "diff", "--name-only", "HEAD"], None, None, &owner).unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    let token = issue_confirm(&app_id, &action, state).map_err(YetiError::Internal)?;
+                    return reply().json(json!({
+                        "app_id": app_id,
+                        "confirmationRequired": true,
+                        "confirm": token,
+                        "expiresIn": CONFIRM_TTL_SECS,
+                        "mode": mode,
+                        "ref": git_ref,
+                        "target": target,
+                        "commitsDropped": dropped.lines().filter(|l| !l.is_empty()).collect::<Vec<_>>(),
+                        "filesDiscarded": discarded.lines().filter(|l| !l.is_empty()).collect::<Vec<_>>(),
+                    }));
+                };
+                if let Err(e) = take_confirm(token, &app_id, &action, &state) {
+                    return reply().code(409).json(json!({"error": e}));
+                }
+
+                let previous = run_git(&["-C", &app_path_str, "rev-parse", "HEAD"], None, None, &owner)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                let output = run_git(&["-C", &app_path_str, "reset", &format!("--{}", mode), &target], None, None, &owner)
+                    .map_err(YetiError::Internal)?;
+                if mode == "hard" {
+                    if let Err(e) = crate::env::reload_app(&app_path) {
+                        yeti_log!(warn, "Reset '{}' to {} but reload failed: {}", app_id, target, e);
+                    }
+                }
+
+                crate::audit!(request, ctx, "repo.reset", &app_id, json!({
+                    "mode": mode,
+                    "ref": git_ref,
+                    "from": previous,
+                    "to": target,
+                }));
+
+                reply().json(json!({
+                    "app_id": app_id,
+                    "reset": true,
+                    "mode": mode,
+                    "from": previous,
+                    "to": target,
+                    "output": output.trim(),
+                }))
+
+            } else if uri_path.contains("/repos/discard/") {
+                // --- Discard file changes (two-step) ---
+                let app_id = app_id_after(uri_path, "discard")?;
+                let app_path = get_apps_directory().join(&app_id);
+                if !app_path.is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                if !app_path.join(".git").is_dir() {
+                    return bad_request(&format!("Application '{}' is not a git repository", app_id));
+                }
+
+                let files: Option<Vec<String>> = body.get("files")
+                    .and_then(|v| v.as_array())
+                    .map(|items| items.iter().filter_map(|v| v.as_str().map(String::from)).collect());
+                let Some(mut files) = files.filter(|f| !f.is_empty()) else {
+                    return bad_request("'files' must be a non-empty array of paths");
+                };
+                for file in &files {
+                    validate_pathspec(file).map_err(YetiError::Validation)?;
+                }
+                files.sort();
+                files.dedup();
+                let app_path_str = app_path.to_string_lossy().to_string();
+
+                // Split the requested paths into tracked changes and untracked files
+                let mut status_args = vec!["-C", app_path_str.as_str(), "status", "--porcelain", "-z", "--untracked-files=all", "--"];
+                status_args.extend(files.iter().map(|f| f.as_str()));
+                let status = run_git(&status_args, None, None, &owner).map_err(YetiError::Internal)?;
+                let (mut tracked, mut untracked) = (Vec::new(), Vec::new());
+                // "XY path\0", renames and copies are followed by "old\0"
+                let mut entries = status.split('\0');
+                while let Some(entry) = entries.next() {
+                    if entry.len() < 4 {
+                        continue;
+                    }
+                    let (code, path) = (&entry[..2], entry[3..].to_string());
+                    if code == "??" {
+                        untracked.push(path);
+                    } else {
+                        if code.starts_with('R') || code.starts_with('C') {
+                            tracked.extend(entries.next().map(String::from));
+                        }
+                        tracked.push(path);
+                    }
+                }
+                if tracked.is_empty() && untracked.is_empty() {
+                    return bad_request("No changes to discard in the given files");
+                }
+
+                let action = format!("discard:{}", files.join("\n"));
+                let state = repo_state(&app_path_str, &owner).map_err(YetiError::Internal)?;
+                let Some(token) = body.get("confirm").and_then(|v| v.as_str()) else {
+                    let token = issue_confirm(&app_id, &action, state).map_err(YetiError::Internal)?;
+                    return reply().json(json!({
+                        "app_id": app_id,
+                        "confirmationRequired": true,
+                        "confirm": token,
+                        "expiresIn": CONFIRM_TTL_SECS,
+                        "restored": tracked,
+                        "deleted": untracked,
+                    }));
+                };
+                if let Err(e) = take_confirm(token, &app_id, &action, &state) {
+                    return reply().code(409).json(json!({"error": e}));
+                }
+
+                let has_head = run_git(&["-C", &app_path_str, "rev-parse", "--verify", "--quiet", "HEAD"], None, None, &owner).is_ok();
+                if !tracked.is_empty() {
+                    // Without a commit there is nothing to restore from; unstage instead
+                    let mut args = vec!["-C", app_path_str.as_str()];
+                    if has_head {
+                        args.extend(["restore", "--source=HEAD", "--staged", "--worktree", "--"]);
+                    } else {
+                        args.extend(["rm", "--cached", "-r", "--quiet", "--"]);
+                    }
+                    args.extend(tracked.iter().map(|f| f.as_str()));
+                    run_git(&args, None, None, &owner).map_err(YetiError::Internal)?;
+                }
+                if !untracked.is_empty() {
+                    let mut args = vec!["-C", app_path_str.as_str(), "clean", "-f", "--"];
+                    args.extend(untracked.iter().map(|f| f.as_str()));
+                    run_git(&args, None, None, &owner).map_err(YetiError::Internal)?;
+                }
+
+                crate::audit!(request, ctx, "repo.discard", &app_id, json!({
+                    "restored": tracked,
+                    "deleted": untracked,
+                }));
+
+                reply().json(json!({
+                    "app_id": app_id,
+                    "discarded": true,
+                    "restored": tracked,
+                    "deleted": untracked,
+                }))
+
+            } else if uri_path.contains("/repos/key/") {
+                // --- Default deploy key ---
+                let app_id = app_id_after(uri_path, "key")?;
+                if !get_apps_directory().join(&app_id).is_dir() {
+                    return not_found(&format!("Application '{}' not found", app_id));
+                }
+                let key = match body.get("key") {
+                    Some(serde_json::Value::Null) => None,
+                    Some(serde_json::Value::String(name)) => {
+                        validate_identifier(name, "key")?;
+                        Some(name.as_str())
+                    }
+                    _ => return bad_request("'key' must be a key name or null"),
+                };
+                crate::keys::set_default(&app_id, key).map_err(YetiError::Validation)?;
+
+                crate::audit!(request, ctx, "repo.key", &app_id, json!({"key": key}));
+
+                reply().json(json!({
+                    "app_id": app_id,
+                    "deployKey": key,
+                }))
+
+            } else {
+                bad_request("Unknown repos operation. Use /repos/clone, or /repos/{pull,checkout,commit,push,reset,discard,key}/{app_id}")
+            }
+        })
     });
 }
 
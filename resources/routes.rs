@@ -0,0 +1,192 @@
+//! Route Inspector Resource
+//!
+//! Enumerates the HTTP routes exposed by every enabled app, to debug path
+//! collisions and see what is actually reachable.
+//!
+//! | Method | Path                       | Description                          |
+//! |--------|----------------------------|--------------------------------------|
+//! | GET    | /admin/routes              | All routes plus detected collisions  |
+//! | GET    | /admin/routes?app={app_id} | Routes of one app                    |
+//!
+//! Routes are derived from what the runtime loads for each app: the route
+//! prefix (`route_prefix`, default `/{app_id}`), each resource's `name()`,
+//! implemented methods and `is_public()`, REST routes of `@export` tables, and
+//! the `static_files` route.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use yeti_core::prelude::*;
+
+pub type Routes = RoutesResource;
+
+const HANDLER_MACROS: &[(&str, &str)] = &[
+    ("get!(", "GET"),
+    ("post!(", "POST"),
+    ("put!(", "PUT"),
+    ("patch!(", "PATCH"),
+    ("delete!(", "DELETE"),
+];
+
+/// Resource name, methods and public flag from a resource source file
+fn scan_resource(source: &str) -> Option<(String, Vec<&'static str>, bool)> {
+    // The first name() whose body is a string literal
+    let name = source.match_indices("fn name(&self)").find_map(|(i, _)| {
+        let after = &source[i..];
+        let body = after[after.find('{')? + 1..].trim_start().strip_prefix('"')?;
+        Some(body[..body.find('"')?].to_string())
+    })?;
+
+    let methods = HANDLER_MACROS
+        .iter()
+        .filter(|(pattern, _)| {
+            source.lines().any(|l| l.trim_start().starts_with(pattern))
+        })
+        .map(|(_, method)| *method)
+        .collect();
+
+    let public = source.find("fn is_public(&self)").is_some_and(|i| {
+        let body = &source[i..];
+        let body = &body[..body.find('}').unwrap_or(body.len())];
+        body.contains("true")
+    });
+
+    Some((name, methods, public))
+}
+
+fn route(app_id: &str, method: &str, path: String, kind: &str, owner: &str, public: bool) -> serde_json::Value {
+    json!({
+        "method": method,
+        "path": path,
+        "app_id": app_id,
+        "kind": kind,
+        "owner": owner,
+        "public": public,
+    })
+}
+
+fn app_routes(app_id: &str, app_path: &Path, config: &serde_yaml::Value) -> Vec<serde_json::Value> {
+    let prefix = config
+        .get("route_prefix")
+        .and_then(|v| v.as_str())
+        .map(|p| p.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("/{}", app_id));
+    let mut routes = Vec::new();
+
+    // Custom resources
+    let resources_dir = app_path.join("resources");
+    let mut files: Vec<_> = std::fs::read_dir(&resources_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "rs"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    for file in files {
+        let Ok(source) = std::fs::read_to_string(&file) else { continue };
+        let Some((name, methods, public)) = scan_resource(&source) else { continue };
+        let owner = format!("resources/{}", file.file_name().unwrap_or_default().to_string_lossy());
+        for method in methods {
+            routes.push(route(app_id, method, format!("{}/{}", prefix, name), "resource", &owner, public));
+            routes.push(route(app_id, method, format!("{}/{}/{{id}}", prefix, name), "resource", &owner, public));
+        }
+    }
+
+    // REST routes of exported tables
+    for table in crate::schemas::app_tables(app_path) {
+        if table["exported"] != true {
+            continue;
+        }
+        let name = table["name"].as_str().unwrap_or("");
+        let owner = format!("table {}", name);
+        for method in ["GET", "POST"] {
+            routes.push(route(app_id, method, format!("{}/{}", prefix, name), "table", &owner, false));
+        }
+        for method in ["GET", "PUT", "PATCH", "DELETE"] {
+            routes.push(route(app_id, method, format!("{}/{}/{{id}}", prefix, name), "table", &owner, false));
+        }
+    }
+
+    // Static files
+    if let Some(static_route) = config.get("static_files").and_then(|s| s.get("route")).and_then(|v| v.as_str()) {
+        let path = format!("{}/{}*", prefix, static_route.trim_matches('/'))
+            .replace("//", "/");
+        routes.push(route(app_id, "GET", path, "static", "static_files", true));
+    }
+
+    routes
+}
+
+#[derive(Default)]
+pub struct RoutesResource;
+
+impl Resource for RoutesResource {
+    fn name(&self) -> &str {
+        "routes"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "routes", "GET", {
+            let query = request.uri().query().unwrap_or("");
+            let app_filter = parse_query_param(query, "app");
+
+            let entries = std::fs::read_dir(get_apps_directory())
+                .map_err(|e| YetiError::Internal(format!("Cannot read applications dir: {}", e)))?;
+
+            let mut routes = Vec::new();
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Some(app_id) = entry.file_name().to_str().map(String::from) else { continue };
+                if !path.is_dir() || app_id.starts_with('.') {
+                    continue;
+                }
+                let Some(config) = std::fs::read_to_string(path.join("config.yaml"))
+                    .ok()
+                    .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+                else {
+                    continue;
+                };
+                if !config.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    continue;
+                }
+                routes.extend(app_routes(&app_id, &path, &config));
+            }
+
+            // Collisions are reported across all apps, even when filtering
+            let mut by_route: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+            for r in &routes {
+                let key = (r["method"].as_str().unwrap_or("").to_string(), r["path"].as_str().unwrap_or("").to_string());
+                by_route.entry(key).or_default().push(format!("{} ({})",
+                    r["app_id"].as_str().unwrap_or(""), r["owner"].as_str().unwrap_or("")));
+            }
+            let collisions: Vec<serde_json::Value> = by_route
+                .into_iter()
+                .filter(|(_, owners)| owners.len() > 1)
+                .filter(|(_, owners)| {
+                    app_filter.as_ref().is_none_or(|app| owners.iter().any(|o| o.starts_with(&format!("{} ", app))))
+                })
+                .map(|((method, path), owners)| json!({
+                    "method": method,
+                    "path": path,
+                    "owners": owners,
+                }))
+                .collect();
+
+            if let Some(app) = &app_filter {
+                routes.retain(|r| r["app_id"] == app.as_str());
+            }
+            routes.sort_by(|a, b| {
+                (a["path"].as_str(), a["method"].as_str()).cmp(&(b["path"].as_str(), b["method"].as_str()))
+            });
+
+            reply().json(json!({
+                "routes": routes,
+                "collisions": collisions,
+            }))
+        })
+    });
+}
+
+register_resource!(RoutesResource);
@@ -1,12 +1,31 @@
 //! Schema/Table Discovery Resource
 //!
-//! Reads schema.graphql from each app and extracts @table directives.
+//! Parses each app's schema.graphql and schemas/*.graphql with a GraphQL
+//! parser and describes its `@table` types, interfaces and enums.
 //!
-//! | Method | Path                                    | Description                   |
-//! |--------|-----------------------------------------|-------------------------------|
-//! | GET    | /yeti-applications/schemas/{app_id}     | Tables with fields & REST URL |
+//! | Method | Path                                         | Description                   |
+//! |--------|----------------------------------------------|-------------------------------|
+//! | GET    | /yeti-applications/schemas/{app_id}          | Tables with fields & REST URL |
+//! | POST   | /yeti-applications/schemas/{app_id}/validate | Check Yeti schema rules       |
+//!
+//! Each table has its `database`, whether it is `exported`, the interfaces it
+//! `implements`, all directives with their arguments, and its `@relationship`
+//! fields (`target` type and directive arguments). Each field has its full
+//! `type` (`[String]!`), `base` type, `nullable`, `list`, `indexed`,
+//! arguments and directives. Definitions carry the `line` they start on.
+//! Files that fail to parse are listed under `errors` and contribute nothing.
+//!
+//! Validate checks parse errors and Yeti's rules: every `@table` type has an
+//! `id` field, `@relationship` fields target a `@table` type and their
+//! `from`/`to` name existing fields, `@indexed` fields are scalars or enums,
+//! and no reserved or duplicate names are used. Each diagnostic has a
+//! `level`, `code`, `message`, `file`, `line` and `column`. The body may give
+//! unsaved content as `{"file": "schema.graphql", "content": "..."}`.
 
-use std::path::PathBuf;
+use graphql_parser::schema::{Definition, Directive, Field, Type, TypeDefinition, Value};
+use graphql_parser::Pos;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use yeti_core::prelude::*;
 
 pub type Schemas = SchemasResource;
@@ -19,81 +38,367 @@ fn apps_dir() -> PathBuf {
     get_apps_directory()
 }
 
-/// Parse schema.graphql to extract table definitions
-fn parse_schema(content: &str) -> Vec<serde_json::Value> {
-    let mut tables = Vec::new();
-    let mut current_table: Option<(String, String)> = None; // (name, database)
-    let mut current_fields: Vec<serde_json::Value> = Vec::new();
+/// Directive argument or default value as JSON
+fn value_json(value: &Value<String>) -> serde_json::Value {
+    match value {
+        Value::Variable(name) => json!(format!("${}", name)),
+        Value::Int(n) => json!(n.as_i64()),
+        Value::Float(f) => json!(f),
+        Value::String(s) | Value::Enum(s) => json!(s),
+        Value::Boolean(b) => json!(b),
+        Value::Null => serde_json::Value::Null,
+        Value::List(items) => json!(items.iter().map(value_json).collect::<Vec<_>>()),
+        Value::Object(map) => json!(map.iter().map(|(k, v)| (k.clone(), value_json(v))).collect::<serde_json::Map<_, _>>()),
+    }
+}
+
+fn directives_json(directives: &[Directive<String>]) -> Vec<serde_json::Value> {
+    directives
+        .iter()
+        .map(|d| json!({
+            "name": d.name,
+            "arguments": d.arguments.iter().map(|(k, v)| (k.clone(), value_json(v))).collect::<serde_json::Map<_, _>>(),
+            "line": d.position.line,
+        }))
+        .collect()
+}
+
+fn has_directive(directives: &[Directive<String>], name: &str) -> bool {
+    directives.iter().any(|d| d.name == name)
+}
+
+/// String argument of the first directive called `name`
+fn directive_str(directives: &[Directive<String>], name: &str, argument: &str) -> Option<String> {
+    let directive = directives.iter().find(|d| d.name == name)?;
+    directive.arguments.iter().find(|(k, _)| k == argument).and_then(|(_, v)| match v {
+        Value::String(s) => Some(s.clone()),
+        _ => None,
+    })
+}
+
+/// Named type under any list and non-null wrappers
+fn base_type(ty: &Type<String>) -> String {
+    match ty {
+        Type::NamedType(name) => name.clone(),
+        Type::ListType(inner) | Type::NonNullType(inner) => base_type(inner),
+    }
+}
+
+fn is_list(ty: &Type<String>) -> bool {
+    match ty {
+        Type::NonNullType(inner) => matches!(**inner, Type::ListType(_)),
+        ty => matches!(ty, Type::ListType(_)),
+    }
+}
+
+fn field_json(field: &Field<String>) -> serde_json::Value {
+    json!({
+        "name": field.name,
+        "type": field.field_type.to_string(),
+        "base": base_type(&field.field_type),
+        "nullable": !matches!(field.field_type, Type::NonNullType(_)),
+        "list": is_list(&field.field_type),
+        "indexed": has_directive(&field.directives, "indexed"),
+        "description": field.description,
+        "arguments": field.arguments.iter().map(|a| json!({
+            "name": a.name,
+            "type": a.value_type.to_string(),
+            "default": a.default_value.as_ref().map(value_json),
+        })).collect::<Vec<_>>(),
+        "directives": directives_json(&field.directives),
+        "line": field.position.line,
+    })
+}
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+/// Parse a schema document into its tables (`@table` object types),
+/// interfaces and enums
+pub fn parse_schema(content: &str) -> std::result::Result<serde_json::Value, String> {
+    let document = graphql_parser::parse_schema::<String>(content).map_err(|e| e.to_string().trim_end().to_string())?;
+    let (mut tables, mut interfaces, mut enums) = (Vec::new(), Vec::new(), Vec::new());
 
-        // Match: type TableName @table(database: "db-name") ...
-        if trimmed.starts_with("type ") && trimmed.contains("@table") {
-            // Save previous table if any
-            if let Some((name, db)) = current_table.take() {
+    for definition in &document.definitions {
+        let Definition::TypeDefinition(definition) = definition else { continue };
+        match definition {
+            TypeDefinition::Object(object) if has_directive(&object.directives, "table") => {
+                let relationships: Vec<serde_json::Value> = object
+                    .fields
+                    .iter()
+                    .filter_map(|f| {
+                        let directive = f.directives.iter().find(|d| d.name == "relationship")?;
+                        Some(json!({
+                            "field": f.name,
+                            "target": base_type(&f.field_type),
+                            "list": is_list(&f.field_type),
+                            "arguments": directive.arguments.iter().map(|(k, v)| (k.clone(), value_json(v))).collect::<serde_json::Map<_, _>>(),
+                        }))
+                    })
+                    .collect();
                 tables.push(json!({
-                    "name": name,
-                    "database": db,
-                    "fields": current_fields.clone(),
+                    "name": object.name,
+                    "database": directive_str(&object.directives, "table", "database").unwrap_or_default(),
+                    "exported": has_directive(&object.directives, "export"),
+                    "description": object.description,
+                    "implements": object.implements_interfaces,
+                    "directives": directives_json(&object.directives),
+                    "fields": object.fields.iter().map(field_json).collect::<Vec<_>>(),
+                    "relationships": relationships,
+                    "line": object.position.line,
                 }));
-                current_fields.clear();
             }
+            TypeDefinition::Interface(interface) => interfaces.push(json!({
+                "name": interface.name,
+                "description": interface.description,
+                "directives": directives_json(&interface.directives),
+                "fields": interface.fields.iter().map(field_json).collect::<Vec<_>>(),
+                "line": interface.position.line,
+            })),
+            TypeDefinition::Enum(definition) => enums.push(json!({
+                "name": definition.name,
+                "description": definition.description,
+                "values": definition.values.iter().map(|v| v.name.clone()).collect::<Vec<_>>(),
+                "directives": directives_json(&definition.directives),
+                "line": definition.position.line,
+            })),
+            _ => {}
+        }
+    }
+
+    Ok(json!({"tables": tables, "interfaces": interfaces, "enums": enums}))
+}
 
-            // Parse table name
-            let after_type = &trimmed[5..];
-            let table_name = after_type.split_whitespace().next().unwrap_or("").to_string();
-
-            // Parse database name from @table(database: "...")
-            let database = if let Some(start) = trimmed.find("database:") {
-                let after_db = &trimmed[start + 9..];
-                let after_db = after_db.trim();
-                // Find quoted string
-                if let Some(q_start) = after_db.find('"') {
-                    let rest = &after_db[q_start + 1..];
-                    if let Some(q_end) = rest.find('"') {
-                        rest[..q_end].to_string()
-                    } else {
-                        String::new()
+/// Schema files of an app: schema.graphql and schemas/*.graphql, each with
+/// a group name (the file name without extension)
+pub fn schema_files(app_path: &Path) -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    let single = app_path.join("schema.graphql");
+    if single.exists() {
+        files.push(("schema".to_string(), single));
+    }
+
+    if let Ok(entries) = std::fs::read_dir(app_path.join("schemas")) {
+        let mut entries: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "graphql"))
+            .collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let group = entry.path().file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            files.push((group, entry.path()));
+        }
+    }
+    files
+}
+
+/// Tables, interfaces and enums of all of an app's schema files, each tagged
+/// with its group, and the files that failed to parse
+fn app_schema(app_path: &Path) -> serde_json::Value {
+    let mut schema = json!({"tables": [], "interfaces": [], "enums": [], "errors": []});
+    for (group, path) in schema_files(app_path) {
+        let Ok(content) = std::fs::read_to_string(&path) else { continue };
+        match parse_schema(&content) {
+            Ok(parsed) => {
+                for key in ["tables", "interfaces", "enums"] {
+                    for mut item in parsed[key].as_array().cloned().unwrap_or_default() {
+                        item["group"] = json!(group);
+                        schema[key].as_array_mut().unwrap().push(item);
                     }
-                } else {
-                    String::new()
                 }
-            } else {
-                String::new()
+            }
+            Err(e) => schema["errors"].as_array_mut().unwrap().push(json!({"group": group, "error": e})),
+        }
+    }
+    schema
+}
+
+/// Tables declared in an app's schema.graphql and/or schemas/*.graphql, each
+/// tagged with a group name (the file name without extension)
+pub fn app_tables(app_path: &Path) -> Vec<serde_json::Value> {
+    app_schema(app_path)["tables"].as_array().cloned().unwrap_or_default()
+}
+
+/// Scalars every schema can use
+const BUILTIN_SCALARS: &[&str] = &["ID", "String", "Int", "Float", "Boolean"];
+/// Root types of the generated GraphQL API
+const RESERVED_TYPES: &[&str] = &["Query", "Mutation", "Subscription"];
+
+fn diagnostic(level: &str, code: &str, file: &str, position: Pos, message: String) -> serde_json::Value {
+    json!({
+        "level": level,
+        "code": code,
+        "message": message,
+        "file": file,
+        "line": position.line,
+        "column": position.column,
+    })
+}
+
+/// Position and message of a parse error ("schema parse error: Parse error at
+/// 3:5\nUnexpected ...")
+fn parse_error(error: &str) -> (Pos, String) {
+    let (head, detail) = error.split_once('\n').unwrap_or((error, ""));
+    let position = head
+        .split_once(" at ")
+        .and_then(|(_, at)| at.trim().split_once(':'))
+        .and_then(|(line, column)| Some(Pos { line: line.parse().ok()?, column: column.parse().ok()? }))
+        .unwrap_or(Pos { line: 1, column: 1 });
+    let detail: Vec<&str> = detail.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    (position, if detail.is_empty() { head.to_string() } else { detail.join("; ") })
+}
+
+/// Kind, name and position of a type definition
+fn definition_info(definition: &TypeDefinition<String>) -> (&'static str, String, Pos) {
+    match definition {
+        TypeDefinition::Object(t) if has_directive(&t.directives, "table") => ("table", t.name.clone(), t.position),
+        TypeDefinition::Object(t) => ("type", t.name.clone(), t.position),
+        TypeDefinition::Interface(t) => ("interface", t.name.clone(), t.position),
+        TypeDefinition::Enum(t) => ("enum", t.name.clone(), t.position),
+        TypeDefinition::Scalar(t) => ("scalar", t.name.clone(), t.position),
+        TypeDefinition::Union(t) => ("union", t.name.clone(), t.position),
+        TypeDefinition::InputObject(t) => ("input", t.name.clone(), t.position),
+    }
+}
+
+/// Check an app's schema files against Yeti's rules. `edited` replaces (or
+/// adds) one file's content, to check unsaved changes.
+fn validate(app_path: &Path, edited: Option<(&str, &str)>) -> Vec<serde_json::Value> {
+    let mut sources: Vec<(String, String)> = schema_files(app_path)
+        .iter()
+        .filter_map(|(_, path)| {
+            let file = path.strip_prefix(app_path).ok()?.to_string_lossy().to_string();
+            Some((file, std::fs::read_to_string(path).ok()?))
+        })
+        .collect();
+    if let Some((file, content)) = edited {
+        match sources.iter_mut().find(|(f, _)| f == file) {
+            Some(source) => source.1 = content.to_string(),
+            None => sources.push((file.to_string(), content.to_string())),
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut documents = Vec::new();
+    for (file, content) in &sources {
+        match graphql_parser::parse_schema::<String>(content) {
+            Ok(document) => documents.push((file.as_str(), document)),
+            Err(e) => {
+                let (position, message) = parse_error(&e.to_string());
+                diagnostics.push(diagnostic("error", "parse", file, position, message));
+            }
+        }
+    }
+
+    // Every type across the app's files, which share one namespace
+    let mut kinds: HashMap<String, &'static str> = HashMap::new();
+    let mut table_fields: HashMap<String, Vec<String>> = HashMap::new();
+    for (file, document) in &documents {
+        for definition in &document.definitions {
+            let Definition::TypeDefinition(definition) = definition else { continue };
+            let (kind, name, position) = definition_info(definition);
+            if name.starts_with("__") || RESERVED_TYPES.contains(&name.as_str()) || BUILTIN_SCALARS.contains(&name.as_str()) {
+                diagnostics.push(diagnostic("error", "reserved-name", file, position, format!("'{}' is a reserved type name", name)));
+            }
+            if kinds.contains_key(&name) {
+                diagnostics.push(diagnostic("error", "duplicate", file, position, format!("Type '{}' is defined more than once", name)));
+                continue;
+            }
+            kinds.insert(name.clone(), kind);
+            if let TypeDefinition::Object(object) = definition {
+                if kind == "table" {
+                    table_fields.insert(name, object.fields.iter().map(|f| f.name.clone()).collect());
+                }
+            }
+        }
+    }
+
+    for (file, document) in &documents {
+        for definition in &document.definitions {
+            let Definition::TypeDefinition(definition) = definition else { continue };
+            let (fields, table) = match definition {
+                TypeDefinition::Object(object) => (&object.fields, has_directive(&object.directives, "table").then_some(object)),
+                TypeDefinition::Interface(interface) => (&interface.fields, None),
+                _ => continue,
             };
 
-            current_table = Some((table_name, database));
-        } else if trimmed == "}" {
-            // End of type block
-            if let Some((name, db)) = current_table.take() {
-                tables.push(json!({
-                    "name": name,
-                    "database": db,
-                    "fields": current_fields.clone(),
-                }));
-                current_fields.clear();
+            let mut seen = HashSet::new();
+            for field in fields {
+                let base = base_type(&field.field_type);
+                if field.name.starts_with("__") {
+                    diagnostics.push(diagnostic("error", "reserved-name", file, field.position, format!("'{}' is a reserved field name", field.name)));
+                }
+                if !seen.insert(field.name.as_str()) {
+                    diagnostics.push(diagnostic("error", "duplicate", file, field.position, format!("Field '{}' is declared more than once", field.name)));
+                }
+                if !BUILTIN_SCALARS.contains(&base.as_str()) && !kinds.contains_key(&base) {
+                    diagnostics.push(diagnostic("warning", "unknown-type", file, field.position, format!("Type '{}' of '{}' is not defined in the app's schemas", base, field.name)));
+                }
             }
-        } else if current_table.is_some() && trimmed.contains(':') && !trimmed.starts_with('#') {
-            // Field line like: fieldName: Type! @indexed
-            let parts: Vec<&str> = trimmed.splitn(2, ':').collect();
-            if parts.len() == 2 {
-                let field_name = parts[0].trim().to_string();
-                let type_part = parts[1].trim();
-                // Extract just the type (before any @directive)
-                let field_type = type_part.split('@').next().unwrap_or(type_part).trim().to_string();
-
-                if !field_name.is_empty() {
-                    current_fields.push(json!({
-                        "name": field_name,
-                        "type": field_type,
-                    }));
+
+            let Some(object) = table else { continue };
+            match object.fields.iter().find(|f| f.name == "id") {
+                None => diagnostics.push(diagnostic("error", "missing-id", file, object.position, format!("Table '{}' has no 'id' field", object.name))),
+                Some(id) if !matches!(id.field_type, Type::NonNullType(_)) => {
+                    diagnostics.push(diagnostic("warning", "nullable-id", file, id.position, format!("'id' of '{}' should be non-null ({}!)", object.name, id.field_type)));
+                }
+                Some(_) => {}
+            }
+
+            for field in &object.fields {
+                let base = base_type(&field.field_type);
+                let relationship = field.directives.iter().find(|d| d.name == "relationship");
+                if has_directive(&field.directives, "indexed") {
+                    let supported = BUILTIN_SCALARS.contains(&base.as_str()) || kinds.get(&base) == Some(&"enum");
+                    if relationship.is_some() || !supported {
+                        diagnostics.push(diagnostic("error", "unsupported-index", file, field.position, format!(
+                            "'{}' cannot be @indexed: {} fields are not indexable (use ID, String, Int, Float, Boolean or an enum)",
+                            field.name,
+                            if relationship.is_some() { "relationship".to_string() } else { format!("'{}'", base) },
+                        )));
+                    }
+                }
+
+                let Some(relationship) = relationship else { continue };
+                if kinds.get(&base) != Some(&"table") {
+                    diagnostics.push(diagnostic("error", "relationship-target", file, relationship.position, format!(
+                        "Relationship '{}' targets '{}', which is not a @table type",
+                        field.name, base,
+                    )));
+                    continue;
+                }
+                let argument = |name: &str| relationship.arguments.iter().find(|(k, _)| k == name).map(|(_, v)| v);
+                let (from, to) = (argument("from"), argument("to"));
+                if from.is_none() && to.is_none() {
+                    diagnostics.push(diagnostic("error", "relationship-key", file, relationship.position, format!("Relationship '{}' needs a 'from' or 'to' argument", field.name)));
+                }
+                for (value, table, side) in [(from, &object.name, "from"), (to, &base, "to")] {
+                    let Some(value) = value else { continue };
+                    let Value::String(key) = value else {
+                        diagnostics.push(diagnostic("error", "relationship-key", file, relationship.position, format!("'{}' of relationship '{}' must be a field name string", side, field.name)));
+                        continue;
+                    };
+                    if !table_fields.get(table).is_some_and(|f| f.contains(key)) {
+                        diagnostics.push(diagnostic("error", "relationship-key", file, relationship.position, format!(
+                            "'{}' of relationship '{}' names '{}', which is not a field of '{}'",
+                            side, field.name, key, table,
+                        )));
+                    }
                 }
             }
         }
     }
 
-    tables
+    diagnostics.sort_by(|a, b| {
+        (a["file"].as_str(), a["line"].as_u64(), a["column"].as_u64()).cmp(&(b["file"].as_str(), b["line"].as_u64(), b["column"].as_u64()))
+    });
+    diagnostics
+}
+
+/// `(app_id, action)` for `/schemas/{id}/{action}` paths
+fn schema_action(uri_path: &str) -> Option<(&str, &str)> {
+    let rest = uri_path.trim_end_matches('/').split_once("/schemas/")?.1;
+    let (app_id, action) = rest.split_once('/')?;
+    (!app_id.is_empty() && !action.is_empty() && !action.contains('/')).then_some((app_id, action))
 }
 
 impl Resource for SchemasResource {
@@ -101,70 +406,68 @@ impl Resource for SchemasResource {
         "schemas"
     }
 
-    get!(_request, ctx, {
-        let app_id = ctx.require_id()?.to_string();
+    get!(request, ctx, {
+        crate::metered!(request, "schemas", "GET", {
+            let app_id = ctx.require_id()?.to_string();
 
-        let apps_path = apps_dir();
-        let app_path = apps_path.join(&app_id);
+            let apps_path = apps_dir();
+            let app_path = apps_path.join(&app_id);
 
-        if !app_path.is_dir() {
-            return not_found(&format!("Application '{}' not found", app_id));
-        }
+            if !app_path.is_dir() {
+                return not_found(&format!("Application '{}' not found", app_id));
+            }
 
-        // Collect tables from schema.graphql and/or schemas/*.graphql
-        // Each file's tables are tagged with a group name (filename without extension)
-        let mut tables: Vec<serde_json::Value> = Vec::new();
+            let mut schema = app_schema(&app_path);
 
-        let single = app_path.join("schema.graphql");
-        if single.exists() {
-            if let Ok(c) = std::fs::read_to_string(&single) {
-                for mut t in parse_schema(&c) {
-                    t["group"] = json!("schema");
-                    tables.push(t);
+            // Add REST URL for each table
+            for table in schema["tables"].as_array_mut().unwrap() {
+                if let Some(name) = table.get("name").and_then(|v| v.as_str()) {
+                    table["rest_url"] = json!(format!("/{}/{}", app_id, name));
                 }
             }
-        }
 
-        let schemas_dir = app_path.join("schemas");
-        if schemas_dir.is_dir() {
-            if let Ok(entries) = std::fs::read_dir(&schemas_dir) {
-                let mut files: Vec<_> = entries
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "graphql"))
-                    .collect();
-                files.sort_by_key(|e| e.file_name());
-                for entry in files {
-                    let group = entry.path().file_stem()
-                        .map(|s| s.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "unknown".to_string());
-                    if let Ok(c) = std::fs::read_to_string(entry.path()) {
-                        for mut t in parse_schema(&c) {
-                            t["group"] = json!(group);
-                            tables.push(t);
-                        }
+            schema["app_id"] = json!(app_id);
+            reply().json(schema)
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "schemas", "POST", {
+            let Some((app_id, "validate")) = schema_action(request.uri().path()) else {
+                return bad_request("Use POST /schemas/{app_id}/validate");
+            };
+            validate_identifier(app_id, "app_id")?;
+            let app_path = apps_dir().join(app_id);
+            if !app_path.is_dir() {
+                return not_found(&format!("Application '{}' not found", app_id));
+            }
+
+            let body = request.json_value().unwrap_or_else(|_| json!({}));
+            let edited = match (body.get("file").and_then(|v| v.as_str()), body.get("content").and_then(|v| v.as_str())) {
+                (Some(file), Some(content)) => {
+                    let in_schemas = file
+                        .strip_prefix("schemas/")
+                        .is_some_and(|name| !name.contains('/') && !name.starts_with('.') && name.ends_with(".graphql"));
+                    if file != "schema.graphql" && !in_schemas {
+                        return bad_request("'file' must be schema.graphql or schemas/{name}.graphql");
                     }
+                    Some((file.to_string(), content.to_string()))
                 }
-            }
-        }
+                (None, None) => None,
+                _ => return bad_request("'file' and 'content' must be given together"),
+            };
 
-        if tables.is_empty() {
-            return reply().json(json!({
+            let diagnostics = validate(&app_path, edited.as_ref().map(|(f, c)| (f.as_str(), c.as_str())));
+            let errors = diagnostics.iter().filter(|d| d["level"] == "error").count();
+            let warnings = diagnostics.iter().filter(|d| d["level"] == "warning").count();
+            reply().json(json!({
                 "app_id": app_id,
-                "tables": [],
-            }));
-        }
-
-        // Add REST URL for each table
-        for table in &mut tables {
-            if let Some(name) = table.get("name").and_then(|v| v.as_str()) {
-                table["rest_url"] = json!(format!("/{}/{}", app_id, name));
-            }
-        }
-
-        reply().json(json!({
-            "app_id": app_id,
-            "tables": tables,
-        }))
+                "valid": errors == 0,
+                "errors": errors,
+                "warnings": warnings,
+                "diagnostics": diagnostics,
+            }))
+        })
     });
 }
 
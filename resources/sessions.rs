@@ -0,0 +1,292 @@
+//! Admin Session Resource
+//!
+//! Lists the credentials (bearer tokens, basic-auth logins) active against the
+//! admin API and revokes them, e.g. after a credential leak.
+//!
+//! | Method | Path                             | Description                        |
+//! |--------|----------------------------------|------------------------------------|
+//! | GET    | /admin/sessions                  | Active sessions, most recent first |
+//! | GET    | /admin/sessions?revoked=true     | Revoked sessions                   |
+//! | DELETE | /admin/sessions/{id}             | Revoke one session                 |
+//! | DELETE | /admin/sessions?actor={actor}    | Log an actor out everywhere        |
+//!
+//! A session is one distinct `Authorization` header, identified by a hash of
+//! it (the credential itself is never stored). Every admin handler records
+//! the caller's IP (`X-Forwarded-For` / `X-Real-IP`), user agent and last
+//! activity through `metered!`, which also rejects revoked sessions with 401.
+//!
+//! Logging an actor out everywhere revokes all of their known sessions and
+//! rejects bearer tokens issued (`iat`) before that moment, so tokens the admin
+//! has not seen yet are covered too. Basic-auth credentials stay revoked until
+//! the password changes. Revocations apply to the admin API; they are kept in
+//! `admin-sessions.json` in the root directory and survive restarts.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use yeti_core::prelude::*;
+
+pub type Sessions = SessionsResource;
+
+const REVOCATIONS_FILE: &str = "admin-sessions.json";
+
+/// Sessions idle for longer than this are dropped from the listing
+const IDLE_SECS: f64 = 7.0 * 24.0 * 3600.0;
+
+#[derive(Clone)]
+struct Session {
+    actor: String,
+    kind: &'static str, // "bearer", "basic"
+    ip: String,
+    user_agent: String,
+    issued_at: Option<f64>,
+    expires_at: Option<f64>,
+    first_seen: f64,
+    last_activity: f64,
+    requests: u64,
+}
+
+/// Persisted revocations: session ids, and per-actor "not before" cutoffs
+#[derive(Default)]
+struct Revocations {
+    sessions: serde_json::Map<String, serde_json::Value>,
+    cutoffs: serde_json::Map<String, serde_json::Value>,
+}
+
+fn sessions() -> &'static Mutex<BTreeMap<String, Session>> {
+    static SESSIONS: OnceLock<Mutex<BTreeMap<String, Session>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn revocations() -> &'static Mutex<Revocations> {
+    static REVOCATIONS: OnceLock<Mutex<Revocations>> = OnceLock::new();
+    REVOCATIONS.get_or_init(|| Mutex::new(read_revocations()))
+}
+
+fn revocations_path() -> PathBuf {
+    get_root_directory().join(REVOCATIONS_FILE)
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn read_revocations() -> Revocations {
+    let value = std::fs::read_to_string(revocations_path())
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .unwrap_or_default();
+    let map = |key: &str| value.get(key).and_then(|v| v.as_object()).cloned().unwrap_or_default();
+    Revocations {
+        sessions: map("sessions"),
+        cutoffs: map("cutoffs"),
+    }
+}
+
+fn write_revocations(revocations: &Revocations) -> std::result::Result<(), String> {
+    let content = serde_json::to_string_pretty(&json!({
+        "sessions": revocations.sessions,
+        "cutoffs": revocations.cutoffs,
+    }))
+    .map_err(|e| format!("Failed to serialize revocations: {}", e))?;
+    let path = revocations_path();
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", REVOCATIONS_FILE, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", REVOCATIONS_FILE, e))
+}
+
+/// Stable, non-reversible id for a credential
+fn session_id(authorization: &str) -> String {
+    Sha256::digest(authorization.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn client_ip(forwarded_for: Option<&str>, real_ip: Option<&str>) -> String {
+    forwarded_for
+        .and_then(|v| v.split(',').next())
+        .or(real_ip)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Record activity for the caller's credential; Err when it has been revoked.
+/// Requests without an `Authorization` header are not tracked.
+pub fn touch(
+    authorization: Option<&str>,
+    forwarded_for: Option<&str>,
+    real_ip: Option<&str>,
+    user_agent: Option<&str>,
+) -> std::result::Result<(), String> {
+    let Some(header) = authorization else { return Ok(()) };
+    let id = session_id(header);
+    let actor = crate::audit::actor(Some(header));
+    let claims = header.strip_prefix("Bearer ").and_then(crate::audit::bearer_claims);
+    let issued_at = claims.as_ref().and_then(|c| c.get("iat")).and_then(|v| v.as_f64());
+
+    {
+        let revocations = revocations().lock().unwrap();
+        if revocations.sessions.contains_key(&id) {
+            return Err("Session has been revoked".to_string());
+        }
+        let cutoff = revocations.cutoffs.get(&actor).and_then(|v| v.as_f64());
+        if let (Some(cutoff), Some(iat)) = (cutoff, issued_at) {
+            if iat < cutoff {
+                return Err("Session has been revoked; sign in again".to_string());
+            }
+        }
+    }
+
+    let now = now_secs();
+    let mut sessions = sessions().lock().unwrap();
+    let session = sessions.entry(id).or_insert_with(|| Session {
+        actor,
+        kind: if header.starts_with("Bearer ") { "bearer" } else { "basic" },
+        ip: String::new(),
+        user_agent: String::new(),
+        issued_at,
+        expires_at: claims.as_ref().and_then(|c| c.get("exp")).and_then(|v| v.as_f64()),
+        first_seen: now,
+        last_activity: now,
+        requests: 0,
+    });
+    session.ip = client_ip(forwarded_for, real_ip);
+    session.user_agent = user_agent.unwrap_or("").to_string();
+    session.last_activity = now;
+    session.requests += 1;
+    Ok(())
+}
+
+/// The `touch` check for a request, as used by `metered!`
+#[macro_export]
+macro_rules! session_check {
+    ($request:expr) => {{
+        let headers = $request.headers();
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        $crate::sessions::touch(header("authorization"), header("x-forwarded-for"), header("x-real-ip"), header("user-agent"))
+    }};
+}
+
+fn view(id: &str, s: &Session, current: Option<&str>) -> serde_json::Value {
+    json!({
+        "id": id,
+        "actor": s.actor,
+        "kind": s.kind,
+        "ip": s.ip,
+        "userAgent": s.user_agent,
+        "issuedAt": s.issued_at,
+        "expiresAt": s.expires_at,
+        "firstSeen": s.first_seen,
+        "lastActivity": s.last_activity,
+        "requests": s.requests,
+        "current": current == Some(id),
+    })
+}
+
+#[derive(Default)]
+pub struct SessionsResource;
+
+impl Resource for SessionsResource {
+    fn name(&self) -> &str {
+        "sessions"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "sessions", "GET", {
+            let query = request.uri().query().unwrap_or("");
+            if parse_query_param(query, "revoked").is_some_and(|v| v == "true") {
+                let revocations = revocations().lock().unwrap();
+                return reply().json(json!({
+                    "sessions": revocations.sessions,
+                    "cutoffs": revocations.cutoffs,
+                }));
+            }
+
+            let current = request.headers().get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .map(session_id);
+            let now = now_secs();
+            let mut sessions = sessions().lock().unwrap();
+            sessions.retain(|_, s| {
+                now - s.last_activity < IDLE_SECS && s.expires_at.is_none_or(|exp| exp > now)
+            });
+            let mut list: Vec<serde_json::Value> = sessions
+                .iter()
+                .map(|(id, s)| view(id, s, current.as_deref()))
+                .collect();
+            list.sort_by(|a, b| b["lastActivity"].as_f64().partial_cmp(&a["lastActivity"].as_f64())
+                .unwrap_or(std::cmp::Ordering::Equal));
+            reply().json(json!(list))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "sessions", "DELETE", {
+            let now = now_secs();
+
+            // DELETE /admin/sessions/{id}
+            if let Some(id) = ctx.path_id() {
+                let id = id.to_string();
+                let Some(session) = sessions().lock().unwrap().remove(&id) else {
+                    return not_found(&format!("Session '{}' not found", id));
+                };
+                {
+                    let mut revocations = revocations().lock().unwrap();
+                    revocations.sessions.insert(id.clone(), json!({
+                        "actor": session.actor,
+                        "revokedAt": now,
+                    }));
+                    write_revocations(&revocations).map_err(YetiError::Internal)?;
+                }
+                crate::audit!(request, ctx, "session.revoke", &id, json!({"actor": session.actor}));
+                return reply().json(json!({
+                    "id": id,
+                    "revoked": true,
+                }));
+            }
+
+            // DELETE /admin/sessions?actor=X: log out everywhere
+            let query = request.uri().query().unwrap_or("");
+            let actor = parse_required_query_param(query, "actor")?;
+            let revoked: Vec<String> = {
+                let mut sessions = sessions().lock().unwrap();
+                let ids: Vec<String> = sessions.iter()
+                    .filter(|(_, s)| s.actor == actor)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in &ids {
+                    sessions.remove(id);
+                }
+                ids
+            };
+            {
+                let mut revocations = revocations().lock().unwrap();
+                for id in &revoked {
+                    revocations.sessions.insert(id.clone(), json!({
+                        "actor": actor,
+                        "revokedAt": now,
+                    }));
+                }
+                revocations.cutoffs.insert(actor.clone(), json!(now));
+                write_revocations(&revocations).map_err(YetiError::Internal)?;
+            }
+
+            crate::audit!(request, ctx, "session.revoke_all", &actor, json!({"sessions": revoked}));
+
+            reply().json(json!({
+                "actor": actor,
+                "revoked": revoked,
+                "notBefore": now,
+            }))
+        })
+    });
+}
+
+register_resource!(SessionsResource);
@@ -0,0 +1,392 @@
+//! Admin State Bundle Resource
+//!
+//! Exports everything the admin manages as one signed bundle and imports it on
+//! another instance, for disaster recovery or cloning an instance.
+//!
+//! | Method | Path                | Description                        |
+//! |--------|---------------------|------------------------------------|
+//! | POST   | /admin/state/export | Build a signed bundle              |
+//! | POST   | /admin/state/import | Verify and apply a bundle          |
+//!
+//! The bundle holds:
+//!
+//! - `apps`: each app's config.yaml (verbatim) and git origin/branch
+//! - `templates`: the application template, as a tar.gz like promotion archives
+//! - `schedules`: the cron task list
+//! - `server`: the `cors`, `domains` and `extensionRegistry` server settings
+//! - `tables`: Webhook, Instance, TestConfig, AppValidation (baselines) and
+//!   non-excluded TestRun records
+//!
+//! SSH keys and the secrets store (secret env vars, webhook secrets, instance
+//! credentials) are never exported; import reports what has to be set again.
+//!
+//! Export body: `{"passphrase": "..."}`. The bundle's `state` is signed with
+//! HMAC-SHA256 keyed by the passphrase, and import refuses a bundle whose
+//! signature does not match. Import body: `{"bundle": {...}, "passphrase":
+//! "...", "overwrite": false, "cloneMissing": false, "dryRun": false}`. Without
+//! `overwrite`, existing apps, records and tasks are kept; with `cloneMissing`,
+//! apps missing here are cloned from their git origin (public repos only).
+
+use base64::Engine;
+use yeti_core::prelude::*;
+
+pub type State = StateResource;
+
+const FORMAT: &str = "yeti-admin-state";
+const VERSION: u64 = 1;
+const MIN_PASSPHRASE_LEN: usize = 8;
+const TEMPLATE_APP: &str = "application-template";
+const SERVER_KEYS: &[&str] = &["cors", "domains", "extensionRegistry"];
+const TABLES: &[&str] = &["Webhook", "Instance", "TestConfig", "AppValidation", "TestRun"];
+
+fn passphrase(body: &serde_json::Value) -> std::result::Result<String, String> {
+    let passphrase = body.get("passphrase").and_then(|v| v.as_str()).unwrap_or("");
+    if passphrase.len() < MIN_PASSPHRASE_LEN {
+        return Err(format!("'passphrase' must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(passphrase.to_string())
+}
+
+fn signature(passphrase: &str, state: &serde_json::Value) -> String {
+    format!("sha256={}", crate::webhooks::sign(passphrase, &state.to_string()))
+}
+
+/// Compare without leaking the position of the first difference
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn git_field(app_path: &std::path::Path, args: &[&str], owner: &str) -> Option<String> {
+    let dir = app_path.to_string_lossy().to_string();
+    let mut full = vec!["-C", dir.as_str()];
+    full.extend_from_slice(args);
+    crate::repos::run_git(&full, None, None, owner)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Every app's config.yaml and git origin
+fn export_apps(owner: &str) -> std::result::Result<Vec<serde_json::Value>, String> {
+    let entries = std::fs::read_dir(get_apps_directory()).map_err(|e| format!("Cannot read applications dir: {}", e))?;
+    let mut apps = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(app_id) = entry.file_name().to_str().map(String::from) else { continue };
+        if app_id.starts_with('.') || app_id == TEMPLATE_APP || !path.is_dir() {
+            continue;
+        }
+        let Ok(config) = std::fs::read_to_string(path.join("config.yaml")) else { continue };
+        let is_git = path.join(".git").is_dir();
+        apps.push(json!({
+            "app_id": app_id,
+            "config": config,
+            // Credentials embedded in the remote must not leave the server
+            "remote_url": if is_git {
+                git_field(&path, &["remote", "get-url", "origin"], owner).map(|url| crate::repos::redact_url(&url))
+            } else {
+                None
+            },
+            "branch": if is_git { git_field(&path, &["branch", "--show-current"], owner) } else { None },
+        }));
+    }
+    apps.sort_by(|a, b| a["app_id"].as_str().cmp(&b["app_id"].as_str()));
+    Ok(apps)
+}
+
+/// Names of secrets an app's config.yaml refers to
+fn env_secret_names(config: &str) -> Vec<String> {
+    serde_yaml::from_str::<serde_yaml::Value>(config)
+        .ok()
+        .and_then(|c| c.get("env_secrets").and_then(|v| serde_json::to_value(v).ok()))
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect()
+}
+
+/// Write an app's config.yaml, cloning the app first when asked to
+fn import_app(app: &serde_json::Value, overwrite: bool, clone_missing: bool, dry_run: bool, owner: &str) -> serde_json::Value {
+    let app_id = app["app_id"].as_str().unwrap_or("");
+    if validate_identifier(app_id, "app_id").is_err() {
+        return json!({"app_id": app_id, "status": "skipped", "reason": "Invalid app_id"});
+    }
+    let config = app["config"].as_str().unwrap_or("");
+    if serde_yaml::from_str::<serde_yaml::Value>(config).is_err() {
+        return json!({"app_id": app_id, "status": "skipped", "reason": "config.yaml does not parse"});
+    }
+    let app_path = get_apps_directory().join(app_id);
+    let exists = app_path.is_dir();
+    let remote = app["remote_url"].as_str();
+    let status = match (exists, overwrite, clone_missing, remote) {
+        (true, false, _, _) => return json!({"app_id": app_id, "status": "kept"}),
+        (true, true, _, _) => "updated",
+        (false, _, true, Some(_)) => "cloned",
+        (false, _, _, _) => return json!({
+            "app_id": app_id,
+            "status": "missing",
+            "remote_url": remote,
+        }),
+    };
+    let url = remote.unwrap_or("");
+    if status == "cloned" {
+        if let Err(e) = crate::repos::validate_git_url(url) {
+            return json!({"app_id": app_id, "status": "skipped", "reason": e});
+        }
+    }
+    if dry_run {
+        return json!({"app_id": app_id, "status": status, "dryRun": true});
+    }
+
+    if status == "cloned" {
+        let path_str = app_path.to_string_lossy().to_string();
+        let mut args = vec!["clone"];
+        if let Some(branch) = app["branch"].as_str() {
+            args.extend(["--branch", branch]);
+        }
+        args.extend(["--", url, path_str.as_str()]);
+        if let Err(e) = crate::repos::run_git(&args, None, None, owner) {
+            let _ = std::fs::remove_dir_all(&app_path);
+            return json!({"app_id": app_id, "status": "failed", "reason": e});
+        }
+    }
+    if let Err(e) = std::fs::write(app_path.join("config.yaml"), config) {
+        return json!({"app_id": app_id, "status": "failed", "reason": format!("Failed to write config.yaml: {}", e)});
+    }
+    if let Err(e) = crate::env::reload_app(&app_path) {
+        yeti_log!(warn, "Imported '{}' but reload failed: {}", app_id, e);
+    }
+    json!({"app_id": app_id, "status": status})
+}
+
+/// Apply the server settings carried by the bundle
+fn import_server(server: &serde_json::Value, overwrite: bool, dry_run: bool) -> std::result::Result<Vec<String>, String> {
+    crate::cors::update_server_config(|config| {
+        let mut applied = Vec::new();
+        for key in SERVER_KEYS {
+            let Some(value) = server.get(*key).filter(|v| !v.is_null()) else { continue };
+            if config.get(*key).is_some() && !overwrite {
+                continue;
+            }
+            let value: serde_yaml::Value = serde_yaml::to_value(value).map_err(|e| format!("Invalid '{}': {}", key, e))?;
+            if !dry_run {
+                config
+                    .as_mapping_mut()
+                    .ok_or("Server config is not a mapping")?
+                    .insert(serde_yaml::Value::String(key.to_string()), value);
+            }
+            applied.push(key.to_string());
+        }
+        Ok(applied)
+    })
+}
+
+/// Merge the bundle's cron tasks by id
+fn import_schedules(tasks: &[serde_json::Value], overwrite: bool, dry_run: bool) -> std::result::Result<usize, String> {
+    let merge = |current: &mut Vec<serde_json::Value>| {
+        let mut imported = 0;
+        for task in tasks {
+            let Some(id) = task["id"].as_str() else { continue };
+            match current.iter_mut().find(|t| t["id"] == id) {
+                Some(existing) if overwrite => *existing = task.clone(),
+                Some(_) => continue,
+                None => current.push(task.clone()),
+            }
+            imported += 1;
+        }
+        imported
+    };
+    if dry_run {
+        return Ok(merge(&mut crate::cron::read_tasks()));
+    }
+    crate::cron::modify_tasks(merge)
+}
+
+#[derive(Default)]
+pub struct StateResource;
+
+impl Resource for StateResource {
+    fn name(&self) -> &str {
+        "state"
+    }
+
+    post!(request, ctx, {
+        crate::metered!(request, "state", "POST", {
+            let body = request.json_value()?;
+            let owner = crate::actor!(request);
+            let passphrase = match passphrase(&body) {
+                Ok(p) => p,
+                Err(e) => return bad_request(&e),
+            };
+
+            match ctx.require_id()? {
+                "export" => {
+                    let export_owner = owner.clone();
+                    let (apps, template) = tokio::task::spawn_blocking(move || {
+                        let apps = export_apps(&export_owner)?;
+                        let template = if get_apps_directory().join(TEMPLATE_APP).is_dir() {
+                            let archive = crate::promote::archive_app(TEMPLATE_APP, &export_owner)?;
+                            Some(base64::engine::general_purpose::STANDARD.encode(archive))
+                        } else {
+                            None
+                        };
+                        Ok::<_, String>((apps, template))
+                    })
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Export task failed: {}", e)))?
+                    .map_err(YetiError::Internal)?;
+
+                    let server_config = crate::cors::read_server_config().map_err(YetiError::Internal)?;
+                    let mut server = serde_json::Map::new();
+                    for key in SERVER_KEYS {
+                        if let Some(value) = server_config.get(*key).and_then(|v| serde_json::to_value(v).ok()) {
+                            server.insert(key.to_string(), value);
+                        }
+                    }
+
+                    let mut tables = serde_json::Map::new();
+                    for name in TABLES {
+                        let Ok(table) = ctx.get_table(name) else { continue };
+                        let mut records = table.scan_all().await.unwrap_or_default();
+                        if *name == "TestRun" {
+                            records.retain(|r| !r.get("excluded").and_then(|v| v.as_bool()).unwrap_or(false));
+                        }
+                        tables.insert(name.to_string(), json!(records));
+                    }
+
+                    let state = json!({
+                        "apps": apps,
+                        "templates": {TEMPLATE_APP: template},
+                        "schedules": crate::cron::read_tasks(),
+                        "server": server,
+                        "tables": tables,
+                    });
+                    let counts = json!({
+                        "apps": state["apps"].as_array().map_or(0, |a| a.len()),
+                        "schedules": state["schedules"].as_array().map_or(0, |a| a.len()),
+                        "records": tables.values().filter_map(|v| v.as_array()).map(|a| a.len()).sum::<usize>(),
+                    });
+
+                    crate::audit!(request, ctx, "state.export", "server", counts.clone());
+
+                    reply().json(json!({
+                        "format": FORMAT,
+                        "version": VERSION,
+                        "createdAt": chrono::Utc::now().to_rfc3339(),
+                        "counts": counts,
+                        "signature": signature(&passphrase, &state),
+                        "state": state,
+                    }))
+                }
+                "import" => {
+                    let bundle = body.get("bundle").ok_or_else(|| YetiError::Validation("'bundle' is required".to_string()))?;
+                    if bundle["format"] != FORMAT || bundle["version"].as_u64() != Some(VERSION) {
+                        return bad_request(&format!("Not a {} v{} bundle", FORMAT, VERSION));
+                    }
+                    let state = &bundle["state"];
+                    let expected = signature(&passphrase, state);
+                    if !constant_time_eq(bundle["signature"].as_str().unwrap_or(""), &expected) {
+                        return reply().code(403).json(json!({"error": "Bundle signature does not match (wrong passphrase or modified bundle)"}));
+                    }
+                    let flag = |key: &str| body.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+                    let (overwrite, clone_missing, dry_run) = (flag("overwrite"), flag("cloneMissing"), flag("dryRun"));
+
+                    let server = import_server(&state["server"], overwrite, dry_run).map_err(YetiError::Internal)?;
+
+                    let templates = state["templates"].clone();
+                    let apps: Vec<serde_json::Value> = state["apps"].as_array().cloned().unwrap_or_default();
+                    let files_owner = owner.clone();
+                    let (template_status, app_results) = tokio::task::spawn_blocking(move || {
+                        let template_status = match templates[TEMPLATE_APP].as_str() {
+                            None => json!(null),
+                            Some(_) if dry_run => json!("dryRun"),
+                            Some(encoded) => match base64::engine::general_purpose::STANDARD.decode(encoded) {
+                                Ok(archive) => match crate::promote::install_archive(TEMPLATE_APP, &archive, overwrite, &files_owner) {
+                                    Ok(_) => json!("installed"),
+                                    Err(e) => json!(e),
+                                },
+                                Err(e) => json!(format!("Invalid archive: {}", e)),
+                            },
+                        };
+                        let results: Vec<serde_json::Value> = apps
+                            .iter()
+                            .map(|app| import_app(app, overwrite, clone_missing, dry_run, &files_owner))
+                            .collect();
+                        (template_status, results)
+                    })
+                    .await
+                    .map_err(|e| YetiError::Internal(format!("Import task failed: {}", e)))?;
+
+                    let mut tables = serde_json::Map::new();
+                    for name in TABLES {
+                        let records = state["tables"][*name].as_array().cloned().unwrap_or_default();
+                        if records.is_empty() {
+                            continue;
+                        }
+                        let Ok(table) = ctx.get_table(name) else { continue };
+                        let (mut written, mut kept) = (0, 0);
+                        for record in records {
+                            let Some(id) = record["id"].as_str() else { continue };
+                            if !overwrite && table.get_by_id(id).await?.is_some() {
+                                kept += 1;
+                                continue;
+                            }
+                            if !dry_run {
+                                table.put(record.clone()).await?;
+                            }
+                            written += 1;
+                        }
+                        tables.insert(name.to_string(), json!({"written": written, "kept": kept}));
+                    }
+                    if !dry_run && tables.contains_key("TestRun") {
+                        crate::bestresults::invalidate_cache();
+                    }
+                    if !dry_run && tables.contains_key("Webhook") {
+                        if let Ok(table) = ctx.get_table("Webhook") {
+                            crate::webhooks::load(table.scan_all().await.unwrap_or_default());
+                        }
+                    }
+
+                    let schedules = state["schedules"].as_array().cloned().unwrap_or_default();
+                    let schedules = import_schedules(&schedules, overwrite, dry_run).map_err(YetiError::Internal)?;
+
+                    // Secrets are not in the bundle; tell what has to be set again
+                    let mut secrets_needed = Vec::new();
+                    for app in state["apps"].as_array().into_iter().flatten() {
+                        let names = env_secret_names(app["config"].as_str().unwrap_or(""));
+                        if !names.is_empty() {
+                            secrets_needed.push(json!({"app_id": app["app_id"], "env": names}));
+                        }
+                    }
+                    for (name, kind) in [("Webhook", "webhook secret"), ("Instance", "instance credentials")] {
+                        for record in state["tables"][name].as_array().into_iter().flatten() {
+                            secrets_needed.push(json!({"id": record["id"], "kind": kind}));
+                        }
+                    }
+
+                    let summary = json!({
+                        "dryRun": dry_run,
+                        "server": server,
+                        "templates": {TEMPLATE_APP: template_status},
+                        "apps": app_results,
+                        "tables": tables,
+                        "schedules": schedules,
+                        "secretsNeeded": secrets_needed,
+                    });
+                    if !dry_run {
+                        crate::audit!(request, ctx, "state.import", "server", json!({
+                            "createdAt": bundle["createdAt"],
+                            "overwrite": overwrite,
+                            "apps": summary["apps"].as_array().map_or(0, |a| a.len()),
+                        }));
+                    }
+                    reply().json(summary)
+                }
+                other => bad_request(&format!("Unknown state operation '{}' (use export or import)", other)),
+            }
+        })
+    });
+}
+
+register_resource!(StateResource);
@@ -0,0 +1,175 @@
+//! System Information Resource
+//!
+//! Host and server vitals, so an operator can judge whether the box can take
+//! a benchmark or another app.
+//!
+//! | Method | Path          | Description                                   |
+//! |--------|---------------|-----------------------------------------------|
+//! | GET    | /admin/system | CPU, memory, disk, file descriptors, uptime   |
+//!
+//! CPU usage is sampled over a short window per request. Values come from
+//! `/proc` and `df`; on hosts without them the affected fields are null.
+
+use yeti_core::prelude::*;
+
+pub type System = SystemResource;
+
+/// Window over which CPU usage is sampled
+const CPU_SAMPLE: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Clock ticks per second assumed when `getconf CLK_TCK` is unavailable
+const DEFAULT_CLK_TCK: f64 = 100.0;
+
+/// (busy, total) jiffies from the aggregate `cpu` line of /proc/stat
+fn cpu_times() -> Option<(u64, u64)> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+    let values: Vec<u64> = line.split_whitespace().skip(1).filter_map(|v| v.parse().ok()).collect();
+    let total: u64 = values.iter().sum();
+    // idle + iowait
+    let idle = values.get(3).copied().unwrap_or(0) + values.get(4).copied().unwrap_or(0);
+    Some((total - idle, total))
+}
+
+/// Value of a `Key: 123 kB` line from a /proc status-style file, in bytes
+fn proc_kb(content: &str, key: &str) -> Option<u64> {
+    content
+        .lines()
+        .find(|l| l.starts_with(key) && l[key.len()..].starts_with(':'))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+fn memory() -> serde_json::Value {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let total = proc_kb(&meminfo, "MemTotal");
+    let available = proc_kb(&meminfo, "MemAvailable");
+    json!({
+        "totalBytes": total,
+        "availableBytes": available,
+        "usedPercent": total.zip(available)
+            .filter(|(t, _)| *t > 0)
+            .map(|(t, a)| (t - a) as f64 / t as f64 * 100.0),
+        "processRssBytes": proc_kb(&status, "VmRSS"),
+    })
+}
+
+/// Space on the filesystem holding `path`, via `df -Pk`
+pub fn disk(path: &std::path::Path) -> serde_json::Value {
+    let output = std::process::Command::new("df")
+        .args(["-Pk", &path.to_string_lossy()])
+        .output()
+        .ok()
+        .filter(|o| o.status.success());
+    let fields: Vec<u64> = output
+        .as_ref()
+        .and_then(|o| String::from_utf8_lossy(&o.stdout).lines().nth(1).map(String::from))
+        .map(|line| line.split_whitespace().skip(1).take(3).filter_map(|v| v.parse().ok()).collect())
+        .unwrap_or_default();
+    let (total, used, available) = match fields[..] {
+        [total, used, available] => (Some(total * 1024), Some(used * 1024), Some(available * 1024)),
+        _ => (None, None, None),
+    };
+    json!({
+        "path": path.to_string_lossy(),
+        "totalBytes": total,
+        "usedBytes": used,
+        "availableBytes": available,
+        "usedPercent": total.zip(used)
+            .filter(|(t, _)| *t > 0)
+            .map(|(t, u)| u as f64 / t as f64 * 100.0),
+    })
+}
+
+fn file_descriptors() -> serde_json::Value {
+    let open = std::fs::read_dir("/proc/self/fd").ok().map(|entries| entries.count());
+    let limit = std::fs::read_to_string("/proc/self/limits")
+        .ok()
+        .and_then(|limits| {
+            limits
+                .lines()
+                .find(|l| l.starts_with("Max open files"))
+                .and_then(|l| l.split_whitespace().nth(3))
+                .and_then(|v| v.parse::<u64>().ok())
+        });
+    json!({ "open": open, "limit": limit })
+}
+
+fn clk_tck() -> f64 {
+    std::process::Command::new("getconf")
+        .arg("CLK_TCK")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8_lossy(&o.stdout).trim().parse::<f64>().ok())
+        .filter(|t| *t > 0.0)
+        .unwrap_or(DEFAULT_CLK_TCK)
+}
+
+/// (host uptime, server process uptime) in seconds
+fn uptime() -> (Option<f64>, Option<f64>) {
+    let host = std::fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|s| s.split_whitespace().next().and_then(|v| v.parse::<f64>().ok()));
+    // starttime is field 22; the command name (field 2) may contain spaces
+    let started_ticks = std::fs::read_to_string("/proc/self/stat").ok().and_then(|stat| {
+        let rest = &stat[stat.rfind(')')? + 2..];
+        rest.split_whitespace().nth(19).and_then(|v| v.parse::<f64>().ok())
+    });
+    let process = host.zip(started_ticks).map(|(host, ticks)| host - ticks / clk_tck());
+    (host, process)
+}
+
+fn load_average() -> Option<Vec<f64>> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    Some(loadavg.split_whitespace().take(3).filter_map(|v| v.parse().ok()).collect())
+}
+
+#[derive(Default)]
+pub struct SystemResource;
+
+impl Resource for SystemResource {
+    fn name(&self) -> &str {
+        "system"
+    }
+
+    get!(request, _ctx, {
+        crate::metered!(request, "system", "GET", {
+            let before = cpu_times();
+            tokio::time::sleep(CPU_SAMPLE).await;
+            let after = cpu_times();
+            let cpu_percent = before.zip(after).and_then(|((b0, t0), (b1, t1))| {
+                let total = t1.saturating_sub(t0);
+                (total > 0).then(|| b1.saturating_sub(b0) as f64 / total as f64 * 100.0)
+            });
+
+            let (host_uptime, process_uptime) = uptime();
+            let exe = std::env::current_exe().ok();
+
+            reply().json(json!({
+                "cpu": {
+                    "cores": std::thread::available_parallelism().map(|n| n.get()).ok(),
+                    "usagePercent": cpu_percent,
+                    "loadAverage": load_average(),
+                },
+                "memory": memory(),
+                "disk": disk(&get_root_directory()),
+                "fileDescriptors": file_descriptors(),
+                "uptime": {
+                    "hostSecs": host_uptime,
+                    "serverSecs": process_uptime,
+                },
+                "server": {
+                    "pid": std::process::id(),
+                    "executable": exe.as_ref().map(|p| p.to_string_lossy().to_string()),
+                    "version": std::env::var("YETI_VERSION").ok(),
+                    "os": std::env::consts::OS,
+                    "arch": std::env::consts::ARCH,
+                },
+            }))
+        })
+    });
+}
+
+register_resource!(SystemResource);
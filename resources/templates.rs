@@ -0,0 +1,309 @@
+//! App Template Resource
+//!
+//! Lists the templates new apps can be created from: local apps flagged
+//! `template: true` in their config.yaml (plus the bundled
+//! `application-template`), and git repositories registered here.
+//!
+//! | Method | Path                      | Description                    |
+//! |--------|---------------------------|--------------------------------|
+//! | GET    | /admin/templates          | All templates                  |
+//! | GET    | /admin/templates/{name}   | Single template                |
+//! | POST   | /admin/templates          | Register a git template        |
+//! | DELETE | /admin/templates/{name}   | Unregister a git template      |
+//!
+//! Register body: `{"name": "api-starter", "repo":
+//! "https://github.com/org/api-starter.git", "ref": "main", "key": "deploy",
+//! "description": "REST API skeleton"}`. Git templates are the `templates:`
+//! section of the server config (`yeti-config.yaml`); local templates are
+//! managed as apps by setting or clearing `template: true`. A git template
+//! cannot take the name of a local one.
+//!
+//! `POST /admin/apps` accepts any template name as `template`. Local templates
+//! are copied from the apps directory; git templates are shallow-cloned into
+//! the plugin cache first and the clone is removed after copying.
+//!
+//! A template may declare variables in a `template.yaml` at its root (not
+//! copied into the new app):
+//!
+//! ```yaml
+//! variables:
+//!   - name: port
+//!     description: Port the API listens on
+//!     default: "8080"
+//!   - name: owner_email
+//!     required: true
+//! ```
+//!
+//! Values come from `variables` in the create body, else `default`; a
+//! `required` variable without either is rejected. `{{app_id}}`, `{{name}}`
+//! and `{{description}}` are always available. Local templates list their
+//! declared variables.
+
+use std::path::{Path, PathBuf};
+use yeti_core::prelude::*;
+
+pub type Templates = TemplatesResource;
+
+const TEMPLATES_KEY: &str = "templates";
+/// Always offered, with or without `template: true`
+pub const DEFAULT_TEMPLATE: &str = "application-template";
+/// Variable declarations at the template root
+pub const TEMPLATE_FILE: &str = "template.yaml";
+
+fn app_config(app_path: &Path) -> Option<serde_yaml::Value> {
+    let content = std::fs::read_to_string(app_path.join("config.yaml")).ok()?;
+    serde_yaml::from_str(&content).ok()
+}
+
+/// Variables declared in a template's `template.yaml`
+fn declared_variables(dir: &Path) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(dir.join(TEMPLATE_FILE))
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<serde_yaml::Value>(&content).ok())
+        .and_then(|yaml| yaml.get("variables").and_then(|v| serde_json::to_value(v).ok()))
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|v| v["name"].as_str().is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')))
+        .collect()
+}
+
+/// Scalar JSON value as substitution text
+fn scalar(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Substitutions for copying the template at `dir`: the built-ins, then each
+/// declared variable from `provided` or its default
+pub fn resolve_variables(
+    dir: &Path,
+    provided: &serde_json::Map<String, serde_json::Value>,
+    builtins: &[(&str, &str)],
+) -> std::result::Result<Vec<(String, String)>, String> {
+    let mut vars: Vec<(String, String)> = builtins.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let mut missing = Vec::new();
+    for variable in declared_variables(dir) {
+        let name = variable["name"].as_str().unwrap_or("");
+        if builtins.iter().any(|(k, _)| *k == name) {
+            continue;
+        }
+        let value = match provided.get(name) {
+            Some(v) => Some(scalar(v).ok_or_else(|| format!("Variable '{}' must be a string, number or boolean", name))?),
+            None => scalar(&variable["default"]),
+        };
+        match value {
+            Some(value) => vars.push((name.to_string(), value)),
+            None if variable["required"].as_bool().unwrap_or(false) => missing.push(name.to_string()),
+            // Optional without a default: the placeholder becomes empty
+            None => vars.push((name.to_string(), String::new())),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!("Missing required template variables: {}", missing.join(", ")));
+    }
+    Ok(vars)
+}
+
+/// Apps flagged `template: true`, and the default template
+fn local_templates() -> Vec<serde_json::Value> {
+    let Ok(entries) = std::fs::read_dir(get_apps_directory()) else { return Vec::new() };
+    let mut list: Vec<serde_json::Value> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if name.starts_with('.') {
+                return None;
+            }
+            let config = app_config(&entry.path())?;
+            let flagged = config.get("template").and_then(|v| v.as_bool()).unwrap_or(false);
+            if !flagged && name != DEFAULT_TEMPLATE {
+                return None;
+            }
+            let field = |key: &str| config.get(key).and_then(|v| v.as_str()).map(String::from);
+            Some(json!({
+                "name": name,
+                "source": "local",
+                "title": field("name"),
+                "description": field("description"),
+                "version": field("version"),
+                "variables": declared_variables(&entry.path()),
+            }))
+        })
+        .collect();
+    list.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    list
+}
+
+/// Registered git templates from the server config
+fn git_templates(config: &serde_yaml::Value) -> Vec<serde_json::Value> {
+    config
+        .get(TEMPLATES_KEY)
+        .and_then(|v| serde_json::to_value(v).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| t["name"].as_str().is_some() && t["repo"].as_str().is_some())
+        .collect()
+}
+
+fn store(config: &mut serde_yaml::Value, templates: &[serde_json::Value]) -> std::result::Result<(), String> {
+    let value = serde_yaml::to_value(templates).map_err(|e| format!("Failed to convert templates: {}", e))?;
+    config
+        .as_mapping_mut()
+        .ok_or("Server config is not a mapping")?
+        .insert(serde_yaml::Value::String(TEMPLATES_KEY.to_string()), value);
+    Ok(())
+}
+
+/// Public view of a git template (the SSH key name is kept, never a secret)
+fn git_view(template: &serde_json::Value) -> serde_json::Value {
+    json!({
+        "name": template["name"],
+        "source": "git",
+        "title": template["name"],
+        "description": template["description"],
+        "repo": template["repo"],
+        "ref": template["ref"],
+        "key": template["key"],
+    })
+}
+
+/// Every template, local ones first
+fn all() -> Vec<serde_json::Value> {
+    let mut list = local_templates();
+    let config = crate::cors::read_server_config().unwrap_or_default();
+    list.extend(git_templates(&config).iter().map(git_view));
+    list
+}
+
+/// The template called `name`, if any
+pub fn find(name: &str) -> Option<serde_json::Value> {
+    all().into_iter().find(|t| t["name"] == name)
+}
+
+/// Directory to copy `template` from, and a temporary directory to remove
+/// afterwards (the clone of a git template)
+pub fn fetch(template: &serde_json::Value, owner: &str) -> std::result::Result<(PathBuf, Option<PathBuf>), String> {
+    let name = template["name"].as_str().unwrap_or("");
+    let Some(repo) = template["repo"].as_str() else {
+        return Ok((get_apps_directory().join(name), None));
+    };
+    crate::repos::validate_git_url(repo)?;
+
+    let dir = get_cache_directory()
+        .join("templates")
+        .join(format!("{}-{}", name, crate::webhooks::random_hex(4)?));
+    if let Some(parent) = dir.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create template cache: {}", e))?;
+    }
+    let dir_str = dir.to_string_lossy().to_string();
+    let mut args = vec!["clone", "--depth", "1"];
+    if let Some(git_ref) = template["ref"].as_str() {
+        args.extend(["--branch", git_ref]);
+    }
+    args.extend([repo, dir_str.as_str()]);
+    if let Err(e) = crate::repos::run_git(&args, None, template["key"].as_str(), owner) {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok((dir.clone(), Some(dir)))
+}
+
+#[derive(Default)]
+pub struct TemplatesResource;
+
+impl Resource for TemplatesResource {
+    fn name(&self) -> &str {
+        "templates"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "templates", "GET", {
+            if let Some(name) = ctx.path_id() {
+                return match find(name) {
+                    Some(template) => reply().json(template),
+                    None => not_found(&format!("Template '{}' not found", name)),
+                };
+            }
+            reply().json(json!(all()))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "templates", "POST", {
+            let body = request.json_value()?;
+            let name = body.require_str("name")?;
+            validate_identifier(&name, "name")?;
+            let repo = body.require_str("repo")?;
+            crate::repos::validate_git_url(&repo).map_err(YetiError::Validation)?;
+            for key in ["ref", "key"] {
+                if let Some(value) = body.get(key).and_then(|v| v.as_str()) {
+                    if value.is_empty() || value.starts_with('-') || value.contains(char::is_whitespace) {
+                        return bad_request(&format!("Invalid '{}'", key));
+                    }
+                }
+            }
+            if local_templates().iter().any(|t| t["name"] == name.as_str()) {
+                return bad_request(&format!("'{}' is already a local template", name));
+            }
+
+            let mut template = json!({"name": name, "repo": repo});
+            for key in ["ref", "key", "description"] {
+                if let Some(value) = body.get(key).and_then(|v| v.as_str()) {
+                    template[key] = json!(value);
+                }
+            }
+            let registered = crate::cors::update_server_config(|config| {
+                let mut templates = git_templates(config);
+                if templates.iter().any(|t| t["name"] == name.as_str()) {
+                    return Ok(false);
+                }
+                templates.push(template.clone());
+                store(config, &templates)?;
+                Ok(true)
+            })
+            .map_err(YetiError::Internal)?;
+            if !registered {
+                return bad_request(&format!("Template '{}' is already registered", name));
+            }
+
+            crate::audit!(request, ctx, "template.register", &name, json!({"repo": repo}));
+
+            reply().code(201).json(git_view(&template))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "templates", "DELETE", {
+            let name = ctx.require_id()?.to_string();
+            let removed = crate::cors::update_server_config(|config| {
+                let mut templates = git_templates(config);
+                let before = templates.len();
+                templates.retain(|t| t["name"] != name.as_str());
+                if templates.len() == before {
+                    return Ok(false);
+                }
+                store(config, &templates)?;
+                Ok(true)
+            })
+            .map_err(YetiError::Internal)?;
+            if !removed {
+                if local_templates().iter().any(|t| t["name"] == name.as_str()) {
+                    return bad_request(&format!("'{}' is a local template; clear 'template: true' in its config instead", name));
+                }
+                return not_found(&format!("Template '{}' not found", name));
+            }
+
+            crate::audit!(request, ctx, "template.unregister", &name, json!({}));
+
+            reply().json(json!({"deleted": true, "name": name}))
+        })
+    });
+}
+
+register_resource!(TemplatesResource);
@@ -0,0 +1,461 @@
+//! Outbound Webhooks Resource
+//!
+//! Register URLs that receive admin events (app created, pull completed,
+//! benchmark finished, regression detected) as signed HTTP POSTs.
+//!
+//! | Method | Path                                 | Description                     |
+//! |--------|--------------------------------------|---------------------------------|
+//! | GET    | /admin/webhooks                      | List webhooks                   |
+//! | GET    | /admin/webhooks/{id}                 | Single webhook                  |
+//! | GET    | /admin/webhooks/{id}/deliveries      | Recent deliveries, newest first |
+//! | POST   | /admin/webhooks                      | Register a webhook              |
+//! | POST   | /admin/webhooks/{id}/test            | Send a `webhook.test` event     |
+//! | PUT    | /admin/webhooks/{id}                 | Update url/events/secret/enabled|
+//! | DELETE | /admin/webhooks/{id}                 | Remove a webhook                |
+//!
+//! POST body: `{"url": "https://ci.example.com/hook", "events": ["app.",
+//! "repo.pull", "benchmark.finished", "benchmark.regression"], "secret": "..."}`.
+//! `events` entries match an event kind exactly or by prefix ending in `.`;
+//! an empty list (or `"*"`) subscribes to everything. Without a `secret` one is
+//! generated and returned once in the create response.
+//!
+//! Every event published with `publish!` / `audit!` is delivered to the
+//! enabled webhooks subscribed to its kind. The body is the event as JSON and
+//! the request carries `X-Yeti-Event`, `X-Yeti-Delivery` and
+//! `X-Yeti-Signature: sha256=<hex HMAC-SHA256 of the body with the secret>`.
+//! Non-2xx responses and network errors are retried with exponential backoff
+//! (`MAX_ATTEMPTS` in total). The last `MAX_DELIVERIES` deliveries are kept in
+//! memory with every attempt's status code, error and duration.
+//!
+//! Webhooks live in the Webhook table; secrets are kept in the secrets store
+//! (`webhooks` scope) and never returned after creation.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use yeti_core::prelude::*;
+
+pub type Webhooks = WebhooksResource;
+
+const SECRETS_SCOPE: &str = "webhooks";
+const MAX_ATTEMPTS: u32 = 5;
+const MAX_DELIVERIES: usize = 500;
+const DEFAULT_LIMIT: usize = 50;
+const TIMEOUT_SECS: &str = "10";
+
+#[derive(Clone)]
+struct Subscription {
+    id: String,
+    url: String,
+    events: Vec<String>,
+    secret: Option<String>,
+}
+
+impl Subscription {
+    fn wants(&self, kind: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == "*" || crate::events::matches_kind(e, kind))
+    }
+}
+
+/// Enabled webhooks, loaded from the Webhook table on first publish and
+/// refreshed whenever a webhook is changed.
+fn subscriptions() -> &'static Mutex<Option<Vec<Subscription>>> {
+    static SUBSCRIPTIONS: OnceLock<Mutex<Option<Vec<Subscription>>>> = OnceLock::new();
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(None))
+}
+
+fn deliveries() -> &'static Mutex<VecDeque<serde_json::Value>> {
+    static DELIVERIES: OnceLock<Mutex<VecDeque<serde_json::Value>>> = OnceLock::new();
+    DELIVERIES.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// Deliveries still being attempted
+pub fn pending_deliveries() -> usize {
+    deliveries().lock().unwrap().iter().filter(|d| d["status"] == "pending").count()
+}
+
+fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+pub fn random_hex(bytes: usize) -> std::result::Result<String, String> {
+    use std::io::Read;
+    let mut buf = vec![0u8; bytes];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut buf))
+        .map_err(|e| format!("Failed to read random bytes: {}", e))?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Hex HMAC-SHA256 of `body` keyed with `secret`
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn loaded() -> bool {
+    subscriptions().lock().unwrap().is_some()
+}
+
+/// Replace the cached subscriptions with the enabled records of the Webhook table
+pub fn load(records: Vec<serde_json::Value>) {
+    let secrets = crate::env::read_secrets(SECRETS_SCOPE);
+    let subs = records
+        .into_iter()
+        .filter(|r| r.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true))
+        .filter_map(|r| {
+            let id = r.get("id")?.as_str()?.to_string();
+            Some(Subscription {
+                url: r.get("url")?.as_str()?.to_string(),
+                events: event_list(&r),
+                secret: secrets.get(&id).and_then(|v| v.as_str()).map(String::from),
+                id,
+            })
+        })
+        .collect();
+    *subscriptions().lock().unwrap() = Some(subs);
+}
+
+/// Queue delivery of an AdminEvent record to every subscribed webhook.
+/// Must be called from within the tokio runtime.
+pub fn dispatch(event: &serde_json::Value) {
+    let kind = event.get("kind").and_then(|v| v.as_str()).unwrap_or("");
+    let targets: Vec<Subscription> = subscriptions()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|subs| subs.iter().filter(|s| s.wants(kind)).cloned().collect())
+        .unwrap_or_default();
+    if targets.is_empty() {
+        return;
+    }
+    let payload = payload(event);
+    for sub in targets {
+        tokio::spawn(deliver(sub, payload.clone()));
+    }
+}
+
+/// The event as sent: `data` is parsed back from its stored string form
+fn payload(event: &serde_json::Value) -> serde_json::Value {
+    let mut payload = event.clone();
+    if let Some(data) = event.get("data").and_then(|v| v.as_str()) {
+        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(data) {
+            payload["data"] = parsed;
+        }
+    }
+    payload
+}
+
+fn record_delivery(delivery: serde_json::Value) {
+    let mut log = deliveries().lock().unwrap();
+    log.push_back(delivery);
+    if log.len() > MAX_DELIVERIES {
+        log.pop_front();
+    }
+}
+
+fn update_delivery(id: &str, f: impl FnOnce(&mut serde_json::Value)) {
+    let mut log = deliveries().lock().unwrap();
+    if let Some(delivery) = log.iter_mut().rev().find(|d| d["id"] == id) {
+        f(delivery);
+    }
+}
+
+/// POST the payload, retrying with backoff until a 2xx or `MAX_ATTEMPTS`
+async fn deliver(sub: Subscription, payload: serde_json::Value) {
+    let body = payload.to_string();
+    let kind = payload["kind"].as_str().unwrap_or("").to_string();
+    let delivery_id = format!("{}-{}", payload["id"].as_str().unwrap_or(""), sub.id);
+    record_delivery(json!({
+        "id": delivery_id,
+        "webhookId": sub.id,
+        "eventId": payload["id"],
+        "kind": kind,
+        "url": sub.url,
+        "status": "pending",
+        "attempts": [],
+        "createdAt": now_secs(),
+        "finishedAt": null,
+    }));
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let started = std::time::Instant::now();
+        let (url, id, kind, body) = (sub.url.clone(), delivery_id.clone(), kind.clone(), body.clone());
+        let signature = sub.secret.as_deref().map(|s| sign(s, &body));
+        let result = tokio::task::spawn_blocking(move || post(&url, &id, &kind, &body, signature.as_deref()))
+            .await
+            .unwrap_or_else(|e| Err(format!("Delivery task failed: {}", e)));
+        let (code, error) = match result {
+            Ok(code) if (200..300).contains(&code) => (Some(code), None),
+            Ok(code) => (Some(code), Some(format!("HTTP {}", code))),
+            Err(e) => (None, Some(e)),
+        };
+        let delivered = error.is_none();
+        update_delivery(&delivery_id, |d| {
+            if let Some(attempts) = d["attempts"].as_array_mut() {
+                attempts.push(json!({
+                    "attempt": attempt,
+                    "at": now_secs(),
+                    "statusCode": code,
+                    "error": error,
+                    "durationMs": started.elapsed().as_millis() as u64,
+                }));
+            }
+            if delivered || attempt == MAX_ATTEMPTS {
+                d["status"] = json!(if delivered { "delivered" } else { "failed" });
+                d["finishedAt"] = json!(now_secs());
+            }
+        });
+        if delivered {
+            return;
+        }
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(std::time::Duration::from_secs(1 << (attempt - 1))).await;
+        }
+    }
+    yeti_log!(warn, "Webhook {} gave up delivering {} to {} after {} attempts", sub.id, kind, sub.url, MAX_ATTEMPTS);
+}
+
+/// One POST via curl; returns the HTTP status code
+fn post(url: &str, delivery_id: &str, kind: &str, body: &str, signature: Option<&str>) -> std::result::Result<u16, String> {
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["-sS", "-o", "/dev/null", "-w", "%{http_code}", "--max-time", TIMEOUT_SECS, "-X", "POST"])
+        .args(["-H", "Content-Type: application/json"])
+        .args(["-H", &format!("X-Yeti-Event: {}", kind)])
+        .args(["-H", &format!("X-Yeti-Delivery: {}", delivery_id)]);
+    if let Some(signature) = signature {
+        cmd.args(["-H", &format!("X-Yeti-Signature: sha256={}", signature)]);
+    }
+    cmd.args(["--data-binary", body, url]);
+
+    let output = crate::processes::output("webhook", "system", &mut cmd)
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| "No HTTP status in response".to_string())
+}
+
+fn event_list(record: &serde_json::Value) -> Vec<String> {
+    record
+        .get("events")
+        .and_then(|v| v.as_array())
+        .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+fn validate_url(url: &str) -> std::result::Result<(), String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or("'url' must start with http:// or https://")?;
+    if rest.is_empty() || url.contains(char::is_whitespace) {
+        return Err(format!("Invalid url '{}'", url));
+    }
+    Ok(())
+}
+
+/// Apply the writable fields of `body` to `record`
+fn apply_fields(record: &mut serde_json::Value, body: &serde_json::Value) -> std::result::Result<(), String> {
+    if let Some(url) = body.get("url") {
+        let url = url.as_str().ok_or("'url' must be a string")?;
+        validate_url(url)?;
+        record["url"] = json!(url);
+    }
+    if let Some(events) = body.get("events") {
+        let events = events.as_array().ok_or("'events' must be an array of event kinds")?;
+        if events.iter().any(|e| !e.as_str().is_some_and(|s| !s.is_empty())) {
+            return Err("'events' must be an array of event kinds".to_string());
+        }
+        record["events"] = json!(events);
+    }
+    if let Some(enabled) = body.get("enabled") {
+        record["enabled"] = json!(enabled.as_bool().ok_or("'enabled' must be a boolean")?);
+    }
+    if let Some(description) = body.get("description") {
+        record["description"] = description.clone();
+    }
+    Ok(())
+}
+
+fn set_secret(id: &str, secret: Option<&str>) -> std::result::Result<(), YetiError> {
+    let mut secrets = crate::env::read_secrets(SECRETS_SCOPE);
+    match secret {
+        Some(s) => secrets.insert(id.to_string(), json!(s)),
+        None => secrets.remove(id),
+    };
+    crate::env::write_secrets(SECRETS_SCOPE, &secrets).map_err(YetiError::Internal)
+}
+
+/// Webhook record as returned by the API (secret presence only)
+fn view(mut record: serde_json::Value) -> serde_json::Value {
+    let id = record["id"].as_str().unwrap_or("").to_string();
+    record["hasSecret"] = json!(crate::env::read_secret(SECRETS_SCOPE, &id).is_some());
+    record
+}
+
+/// `{id}` from `/webhooks/{id}/{action}`
+fn id_before(uri_path: &str, action: &str) -> Option<String> {
+    let prefix = uri_path.trim_end_matches('/').strip_suffix(action)?;
+    Some(prefix.rsplit('/').next().unwrap_or("").to_string())
+}
+
+#[derive(Default)]
+pub struct WebhooksResource;
+
+impl Resource for WebhooksResource {
+    fn name(&self) -> &str {
+        "webhooks"
+    }
+
+    get!(request, ctx, {
+        crate::metered!(request, "webhooks", "GET", {
+            if let Some(id) = id_before(request.uri().path(), "/deliveries") {
+                let query = request.uri().query().unwrap_or("");
+                let limit = parse_query_param(query, "limit")
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .unwrap_or(DEFAULT_LIMIT);
+                let list: Vec<serde_json::Value> = deliveries()
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .rev()
+                    .filter(|d| d["webhookId"] == id.as_str())
+                    .take(limit)
+                    .cloned()
+                    .collect();
+                return reply().json(json!({
+                    "webhookId": id,
+                    "deliveries": list,
+                }));
+            }
+
+            let table = ctx.get_table("Webhook")?;
+            if let Some(id) = ctx.path_id() {
+                return match table.get_by_id(id).await? {
+                    Some(record) => reply().json(view(record)),
+                    None => not_found(&format!("Webhook '{}' not found", id)),
+                };
+            }
+
+            let records = table.scan_all().await?;
+            load(records.clone());
+            let mut webhooks: Vec<serde_json::Value> = records.into_iter().map(view).collect();
+            webhooks.sort_by(|a, b| a["createdAt"].as_f64().partial_cmp(&b["createdAt"].as_f64())
+                .unwrap_or(std::cmp::Ordering::Equal));
+            reply().json(json!(webhooks))
+        })
+    });
+
+    post!(request, ctx, {
+        crate::metered!(request, "webhooks", "POST", {
+            let table = ctx.get_table("Webhook")?;
+
+            // POST /admin/webhooks/{id}/test
+            if let Some(id) = id_before(request.uri().path(), "/test") {
+                let Some(record) = table.get_by_id(&id).await? else {
+                    return not_found(&format!("Webhook '{}' not found", id));
+                };
+                let sub = Subscription {
+                    url: record["url"].as_str().unwrap_or("").to_string(),
+                    events: Vec::new(),
+                    secret: crate::env::read_secret(SECRETS_SCOPE, &id),
+                    id: id.clone(),
+                };
+                let event = crate::events::event("webhook.test", &id, json!({"actor": crate::actor!(request)}));
+                tokio::spawn(deliver(sub, payload(&event)));
+                return reply().code(202).json(json!({
+                    "webhookId": id,
+                    "eventId": event["id"],
+                    "status": "pending",
+                }));
+            }
+
+            let body = request.json_value()?;
+            if body.get("url").is_none() {
+                return bad_request("Missing 'url'");
+            }
+            let mut record = json!({
+                "id": format!("wh-{}", random_hex(6).map_err(YetiError::Internal)?),
+                "events": [],
+                "enabled": true,
+                "createdAt": now_secs(),
+            });
+            if let Err(e) = apply_fields(&mut record, &body) {
+                return bad_request(&e);
+            }
+            let id = record["id"].as_str().unwrap_or("").to_string();
+            let secret = match body.get("secret").and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
+                Some(s) => s.to_string(),
+                None => random_hex(32).map_err(YetiError::Internal)?,
+            };
+            set_secret(&id, Some(&secret))?;
+            table.put(record.clone()).await?;
+            load(table.scan_all().await.unwrap_or_default());
+
+            crate::audit!(request, ctx, "webhook.create", &id, json!({
+                "url": record["url"],
+                "events": record["events"],
+            }));
+
+            let mut created = view(record);
+            created["secret"] = json!(secret);
+            reply().code(201).json(created)
+        })
+    });
+
+    put!(request, ctx, {
+        crate::metered!(request, "webhooks", "PUT", {
+            let id = ctx.require_id()?.to_string();
+            let table = ctx.get_table("Webhook")?;
+            let Some(mut record) = table.get_by_id(&id).await? else {
+                return not_found(&format!("Webhook '{}' not found", id));
+            };
+
+            let body = request.json_value()?;
+            if let Err(e) = apply_fields(&mut record, &body) {
+                return bad_request(&e);
+            }
+            if let Some(secret) = body.get("secret") {
+                set_secret(&id, secret.as_str().filter(|s| !s.is_empty()))?;
+            }
+            table.put(record.clone()).await?;
+            load(table.scan_all().await.unwrap_or_default());
+
+            let changed: Vec<&String> = body.as_object().map(|o| o.keys().collect()).unwrap_or_default();
+            crate::audit!(request, ctx, "webhook.update", &id, json!({"fields": changed}));
+
+            reply().json(view(record))
+        })
+    });
+
+    delete!(request, ctx, {
+        crate::metered!(request, "webhooks", "DELETE", {
+            let id = ctx.require_id()?.to_string();
+            let table = ctx.get_table("Webhook")?;
+            if table.get_by_id(&id).await?.is_none() {
+                return not_found(&format!("Webhook '{}' not found", id));
+            }
+            table.delete(&id).await?;
+            set_secret(&id, None)?;
+            load(table.scan_all().await.unwrap_or_default());
+
+            crate::audit!(request, ctx, "webhook.delete", &id, json!({}));
+
+            reply().json(json!({
+                "id": id,
+                "deleted": true,
+            }))
+        })
+    });
+}
+
+register_resource!(WebhooksResource);
@@ -12,16 +12,22 @@ interface TestDef {
 const TESTS: TestDef[] = [
   { id: 'rest-read', name: 'REST Reads', binary: 'load-rest', duration: 30, vus: 50 },
   { id: 'rest-write', name: 'REST Writes', binary: 'load-rest', duration: 30, vus: 50 },
+  { id: 'rest-bulk-write', name: 'REST Bulk Writes', binary: 'load-rest', duration: 30, vus: 10 },
   { id: 'rest-update', name: 'REST Update', binary: 'load-rest', duration: 30, vus: 50 },
+  { id: 'rest-scan', name: 'REST Pagination', binary: 'load-rest', duration: 30, vus: 20 },
+  { id: 'rest-conn-churn', name: 'REST Connection Churn', binary: 'load-rest', duration: 30, vus: 50 },
   { id: 'rest-join', name: 'REST Join', binary: 'load-rest', duration: 30, vus: 50 },
   { id: 'graphql-read', name: 'GraphQL Reads', binary: 'load-graphql', duration: 30, vus: 50 },
   { id: 'graphql-mutation', name: 'GraphQL Mutations', binary: 'load-graphql', duration: 30, vus: 50 },
   { id: 'graphql-join', name: 'GraphQL Join', binary: 'load-graphql', duration: 30, vus: 50 },
   { id: 'vector-embed', name: 'Vector Embed', binary: 'load-vector', duration: 30, vus: 50 },
+  { id: 'vector-bulk-load', name: 'Vector Bulk Load', binary: 'load-vector', duration: 600, vus: 20 },
   { id: 'vector-search', name: 'Vector Search', binary: 'load-vector', duration: 30, vus: 50 },
   { id: 'ws', name: 'WebSocket', binary: 'load-realtime', duration: 30, vus: 50 },
   { id: 'sse', name: 'SSE Streaming', binary: 'load-realtime', duration: 30, vus: 50 },
+  { id: 'sse-reconnect', name: 'SSE Reconnect', binary: 'load-realtime', duration: 60, vus: 50 },
   { id: 'blob-retrieval', name: '150k Blob Retrieval', binary: 'load-blob', duration: 30, vus: 50 },
+  { id: 'blob-upload', name: '150k Blob Upload', binary: 'load-blob', duration: 30, vus: 20 },
 ]
 
 interface TestConfig {
@@ -36,6 +42,7 @@ interface LatestResult {
   results: {
     throughput?: number
     p50?: number
+    p95?: number
     p99?: number
     total?: number
     errors?: number
@@ -65,6 +72,11 @@ interface HistoryRun {
   extrapolatedThroughput: string
 }
 
+interface LatencyBucket {
+  le: number
+  count: number
+}
+
 function formatNumber(n: number): string {
   if (n >= 1000000) return (n / 1000000).toFixed(1) + 'M'
   if (n >= 1000) return (n / 1000).toFixed(n >= 10000 ? 0 : 1) + 'k'
@@ -80,6 +92,22 @@ function formatDescription(cfg: TestConfig): string {
   return `${cfg.duration}s Duration. ${cfg.vus} VUs.`
 }
 
+function LatencyChart({ buckets }: { buckets: LatencyBucket[] }) {
+  const max = Math.max(...buckets.map(b => b.count), 1)
+  return (
+    <div className="bench-latency-chart">
+      {buckets.map(b => (
+        <div
+          key={b.le}
+          className="bench-latency-bar"
+          style={{ height: `${Math.max((b.count / max) * 100, b.count > 0 ? 4 : 0)}%` }}
+          title={`≤ ${formatMs(b.le)}: ${b.count.toLocaleString()} requests`}
+        />
+      ))}
+    </div>
+  )
+}
+
 function ListIcon() {
   return (
     <svg width="14" height="14" viewBox="0 0 16 16" fill="none" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round">
@@ -100,7 +128,7 @@ export default function BenchmarksPanel() {
   const [historyModal, setHistoryModal] = useState<{ testId: string; testName: string } | null>(null)
   const [history, setHistory] = useState<HistoryRun[]>([])
   const [error, setError] = useState<string | null>(null)
-  const pollRef = useRef<number | null>(null)
+  const busyRef = useRef(false)
 
   const getConfig = useCallback((testId: string): TestConfig => {
     if (configs[testId]) return configs[testId]
@@ -114,8 +142,9 @@ export default function BenchmarksPanel() {
       if (resp.ok) {
         const data = await resp.json()
         const map: Record<string, LatestResult> = {}
+        // One entry per (env, test); show the best across environments
         for (const t of data.tests || []) {
-          map[t.name] = t
+          if (!map[t.name] || t.throughput > map[t.name].throughput) map[t.name] = t
         }
         setLatestResults(map)
       }
@@ -157,13 +186,13 @@ export default function BenchmarksPanel() {
           setConfigs(cfgMap)
         }
 
-        if (state.status === 'idle' && pollRef.current) {
-          clearInterval(pollRef.current)
-          pollRef.current = null
+        if (state.status === 'idle' && busyRef.current) {
+          busyRef.current = false
           fetchLatestResults()
           if (historyModal) fetchHistory(historyModal.testId)
           if (state.error) setError(state.error)
         }
+        if (state.status !== 'idle') busyRef.current = true
       }
     } catch {
       // Server may not be ready yet
@@ -189,6 +218,52 @@ export default function BenchmarksPanel() {
     fetchRunnerState()
   }, [fetchLatestResults, fetchRunnerState])
 
+  // Runner transitions are pushed over the RunnerEvent table stream; refetch state on each one
+  const fetchRunnerStateRef = useRef(fetchRunnerState)
+  fetchRunnerStateRef.current = fetchRunnerState
+  useEffect(() => {
+    let ws: WebSocket | null = null
+    let retry: number | null = null
+    let closed = false
+
+    const connect = () => {
+      const proto = window.location.protocol === 'https:' ? 'wss:' : 'ws:'
+      ws = new WebSocket(`${proto}//${window.location.host}${BASE}/RunnerEvent?stream=ws`)
+      ws.onmessage = (event) => {
+        try {
+          const data = JSON.parse(event.data)
+          if (data.status) fetchRunnerStateRef.current()
+        } catch {
+          // ignore non-JSON frames
+        }
+      }
+      ws.onclose = () => {
+        if (!closed) retry = window.setTimeout(connect, 2000)
+      }
+    }
+    connect()
+
+    return () => {
+      closed = true
+      if (retry) clearTimeout(retry)
+      ws?.close()
+    }
+  }, [])
+
+  // Advance the elapsed timer locally while a test runs (no polling needed)
+  useEffect(() => {
+    if (runner.status !== 'running' || !runner.startedAt) return
+    const timer = window.setInterval(() => {
+      setRunner(prev => {
+        if (prev.status !== 'running' || !prev.startedAt) return prev
+        const elapsed = Date.now() / 1000 - prev.startedAt
+        const cap = prev.configuredDuration || elapsed
+        return { ...prev, elapsedSecs: Math.min(elapsed, cap) }
+      })
+    }, 1000)
+    return () => clearInterval(timer)
+  }, [runner.status, runner.startedAt])
+
   const startTest = async (testId: string) => {
     setError(null)
     try {
@@ -198,9 +273,9 @@ export default function BenchmarksPanel() {
         body: JSON.stringify({ test: testId }),
       })
       if (resp.ok) {
+        busyRef.current = true
         setRunner({ status: 'warming', test: testId, startedAt: Date.now() / 1000 })
-        if (pollRef.current) clearInterval(pollRef.current)
-        pollRef.current = window.setInterval(fetchRunnerState, 1000)
+        fetchRunnerState()
       } else {
         const text = await resp.text()
         setError(text || 'Failed to start test')
@@ -383,7 +458,7 @@ function TestCard({ test, config, latest, phase, isDisabled, warmupSecs, elapsed
           <span className="bench-stat-label">req/sec</span>
         </div>
         <div className="bench-stat">
-          <span className="bench-stat-value">{hasData ? formatMs(results.p50 ?? 0) : '—'}</span>
+          <span className="bench-stat-value">{hasData ? formatMs(results.p95 ?? results.p50 ?? 0) : '—'}</span>
           <span className="bench-stat-label">p95 latency</span>
         </div>
         {hasData && (
@@ -425,12 +500,13 @@ function HistoryModal({ testName, runs, onClose }: HistoryModalProps) {
                   <th>Throughput</th>
                   <th>Extrapolated</th>
                   <th>Duration</th>
+                  <th>Latency</th>
                   <th>Summary</th>
                 </tr>
               </thead>
               <tbody>
                 {runs.map(run => {
-                  let parsed: Record<string, number> = {}
+                  let parsed: { throughput?: number; latencyBuckets?: LatencyBucket[] } = {}
                   try { parsed = JSON.parse(run.results || '{}') } catch { /* ignore */ }
 
                   return (
@@ -439,6 +515,7 @@ function HistoryModal({ testName, runs, onClose }: HistoryModalProps) {
                       <td>{formatNumber(parsed.throughput ?? 0)} /s</td>
                       <td>{run.extrapolatedThroughput ? formatNumber(parseFloat(run.extrapolatedThroughput)) + ' /s' : '-'}</td>
                       <td>{run.durationSecs?.toFixed(1)}s</td>
+                      <td>{parsed.latencyBuckets?.length ? <LatencyChart buckets={parsed.latencyBuckets} /> : '-'}</td>
                       <td>{run.summary || '-'}</td>
                     </tr>
                   )
@@ -0,0 +1,39 @@
+import { useEffect, useRef } from 'react'
+import { BASE } from '../api'
+
+export interface AdminEvent {
+  id: string
+  kind: string
+  target: string
+  timestamp: number
+  data?: string
+}
+
+/**
+ * Subscribe to the AdminEvent table stream and call `onEvent` for each event
+ * whose kind starts with one of `prefixes` (e.g. "app.", "repo.clone").
+ */
+export function useAdminEvents(prefixes: string[], onEvent: (event: AdminEvent) => void) {
+  const onEventRef = useRef(onEvent)
+  onEventRef.current = onEvent
+  const key = prefixes.join(',')
+
+  useEffect(() => {
+    const kinds = key.split(',').filter(Boolean)
+    const es = new EventSource(`${BASE}/AdminEvent?stream=sse`)
+
+    // Server sends "event: update" for each new record
+    es.addEventListener('update', (message: MessageEvent) => {
+      try {
+        const event = JSON.parse(message.data) as AdminEvent
+        if (kinds.length === 0 || kinds.some(k => event.kind?.startsWith(k))) {
+          onEventRef.current(event)
+        }
+      } catch {
+        // skip unparseable messages
+      }
+    })
+
+    return () => es.close()
+  }, [key])
+}
@@ -1,6 +1,7 @@
 import { useState } from 'react'
-import { createFileRoute, useNavigate } from '@tanstack/react-router'
+import { createFileRoute, useNavigate, useRouter } from '@tanstack/react-router'
 import { api, BASE } from '../../api'
+import { useAdminEvents } from '../../hooks/useAdminEvents'
 import { AppSummary } from '../../types'
 import { NewAppModal } from '../../components/NewAppModal'
 
@@ -12,9 +13,13 @@ export const Route = createFileRoute('/applications/')({
 function ApplicationsList() {
   const apps = Route.useLoaderData()
   const navigate = useNavigate()
+  const router = useRouter()
   const [filter, setFilter] = useState('')
   const [showNewApp, setShowNewApp] = useState(false)
 
+  // Reload the list when apps are created, changed or removed elsewhere
+  useAdminEvents(['app.', 'repo.clone'], () => router.invalidate())
+
   const sorted = [...apps]
     .filter(app => !app.is_extension)
     .sort((a, b) => a.app_id.localeCompare(b.app_id))